@@ -0,0 +1,16 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // the gRPC server is optional, so that building does not require `protoc` by default
+    #[cfg(feature = "grpc")]
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/pointing_sim.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Source of the current time for the mount axes, the target source and the target interpolator.
+//!
+//! The simulation normally uses `SystemClock`; tests can substitute a `ManualClock` and advance it explicitly
+//! to reproduce exact motion profiles.
+
+use std::{sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Returns time elapsed since `t` (zero if `t` is in the future).
+    fn since(&self, t: Instant) -> Duration { self.now().saturating_duration_since(t) }
+}
+
+/// The system's monotonic clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant { Instant::now() }
+}
+
+/// Returns the system clock (the default one of all users of `Clock`).
+pub fn system_clock() -> Arc<dyn Clock> { Arc::new(SystemClock) }
+
+/// Clock standing still unless advanced with `advance`; starts at the time of its creation.
+pub struct ManualClock {
+    now: Mutex<Instant>
+}
+
+impl Default for ManualClock {
+    fn default() -> ManualClock { ManualClock{ now: Mutex::new(Instant::now()) } }
+}
+
+impl ManualClock {
+    pub fn advance(&self, dt: Duration) { *self.now.lock().unwrap() += dt; }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant { *self.now.lock().unwrap() }
+}
@@ -0,0 +1,103 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Color modes of camera output (recorded and streamed frames).
+
+/// Channel indices (R = 0, G = 1, B = 2) of a 2×2 Bayer cell: top left, top right, bottom left, bottom right.
+type BayerCell = [usize; 4];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BayerPattern { Rggb, Bggr, Grbg, Gbrg }
+
+impl BayerPattern {
+    fn cell(&self) -> BayerCell {
+        match self {
+            BayerPattern::Rggb => [0, 1, 1, 2],
+            BayerPattern::Bggr => [2, 1, 1, 0],
+            BayerPattern::Grbg => [1, 0, 2, 1],
+            BayerPattern::Gbrg => [1, 2, 0, 1]
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ColorMode {
+    #[default]
+    Rgb,
+    /// Luminance.
+    Mono,
+    /// Raw color filter array mosaic.
+    Bayer(BayerPattern)
+}
+
+impl ColorMode {
+    pub const ALL: [ColorMode; 6] = [
+        ColorMode::Rgb,
+        ColorMode::Mono,
+        ColorMode::Bayer(BayerPattern::Rggb),
+        ColorMode::Bayer(BayerPattern::Bggr),
+        ColorMode::Bayer(BayerPattern::Grbg),
+        ColorMode::Bayer(BayerPattern::Gbrg)
+    ];
+
+    /// Returns the number of bytes per pixel of the output of `convert`.
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            ColorMode::Rgb => 4,
+            ColorMode::Mono | ColorMode::Bayer(_) => 1
+        }
+    }
+
+    /// Converts an RGBA image (top row first); returns RGBA data for `Rgb`, 8-bit values otherwise.
+    pub fn convert(&self, width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+        match self {
+            ColorMode::Rgb => rgba.to_vec(),
+
+            ColorMode::Mono => rgba.chunks_exact(4).map(|pixel| {
+                // Rec. 709 luma coefficients
+                (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32).round() as u8
+            }).collect(),
+
+            ColorMode::Bayer(pattern) => {
+                let cell = pattern.cell();
+                let mut output = Vec::with_capacity((width * height) as usize);
+                for y in 0..height as usize {
+                    for x in 0..width as usize {
+                        let channel = cell[2 * (y % 2) + x % 2];
+                        output.push(rgba[4 * (y * width as usize + x) + channel]);
+                    }
+                }
+                output
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ColorMode::Rgb => "rgb",
+            ColorMode::Mono => "mono",
+            ColorMode::Bayer(BayerPattern::Rggb) => "bayer-rggb",
+            ColorMode::Bayer(BayerPattern::Bggr) => "bayer-bggr",
+            ColorMode::Bayer(BayerPattern::Grbg) => "bayer-grbg",
+            ColorMode::Bayer(BayerPattern::Gbrg) => "bayer-gbrg"
+        })
+    }
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ColorMode, String> {
+        ColorMode::ALL.iter()
+            .find(|mode| mode.to_string() == s.trim().to_lowercase())
+            .copied()
+            .ok_or_else(|| format!("expected one of: {}", ColorMode::ALL.map(|mode| mode.to_string()).join(", ")))
+    }
+}
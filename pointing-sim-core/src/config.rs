@@ -0,0 +1,360 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use crate::workers;
+use pointing_utils::uom;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::PathBuf};
+use uom::{si::f64, si::length};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+const MOUNT_PROFILES_FILE_NAME: &str = "mount_profiles.toml";
+
+/// Parameters of a single mount axis.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AxisConfig {
+    pub acceleration_deg_per_s2: f64,
+    pub max_rate_deg_per_s: f64,
+    pub min_pos_deg: Option<f64>,
+    pub max_pos_deg: Option<f64>,
+    pub backlash_arcsec: f64,
+    /// Amplitude of periodic error; its period is one worm revolution (360° / `gear_ratio` of axis motion).
+    pub periodic_error_arcsec: f64,
+    /// Number of motor (worm) revolutions per one axis revolution.
+    pub gear_ratio: f64,
+    /// Resolution of the motor-side encoder.
+    pub encoder_counts_per_motor_rev: u32,
+    /// Natural frequency of the gearbox/structure oscillatory mode excited by acceleration changes (e.g., fast
+    /// stops); `None` disables it.
+    pub resonance_frequency_hz: Option<f64>,
+    /// Damping ratio of the oscillatory mode (0-1).
+    pub resonance_damping_ratio: f64
+}
+
+impl Default for AxisConfig {
+    fn default() -> AxisConfig {
+        AxisConfig{
+            acceleration_deg_per_s2: 6.0,
+            max_rate_deg_per_s: 10.0,
+            min_pos_deg: None,
+            max_pos_deg: None,
+            backlash_arcsec: 0.0,
+            periodic_error_arcsec: 0.0,
+            gear_ratio: 360.0,
+            encoder_counts_per_motor_rev: 4096,
+            resonance_frequency_hz: None,
+            resonance_damping_ratio: 0.05
+        }
+    }
+}
+
+impl AxisConfig {
+    /// Returns encoder resolution in degrees.
+    pub fn encoder_resolution_deg(&self) -> f64 {
+        360.0 / (self.encoder_counts_per_motor_rev as f64 * self.gear_ratio)
+    }
+
+    /// Replaces values the mount model cannot work with (e.g., a non-positive acceleration) with the defaults,
+    /// logging each of them; `name` identifies the axis in log messages.
+    fn validated(mut self, name: &str) -> AxisConfig {
+        let default = AxisConfig::default();
+        let check = |value: &mut f64, default: f64, valid: bool, field: &str| if !valid {
+            log::error!("invalid {} of {}: {}; using {}", field, name, value, default);
+            *value = default;
+        };
+
+        let valid = is_positive(self.acceleration_deg_per_s2);
+        check(&mut self.acceleration_deg_per_s2, default.acceleration_deg_per_s2, valid, "acceleration");
+        let valid = is_positive(self.max_rate_deg_per_s);
+        check(&mut self.max_rate_deg_per_s, default.max_rate_deg_per_s, valid, "max. rate");
+        let valid = is_positive(self.gear_ratio);
+        check(&mut self.gear_ratio, default.gear_ratio, valid, "gear ratio");
+        let valid = self.backlash_arcsec.is_finite() && self.backlash_arcsec >= 0.0;
+        check(&mut self.backlash_arcsec, default.backlash_arcsec, valid, "backlash");
+        let valid = self.periodic_error_arcsec.is_finite();
+        check(&mut self.periodic_error_arcsec, default.periodic_error_arcsec, valid, "periodic error");
+        let valid = (0.0..=1.0).contains(&self.resonance_damping_ratio);
+        check(&mut self.resonance_damping_ratio, default.resonance_damping_ratio, valid, "damping ratio");
+
+        if self.encoder_counts_per_motor_rev == 0 {
+            log::error!("invalid encoder resolution of {}: 0; using {}", name, default.encoder_counts_per_motor_rev);
+            self.encoder_counts_per_motor_rev = default.encoder_counts_per_motor_rev;
+        }
+        if let Some(frequency) = self.resonance_frequency_hz.filter(|f| !is_positive(*f)) {
+            log::error!("invalid resonance frequency of {}: {}; disabling the oscillatory mode", name, frequency);
+            self.resonance_frequency_hz = None;
+        }
+        for (limit, field) in [(&mut self.min_pos_deg, "min. position"), (&mut self.max_pos_deg, "max. position")] {
+            if let Some(value) = limit.filter(|value| !value.is_finite()) {
+                log::error!("invalid {} of {}: {}; ignoring", field, name, value);
+                *limit = None;
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_pos_deg, self.max_pos_deg) {
+            if min > max {
+                log::error!("invalid position limits of {}: min. {} > max. {}; ignoring", name, min, max);
+                self.min_pos_deg = None;
+                self.max_pos_deg = None;
+            }
+        }
+
+        self
+    }
+}
+
+/// Returns true if `value` is positive and finite.
+fn is_positive(value: f64) -> bool { value.is_finite() && value > 0.0 }
+
+/// Parameters of the focuser (see `workers::focuser`).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FocuserConfig {
+    pub max_position: u32,
+    pub initial_position: u32,
+    /// Position of best focus (of the focusing element, i.e., excluding backlash).
+    pub best_focus: u32,
+    /// Steps per second.
+    pub speed: f64,
+    pub backlash_steps: u32,
+    /// Diameter of the defocused image of a point source per step of focus error.
+    pub defocus_arcsec_per_step: f64
+}
+
+impl Default for FocuserConfig {
+    fn default() -> FocuserConfig {
+        FocuserConfig{
+            max_position: 10000,
+            initial_position: 4700,
+            best_focus: 5000,
+            speed: 500.0,
+            backlash_steps: 20,
+            defocus_arcsec_per_step: 0.5
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FilterConfig {
+    pub name: String,
+    /// Relative transmission in the red, green and blue channels.
+    pub transmission: [f32; 3]
+}
+
+/// Parameters of the filter wheel (see `workers::filter_wheel`).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FilterWheelConfig {
+    pub filters: Vec<FilterConfig>,
+    /// Time of moving the wheel by one slot (seconds).
+    pub slot_change_time_s: f64
+}
+
+impl Default for FilterWheelConfig {
+    fn default() -> FilterWheelConfig {
+        let filter = |name: &str, transmission| FilterConfig{ name: name.into(), transmission };
+        FilterWheelConfig{
+            filters: vec![
+                filter("L", [1.0, 1.0, 1.0]),
+                filter("R", [0.9, 0.05, 0.02]),
+                filter("G", [0.05, 0.9, 0.05]),
+                filter("B", [0.02, 0.1, 0.9]),
+                filter("Ha", [0.25, 0.0, 0.0])
+            ],
+            slot_change_time_s: 0.5
+        }
+    }
+}
+
+/// Parameters of the mount model.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MountConfig {
+    pub axis1: AxisConfig,
+    pub axis2: AxisConfig,
+    pub cone_error_arcsec: f64,
+    pub non_perpendicularity_arcsec: f64,
+    pub tube_flexure_arcsec: f64,
+    /// Guide rate as a multiple of sidereal rate.
+    pub guide_rate_sidereal: f64,
+    /// If set, axes are stopped when the client sends no messages for this long (seconds). Clients may change
+    /// it with the `set_heartbeat_timeout` message.
+    pub heartbeat_timeout_s: Option<f64>,
+    pub focuser: FocuserConfig,
+    pub filter_wheel: FilterWheelConfig
+}
+
+impl MountConfig {
+    /// Replaces values the mount model cannot work with with the defaults, logging each of them.
+    pub fn validated(mut self) -> MountConfig {
+        self.axis1 = self.axis1.validated("axis 1");
+        self.axis2 = self.axis2.validated("axis 2");
+
+        let default = MountConfig::default();
+        for (value, default, name) in [
+            (&mut self.cone_error_arcsec, default.cone_error_arcsec, "cone error"),
+            (&mut self.non_perpendicularity_arcsec, default.non_perpendicularity_arcsec, "non-perpendicularity"),
+            (&mut self.tube_flexure_arcsec, default.tube_flexure_arcsec, "tube flexure")
+        ] {
+            if !value.is_finite() {
+                log::error!("invalid {}: {}; using {}", name, value, default);
+                *value = default;
+            }
+        }
+        if !self.guide_rate_sidereal.is_finite() || self.guide_rate_sidereal < 0.0 {
+            log::error!("invalid guide rate: {}; using {}", self.guide_rate_sidereal, default.guide_rate_sidereal);
+            self.guide_rate_sidereal = default.guide_rate_sidereal;
+        }
+        if let Some(timeout) = self.heartbeat_timeout_s {
+            if !is_positive(timeout) || std::time::Duration::try_from_secs_f64(timeout).is_err() {
+                log::error!("invalid heartbeat timeout: {}; disabling the heartbeat failsafe", timeout);
+                self.heartbeat_timeout_s = None;
+            }
+        }
+        if !is_positive(self.focuser.speed) {
+            log::error!("invalid focuser speed: {}; using {}", self.focuser.speed, default.focuser.speed);
+            self.focuser.speed = default.focuser.speed;
+        }
+        let slot_change_time = self.filter_wheel.slot_change_time_s;
+        if !slot_change_time.is_finite() || slot_change_time < 0.0 {
+            log::error!(
+                "invalid filter wheel slot change time: {}; using {}",
+                slot_change_time, default.filter_wheel.slot_change_time_s
+            );
+            self.filter_wheel.slot_change_time_s = default.filter_wheel.slot_change_time_s;
+        }
+
+        self
+    }
+}
+
+impl Default for MountConfig {
+    fn default() -> MountConfig {
+        MountConfig{
+            axis1: AxisConfig::default(),
+            axis2: AxisConfig::default(),
+            cone_error_arcsec: 0.0,
+            non_perpendicularity_arcsec: 0.0,
+            tube_flexure_arcsec: 0.0,
+            guide_rate_sidereal: 0.5,
+            heartbeat_timeout_s: None,
+            focuser: FocuserConfig::default(),
+            filter_wheel: FilterWheelConfig::default()
+        }
+    }
+}
+
+/// Contents of the mount profiles file, e.g.:
+///
+/// ```toml
+/// [profiles.heavy]
+/// guide_rate_sidereal = 0.25
+///
+/// [profiles.heavy.axis1]
+/// acceleration_deg_per_s2 = 1.5
+/// backlash_arcsec = 30.0
+/// ```
+#[derive(Default, Deserialize, Serialize)]
+struct MountProfiles {
+    #[serde(default)]
+    profiles: HashMap<String, MountConfig>
+}
+
+/// Observer's location (of the first station).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LocationConfig {
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub elevation_m: f64
+}
+
+impl LocationConfig {
+    pub fn site(&self) -> workers::Site {
+        workers::Site{
+            lat: cgmath::Deg(self.lat_deg),
+            lon: cgmath::Deg(self.lon_deg),
+            elevation: f64::Length::new::<length::meter>(self.elevation_m)
+        }
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("pointing-sim"))
+}
+
+/// Loads the given mount profile from the profiles file in the configuration directory. The built-in
+/// default profile is used if no profile has been specified or it cannot be loaded.
+pub fn load_mount_config(profile: Option<&str>) -> MountConfig {
+    let profile = match profile {
+        None => return MountConfig::default(),
+        Some(p) => p
+    };
+
+    let path = match config_dir() {
+        Some(dir) => dir.join(MOUNT_PROFILES_FILE_NAME),
+        None => {
+            log::error!("cannot determine configuration directory; using default mount profile");
+            return MountConfig::default();
+        }
+    };
+
+    let profiles: MountProfiles = match std::fs::read_to_string(&path) {
+        Ok(contents) => match toml::from_str::<MountProfiles>(&contents) {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                log::error!("failed to parse {}: {}", path.display(), e);
+                MountProfiles::default()
+            }
+        },
+        Err(e) => {
+            if profile != DEFAULT_PROFILE { log::error!("failed to read {}: {}", path.display(), e); }
+            MountProfiles::default()
+        }
+    };
+
+    match profiles.profiles.get(profile) {
+        Some(config) => {
+            log::info!("using mount profile \"{}\"", profile);
+            config.clone().validated()
+        },
+        None => {
+            if profile != DEFAULT_PROFILE { log::error!("mount profile \"{}\" not found; using default", profile); }
+            MountConfig::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_mount_values_are_replaced_with_defaults() {
+        let mut config = MountConfig::default();
+        config.axis1.acceleration_deg_per_s2 = 0.0;
+        config.axis2.max_rate_deg_per_s = f64::NAN;
+        config.axis2.encoder_counts_per_motor_rev = 0;
+        config.axis2.resonance_frequency_hz = Some(-1.0);
+        config.focuser.speed = -5.0;
+        config.guide_rate_sidereal = -0.5;
+        config.axis1.min_pos_deg = Some(10.0);
+        config.axis1.max_pos_deg = Some(-10.0);
+
+        let config = config.validated();
+        let default = MountConfig::default();
+        assert_eq!(default.axis1.acceleration_deg_per_s2, config.axis1.acceleration_deg_per_s2);
+        assert_eq!(default.axis2.max_rate_deg_per_s, config.axis2.max_rate_deg_per_s);
+        assert_eq!(default.axis2.encoder_counts_per_motor_rev, config.axis2.encoder_counts_per_motor_rev);
+        assert!(config.axis2.resonance_frequency_hz.is_none());
+        assert_eq!(default.focuser.speed, config.focuser.speed);
+        assert_eq!(default.guide_rate_sidereal, config.guide_rate_sidereal);
+        assert!(config.axis1.min_pos_deg.is_none() && config.axis1.max_pos_deg.is_none());
+    }
+}
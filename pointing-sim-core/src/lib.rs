@@ -0,0 +1,28 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Simulation core of the Pointing Simulator, usable without the GUI (e.g., in other projects' integration tests).
+//!
+//! - `workers`: the mount model (`Mount`, served over TCP by `mount_model`) and the target source
+//! (`target_source`, configured by `TargetSourceOptions`) with their network protocols
+//! - `workers::TargetSource`: interface of pluggable target sources (passed in `TargetSourceOptions::sources`)
+//! - `target_interpolator`: interpolation of target positions between the received messages
+//! - `config`, `scenario`: mount profiles, observer location and scenario files
+//! - `clock`: time source of the simulation (`ManualClock` for deterministic tests)
+//!
+//! All workers are blocking functions meant to be run in their own threads. See `tests/embedding.rs` for an example
+//! of driving the mount and the target interpolator without the GUI.
+
+pub mod clock;
+pub mod color_mode;
+pub mod config;
+pub mod refraction;
+pub mod scenario;
+pub mod target_interpolator;
+pub mod units;
+pub mod workers;
@@ -0,0 +1,60 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use cgmath::{InnerSpace, Vector3};
+
+/// Conditions determining atmospheric refraction.
+#[derive(Copy, Clone, Debug)]
+pub struct Atmosphere {
+    pub temperature_celsius: f64,
+    pub pressure_hpa: f64
+}
+
+impl Default for Atmosphere {
+    fn default() -> Atmosphere {
+        Atmosphere{ temperature_celsius: 10.0, pressure_hpa: 1010.0 }
+    }
+}
+
+impl std::str::FromStr for Atmosphere {
+    type Err = String;
+
+    /// Parses `<temperature (°C)>,<pressure (hPa)>`.
+    fn from_str(s: &str) -> Result<Atmosphere, String> {
+        let (temperature, pressure) = s.split_once(',').ok_or(format!("expected <temperature>,<pressure>: {}", s))?;
+        Ok(Atmosphere{
+            temperature_celsius: temperature.trim().parse::<f64>().map_err(|e| format!("invalid temperature: {}", e))?,
+            pressure_hpa: pressure.trim().parse::<f64>().map_err(|e| format!("invalid pressure: {}", e))?
+        })
+    }
+}
+
+impl Atmosphere {
+    /// Returns refraction (apparent minus true altitude, in degrees) for the given true altitude (degrees).
+    ///
+    /// Uses Sæmundsson's formula; below -1° the value for -1° is used.
+    pub fn refraction(&self, true_altitude: f64) -> f64 {
+        let h = true_altitude.max(-1.0);
+        let r_arcmin = 1.02 / (h + 10.3 / (h + 5.11)).to_radians().tan();
+        let scale = (self.pressure_hpa / 1010.0) * (283.0 / (273.0 + self.temperature_celsius));
+        (r_arcmin * scale / 60.0).max(0.0)
+    }
+
+    /// Returns the apparent position of a point given in the local frame (x north, y west, z up): raised
+    /// towards zenith by refraction, with azimuth and distance unchanged.
+    pub fn apparent_position(&self, position: Vector3<f64>) -> Vector3<f64> {
+        let horizontal = position.x.hypot(position.y);
+        if horizontal == 0.0 { return position; }
+
+        let altitude = position.z.atan2(horizontal).to_degrees();
+        let apparent_altitude = (altitude + self.refraction(altitude)).min(90.0).to_radians();
+        let distance = position.magnitude();
+        let scale = distance * apparent_altitude.cos() / horizontal;
+        Vector3::new(position.x * scale, position.y * scale, distance * apparent_altitude.sin())
+    }
+}
@@ -0,0 +1,120 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Scenarios: complete, reproducible test cases (observer, targets, fault injections, start time) stored
+//! as TOML files.
+//!
+//! A scenario is launched with `--scenario <file>`; it overrides the location from the settings and the
+//! corresponding command-line options.
+
+use crate::{config, config::LocationConfig, workers::{TargetDefinition, TargetSourceOptions}};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const SCENARIOS_DIR_NAME: &str = "scenarios";
+
+/// Probability of an ambiguous CPR decoding if ADS-B CPR glitches are enabled.
+pub const ADSB_CPR_GLITCH_PROBABILITY: f64 = 0.01;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Faults {
+    /// If set, ADS-B feed positions are passed through CPR encoding/decoding (with occasional glitches).
+    pub adsb_cpr_glitches: bool,
+    /// Probability of swapping data of nearby targets (per feed update and pair of targets).
+    pub target_swap_probability: Option<f64>
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Scenario {
+    pub description: String,
+    /// Location of the first station.
+    pub observer: LocationConfig,
+    /// Start time of the sky model (RFC 3339 or `now`); if not set, the sky model is disabled.
+    pub start_time: Option<String>,
+    /// Seed of the random number generators.
+    pub seed: u64,
+    pub targets: Vec<TargetDefinition>,
+    /// Number of randomly generated targets (in addition to `targets`).
+    pub generated_targets: usize,
+    pub faults: Faults,
+    /// Scenario script (relative to the scenario file).
+    pub script: Option<PathBuf>
+}
+
+impl Default for Scenario {
+    fn default() -> Scenario {
+        Scenario{
+            description: String::new(),
+            observer: LocationConfig::default(),
+            start_time: None,
+            seed: 0,
+            targets: vec![TargetDefinition::default()],
+            generated_targets: 0,
+            faults: Faults::default(),
+            script: None
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Scenario, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let scenario: Scenario = toml::from_str(&contents).map_err(|e| e.to_string())?;
+        if let Some(probability) = scenario.faults.target_swap_probability.filter(|p| !(0.0..=1.0).contains(p)) {
+            return Err(format!("invalid target swap probability: {} (must be between 0 and 1)", probability));
+        }
+        Ok(scenario)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() { std::fs::create_dir_all(dir).map_err(|e| e.to_string())?; }
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    /// Returns the path of the scenario script (if any) of the scenario loaded from `path`.
+    pub fn script_path(&self, path: &Path) -> Option<PathBuf> {
+        self.script.as_ref().map(|script| path.parent().unwrap_or(Path::new("")).join(script))
+    }
+
+    /// Applies the scenario's targets and faults to the target source options.
+    pub fn apply(&self, options: &mut TargetSourceOptions) {
+        options.targets = self.targets.clone();
+        if let Some(target) = self.targets.first() {
+            *options.target_motion.lock().unwrap() = target.motion();
+        }
+        options.num_generated_targets = self.generated_targets;
+        options.seed = self.seed;
+        options.adsb_cpr_glitch_probability = if self.faults.adsb_cpr_glitches {
+            Some(ADSB_CPR_GLITCH_PROBABILITY)
+        } else {
+            None
+        };
+        options.target_swap_probability = self.faults.target_swap_probability;
+    }
+}
+
+/// Returns the directory of scenario files in the configuration directory.
+pub fn scenarios_dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join(SCENARIOS_DIR_NAME))
+}
+
+/// Returns the scenario files in the scenarios directory, sorted by name.
+pub fn list_scenarios() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = scenarios_dir()
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "toml"))
+            .collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
@@ -0,0 +1,168 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use crate::{clock, clock::Clock};
+use pointing_utils::{Local, Point3, Vector3, TargetInfoMessage};
+use std::{cell::RefCell, collections::VecDeque, rc::Weak, sync::Arc, time::{Duration, Instant}};
+use subscriber_rs::{Subscriber, SubscriberCollection};
+
+/// Duration of the target's position history.
+const HISTORY_DURATION: Duration = Duration::from_secs(20);
+
+/// Min. interval between positions recorded in the history.
+const HISTORY_INTERVAL: Duration = Duration::from_millis(200);
+
+struct Interpolated {
+    position: Point3<f64, Local>,
+    velocity: Vector3<f64, Local>,
+}
+
+pub struct TargetInterpolator {
+    last_info: Option<(Instant, TargetInfoMessage)>,
+    interpolated: Option<Interpolated>,
+    /// Recent target positions, oldest first.
+    history: VecDeque<(Instant, cgmath::Point3<f64>)>,
+    subscribers: SubscriberCollection<TargetInfoMessage>,
+    clock: Arc<dyn Clock>
+}
+
+impl Default for TargetInterpolator {
+    fn default() -> TargetInterpolator { TargetInterpolator::new() }
+}
+
+impl TargetInterpolator {
+    pub fn new() -> TargetInterpolator {
+        TargetInterpolator::with_clock(clock::system_clock())
+    }
+
+    /// Creates an interpolator measuring the age of target messages with `clock` (e.g., a manually advanced one
+    /// in tests).
+    pub fn with_clock(clock: Arc<dyn Clock>) -> TargetInterpolator {
+        TargetInterpolator{
+            last_info: None,
+            interpolated: None,
+            history: VecDeque::new(),
+            subscribers: Default::default(),
+            clock
+        }
+    }
+
+    pub fn add_subscriber(&mut self, subscriber: Weak<RefCell<dyn Subscriber<TargetInfoMessage>>>) {
+        self.subscribers.add(subscriber as _);
+    }
+
+    pub fn interpolate(&mut self) {
+        if let Some(last_info) = &self.last_info {
+            let dt = self.clock.since(last_info.0);
+            let interpolated = Interpolated{
+                position: Point3::<f64, Local>::from(last_info.1.position.0 + last_info.1.velocity.0 * dt.as_secs_f64()),
+                velocity: last_info.1.velocity.clone(),
+            };
+            self.subscribers.notify(&TargetInfoMessage{
+                position: interpolated.position.clone(),
+                velocity: interpolated.velocity.clone(),
+                track: last_info.1.track,
+                altitude: last_info.1.altitude
+            });
+            self.record_history(interpolated.position.0);
+            self.interpolated = Some(interpolated);
+        }
+    }
+
+    /// Returns the target as of the last call to `interpolate` (`None` if no message has been received).
+    pub fn current(&self) -> Option<TargetInfoMessage> {
+        let (last_info, interpolated) = (&self.last_info.as_ref()?.1, self.interpolated.as_ref()?);
+        Some(TargetInfoMessage{
+            position: interpolated.position.clone(),
+            velocity: interpolated.velocity.clone(),
+            track: last_info.track,
+            altitude: last_info.altitude
+        })
+    }
+
+    fn record_history(&mut self, position: cgmath::Point3<f64>) {
+        let now = self.clock.now();
+        if self.history.back().map_or(true, |(t, _)| now - *t >= HISTORY_INTERVAL) {
+            self.history.push_back((now, position));
+        }
+        while self.history.front().map_or(false, |(t, _)| now - *t > HISTORY_DURATION) {
+            self.history.pop_front();
+        }
+    }
+
+    /// Returns recent target positions, oldest first.
+    pub fn history(&self) -> impl Iterator<Item=&cgmath::Point3<f64>> {
+        self.history.iter().map(|(_, position)| position)
+    }
+
+    /// Returns time elapsed since the last target message (`None` if none has been received).
+    pub fn data_age(&self) -> Option<Duration> {
+        self.last_info.as_ref().map(|(t, _)| self.clock.since(*t))
+    }
+
+    /// Returns target positions extrapolated (assuming constant velocity) from now till `duration` ahead,
+    /// every `step`.
+    pub fn predicted_path(&self, duration: Duration, step: Duration) -> Vec<cgmath::Point3<f64>> {
+        match &self.interpolated {
+            Some(interpolated) => (0..=(duration.as_secs_f64() / step.as_secs_f64()) as usize)
+                .map(|i| interpolated.position.0 + interpolated.velocity.0 * (i as f64 * step.as_secs_f64()))
+                .collect(),
+            None => vec![]
+        }
+    }
+}
+
+impl Subscriber<TargetInfoMessage> for TargetInterpolator {
+    fn notify(&mut self, value: &TargetInfoMessage) {
+        self.last_info = Some((self.clock.now(), value.clone()));
+        self.interpolated = Some(Interpolated{ position: value.position.clone(), velocity: value.velocity.clone() });
+        self.subscribers.notify(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::InnerSpace;
+    use crate::clock::ManualClock;
+    use pointing_utils::uom::si::{f64, length};
+
+    fn message(position: [f64; 3], velocity: [f64; 3]) -> TargetInfoMessage {
+        TargetInfoMessage{
+            position: Point3::from(cgmath::Point3::from(position)),
+            velocity: Vector3::from(cgmath::Vector3::from(velocity)),
+            track: cgmath::Deg(90.0),
+            altitude: f64::Length::new::<length::meter>(position[2])
+        }
+    }
+
+    fn interpolated_position(interpolator: &TargetInterpolator) -> cgmath::Point3<f64> {
+        interpolator.predicted_path(Duration::ZERO, Duration::from_secs(1))[0]
+    }
+
+    #[test]
+    fn extrapolates_with_constant_velocity() {
+        let clock = Arc::new(ManualClock::default());
+        let mut interpolator = TargetInterpolator::with_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+        interpolator.notify(&message([10_000.0, 0.0, 3_000.0], [0.0, -200.0, 5.0]));
+
+        clock.advance(Duration::from_millis(1500));
+        interpolator.interpolate();
+        let position = interpolated_position(&interpolator);
+        assert!((position - cgmath::Point3::new(10_000.0, -300.0, 3_007.5)).magnitude() < 1.0e-9);
+        assert_eq!(interpolator.data_age(), Some(Duration::from_millis(1500)));
+
+        // a new message resets the extrapolation
+        interpolator.notify(&message([10_000.0, -310.0, 3_008.0], [0.0, -200.0, 0.0]));
+        assert_eq!(interpolator.data_age(), Some(Duration::ZERO));
+        clock.advance(Duration::from_millis(250));
+        interpolator.interpolate();
+        let position = interpolated_position(&interpolator);
+        assert!((position - cgmath::Point3::new(10_000.0, -360.0, 3_008.0)).magnitude() < 1.0e-9);
+    }
+}
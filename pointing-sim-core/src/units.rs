@@ -0,0 +1,28 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Conversions between `uom` quantities and the `cgmath` angles used by the rendering code.
+//!
+//! Angles are kept as `uom` quantities for as long as possible and converted only when passed to `cgmath`
+//! (and further to OpenGL).
+
+use cgmath::{Deg, Rad, Vector3};
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+pub fn deg(value: f64) -> f64::Angle { f64::Angle::new::<angle::degree>(value) }
+
+pub fn from_deg(value: Deg<f64>) -> f64::Angle { f64::Angle::new::<angle::degree>(value.0) }
+
+pub fn to_rad(value: f64::Angle) -> Rad<f64> { Rad(value.get::<angle::radian>()) }
+
+/// Returns the azimuth (measured from north towards east, from -180° to 180°) of a direction in the local frame
+/// (x points north, y west, z up).
+pub fn azimuth(direction: Vector3<f64>) -> f64::Angle {
+    f64::Angle::new::<angle::radian>((-direction.y).atan2(direction.x))
+}
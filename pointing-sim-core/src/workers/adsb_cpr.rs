@@ -0,0 +1,140 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Simulation of ADS-B airborne position encoding (Compact Position Reporting, 17-bit) and decoding.
+
+use cgmath::Deg;
+use pointing_utils::{LatLon, uom};
+use rand::Rng;
+use uom::{si::f64, si::length};
+
+/// Number of latitude zones between the equator and a pole.
+const NZ: f64 = 15.0;
+
+const CPR_MAX: f64 = (1 << 17) as f64;
+
+/// Altitude quantization step of ADS-B (25 ft).
+const ALTITUDE_STEP_FT: f64 = 25.0;
+
+#[derive(Copy, Clone)]
+struct CprFrame {
+    yz: u32,
+    xz: u32
+}
+
+fn modulo(x: f64, y: f64) -> f64 { x - y * (x / y).floor() }
+
+/// Returns number of longitude zones at the given latitude.
+fn nl(lat: f64) -> u32 {
+    let lat = lat.abs();
+    if lat == 0.0 {
+        59
+    } else if lat == 87.0 {
+        2
+    } else if lat > 87.0 {
+        1
+    } else {
+        let a = 1.0 - (std::f64::consts::PI / (2.0 * NZ)).cos();
+        let b = (std::f64::consts::PI / 180.0 * lat).cos().powi(2);
+        (2.0 * std::f64::consts::PI / (1.0 - a / b).acos()).floor() as u32
+    }
+}
+
+fn encode(lat: f64, lon: f64, odd: bool) -> CprFrame {
+    let i = if odd { 1.0 } else { 0.0 };
+    let dlat = 360.0 / (4.0 * NZ - i);
+    let yz = (CPR_MAX * modulo(lat, dlat) / dlat + 0.5).floor();
+    let rlat = dlat * (yz / CPR_MAX + (lat / dlat).floor());
+    let dlon = 360.0 / (nl(rlat) as f64 - i).max(1.0);
+    let xz = (CPR_MAX * modulo(lon, dlon) / dlon + 0.5).floor();
+
+    CprFrame{ yz: modulo(yz, CPR_MAX) as u32, xz: modulo(xz, CPR_MAX) as u32 }
+}
+
+/// Performs global decoding of an even/odd frame pair; `lat_zone_error` is added to the latitude zone index
+/// (non-zero values simulate an ambiguous decoding).
+fn decode_global(even: CprFrame, odd: CprFrame, most_recent_odd: bool, lat_zone_error: i32) -> Option<(f64, f64)> {
+    let dlat_even = 360.0 / (4.0 * NZ);
+    let dlat_odd = 360.0 / (4.0 * NZ - 1.0);
+
+    let lat_cpr_even = even.yz as f64 / CPR_MAX;
+    let lat_cpr_odd = odd.yz as f64 / CPR_MAX;
+    let lon_cpr_even = even.xz as f64 / CPR_MAX;
+    let lon_cpr_odd = odd.xz as f64 / CPR_MAX;
+
+    let j = (59.0 * lat_cpr_even - 60.0 * lat_cpr_odd + 0.5).floor() + lat_zone_error as f64;
+
+    let wrap = |lat: f64| if lat >= 270.0 { lat - 360.0 } else { lat };
+    let rlat_even = wrap(dlat_even * (modulo(j, 60.0) + lat_cpr_even));
+    let rlat_odd = wrap(dlat_odd * (modulo(j, 59.0) + lat_cpr_odd));
+
+    if nl(rlat_even) != nl(rlat_odd) {
+        // frames straddle a latitude zone boundary; the pair cannot be decoded
+        return None;
+    }
+
+    let (lat, ni, lon_cpr) = if most_recent_odd {
+        (rlat_odd, (nl(rlat_odd) as f64 - 1.0).max(1.0), lon_cpr_odd)
+    } else {
+        (rlat_even, (nl(rlat_even) as f64).max(1.0), lon_cpr_even)
+    };
+
+    let nl_lat = nl(lat) as f64;
+    let m = (lon_cpr_even * (nl_lat - 1.0) - lon_cpr_odd * nl_lat + 0.5).floor();
+    let mut lon = (360.0 / ni) * (modulo(m, ni) + lon_cpr);
+    if lon >= 180.0 { lon -= 360.0; }
+
+    Some((lat, lon))
+}
+
+/// Passes target positions through ADS-B encoding and decoding, alternating even and odd frames.
+pub struct CprQuantizer {
+    last_even: Option<CprFrame>,
+    last_odd: Option<CprFrame>,
+    next_odd: bool,
+    /// Probability of an ambiguous (wrong latitude zone) decoding of a frame pair.
+    glitch_probability: f64
+}
+
+impl CprQuantizer {
+    pub fn new(glitch_probability: f64) -> CprQuantizer {
+        CprQuantizer{
+            last_even: None,
+            last_odd: None,
+            next_odd: false,
+            glitch_probability: glitch_probability.clamp(0.0, 1.0)
+        }
+    }
+
+    /// Returns the position and altitude as decoded by an ADS-B receiver; `None` if the position cannot be
+    /// decoded (yet).
+    pub fn quantize(&mut self, lat_lon: &LatLon, altitude: f64::Length) -> Option<(LatLon, f64::Length)> {
+        let odd = self.next_odd;
+        self.next_odd = !self.next_odd;
+
+        let frame = encode(lat_lon.lat.0, lat_lon.lon.0, odd);
+        if odd { self.last_odd = Some(frame); } else { self.last_even = Some(frame); }
+
+        let (even, odd_frame) = (self.last_even?, self.last_odd?);
+
+        let mut rng = rand::thread_rng();
+        let lat_zone_error = if rng.gen_bool(self.glitch_probability) {
+            if rng.gen_bool(0.5) { 1 } else { -1 }
+        } else {
+            0
+        };
+        if lat_zone_error != 0 { log::debug!("simulating ambiguous CPR decoding"); }
+
+        let (lat, lon) = decode_global(even, odd_frame, odd, lat_zone_error)?;
+
+        let altitude_ft = altitude.get::<length::foot>();
+        let quantized_alt = f64::Length::new::<length::foot>((altitude_ft / ALTITUDE_STEP_FT).round() * ALTITUDE_STEP_FT);
+
+        Some((LatLon::new(Deg(lat), Deg(lon)), quantized_alt))
+    }
+}
@@ -0,0 +1,119 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Targets received from a real ADS-B decoder in the SBS-1 (BaseStation) format, as served e.g. by dump1090
+//! on port 30003.
+
+use cgmath::Deg;
+use crate::workers::{source_manager::{TargetSource, TargetState}, target_subscription::TargetKind};
+use pointing_utils::{GeoPos, LatLon, uom};
+use std::{
+    collections::HashMap,
+    io::BufRead,
+    net::TcpStream,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+use uom::{si::f64, si::length, si::velocity};
+
+/// Aircraft not heard from for this long are removed.
+const AIRCRAFT_TIMEOUT: Duration = Duration::from_secs(60);
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Last reported state of an aircraft.
+#[derive(Default)]
+struct Aircraft {
+    /// Latitude and longitude (degrees).
+    lat_lon: Option<(f64, f64)>,
+    altitude: Option<f64::Length>,
+    /// Ground speed in m/s.
+    ground_speed: f64,
+    track_deg: f64,
+    /// Vertical speed in m/s.
+    vertical_speed: f64,
+    last_message: Option<Instant>
+}
+
+pub struct AdsbInput {
+    address: String,
+    /// Keyed by ICAO address.
+    aircraft: Arc<Mutex<HashMap<u32, Aircraft>>>
+}
+
+impl AdsbInput {
+    /// Starts receiving messages from `address` (`<host>:<port>`); reconnects if the connection is lost.
+    pub fn connect(address: &str) -> AdsbInput {
+        let aircraft = Arc::new(Mutex::new(HashMap::new()));
+        let aircraft2 = Arc::clone(&aircraft);
+        let address2 = address.to_string();
+        std::thread::spawn(move || loop {
+            match TcpStream::connect(&address2) {
+                Ok(stream) => {
+                    log::info!("connected to ADS-B input {}", address2);
+                    for line in std::io::BufReader::new(stream).lines() {
+                        match line {
+                            Ok(line) => update_aircraft(&mut aircraft2.lock().unwrap(), &line),
+                            Err(e) => { log::error!("error reading ADS-B input {}: {}", address2, e); break; }
+                        }
+                    }
+                    log::info!("disconnected from ADS-B input {}", address2);
+                },
+                Err(e) => log::error!("cannot connect to ADS-B input {}: {}", address2, e)
+            }
+            std::thread::sleep(RECONNECT_INTERVAL);
+        });
+
+        AdsbInput{ address: address.to_string(), aircraft }
+    }
+}
+
+/// Updates the state of an aircraft from an SBS-1 message; other messages are ignored.
+fn update_aircraft(aircraft: &mut HashMap<u32, Aircraft>, message: &str) {
+    let fields: Vec<&str> = message.trim().split(',').collect();
+    if fields.len() < 17 || fields[0] != "MSG" { return; }
+    let icao = match u32::from_str_radix(fields[4], 16) {
+        Ok(icao) => icao,
+        Err(_) => return
+    };
+    let value = |i: usize| fields[i].parse::<f64>().ok();
+    let m_per_s = |speed: f64::Velocity| speed.get::<velocity::meter_per_second>();
+
+    let state = aircraft.entry(icao).or_default();
+    state.last_message = Some(Instant::now());
+    if let Some(altitude) = value(11) { state.altitude = Some(f64::Length::new::<length::foot>(altitude)); }
+    if let Some(speed) = value(12) { state.ground_speed = m_per_s(f64::Velocity::new::<velocity::knot>(speed)); }
+    if let Some(track) = value(13) { state.track_deg = track; }
+    if let (Some(lat), Some(lon)) = (value(14), value(15)) { state.lat_lon = Some((lat, lon)); }
+    if let Some(rate) = value(16) {
+        state.vertical_speed = m_per_s(f64::Velocity::new::<velocity::foot_per_minute>(rate));
+    }
+}
+
+impl TargetSource for AdsbInput {
+    fn name(&self) -> String { format!("ADS-B input {}", self.address) }
+
+    fn update(&mut self, _dt: Duration) -> Vec<TargetState> {
+        let mut aircraft = self.aircraft.lock().unwrap();
+        aircraft.retain(|_, state| state.last_message.map_or(false, |t| t.elapsed() < AIRCRAFT_TIMEOUT));
+        aircraft.iter()
+            .filter_map(|(icao, state)| {
+                let (lat, lon) = state.lat_lon?;
+                let geo_pos = GeoPos{ lat_lon: LatLon::new(Deg(lat), Deg(lon)), elevation: state.altitude? };
+                Some(TargetState::from_geo(
+                    *icao,
+                    TargetKind::Aircraft,
+                    &geo_pos,
+                    Deg(state.track_deg),
+                    state.ground_speed,
+                    state.vertical_speed
+                ))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,342 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Compact binary framing of the mount and target feed protocols, served on dedicated ports (`BINARY_PORT_OFFSET`
+//! above the text ones).
+//!
+//! Every message is a frame: payload length (u32, little-endian) followed by the payload, a value encoded with
+//! postcard (https://postcard.jamesmunns.com/wire-format):
+//!
+//!   - mount clients send `BinaryRequest`s and receive `BinaryResponse`s; simulator-specific messages (see
+//!     `ext_protocol`) are carried as `BinaryExtMessage`s
+//!   - target feed clients receive `BinaryTargetInfo`s and send `BinarySubscription`s
+//!
+//! Binary traffic is not captured by the traffic monitor.
+
+use crate::workers::{
+    derotator::Derotator,
+    equatorial::PierSide,
+    ext_protocol::{ExtMessage, GuideDirection},
+    mount_model::Mount,
+    mount_protocol::{Codec, Request, Response, serve_client},
+    protocol_trace::{TraceFile, Traced},
+    target_subscription::{Region, Subscription, TargetKind, positive_rate}
+};
+use pointing_utils::{TargetInfoMessage, uom};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufRead, ErrorKind, Read, Write},
+    net::TcpListener,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration
+};
+use uom::{si::f64, si::{angle, angular_velocity, length}};
+
+/// Offset of the ports of the binary protocols relative to the corresponding text ones.
+pub const BINARY_PORT_OFFSET: u16 = 100;
+
+/// Longer frames are rejected as invalid.
+const MAX_FRAME_LEN: usize = 65536;
+
+#[derive(Deserialize, Serialize)]
+pub enum BinaryRequest {
+    GetPosition,
+    Slew{ axis1_deg_per_s: f64, axis2_deg_per_s: f64 },
+    Stop,
+    Ext(BinaryExtMessage)
+}
+
+/// Failure of a command; as in the text protocol's reply (see `mount_error`).
+#[derive(Deserialize, Serialize)]
+pub struct BinaryError {
+    pub code: u32,
+    pub details: String
+}
+
+#[derive(Deserialize, Serialize)]
+pub enum BinaryResponse {
+    Position{ axis1_deg: f64, axis2_deg: f64 },
+    Reply(Result<(), BinaryError>),
+    Ext(BinaryExtMessage)
+}
+
+#[derive(Deserialize, Serialize)]
+pub enum BinaryDerotator {
+    Off,
+    Auto,
+    Fixed{ rotation_deg: f64 }
+}
+
+/// Simulator-specific mount message; as `ExtMessage`, with plain values instead of quantities.
+#[derive(Deserialize, Serialize)]
+pub enum BinaryExtMessage {
+    GetPierSide,
+    /// `None` in alt-az mode.
+    PierSide(Option<PierSide>),
+    MeridianFlip,
+    PulseGuide{ direction: GuideDirection, duration_ms: u64 },
+    GetGuideRate,
+    GuideRate{ arcsec_per_s: f64 },
+    SetGuideRate{ arcsec_per_s: f64 },
+    Park,
+    Unpark,
+    GetParked,
+    Parked(bool),
+    Heartbeat,
+    /// 0 disables the failsafe.
+    SetHeartbeatTimeout{ timeout_ms: u64 },
+    SetDerotator(BinaryDerotator),
+    GetFieldRotation,
+    FieldRotation{ parallactic_angle_deg: f64, image_rotation_deg: f64 },
+    MoveFocuser(u32),
+    HaltFocuser,
+    GetFocuser,
+    Focuser{ position: u32, moving: bool },
+    SetFilter(u32),
+    GetFilter,
+    /// `None` while the wheel is moving.
+    Filter(Option<u32>),
+    GetFilterNames,
+    FilterNames(Vec<String>)
+}
+
+impl From<ExtMessage> for BinaryExtMessage {
+    fn from(msg: ExtMessage) -> BinaryExtMessage {
+        let arcsec_per_s = |rate: f64::AngularVelocity| rate.get::<angular_velocity::degree_per_second>() * 3600.0;
+        match msg {
+            ExtMessage::GetPierSide => BinaryExtMessage::GetPierSide,
+            ExtMessage::PierSide(side) => BinaryExtMessage::PierSide(side),
+            ExtMessage::MeridianFlip => BinaryExtMessage::MeridianFlip,
+            ExtMessage::PulseGuide{ direction, duration } =>
+                BinaryExtMessage::PulseGuide{ direction, duration_ms: duration.as_millis() as u64 },
+            ExtMessage::GetGuideRate => BinaryExtMessage::GetGuideRate,
+            ExtMessage::GuideRate(rate) => BinaryExtMessage::GuideRate{ arcsec_per_s: arcsec_per_s(rate) },
+            ExtMessage::SetGuideRate(rate) => BinaryExtMessage::SetGuideRate{ arcsec_per_s: arcsec_per_s(rate) },
+            ExtMessage::Park => BinaryExtMessage::Park,
+            ExtMessage::Unpark => BinaryExtMessage::Unpark,
+            ExtMessage::GetParked => BinaryExtMessage::GetParked,
+            ExtMessage::Parked(parked) => BinaryExtMessage::Parked(parked),
+            ExtMessage::Heartbeat => BinaryExtMessage::Heartbeat,
+            ExtMessage::SetHeartbeatTimeout(timeout) =>
+                BinaryExtMessage::SetHeartbeatTimeout{ timeout_ms: timeout.map_or(0, |t| t.as_millis() as u64) },
+            ExtMessage::SetDerotator(derotator) => BinaryExtMessage::SetDerotator(match derotator {
+                Derotator::Off => BinaryDerotator::Off,
+                Derotator::Auto => BinaryDerotator::Auto,
+                Derotator::Fixed(rotation) => BinaryDerotator::Fixed{ rotation_deg: rotation.get::<angle::degree>() }
+            }),
+            ExtMessage::GetFieldRotation => BinaryExtMessage::GetFieldRotation,
+            ExtMessage::FieldRotation{ parallactic_angle, image_rotation } => BinaryExtMessage::FieldRotation{
+                parallactic_angle_deg: parallactic_angle.get::<angle::degree>(),
+                image_rotation_deg: image_rotation.get::<angle::degree>()
+            },
+            ExtMessage::MoveFocuser(position) => BinaryExtMessage::MoveFocuser(position),
+            ExtMessage::HaltFocuser => BinaryExtMessage::HaltFocuser,
+            ExtMessage::GetFocuser => BinaryExtMessage::GetFocuser,
+            ExtMessage::Focuser{ position, moving } => BinaryExtMessage::Focuser{ position, moving },
+            ExtMessage::SetFilter(slot) => BinaryExtMessage::SetFilter(slot as u32),
+            ExtMessage::GetFilter => BinaryExtMessage::GetFilter,
+            ExtMessage::Filter(slot) => BinaryExtMessage::Filter(slot.map(|slot| slot as u32)),
+            ExtMessage::GetFilterNames => BinaryExtMessage::GetFilterNames,
+            ExtMessage::FilterNames(names) => BinaryExtMessage::FilterNames(names)
+        }
+    }
+}
+
+impl From<BinaryExtMessage> for ExtMessage {
+    fn from(msg: BinaryExtMessage) -> ExtMessage {
+        let arcsec_per_s =
+            |value: f64| f64::AngularVelocity::new::<angular_velocity::degree_per_second>(value / 3600.0);
+        let deg = f64::Angle::new::<angle::degree>;
+        match msg {
+            BinaryExtMessage::GetPierSide => ExtMessage::GetPierSide,
+            BinaryExtMessage::PierSide(side) => ExtMessage::PierSide(side),
+            BinaryExtMessage::MeridianFlip => ExtMessage::MeridianFlip,
+            BinaryExtMessage::PulseGuide{ direction, duration_ms } =>
+                ExtMessage::PulseGuide{ direction, duration: Duration::from_millis(duration_ms) },
+            BinaryExtMessage::GetGuideRate => ExtMessage::GetGuideRate,
+            BinaryExtMessage::GuideRate{ arcsec_per_s: rate } => ExtMessage::GuideRate(arcsec_per_s(rate)),
+            BinaryExtMessage::SetGuideRate{ arcsec_per_s: rate } => ExtMessage::SetGuideRate(arcsec_per_s(rate)),
+            BinaryExtMessage::Park => ExtMessage::Park,
+            BinaryExtMessage::Unpark => ExtMessage::Unpark,
+            BinaryExtMessage::GetParked => ExtMessage::GetParked,
+            BinaryExtMessage::Parked(parked) => ExtMessage::Parked(parked),
+            BinaryExtMessage::Heartbeat => ExtMessage::Heartbeat,
+            BinaryExtMessage::SetHeartbeatTimeout{ timeout_ms } => ExtMessage::SetHeartbeatTimeout(
+                if timeout_ms > 0 { Some(Duration::from_millis(timeout_ms)) } else { None }
+            ),
+            BinaryExtMessage::SetDerotator(derotator) => ExtMessage::SetDerotator(match derotator {
+                BinaryDerotator::Off => Derotator::Off,
+                BinaryDerotator::Auto => Derotator::Auto,
+                BinaryDerotator::Fixed{ rotation_deg } => Derotator::Fixed(deg(rotation_deg))
+            }),
+            BinaryExtMessage::GetFieldRotation => ExtMessage::GetFieldRotation,
+            BinaryExtMessage::FieldRotation{ parallactic_angle_deg, image_rotation_deg } => ExtMessage::FieldRotation{
+                parallactic_angle: deg(parallactic_angle_deg),
+                image_rotation: deg(image_rotation_deg)
+            },
+            BinaryExtMessage::MoveFocuser(position) => ExtMessage::MoveFocuser(position),
+            BinaryExtMessage::HaltFocuser => ExtMessage::HaltFocuser,
+            BinaryExtMessage::GetFocuser => ExtMessage::GetFocuser,
+            BinaryExtMessage::Focuser{ position, moving } => ExtMessage::Focuser{ position, moving },
+            BinaryExtMessage::SetFilter(slot) => ExtMessage::SetFilter(slot as usize),
+            BinaryExtMessage::GetFilter => ExtMessage::GetFilter,
+            BinaryExtMessage::Filter(slot) => ExtMessage::Filter(slot.map(|slot| slot as usize)),
+            BinaryExtMessage::GetFilterNames => ExtMessage::GetFilterNames,
+            BinaryExtMessage::FilterNames(names) => ExtMessage::FilterNames(names)
+        }
+    }
+}
+
+/// Target feed subscription; as the text protocol's `Subscription`, which describes the criteria.
+#[derive(Default, Deserialize, Serialize)]
+pub struct BinarySubscription {
+    pub ids: Option<Vec<u32>>,
+    pub kinds: Option<Vec<TargetKind>>,
+    /// Latitude min., longitude min., latitude max., longitude max. (deg).
+    pub region: Option<[f64; 4]>,
+    pub max_rate: Option<f64>,
+    pub rate: Option<f64>,
+    pub apparent_position: bool
+}
+
+/// Decodes the payload of a binary subscription message.
+pub(super) fn decode_subscription(payload: &[u8]) -> Result<Subscription, String> {
+    let s = postcard::from_bytes::<BinarySubscription>(payload).map_err(|e| format!("decoding error: {}", e))?;
+    Ok(Subscription{
+        ids: s.ids,
+        kinds: s.kinds,
+        region: s.region.map(|[lat_min, lon_min, lat_max, lon_max]| Region{
+            lat_min: cgmath::Deg(lat_min),
+            lon_min: cgmath::Deg(lon_min),
+            lat_max: cgmath::Deg(lat_max),
+            lon_max: cgmath::Deg(lon_max)
+        }),
+        max_rate: s.max_rate.map(|rate| positive_rate(rate, "max. rate")).transpose()?,
+        rate: s.rate.map(|rate| positive_rate(rate, "rate")).transpose()?,
+        apparent_position: s.apparent_position
+    })
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct BinaryTargetInfo {
+    /// Position relative to the observer (m; x points north, y west, z up).
+    pub position: [f64; 3],
+    /// Velocity (m/s) in the same frame as `position`.
+    pub velocity: [f64; 3],
+    /// Track (clockwise from north).
+    pub track_deg: f64,
+    pub altitude_m: f64,
+    /// Apparent (refracted) azimuth and altitude (deg); set if requested in the subscription.
+    pub apparent_position: Option<[f64; 2]>
+}
+
+/// Reads a frame; returns `Ok(None)` if the peer has disconnected.
+pub(super) fn read_frame<R: Read + ?Sized>(reader: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => (),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e)
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(std::io::Error::new(ErrorKind::InvalidData, format!("frame too long ({} bytes)", len)));
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+
+    Ok(Some(payload))
+}
+
+/// Returns `value` encoded as a frame.
+fn frame<T: Serialize>(value: &T) -> Vec<u8> {
+    // encoding to memory fails only for types not supported by postcard
+    let payload = postcard::to_allocvec(value).unwrap();
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    frame
+}
+
+/// Returns the frame of a target feed message; `apparent_position`: azimuth and altitude (deg).
+pub(super) fn target_frame(msg: &TargetInfoMessage, apparent_position: Option<(f64, f64)>) -> Vec<u8> {
+    let (p, v) = (msg.position.0, msg.velocity.0);
+    frame(&BinaryTargetInfo{
+        position: [p.x, p.y, p.z],
+        velocity: [v.x, v.y, v.z],
+        track_deg: msg.track.0,
+        altitude_m: msg.altitude.get::<length::meter>(),
+        apparent_position: apparent_position.map(|(azimuth, altitude)| [azimuth, altitude])
+    })
+}
+
+/// Binary mount protocol.
+pub struct BinaryCodec;
+
+impl Codec for BinaryCodec {
+    fn read_request(&mut self, reader: &mut dyn BufRead) -> std::io::Result<Option<Result<Request, String>>> {
+        let payload = match read_frame(reader)? {
+            Some(payload) => payload,
+            None => return Ok(None)
+        };
+        let deg_per_s = |value| f64::AngularVelocity::new::<angular_velocity::degree_per_second>(value);
+
+        Ok(Some(match postcard::from_bytes::<BinaryRequest>(&payload) {
+            Err(e) => Err(format!("error decoding mount message: {}", e)),
+            Ok(BinaryRequest::GetPosition) => Ok(Request::GetPosition),
+            Ok(BinaryRequest::Slew{ axis1_deg_per_s, axis2_deg_per_s })
+                if !axis1_deg_per_s.is_finite() || !axis2_deg_per_s.is_finite() => Err("invalid rate".to_string()),
+            Ok(BinaryRequest::Slew{ axis1_deg_per_s, axis2_deg_per_s }) =>
+                Ok(Request::Slew{ axis1: deg_per_s(axis1_deg_per_s), axis2: deg_per_s(axis2_deg_per_s) }),
+            Ok(BinaryRequest::Stop) => Ok(Request::Stop),
+            Ok(BinaryRequest::Ext(BinaryExtMessage::SetGuideRate{ arcsec_per_s }))
+                if !arcsec_per_s.is_finite() => Err("invalid rate".to_string()),
+            Ok(BinaryRequest::Ext(BinaryExtMessage::SetDerotator(BinaryDerotator::Fixed{ rotation_deg })))
+                if !rotation_deg.is_finite() => Err("invalid derotator setting".to_string()),
+            Ok(BinaryRequest::Ext(msg)) => Ok(Request::Ext(msg.into()))
+        }))
+    }
+
+    fn write_response(&mut self, response: Response, writer: &mut dyn Write) -> std::io::Result<()> {
+        let response = match response {
+            Response::Position(axis1, axis2) => BinaryResponse::Position{
+                axis1_deg: axis1.get::<angle::degree>(),
+                axis2_deg: axis2.get::<angle::degree>()
+            },
+            Response::Reply(result) => BinaryResponse::Reply(
+                result.map_err(|e| BinaryError{ code: e.code as u32, details: e.details })
+            ),
+            Response::Ext(msg) => BinaryResponse::Ext(msg.into())
+        };
+        // a single write, so that the length prefix is not sent in a separate segment
+        writer.write_all(&frame(&response))
+    }
+}
+
+/// Serves the binary mount protocol on `port`; if `trace_dir` is set, each connection is recorded there.
+pub fn mount_model_binary(mount: Arc<Mount>, port: u16, trace_dir: Option<PathBuf>) {
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => { log::error!("cannot serve binary mount protocol on port {}: {}", port, e); return; }
+    };
+
+    loop {
+        log::info!("waiting for binary protocol client on port {}", port);
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => { log::error!("error accepting mount client: {}", e); continue; }
+        };
+        log::info!("binary protocol client connected");
+
+        let trace = trace_dir.as_ref().and_then(|dir| TraceFile::create(dir, &format!("binary-{}", port)));
+        let mut reader = std::io::BufReader::new(Traced::new(stream.try_clone().unwrap(), trace.clone()));
+        let mut writer = Traced::new(stream, trace);
+        serve_client(&mut reader, &mut writer, &mount, &mut BinaryCodec);
+    }
+}
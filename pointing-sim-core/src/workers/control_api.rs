@@ -0,0 +1,213 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! HTTP control API (JSON) for external test harnesses.
+//!
+//! Endpoints (request and response bodies are JSON objects):
+//!
+//! - `GET /state`: mount states, motion of the default target, IDs of the current targets
+//! - `POST /targets`: spawns a target; body: `TargetDefinition` fields (unspecified ones take default values; if
+//! `id` is omitted or already in use, a free one is assigned)
+//! - `PUT /target-motion`: `speed_m_per_s`, `track_noise` of the default target
+//! - `PUT /mounts/<index>/parameters`: `wind_enabled`, `turbulence_rms_arcsec`, `gust_amplitude_arcsec`,
+//! `cone_arcsec`, `non_perpendicularity_arcsec`, `tube_flexure_arcsec`
+//! - `POST /mounts/<index>/stop`, `/park`, `/unpark`
+//! - `POST /faults`: `{"fault": "drop_feed", "duration_s": <s>}` or `{"fault": "stop_mount", "mount": <index>}`
+//!
+//! Unspecified parameters are left unchanged. Errors are reported as `{"error": <message>}`.
+
+use crate::workers::{Mount, TargetControl, TargetDefinition, TargetMotion};
+use pointing_utils::uom;
+use serde::Deserialize;
+use serde_json::json;
+use std::{io::Read, sync::{Arc, Mutex}, time::{Duration, Instant}};
+use tiny_http::{Header, Method, Response, Server};
+use uom::{si::f64, si::{angle, angular_velocity}};
+
+pub const CONTROL_API_PORT: u16 = 45506;
+
+/// Parts of the simulation controlled via the API.
+pub struct ControlledSimulation {
+    /// Mounts of the stations.
+    pub mounts: Vec<Arc<Mount>>,
+    pub target_motion: Arc<Mutex<TargetMotion>>,
+    pub target_control: Arc<Mutex<TargetControl>>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TargetMotionRequest {
+    speed_m_per_s: Option<f64>,
+    track_noise: Option<f64>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MountParameters {
+    wind_enabled: Option<bool>,
+    turbulence_rms_arcsec: Option<f64>,
+    gust_amplitude_arcsec: Option<f64>,
+    cone_arcsec: Option<f64>,
+    non_perpendicularity_arcsec: Option<f64>,
+    tube_flexure_arcsec: Option<f64>
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "fault", rename_all = "snake_case")]
+enum Fault {
+    /// The target feeds publish nothing for the given time.
+    DropFeed{ duration_s: f64 },
+    StopMount{ #[serde(default)] mount: usize }
+}
+
+/// Error response: HTTP status code and message.
+type ApiError = (u16, String);
+
+fn arcsec(value: f64) -> f64::Angle { f64::Angle::new::<angle::second>(value) }
+
+fn parse<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, ApiError> {
+    serde_json::from_str(body).map_err(|e| (400, e.to_string()))
+}
+
+fn mount(simulation: &ControlledSimulation, index: usize) -> Result<&Mount, ApiError> {
+    simulation.mounts.get(index).map(|mount| mount.as_ref()).ok_or((404, format!("no mount {}", index)))
+}
+
+fn mount_index(segment: &str) -> Result<usize, ApiError> {
+    segment.parse::<usize>().map_err(|_| (404, format!("invalid mount index: {}", segment)))
+}
+
+/// Returns the state of `mount` (also used by the WebSocket telemetry).
+pub(super) fn mount_state(mount: &Mount) -> serde_json::Value {
+    let state = mount.get();
+    json!({
+        "axis1_deg": state.axis1_pos.get::<angle::degree>(),
+        "axis2_deg": state.axis2_pos.get::<angle::degree>(),
+        "axis1_speed_deg_per_s": state.axis1_spd.get::<angular_velocity::degree_per_second>(),
+        "axis2_speed_deg_per_s": state.axis2_spd.get::<angular_velocity::degree_per_second>(),
+        "azimuth_deg": state.boresight_az.get::<angle::degree>(),
+        "altitude_deg": state.boresight_alt.get::<angle::degree>(),
+        "parked": mount.is_parked(),
+        "client": format!("{:?}", mount.client_status())
+    })
+}
+
+fn state(simulation: &ControlledSimulation) -> serde_json::Value {
+    let mounts: Vec<serde_json::Value> = simulation.mounts.iter().map(|mount| mount_state(mount)).collect();
+    let motion = *simulation.target_motion.lock().unwrap();
+    let control = simulation.target_control.lock().unwrap();
+
+    json!({
+        "mounts": mounts,
+        "target_motion": { "speed_m_per_s": motion.speed, "track_noise": motion.track_noise },
+        "target_ids": control.targets.iter().map(|target| target.id).collect::<Vec<u32>>(),
+        "feed_dropped": control.feed_dropped()
+    })
+}
+
+fn set_mount_parameters(mount: &Mount, parameters: MountParameters) {
+    let mut wind = mount.wind_settings();
+    if let Some(enabled) = parameters.wind_enabled { wind.enabled = enabled; }
+    if let Some(value) = parameters.turbulence_rms_arcsec { wind.turbulence_rms = arcsec(value); }
+    if let Some(value) = parameters.gust_amplitude_arcsec { wind.gust_amplitude = arcsec(value); }
+    mount.set_wind_settings(wind);
+
+    let mut errors = mount.pointing_errors();
+    if let Some(value) = parameters.cone_arcsec { errors.cone = arcsec(value); }
+    if let Some(value) = parameters.non_perpendicularity_arcsec { errors.non_perpendicularity = arcsec(value); }
+    if let Some(value) = parameters.tube_flexure_arcsec { errors.tube_flexure = arcsec(value); }
+    mount.set_pointing_errors(errors);
+}
+
+fn handle(
+    simulation: &ControlledSimulation,
+    method: &Method,
+    path: &str,
+    body: &str
+) -> Result<serde_json::Value, ApiError> {
+    let segments: Vec<&str> = path.split('?').next().unwrap_or("").trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (Method::Get, ["state"]) => return Ok(state(simulation)),
+
+        (Method::Post, ["targets"]) => {
+            let mut definition: serde_json::Value = parse(body)?;
+            // an unspecified ID shall be assigned by the target source (instead of defaulting to that of the
+            // default target)
+            if let Some(object) = definition.as_object_mut() { object.entry("id").or_insert(json!(0)); }
+            let definition: TargetDefinition =
+                serde_json::from_value(definition).map_err(|e| (400, e.to_string()))?;
+            simulation.target_control.lock().unwrap().spawn.push(definition);
+        },
+
+        (Method::Put, ["target-motion"]) => {
+            let request: TargetMotionRequest = parse(body)?;
+            let mut motion = simulation.target_motion.lock().unwrap();
+            if let Some(speed) = request.speed_m_per_s { motion.speed = speed; }
+            if let Some(track_noise) = request.track_noise { motion.track_noise = track_noise; }
+        },
+
+        (Method::Put, ["mounts", index, "parameters"]) => {
+            let mount = mount(simulation, mount_index(index)?)?;
+            set_mount_parameters(mount, parse(body)?);
+        },
+
+        (Method::Post, ["mounts", index, command]) => {
+            let mount = mount(simulation, mount_index(index)?)?;
+            match *command {
+                "stop" => mount.stop(),
+                "park" => mount.park(),
+                "unpark" => mount.unpark(),
+                _ => return Err((404, format!("unknown mount command: {}", command)))
+            }
+        },
+
+        (Method::Post, ["faults"]) => match parse(body)? {
+            Fault::DropFeed{ duration_s } => {
+                log::info!("control API: dropping target feed for {:.1} s", duration_s);
+                let until = Duration::try_from_secs_f64(duration_s.max(0.0)).ok()
+                    .and_then(|duration| Instant::now().checked_add(duration))
+                    .ok_or_else(|| (400, format!("invalid outage duration: {}", duration_s)))?;
+                simulation.target_control.lock().unwrap().feed_outage_until = Some(until);
+            },
+            Fault::StopMount{ mount: index } => mount(simulation, index)?.stop()
+        },
+
+        _ => return Err((404, format!("unknown endpoint: {} {}", method, path)))
+    }
+
+    Ok(json!({}))
+}
+
+/// Serves the control API on `port` (localhost only).
+pub fn control_api(simulation: ControlledSimulation, port: u16) {
+    let server = match Server::http(("127.0.0.1", port)) {
+        Ok(server) => server,
+        Err(e) => { log::error!("cannot start control API on port {}: {}", port, e); return; }
+    };
+    log::info!("serving control API on port {}", port);
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let result = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => handle(&simulation, request.method(), request.url(), &body),
+            Err(e) => Err((400, e.to_string()))
+        };
+        let (status, contents) = match result {
+            Ok(contents) => (200, contents),
+            Err((status, message)) => {
+                log::warn!("control API: {} {}: {}", request.method(), request.url(), message);
+                (status, json!({ "error": message }))
+            }
+        };
+        let response = Response::from_string(contents.to_string())
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(e) = request.respond(response) { log::error!("control API: error sending response: {}", e); }
+    }
+}
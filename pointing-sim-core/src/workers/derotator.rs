@@ -0,0 +1,70 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Field rotation of a camera on an alt-az mount and the (optional) derotator compensating it.
+//!
+//! Image rotations are counter-clockwise, relative to the alt-az frame (zenith up).
+
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Derotator {
+    /// The camera is fixed to the mount; the field rotates as the mount tracks.
+    Off,
+    /// The image is rotated by the parallactic angle, keeping celestial north up.
+    Auto,
+    /// The image is rotated by a fixed angle.
+    Fixed(f64::Angle)
+}
+
+impl Derotator {
+    /// Returns rotation of the image for the given parallactic angle at the boresight.
+    pub fn rotation(&self, parallactic_angle: f64::Angle) -> f64::Angle {
+        match self {
+            Derotator::Off => f64::Angle::new::<angle::degree>(0.0),
+            Derotator::Auto => parallactic_angle,
+            Derotator::Fixed(rotation) => *rotation
+        }
+    }
+}
+
+impl std::fmt::Display for Derotator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Derotator::Off => write!(f, "off"),
+            Derotator::Auto => write!(f, "auto"),
+            Derotator::Fixed(rotation) => write!(f, "{}", rotation.get::<angle::degree>())
+        }
+    }
+}
+
+impl std::str::FromStr for Derotator {
+    type Err = String;
+
+    /// Parses `off`, `auto` or a fixed rotation in degrees.
+    fn from_str(s: &str) -> Result<Derotator, String> {
+        match s {
+            "off" => Ok(Derotator::Off),
+            "auto" => Ok(Derotator::Auto),
+            _ => match s.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(Derotator::Fixed(f64::Angle::new::<angle::degree>(value))),
+                _ => Err(format!("invalid derotator setting: {}", s))
+            }
+        }
+    }
+}
+
+/// Returns the parallactic angle at the given position, i.e., the angle between the directions towards the zenith
+/// and towards the north celestial pole; negative east of the meridian.
+pub fn parallactic_angle(az: f64::Angle, alt: f64::Angle, latitude: f64::Angle) -> f64::Angle {
+    let (az, alt, lat) = (az.get::<angle::radian>(), alt.get::<angle::radian>(), latitude.get::<angle::radian>());
+    f64::Angle::new::<angle::radian>(
+        (-az.sin() * lat.cos()).atan2(alt.cos() * lat.sin() - alt.sin() * lat.cos() * az.cos())
+    )
+}
@@ -0,0 +1,145 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use crate::clock::Clock;
+use pointing_utils::uom;
+use rand::Rng;
+use rand_distr::{Distribution, Exp, StandardNormal};
+use std::{sync::Arc, time::{Duration, Instant}};
+use uom::{si::f64, si::angle};
+
+/// Gust rates above this are treated as equal to it.
+pub const MAX_GUSTS_PER_MINUTE: f64 = 600.0;
+
+/// Min. interval between consecutive gusts.
+const MIN_GUST_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Clone)]
+pub struct WindSettings {
+    pub enabled: bool,
+    /// RMS deviation caused by continuous turbulence.
+    pub turbulence_rms: f64::Angle,
+    /// Correlation time of turbulence; determines the corner frequency of its (first-order) spectrum.
+    pub turbulence_corr_time: Duration,
+    /// Mean number of gusts per minute.
+    pub gusts_per_minute: f64,
+    /// Max. deviation caused by a single gust.
+    pub gust_amplitude: f64::Angle,
+    pub gust_duration: Duration
+}
+
+impl Default for WindSettings {
+    fn default() -> WindSettings {
+        WindSettings{
+            enabled: false,
+            turbulence_rms: f64::Angle::new::<angle::second>(2.0),
+            turbulence_corr_time: Duration::from_millis(500),
+            gusts_per_minute: 4.0,
+            gust_amplitude: f64::Angle::new::<angle::second>(20.0),
+            gust_duration: Duration::from_secs(2)
+        }
+    }
+}
+
+struct Gust {
+    start: Instant,
+    /// Per-axis amplitudes (radians).
+    amplitude: [f64; 2]
+}
+
+/// Wind-induced angular deviations of both mount axes.
+pub struct WindDisturbance {
+    settings: WindSettings,
+    last_update: Instant,
+    /// Per-axis turbulence deviation (radians).
+    turbulence: [f64; 2],
+    gusts: Vec<Gust>,
+    next_gust: Instant,
+    clock: Arc<dyn Clock>
+}
+
+impl WindDisturbance {
+    pub fn new(settings: WindSettings, clock: Arc<dyn Clock>) -> WindDisturbance {
+        let now = clock.now();
+        let mut disturbance = WindDisturbance{
+            settings,
+            last_update: now,
+            turbulence: [0.0; 2],
+            gusts: vec![],
+            next_gust: now,
+            clock
+        };
+        disturbance.schedule_gust(now);
+        disturbance
+    }
+
+    pub fn settings(&self) -> &WindSettings { &self.settings }
+
+    pub fn set_settings(&mut self, settings: WindSettings) {
+        self.settings = settings;
+        let now = self.clock.now();
+        self.schedule_gust(now);
+    }
+
+    fn schedule_gust(&mut self, after: Instant) {
+        let rate = self.settings.gusts_per_minute.min(MAX_GUSTS_PER_MINUTE) / 60.0;
+        self.next_gust = if rate > 0.0 {
+            let interval = Duration::from_secs_f64(Exp::new(rate).unwrap().sample(&mut rand::thread_rng()));
+            after + interval.max(MIN_GUST_INTERVAL)
+        } else {
+            after + Duration::from_secs(3600 * 24 * 365)
+        };
+    }
+
+    /// Advances the disturbance to the current time and returns per-axis deviations.
+    pub fn update(&mut self) -> (f64::Angle, f64::Angle) {
+        if !self.settings.enabled {
+            self.last_update = self.clock.now();
+            return (f64::Angle::new::<angle::radian>(0.0), f64::Angle::new::<angle::radian>(0.0));
+        }
+
+        let now = self.clock.now();
+        let dt = now.saturating_duration_since(self.last_update).as_secs_f64();
+        self.last_update = now;
+
+        let mut rng = rand::thread_rng();
+
+        // Ornstein-Uhlenbeck process (exact discretization)
+        let tau = self.settings.turbulence_corr_time.as_secs_f64().max(1.0e-3);
+        let sigma = self.settings.turbulence_rms.get::<angle::radian>();
+        let decay = (-dt / tau).exp();
+        for value in &mut self.turbulence {
+            let noise: f64 = StandardNormal.sample(&mut rng);
+            *value = *value * decay + sigma * (1.0 - decay * decay).sqrt() * noise;
+        }
+
+        while now >= self.next_gust {
+            let amplitude = self.settings.gust_amplitude.get::<angle::radian>();
+            self.gusts.push(Gust{
+                start: self.next_gust,
+                amplitude: [rng.gen_range(-1.0..=1.0) * amplitude, rng.gen_range(-1.0..=1.0) * amplitude]
+            });
+            let last_gust = self.next_gust;
+            self.schedule_gust(last_gust);
+        }
+
+        let gust_duration = self.settings.gust_duration.as_secs_f64().max(1.0e-3);
+        self.gusts.retain(|gust| now.duration_since(gust.start).as_secs_f64() < gust_duration);
+
+        let mut deviation = self.turbulence;
+        for gust in &self.gusts {
+            // raised-cosine pulse
+            let phase = now.duration_since(gust.start).as_secs_f64() / gust_duration;
+            let shape = 0.5 * (1.0 - (2.0 * std::f64::consts::PI * phase).cos());
+            deviation[0] += gust.amplitude[0] * shape;
+            deviation[1] += gust.amplitude[1] * shape;
+        }
+
+        (f64::Angle::new::<angle::radian>(deviation[0]), f64::Angle::new::<angle::radian>(deviation[1]))
+    }
+}
@@ -0,0 +1,115 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use crate::config::AxisConfig;
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+/// Models the mechanical imperfections between the motor (where the encoder is located) and the axis output:
+/// backlash, periodic error of the worm gear, and finite encoder resolution.
+pub struct DriveTrain {
+    config: AxisConfig,
+    /// Output position without periodic error; `None` until the first update.
+    output: Option<f64::Angle>
+}
+
+impl DriveTrain {
+    pub fn new(config: AxisConfig) -> DriveTrain {
+        DriveTrain{ config, output: None }
+    }
+
+    /// Returns the true axis position corresponding to motor-side position `motor_pos`.
+    ///
+    /// Backlash is modeled as play: the output follows the motor only after the slack has been taken up.
+    pub fn output(&mut self, motor_pos: f64::Angle) -> f64::Angle {
+        let half_backlash = f64::Angle::new::<angle::second>(self.config.backlash_arcsec / 2.0);
+
+        let output = match self.output {
+            None => motor_pos,
+            Some(prev) => if prev < motor_pos - half_backlash {
+                motor_pos - half_backlash
+            } else if prev > motor_pos + half_backlash {
+                motor_pos + half_backlash
+            } else {
+                prev
+            }
+        };
+        self.output = Some(output);
+
+        output + self.periodic_error(motor_pos)
+    }
+
+    /// Returns the encoder reading (quantized to encoder resolution) of motor-side position `motor_pos`.
+    pub fn encoder_reading(&self, motor_pos: f64::Angle) -> f64::Angle {
+        let resolution = self.config.encoder_resolution_deg();
+        f64::Angle::new::<angle::degree>((motor_pos.get::<angle::degree>() / resolution).round() * resolution)
+    }
+
+    /// Periodic error has the period of one worm revolution.
+    fn periodic_error(&self, motor_pos: f64::Angle) -> f64::Angle {
+        let worm_angle = motor_pos.get::<angle::radian>() * self.config.gear_ratio;
+        f64::Angle::new::<angle::second>(self.config.periodic_error_arcsec * worm_angle.sin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deg(value: f64) -> f64::Angle { f64::Angle::new::<angle::degree>(value) }
+
+    fn arcsec(angle: f64::Angle) -> f64 { angle.get::<angle::second>() }
+
+    #[test]
+    fn encoder_reading_is_within_half_resolution() {
+        let drive_train = DriveTrain::new(AxisConfig::default());
+        let half_resolution = drive_train.config.encoder_resolution_deg() * 3600.0 / 2.0;
+        assert!(half_resolution < 1.0);
+
+        for i in 0..10_000 {
+            let pos = deg(-180.0 + i as f64 * 0.0360017);
+            let error = arcsec(drive_train.encoder_reading(pos) - pos).abs();
+            assert!(error <= half_resolution + 1.0e-9, "encoder error of {}\" at {:?}", error, pos);
+        }
+    }
+
+    #[test]
+    fn encoder_reading_is_exact_at_count_boundaries() {
+        let drive_train = DriveTrain::new(AxisConfig::default());
+        let resolution = drive_train.config.encoder_resolution_deg();
+        for count in [-1_000_000, -1, 0, 1, 12_345, 1_000_000] {
+            let pos = deg(count as f64 * resolution);
+            assert!(arcsec(drive_train.encoder_reading(pos) - pos).abs() < 1.0e-6);
+        }
+    }
+
+    #[test]
+    fn backlash_is_taken_up_on_reversal() {
+        let mut drive_train = DriveTrain::new(AxisConfig{ backlash_arcsec: 0.6, ..Default::default() });
+        assert!(arcsec(drive_train.output(deg(10.0)) - deg(10.0)).abs() < 1.0e-6);
+
+        // moving forward: the output lags by half of the backlash
+        let forward = drive_train.output(deg(11.0));
+        assert!((arcsec(deg(11.0) - forward) - 0.3).abs() < 1.0e-6);
+
+        // reversing by less than the backlash does not move the output
+        let reversed = drive_train.output(deg(11.0) - f64::Angle::new::<angle::second>(0.5));
+        assert!(arcsec(reversed - forward).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn periodic_error_has_configured_amplitude() {
+        let config = AxisConfig{ periodic_error_arcsec: 0.7, ..Default::default() };
+        let period_deg = 360.0 / config.gear_ratio;
+        let mut drive_train = DriveTrain::new(config);
+
+        // a quarter of the worm period maximizes sin(worm angle)
+        let pos = deg(period_deg / 4.0);
+        assert!((arcsec(drive_train.output(pos) - pos) - 0.7).abs() < 1.0e-6);
+    }
+}
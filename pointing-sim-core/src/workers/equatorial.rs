@@ -0,0 +1,108 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use pointing_utils::uom;
+use serde::{Deserialize, Serialize};
+use uom::{si::f64, si::angle};
+
+/// Side of the pier on which the telescope is located (German equatorial mount).
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum PierSide {
+    /// Normal pointing state; mechanical declination within [-90°, 90°].
+    East,
+    /// Pointing state "beyond the pole"; mechanical declination outside [-90°, 90°].
+    West
+}
+
+impl std::fmt::Display for PierSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self { PierSide::East => "east", PierSide::West => "west" })
+    }
+}
+
+impl std::str::FromStr for PierSide {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PierSide, String> {
+        match s {
+            "east" => Ok(PierSide::East),
+            "west" => Ok(PierSide::West),
+            _ => Err(format!("invalid pier side: {}", s))
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EquatorialSettings {
+    pub latitude: f64::Angle,
+    /// Max. hour angle past the meridian (on the east pier side) before an automatic flip is performed.
+    pub meridian_limit: f64::Angle,
+    pub auto_flip: bool
+}
+
+impl Default for EquatorialSettings {
+    fn default() -> EquatorialSettings {
+        EquatorialSettings{
+            latitude: deg(50.0),
+            meridian_limit: deg(5.0),
+            auto_flip: true
+        }
+    }
+}
+
+/// Mount geometry. In alt-az mode, axis 1 is azimuth and axis 2 is altitude; in equatorial mode, axis 1 is
+/// mechanical hour angle and axis 2 is mechanical declination.
+#[derive(Clone)]
+pub enum MountMode {
+    AltAz,
+    Equatorial(EquatorialSettings)
+}
+
+fn deg(value: f64) -> f64::Angle { f64::Angle::new::<angle::degree>(value) }
+
+/// Normalizes angle to (-180°, 180°].
+fn normalize(a: f64::Angle) -> f64::Angle {
+    let mut value = a.get::<angle::degree>() % 360.0;
+    if value > 180.0 { value -= 360.0; } else if value <= -180.0 { value += 360.0; }
+    deg(value)
+}
+
+/// Converts mechanical axis positions to (hour angle, declination, pier side).
+pub fn to_ha_dec(axis1_pos: f64::Angle, axis2_pos: f64::Angle) -> (f64::Angle, f64::Angle, PierSide) {
+    let axis2_deg = normalize(axis2_pos).get::<angle::degree>();
+    if axis2_deg.abs() <= 90.0 {
+        (normalize(axis1_pos), deg(axis2_deg), PierSide::East)
+    } else {
+        let dec = if axis2_deg > 0.0 { 180.0 - axis2_deg } else { -180.0 - axis2_deg };
+        (normalize(axis1_pos - deg(180.0)), deg(dec), PierSide::West)
+    }
+}
+
+/// Converts (hour angle, declination) to mechanical axis positions for the given pier side.
+pub fn to_mechanical(ha: f64::Angle, dec: f64::Angle, pier_side: PierSide) -> (f64::Angle, f64::Angle) {
+    match pier_side {
+        PierSide::East => (normalize(ha), dec),
+        PierSide::West => {
+            let dec_deg = dec.get::<angle::degree>();
+            (
+                normalize(ha + deg(180.0)),
+                deg(if dec_deg >= 0.0 { 180.0 - dec_deg } else { -180.0 - dec_deg })
+            )
+        }
+    }
+}
+
+/// Converts (hour angle, declination) to (azimuth, altitude); azimuth is measured from north towards east.
+pub fn to_az_alt(ha: f64::Angle, dec: f64::Angle, latitude: f64::Angle) -> (f64::Angle, f64::Angle) {
+    let (h, d, lat) = (ha.get::<angle::radian>(), dec.get::<angle::radian>(), latitude.get::<angle::radian>());
+
+    let alt = (d.sin() * lat.sin() + d.cos() * lat.cos() * h.cos()).asin();
+    let az = (-d.cos() * h.sin()).atan2(d.sin() * lat.cos() - d.cos() * lat.sin() * h.cos());
+
+    (f64::Angle::new::<angle::radian>(az), f64::Angle::new::<angle::radian>(alt))
+}
@@ -0,0 +1,277 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use crate::workers::{derotator::Derotator, equatorial::PierSide};
+use pointing_utils::uom;
+use serde::{Deserialize, Serialize};
+use uom::{si::f64, si::angle, si::angular_velocity};
+
+/// Direction of an ST-4 style guide pulse. North/south move axis 2 in positive/negative direction,
+/// west/east move axis 1 in positive/negative direction.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum GuideDirection {
+    North,
+    South,
+    East,
+    West
+}
+
+impl std::fmt::Display for GuideDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            GuideDirection::North => "north",
+            GuideDirection::South => "south",
+            GuideDirection::East => "east",
+            GuideDirection::West => "west"
+        })
+    }
+}
+
+impl std::str::FromStr for GuideDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<GuideDirection, String> {
+        match s {
+            "north" => Ok(GuideDirection::North),
+            "south" => Ok(GuideDirection::South),
+            "east" => Ok(GuideDirection::East),
+            "west" => Ok(GuideDirection::West),
+            _ => Err(format!("invalid guide direction: {}", s))
+        }
+    }
+}
+
+fn arcsec_per_s(value: f64) -> f64::AngularVelocity {
+    f64::AngularVelocity::new::<angular_velocity::degree_per_second>(value / 3600.0)
+}
+
+fn to_arcsec_per_s(value: f64::AngularVelocity) -> f64 {
+    value.get::<angular_velocity::degree_per_second>() * 3600.0
+}
+
+/// Simulator-specific mount messages not covered by `pointing_utils::MountSimulatorMessage`.
+///
+/// Text format: `<name>[;<argument>...]`, one message per line. Requests without a dedicated reply are answered
+/// with `MountSimulatorMessage::Reply`.
+#[derive(Clone, Debug)]
+pub enum ExtMessage {
+    GetPierSide,
+    /// Reply to `GetPierSide`; `None` in alt-az mode.
+    PierSide(Option<PierSide>),
+    MeridianFlip,
+    /// Duration is sent in milliseconds.
+    PulseGuide{ direction: GuideDirection, duration: std::time::Duration },
+    GetGuideRate,
+    /// Reply to `GetGuideRate`; rate is sent in arcseconds per second.
+    GuideRate(f64::AngularVelocity),
+    /// Rate is sent in arcseconds per second.
+    SetGuideRate(f64::AngularVelocity),
+    Park,
+    Unpark,
+    GetParked,
+    /// Reply to `GetParked`.
+    Parked(bool),
+    /// Keeps the connection alive when the client has no other commands to send.
+    Heartbeat,
+    /// Timeout is sent in milliseconds; 0 disables the failsafe.
+    SetHeartbeatTimeout(Option<std::time::Duration>),
+    /// Derotator is sent as `off`, `auto` or a fixed rotation in degrees.
+    SetDerotator(Derotator),
+    GetFieldRotation,
+    /// Reply to `GetFieldRotation`; angles are sent in degrees. Image rotation is counter-clockwise, relative
+    /// to the alt-az frame (zenith up).
+    FieldRotation{ parallactic_angle: f64::Angle, image_rotation: f64::Angle },
+    /// Moves the focuser to the given absolute position (steps).
+    MoveFocuser(u32),
+    HaltFocuser,
+    GetFocuser,
+    /// Reply to `GetFocuser`.
+    Focuser{ position: u32, moving: bool },
+    /// Selects filter wheel slot (0-based).
+    SetFilter(usize),
+    GetFilter,
+    /// Reply to `GetFilter`; `None` (sent as `moving`) while the wheel is moving.
+    Filter(Option<usize>),
+    GetFilterNames,
+    /// Reply to `GetFilterNames`; names are sent as separate arguments, in slot order.
+    FilterNames(Vec<String>)
+}
+
+impl std::fmt::Display for ExtMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtMessage::GetPierSide => writeln!(f, "get_pier_side"),
+            ExtMessage::PierSide(side) => match side {
+                Some(side) => writeln!(f, "pier_side;{}", side),
+                None => writeln!(f, "pier_side;none")
+            },
+            ExtMessage::MeridianFlip => writeln!(f, "meridian_flip"),
+            ExtMessage::PulseGuide{ direction, duration } =>
+                writeln!(f, "pulse_guide;{};{}", direction, duration.as_millis()),
+            ExtMessage::GetGuideRate => writeln!(f, "get_guide_rate"),
+            ExtMessage::GuideRate(rate) => writeln!(f, "guide_rate;{}", to_arcsec_per_s(*rate)),
+            ExtMessage::SetGuideRate(rate) => writeln!(f, "set_guide_rate;{}", to_arcsec_per_s(*rate)),
+            ExtMessage::Park => writeln!(f, "park"),
+            ExtMessage::Unpark => writeln!(f, "unpark"),
+            ExtMessage::GetParked => writeln!(f, "get_parked"),
+            ExtMessage::Parked(parked) => writeln!(f, "parked;{}", parked),
+            ExtMessage::Heartbeat => writeln!(f, "heartbeat"),
+            ExtMessage::SetHeartbeatTimeout(timeout) =>
+                writeln!(f, "set_heartbeat_timeout;{}", timeout.map_or(0, |t| t.as_millis())),
+            ExtMessage::SetDerotator(derotator) => writeln!(f, "set_derotator;{}", derotator),
+            ExtMessage::GetFieldRotation => writeln!(f, "get_field_rotation"),
+            ExtMessage::FieldRotation{ parallactic_angle, image_rotation } => writeln!(
+                f,
+                "field_rotation;{};{}",
+                parallactic_angle.get::<angle::degree>(),
+                image_rotation.get::<angle::degree>()
+            ),
+            ExtMessage::MoveFocuser(position) => writeln!(f, "move_focuser;{}", position),
+            ExtMessage::HaltFocuser => writeln!(f, "halt_focuser"),
+            ExtMessage::GetFocuser => writeln!(f, "get_focuser"),
+            ExtMessage::Focuser{ position, moving } => writeln!(f, "focuser;{};{}", position, moving),
+            ExtMessage::SetFilter(position) => writeln!(f, "set_filter;{}", position),
+            ExtMessage::GetFilter => writeln!(f, "get_filter"),
+            ExtMessage::Filter(position) => match position {
+                Some(position) => writeln!(f, "filter;{}", position),
+                None => writeln!(f, "filter;moving")
+            },
+            ExtMessage::GetFilterNames => writeln!(f, "get_filter_names"),
+            ExtMessage::FilterNames(names) => {
+                write!(f, "filter_names")?;
+                for name in names { write!(f, ";{}", name)?; }
+                writeln!(f)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ExtMessage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ExtMessage, String> {
+        let mut fields = s.trim().split(';');
+        let name = fields.next().unwrap_or("");
+        let args: Vec<&str> = fields.collect();
+
+        let expect_args = |n: usize| -> Result<(), String> {
+            if args.len() != n {
+                Err(format!("\"{}\" expects {} argument(s), got {}", name, n, args.len()))
+            } else {
+                Ok(())
+            }
+        };
+
+        match name {
+            "get_pier_side" => { expect_args(0)?; Ok(ExtMessage::GetPierSide) },
+
+            "pier_side" => {
+                expect_args(1)?;
+                match args[0] {
+                    "none" => Ok(ExtMessage::PierSide(None)),
+                    side => Ok(ExtMessage::PierSide(Some(side.parse::<PierSide>()?)))
+                }
+            },
+
+            "meridian_flip" => { expect_args(0)?; Ok(ExtMessage::MeridianFlip) },
+
+            "pulse_guide" => {
+                expect_args(2)?;
+                let direction = args[0].parse::<GuideDirection>()?;
+                let duration_ms = args[1].parse::<u64>().map_err(|e| format!("invalid duration: {}", e))?;
+                Ok(ExtMessage::PulseGuide{ direction, duration: std::time::Duration::from_millis(duration_ms) })
+            },
+
+            "get_guide_rate" => { expect_args(0)?; Ok(ExtMessage::GetGuideRate) },
+
+            "guide_rate" | "set_guide_rate" => {
+                expect_args(1)?;
+                let rate = args[0].parse::<f64>().map_err(|e| format!("invalid rate: {}", e))?;
+                if !rate.is_finite() { return Err(format!("invalid rate: {}", args[0])); }
+                let rate = arcsec_per_s(rate);
+                Ok(if name == "guide_rate" { ExtMessage::GuideRate(rate) } else { ExtMessage::SetGuideRate(rate) })
+            },
+
+            "park" => { expect_args(0)?; Ok(ExtMessage::Park) },
+
+            "unpark" => { expect_args(0)?; Ok(ExtMessage::Unpark) },
+
+            "get_parked" => { expect_args(0)?; Ok(ExtMessage::GetParked) },
+
+            "parked" => {
+                expect_args(1)?;
+                Ok(ExtMessage::Parked(args[0].parse::<bool>().map_err(|e| format!("invalid value: {}", e))?))
+            },
+
+            "heartbeat" => { expect_args(0)?; Ok(ExtMessage::Heartbeat) },
+
+            "set_heartbeat_timeout" => {
+                expect_args(1)?;
+                let timeout_ms = args[0].parse::<u64>().map_err(|e| format!("invalid timeout: {}", e))?;
+                Ok(ExtMessage::SetHeartbeatTimeout(
+                    if timeout_ms > 0 { Some(std::time::Duration::from_millis(timeout_ms)) } else { None }
+                ))
+            },
+
+            "set_derotator" => { expect_args(1)?; Ok(ExtMessage::SetDerotator(args[0].parse::<Derotator>()?)) },
+
+            "get_field_rotation" => { expect_args(0)?; Ok(ExtMessage::GetFieldRotation) },
+
+            "field_rotation" => {
+                expect_args(2)?;
+                let parse_deg = |s: &str| s.parse::<f64>()
+                    .map(f64::Angle::new::<angle::degree>)
+                    .map_err(|e| format!("invalid angle: {}", e));
+                Ok(ExtMessage::FieldRotation{
+                    parallactic_angle: parse_deg(args[0])?,
+                    image_rotation: parse_deg(args[1])?
+                })
+            },
+
+            "move_focuser" => {
+                expect_args(1)?;
+                Ok(ExtMessage::MoveFocuser(args[0].parse::<u32>().map_err(|e| format!("invalid position: {}", e))?))
+            },
+
+            "halt_focuser" => { expect_args(0)?; Ok(ExtMessage::HaltFocuser) },
+
+            "get_focuser" => { expect_args(0)?; Ok(ExtMessage::GetFocuser) },
+
+            "focuser" => {
+                expect_args(2)?;
+                Ok(ExtMessage::Focuser{
+                    position: args[0].parse::<u32>().map_err(|e| format!("invalid position: {}", e))?,
+                    moving: args[1].parse::<bool>().map_err(|e| format!("invalid value: {}", e))?
+                })
+            },
+
+            "set_filter" => {
+                expect_args(1)?;
+                Ok(ExtMessage::SetFilter(args[0].parse::<usize>().map_err(|e| format!("invalid position: {}", e))?))
+            },
+
+            "get_filter" => { expect_args(0)?; Ok(ExtMessage::GetFilter) },
+
+            "filter" => {
+                expect_args(1)?;
+                match args[0] {
+                    "moving" => Ok(ExtMessage::Filter(None)),
+                    position => Ok(ExtMessage::Filter(Some(
+                        position.parse::<usize>().map_err(|e| format!("invalid position: {}", e))?
+                    )))
+                }
+            },
+
+            "get_filter_names" => { expect_args(0)?; Ok(ExtMessage::GetFilterNames) },
+
+            "filter_names" => Ok(ExtMessage::FilterNames(args.iter().map(|name| name.to_string()).collect())),
+
+            _ => Err(format!("unknown message: {}", name))
+        }
+    }
+}
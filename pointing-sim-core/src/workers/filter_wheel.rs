@@ -0,0 +1,62 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Filter wheel; the selected filter determines the color transmission of the camera image.
+
+use crate::{clock::Clock, config::FilterWheelConfig, workers::mount_error::{ErrorCode, MountError}};
+use std::{sync::Arc, time::{Duration, Instant}};
+
+pub struct FilterWheel {
+    config: FilterWheelConfig,
+    /// Slot being moved to (or the current one, if the wheel is not moving).
+    target: usize,
+    /// Time when the wheel reaches `target`.
+    arrival: Instant,
+    clock: Arc<dyn Clock>
+}
+
+impl FilterWheel {
+    pub fn new(config: FilterWheelConfig, clock: Arc<dyn Clock>) -> FilterWheel {
+        FilterWheel{ config, target: 0, arrival: clock.now(), clock }
+    }
+
+    pub fn filter_names(&self) -> Vec<String> { self.config.filters.iter().map(|f| f.name.clone()).collect() }
+
+    /// Returns the current slot; `None` while moving.
+    pub fn position(&self) -> Option<usize> {
+        if self.clock.now() >= self.arrival { Some(self.target) } else { None }
+    }
+
+    pub fn set_position(&mut self, position: usize) -> Result<(), MountError> {
+        let num_slots = self.config.filters.len();
+        if position >= num_slots {
+            return Err(MountError::new(
+                ErrorCode::LimitViolation,
+                &format!("filter wheel has {} slot(s), requested: {}", num_slots, position)
+            ));
+        }
+        if self.position().is_none() {
+            return Err(MountError::new(ErrorCode::Busy, "filter wheel is moving"));
+        }
+
+        // the wheel turns in the shorter direction
+        let distance = (position as isize - self.target as isize).unsigned_abs();
+        let num_steps = distance.min(num_slots - distance);
+        self.arrival = self.clock.now() + Duration::from_secs_f64(num_steps as f64 * self.config.slot_change_time_s);
+        self.target = position;
+        Ok(())
+    }
+
+    /// Returns transmission in the red, green and blue channels; zero while moving (the light path is blocked).
+    pub fn transmission(&self) -> [f32; 3] {
+        match self.position() {
+            Some(position) => self.config.filters.get(position).map_or([1.0; 3], |filter| filter.transmission),
+            None => [0.0; 3]
+        }
+    }
+}
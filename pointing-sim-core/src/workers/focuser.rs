@@ -0,0 +1,83 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Stepper motor focuser with backlash; its position determines the defocus blur of the camera image.
+
+use crate::{clock::Clock, config::FocuserConfig, workers::mount_error::{ErrorCode, MountError}};
+use pointing_utils::uom;
+use std::{sync::Arc, time::Instant};
+use uom::{si::f64, si::angle};
+
+pub struct Focuser {
+    config: FocuserConfig,
+    /// Motor position (steps) at `start`.
+    start_pos: f64,
+    /// Position of the focusing element (steps) at `start`; lags the motor by up to `backlash_steps`.
+    start_element_pos: f64,
+    start: Instant,
+    /// Motor position being moved to.
+    target: f64,
+    clock: Arc<dyn Clock>
+}
+
+impl Focuser {
+    pub fn new(config: FocuserConfig, clock: Arc<dyn Clock>) -> Focuser {
+        let pos = config.initial_position.min(config.max_position) as f64;
+        Focuser{ config, start_pos: pos, start_element_pos: pos, start: clock.now(), target: pos, clock }
+    }
+
+    fn motor_position(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.start).as_secs_f64();
+        let distance = (self.config.speed * elapsed).min((self.target - self.start_pos).abs());
+        self.start_pos + distance * (self.target - self.start_pos).signum()
+    }
+
+    /// The motor moves monotonically since `start`, so the element's position is obtained by taking up the slack
+    /// once.
+    fn element_position(&self, now: Instant) -> f64 {
+        let motor_pos = self.motor_position(now);
+        self.start_element_pos.clamp(motor_pos - self.config.backlash_steps as f64, motor_pos)
+    }
+
+    /// Returns the (motor) position reported to clients.
+    pub fn position(&self) -> u32 { self.motor_position(self.clock.now()).round() as u32 }
+
+    pub fn is_moving(&self) -> bool {
+        self.config.speed * self.clock.since(self.start).as_secs_f64() < (self.target - self.start_pos).abs()
+    }
+
+    pub fn max_position(&self) -> u32 { self.config.max_position }
+
+    pub fn move_to(&mut self, position: u32) -> Result<(), MountError> {
+        if position > self.config.max_position {
+            return Err(MountError::new(
+                ErrorCode::LimitViolation,
+                &format!("focuser position {} exceeds the maximum of {}", position, self.config.max_position)
+            ));
+        }
+        self.start_motion(Some(position as f64));
+        Ok(())
+    }
+
+    pub fn halt(&mut self) { self.start_motion(None); }
+
+    /// Starts moving from the current position to `target`; `None` stops the motor.
+    fn start_motion(&mut self, target: Option<f64>) {
+        let now = self.clock.now();
+        self.start_element_pos = self.element_position(now);
+        self.start_pos = self.motor_position(now);
+        self.start = now;
+        self.target = target.unwrap_or(self.start_pos);
+    }
+
+    /// Returns the diameter of the defocused image of a point source.
+    pub fn defocus(&self) -> f64::Angle {
+        let error = (self.element_position(self.clock.now()) - self.config.best_focus as f64).abs();
+        f64::Angle::new::<angle::second>(error * self.config.defocus_arcsec_per_step)
+    }
+}
@@ -0,0 +1,361 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! gRPC server for strongly-typed clients, served alongside the text protocols; the schemas are defined in
+//! `proto/pointing_sim.proto`.
+//!
+//! `MountControl` requests are executed like the corresponding messages of the text protocol (see
+//! `mount_protocol`). `TargetTelemetry` streams the messages published by a station's ADS-B feed (see
+//! `target_source`), with directions as seen from the station.
+
+use cgmath::{EuclideanSpace, InnerSpace};
+use crate::units;
+use crate::workers::{
+    derotator::Derotator,
+    equatorial::PierSide,
+    ext_protocol::{ExtMessage, GuideDirection},
+    mount_model::{ClientId, Mount},
+    mount_protocol::{self, Request, Response},
+    target_source::{FeedListener, FeedMessage, TargetControl},
+    target_subscription::{Subscription, TargetKind}
+};
+use pointing_utils::uom;
+use std::{pin::Pin, sync::{Arc, Mutex}, time::Duration};
+use tokio_stream::{Stream, wrappers::ReceiverStream};
+use tonic::Status;
+use uom::{si::f64, si::{angle, angular_velocity, length}};
+
+// generated by the build script; not all of the generated items are used
+#[allow(dead_code)]
+mod proto { tonic::include_proto!("pointing_sim"); }
+
+use proto::{
+    mount_control_server::{MountControl, MountControlServer},
+    target_telemetry_server::{TargetTelemetry, TargetTelemetryServer}
+};
+
+pub const GRPC_PORT: u16 = 45508;
+
+/// Range of rates (Hz) of target updates.
+const MIN_TARGET_STREAM_RATE: f64 = 0.01;
+const MAX_TARGET_STREAM_RATE: f64 = 100.0;
+
+type GrpcResult<T> = Result<tonic::Response<T>, Status>;
+
+fn unexpected_response() -> Status { Status::internal("unexpected response of the mount") }
+
+struct MountService {
+    /// Mounts and the IDs under which gRPC requests are registered as client activity (all gRPC requests to a mount
+    /// count as a single client connection).
+    mounts: Vec<(Arc<Mount>, ClientId)>
+}
+
+fn arcsec_per_s(value: f64) -> f64::AngularVelocity {
+    f64::AngularVelocity::new::<angular_velocity::degree_per_second>(value / 3600.0)
+}
+
+fn invalid_enum_value(name: &str, value: i32) -> Status {
+    Status::invalid_argument(format!("invalid {}: {}", name, value))
+}
+
+impl MountService {
+    /// Executes the request like a message received from a text protocol client (including the client activity
+    /// bookkeeping).
+    fn execute(&self, mount: u32, request: Request) -> Result<Response, Status> {
+        let (mount, client) = self.mounts.get(mount as usize)
+            .ok_or_else(|| Status::not_found(format!("no mount {}", mount)))?;
+        mount.register_client_message(*client);
+        mount_protocol::execute(request, mount).ok_or_else(unexpected_response)
+    }
+
+    /// Executes a simulator-specific request answered with a dedicated reply.
+    fn query(&self, mount: u32, msg: ExtMessage) -> Result<ExtMessage, Status> {
+        match self.execute(mount, Request::Ext(msg))? {
+            Response::Ext(reply) => Ok(reply),
+            _ => Err(unexpected_response())
+        }
+    }
+
+    fn reply(&self, mount: u32, request: Request) -> GrpcResult<proto::Reply> {
+        match self.execute(mount, request)? {
+            Response::Reply(result) => Ok(tonic::Response::new(proto::Reply{
+                error: result.err().map(|e| proto::MountError{ code: e.code as u32, details: e.details })
+            })),
+            _ => Err(unexpected_response())
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl MountControl for MountService {
+    async fn get_position(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Position> {
+        match self.execute(request.get_ref().mount, Request::GetPosition)? {
+            Response::Position(axis1, axis2) => Ok(tonic::Response::new(proto::Position{
+                axis1_deg: axis1.get::<angle::degree>(),
+                axis2_deg: axis2.get::<angle::degree>()
+            })),
+            _ => Err(unexpected_response())
+        }
+    }
+
+    async fn slew(&self, request: tonic::Request<proto::SlewRequest>) -> GrpcResult<proto::Reply> {
+        let request = request.into_inner();
+        if !request.axis1_deg_per_s.is_finite() || !request.axis2_deg_per_s.is_finite() {
+            return Err(Status::invalid_argument(format!(
+                "invalid rate: {}, {}", request.axis1_deg_per_s, request.axis2_deg_per_s
+            )));
+        }
+        let rate = |value| f64::AngularVelocity::new::<angular_velocity::degree_per_second>(value);
+        self.reply(request.mount, Request::Slew{
+            axis1: rate(request.axis1_deg_per_s),
+            axis2: rate(request.axis2_deg_per_s)
+        })
+    }
+
+    async fn stop(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Reply> {
+        self.reply(request.get_ref().mount, Request::Stop)
+    }
+
+    async fn get_pier_side(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::PierSideReply> {
+        match self.query(request.get_ref().mount, ExtMessage::GetPierSide)? {
+            ExtMessage::PierSide(side) => Ok(tonic::Response::new(proto::PierSideReply{
+                side: match side {
+                    None => proto::PierSide::None,
+                    Some(PierSide::East) => proto::PierSide::East,
+                    Some(PierSide::West) => proto::PierSide::West
+                } as i32
+            })),
+            _ => Err(unexpected_response())
+        }
+    }
+
+    async fn meridian_flip(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Reply> {
+        self.reply(request.get_ref().mount, Request::Ext(ExtMessage::MeridianFlip))
+    }
+
+    async fn pulse_guide(&self, request: tonic::Request<proto::PulseGuideRequest>) -> GrpcResult<proto::Reply> {
+        let request = request.into_inner();
+        let direction = match proto::GuideDirection::try_from(request.direction) {
+            Ok(proto::GuideDirection::North) => GuideDirection::North,
+            Ok(proto::GuideDirection::South) => GuideDirection::South,
+            Ok(proto::GuideDirection::East) => GuideDirection::East,
+            Ok(proto::GuideDirection::West) => GuideDirection::West,
+            Err(_) => return Err(invalid_enum_value("guide direction", request.direction))
+        };
+        self.reply(request.mount, Request::Ext(ExtMessage::PulseGuide{
+            direction,
+            duration: Duration::from_millis(request.duration_ms as u64)
+        }))
+    }
+
+    async fn get_guide_rate(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::GuideRate> {
+        match self.query(request.get_ref().mount, ExtMessage::GetGuideRate)? {
+            ExtMessage::GuideRate(rate) => Ok(tonic::Response::new(proto::GuideRate{
+                arcsec_per_s: rate.get::<angular_velocity::degree_per_second>() * 3600.0
+            })),
+            _ => Err(unexpected_response())
+        }
+    }
+
+    async fn set_guide_rate(&self, request: tonic::Request<proto::SetGuideRateRequest>) -> GrpcResult<proto::Reply> {
+        let request = request.into_inner();
+        if !request.arcsec_per_s.is_finite() {
+            return Err(Status::invalid_argument(format!("invalid guide rate: {}", request.arcsec_per_s)));
+        }
+        self.reply(request.mount, Request::Ext(ExtMessage::SetGuideRate(arcsec_per_s(request.arcsec_per_s))))
+    }
+
+    async fn park(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Reply> {
+        self.reply(request.get_ref().mount, Request::Ext(ExtMessage::Park))
+    }
+
+    async fn unpark(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Reply> {
+        self.reply(request.get_ref().mount, Request::Ext(ExtMessage::Unpark))
+    }
+
+    async fn get_parked(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Parked> {
+        match self.query(request.get_ref().mount, ExtMessage::GetParked)? {
+            ExtMessage::Parked(parked) => Ok(tonic::Response::new(proto::Parked{ parked })),
+            _ => Err(unexpected_response())
+        }
+    }
+
+    async fn heartbeat(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Reply> {
+        self.reply(request.get_ref().mount, Request::Ext(ExtMessage::Heartbeat))
+    }
+
+    async fn set_heartbeat_timeout(
+        &self,
+        request: tonic::Request<proto::SetHeartbeatTimeoutRequest>
+    ) -> GrpcResult<proto::Reply> {
+        let request = request.into_inner();
+        let timeout = Some(Duration::from_millis(request.timeout_ms as u64)).filter(|_| request.timeout_ms > 0);
+        self.reply(request.mount, Request::Ext(ExtMessage::SetHeartbeatTimeout(timeout)))
+    }
+
+    async fn set_derotator(&self, request: tonic::Request<proto::SetDerotatorRequest>) -> GrpcResult<proto::Reply> {
+        let request = request.into_inner();
+        let derotator = match proto::DerotatorMode::try_from(request.mode) {
+            Ok(proto::DerotatorMode::Off) => Derotator::Off,
+            Ok(proto::DerotatorMode::Auto) => Derotator::Auto,
+            Ok(proto::DerotatorMode::Fixed) => {
+                let rotation = request.fixed_rotation_deg;
+                if !rotation.is_finite() {
+                    return Err(Status::invalid_argument(format!("invalid rotation: {}", rotation)));
+                }
+                Derotator::Fixed(f64::Angle::new::<angle::degree>(rotation))
+            },
+            Err(_) => return Err(invalid_enum_value("derotator mode", request.mode))
+        };
+        self.reply(request.mount, Request::Ext(ExtMessage::SetDerotator(derotator)))
+    }
+
+    async fn get_field_rotation(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::FieldRotation> {
+        match self.query(request.get_ref().mount, ExtMessage::GetFieldRotation)? {
+            ExtMessage::FieldRotation{ parallactic_angle, image_rotation } =>
+                Ok(tonic::Response::new(proto::FieldRotation{
+                    parallactic_angle_deg: parallactic_angle.get::<angle::degree>(),
+                    image_rotation_deg: image_rotation.get::<angle::degree>()
+                })),
+            _ => Err(unexpected_response())
+        }
+    }
+
+    async fn move_focuser(&self, request: tonic::Request<proto::MoveFocuserRequest>) -> GrpcResult<proto::Reply> {
+        let request = request.into_inner();
+        self.reply(request.mount, Request::Ext(ExtMessage::MoveFocuser(request.position)))
+    }
+
+    async fn halt_focuser(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Reply> {
+        self.reply(request.get_ref().mount, Request::Ext(ExtMessage::HaltFocuser))
+    }
+
+    async fn get_focuser(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Focuser> {
+        match self.query(request.get_ref().mount, ExtMessage::GetFocuser)? {
+            ExtMessage::Focuser{ position, moving } => Ok(tonic::Response::new(proto::Focuser{ position, moving })),
+            _ => Err(unexpected_response())
+        }
+    }
+
+    async fn set_filter(&self, request: tonic::Request<proto::SetFilterRequest>) -> GrpcResult<proto::Reply> {
+        let request = request.into_inner();
+        self.reply(request.mount, Request::Ext(ExtMessage::SetFilter(request.slot as usize)))
+    }
+
+    async fn get_filter(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::Filter> {
+        match self.query(request.get_ref().mount, ExtMessage::GetFilter)? {
+            ExtMessage::Filter(slot) => Ok(tonic::Response::new(proto::Filter{
+                moving: slot.is_none(),
+                slot: slot.unwrap_or(0) as u32
+            })),
+            _ => Err(unexpected_response())
+        }
+    }
+
+    async fn get_filter_names(&self, request: tonic::Request<proto::MountRequest>) -> GrpcResult<proto::FilterNames> {
+        match self.query(request.get_ref().mount, ExtMessage::GetFilterNames)? {
+            ExtMessage::FilterNames(names) => Ok(tonic::Response::new(proto::FilterNames{ names })),
+            _ => Err(unexpected_response())
+        }
+    }
+}
+
+fn target_kind(kind: TargetKind) -> proto::TargetKind {
+    match kind {
+        TargetKind::Aircraft => proto::TargetKind::Aircraft,
+        TargetKind::Helicopter => proto::TargetKind::Helicopter,
+        TargetKind::Balloon => proto::TargetKind::Balloon,
+        TargetKind::Drone => proto::TargetKind::Drone
+    }
+}
+
+fn target_update(messages: &[FeedMessage]) -> proto::TargetUpdate {
+    proto::TargetUpdate{
+        time: chrono::Utc::now().timestamp_micros() as f64 * 1.0e-6,
+        targets: messages.iter().map(|msg| {
+            // local frame of the station: x points north, y west, z up
+            let p = msg.message.position.0.to_vec();
+            proto::Target{
+                id: msg.id,
+                kind: target_kind(msg.kind) as i32,
+                lat_deg: msg.lat_lon.lat.0,
+                lon_deg: msg.lat_lon.lon.0,
+                elevation_m: msg.message.altitude.get::<length::meter>(),
+                track_deg: msg.message.track.0,
+                speed_m_per_s: msg.message.velocity.0.magnitude(),
+                azimuth_deg: units::azimuth(p).get::<angle::degree>().rem_euclid(360.0),
+                altitude_deg: p.z.atan2(p.x.hypot(p.y)).to_degrees(),
+                range_m: p.magnitude()
+            }
+        }).collect()
+    }
+}
+
+struct TargetService {
+    /// Ports of the stations' ADS-B feeds.
+    feed_ports: Vec<u16>,
+    target_control: Arc<Mutex<TargetControl>>
+}
+
+type TargetStream = Pin<Box<dyn Stream<Item = Result<proto::TargetUpdate, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl TargetTelemetry for TargetService {
+    type StreamTargetsStream = TargetStream;
+
+    async fn stream_targets(&self, request: tonic::Request<proto::TargetStreamRequest>) -> GrpcResult<TargetStream> {
+        let request = request.into_inner();
+        let port = *self.feed_ports.get(request.station as usize)
+            .ok_or_else(|| Status::not_found(format!("no station {}", request.station)))?;
+        if request.rate_hz.is_nan() || (request.rate_hz > 0.0 && request.rate_hz < MIN_TARGET_STREAM_RATE) {
+            return Err(Status::invalid_argument(format!(
+                "rate must be at least {} Hz: {}", MIN_TARGET_STREAM_RATE, request.rate_hz
+            )));
+        }
+        let subscription = Subscription{
+            rate: Some(request.rate_hz.min(MAX_TARGET_STREAM_RATE)).filter(|rate| *rate > 0.0),
+            ..Default::default()
+        };
+
+        let (feed_sender, feed_receiver) = crossbeam::channel::unbounded::<Vec<FeedMessage>>();
+        self.target_control.lock().unwrap().listeners.push(FeedListener{ port, subscription, sender: feed_sender });
+
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        // the feed's updates are received by a blocking thread, which ends (detaching the listener) once the client
+        // has disconnected
+        std::thread::spawn(move || {
+            for messages in feed_receiver {
+                if sender.blocking_send(Ok(target_update(&messages))).is_err() { break; }
+            }
+        });
+
+        Ok(tonic::Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+}
+
+/// Serves mount control of `stations` (mounts with the ports of their ADS-B feeds) and target telemetry published by
+/// the target source's feeds (attached via `target_control`) via gRPC on `port` (localhost only).
+pub fn grpc_server(stations: Vec<(Arc<Mount>, u16)>, target_control: Arc<Mutex<TargetControl>>, port: u16) {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => { log::error!("cannot start gRPC server: {}", e); return; }
+    };
+    let mount_service = MountService{
+        mounts: stations.iter().map(|(mount, _)| (Arc::clone(mount), mount.register_client_connection())).collect()
+    };
+    let target_service = TargetService{ feed_ports: stations.iter().map(|(_, port)| *port).collect(), target_control };
+
+    log::info!("serving gRPC on port {}", port);
+    let result = runtime.block_on(
+        tonic::transport::Server::builder()
+            .add_service(MountControlServer::new(mount_service))
+            .add_service(TargetTelemetryServer::new(target_service))
+            .serve(([127, 0, 0, 1], port).into())
+    );
+    if let Err(e) = result { log::error!("gRPC server on port {} failed: {}", port, e); }
+}
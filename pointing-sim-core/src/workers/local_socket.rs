@@ -0,0 +1,49 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Unix domain socket transports, for same-host clients which should not depend on free TCP ports.
+//!
+//! Windows named pipes are not supported; on Windows, same-host clients have to use the TCP ports.
+
+use crate::workers::{
+    mount_model::Mount,
+    mount_protocol::{TextCodec, serve_client},
+    protocol_trace::{TraceFile, Traced}
+};
+use std::{os::unix::net::UnixListener, path::{Path, PathBuf}, sync::Arc};
+
+/// Binds a listener at `path`, replacing a socket file left over by a previous run.
+pub fn bind(path: &Path) -> std::io::Result<UnixListener> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path)
+}
+
+/// Serves the mount protocol on a Unix domain socket at `path`; if `trace_dir` is set, each connection is recorded
+/// there.
+pub fn mount_model_local_socket(mount: Arc<Mount>, path: PathBuf, trace_dir: Option<PathBuf>) {
+    let listener = match bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => { log::error!("cannot listen on {}: {}", path.display(), e); return; }
+    };
+
+    loop {
+        log::info!("waiting for mount client on {}", path.display());
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => { log::error!("error accepting mount client: {}", e); continue; }
+        };
+        log::info!("client connected via local socket");
+
+        let trace = trace_dir.as_ref().and_then(|dir| TraceFile::create(dir, "local-socket"));
+        let mut reader = std::io::BufReader::new(Traced::new(stream.try_clone().unwrap(), trace.clone()));
+        let mut writer = Traced::new(stream, trace);
+        serve_client(&mut reader, &mut writer, &mount, &mut TextCodec);
+    }
+}
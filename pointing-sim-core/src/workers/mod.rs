@@ -0,0 +1,101 @@
+mod adsb_cpr;
+mod adsb_input;
+mod binary_protocol;
+mod control_api;
+mod derotator;
+mod disturbance;
+mod drive_train;
+mod equatorial;
+mod filter_wheel;
+mod focuser;
+mod ext_protocol;
+#[cfg(feature = "grpc")]
+mod grpc_server;
+#[cfg(unix)]
+mod local_socket;
+mod motion_log;
+mod mount_error;
+mod mount_model;
+mod mount_persistence;
+mod mount_protocol;
+mod pointing_model;
+mod protocol_diagnostics;
+mod protocol_trace;
+#[cfg(unix)]
+mod serial_transport;
+mod session;
+mod source_manager;
+mod status;
+mod structural_mode;
+mod synthetic_targets;
+mod target_receiver;
+mod target_replay;
+mod target_source;
+mod target_subscription;
+mod target_swap;
+mod traffic_monitor;
+mod video_stream;
+mod weather;
+mod websocket_telemetry;
+
+pub use adsb_input::AdsbInput;
+pub use binary_protocol::{
+    BINARY_PORT_OFFSET,
+    BinaryDerotator,
+    BinaryError,
+    BinaryExtMessage,
+    BinaryRequest,
+    BinaryResponse,
+    BinarySubscription,
+    BinaryTargetInfo,
+    mount_model_binary
+};
+pub use control_api::{CONTROL_API_PORT, ControlledSimulation, control_api};
+pub use derotator::Derotator;
+pub use disturbance::{MAX_GUSTS_PER_MINUTE, WindSettings};
+pub use equatorial::{EquatorialSettings, MountMode};
+#[cfg(feature = "grpc")]
+pub use grpc_server::{GRPC_PORT, grpc_server};
+pub use motion_log::{
+    DEFAULT_MOTION_LOG_RATE,
+    MAX_MOTION_LOG_RATE,
+    MIN_MOTION_LOG_RATE,
+    convert_motion_log,
+    motion_log
+};
+pub use mount_model::{ClientStatus, MOUNT_SERVER_PORT, Mount, MountState, mount_model};
+pub use mount_protocol::{execute_message, message_templates};
+pub use protocol_trace::replay_trace;
+#[cfg(unix)]
+pub use local_socket::mount_model_local_socket;
+#[cfg(unix)]
+pub use serial_transport::mount_model_pty;
+pub use session::{Playback, Session, SessionReplay, TrafficSample, session_recorder, session_replay};
+pub use source_manager::{SourceManager, TargetSource, TargetState};
+pub use status::{StatusSender, StatusUpdate};
+pub use target_receiver::target_receiver;
+pub use target_replay::TargetReplay;
+pub use target_subscription::{Subscription, TargetKind};
+pub use target_source::{
+    DEFAULT_TARGET_MOTION,
+    FeedListener,
+    FeedMessage,
+    Site,
+    TARGET_SOURCE_PORT,
+    TargetControl,
+    TargetDefinition,
+    TargetMotion,
+    TargetSourceOptions,
+    target_source
+};
+pub use traffic_monitor::{TrafficEntry, TrafficMonitor};
+pub use video_stream::{VIDEO_STREAM_PORT, VideoFrame, VideoStreamSettings, video_stream};
+pub use weather::{WEATHER_FORECAST_PORT, Weather, weather_forecast_feed};
+pub use websocket_telemetry::{
+    DEFAULT_WEBSOCKET_TELEMETRY_RATE,
+    MIN_WEBSOCKET_TELEMETRY_RATE,
+    TelemetryStation,
+    TrackedTarget,
+    WEBSOCKET_TELEMETRY_PORT,
+    websocket_telemetry
+};
@@ -0,0 +1,147 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Continuous logging of per-axis motion (commanded vs. actual) for analysis of mount controllers in external tools.
+//!
+//! Motion log format (little-endian):
+//!
+//!   - magic bytes `MOTION_LOG_MAGIC`
+//!   - records, each consisting of:
+//!     - time since the start of logging (µs): u64
+//!     - for axis 1, then axis 2, `NUM_AXIS_VALUES` f64 values in the order of `AxisMotion`'s fields
+//!       (degrees, seconds)
+//!
+//! Use `convert_motion_log` to obtain a CSV file.
+
+use crate::workers::mount_model::Mount;
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant}
+};
+
+const MOTION_LOG_MAGIC: &[u8; 8] = b"PSMOTN01";
+
+const NUM_AXIS_VALUES: usize = 5;
+
+pub const DEFAULT_MOTION_LOG_RATE: f64 = 100.0;
+
+/// Range (Hz) of accepted motion log rates.
+pub const MIN_MOTION_LOG_RATE: f64 = 0.01;
+pub const MAX_MOTION_LOG_RATE: f64 = 10_000.0;
+
+/// Records are flushed to disk at this interval.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Motion of a mount axis (motor side, i.e., without drive train imperfections and disturbances).
+#[derive(Copy, Clone, Debug)]
+pub struct AxisMotion {
+    /// Position the axis would have if it had reached the commanded rate instantly (deg).
+    pub commanded_pos: f64,
+    /// Commanded rate, including guide pulses (deg/s).
+    pub commanded_spd: f64,
+    /// Deg.
+    pub pos: f64,
+    /// Deg/s.
+    pub spd: f64,
+    /// Deg/s².
+    pub accel: f64
+}
+
+impl AxisMotion {
+    fn values(&self) -> [f64; NUM_AXIS_VALUES] {
+        [self.commanded_pos, self.commanded_spd, self.pos, self.spd, self.accel]
+    }
+}
+
+/// Logs motion of `mount`'s axes at `rate` (Hz; between `MIN_MOTION_LOG_RATE` and `MAX_MOTION_LOG_RATE`) into a new
+/// file in `dir`; does not return.
+pub fn motion_log(mount: Arc<Mount>, dir: &Path, rate: f64) {
+    let path = dir.join(format!(
+        "motion-{}-{}.bin", mount.instance() + 1, chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")
+    ));
+    let mut file = match std::fs::create_dir_all(dir).and_then(|_| File::create(&path)) {
+        Ok(file) => BufWriter::new(file),
+        Err(e) => { log::error!("failed to create motion log {}: {}", path.display(), e); return; }
+    };
+    if let Err(e) = file.write_all(MOTION_LOG_MAGIC) {
+        log::error!("failed to write to motion log: {}", e);
+        return;
+    }
+    log::info!("logging mount motion to {}", path.display());
+
+    let interval = match Duration::try_from_secs_f64(1.0 / rate.clamp(MIN_MOTION_LOG_RATE, MAX_MOTION_LOG_RATE)) {
+        Ok(interval) => interval,
+        Err(_) => { log::error!("invalid motion log rate: {}", rate); return; }
+    };
+    let start = Instant::now();
+    let mut next_sample = start;
+    let mut last_flush = start;
+    loop {
+        let motion = mount.axis_motion();
+        let mut record = Vec::with_capacity(8 * (1 + 2 * NUM_AXIS_VALUES));
+        record.extend_from_slice(&(start.elapsed().as_micros() as u64).to_le_bytes());
+        for value in motion.iter().flat_map(|axis| axis.values()) {
+            record.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut result = file.write_all(&record);
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            result = result.and_then(|_| file.flush());
+            last_flush = Instant::now();
+        }
+        if let Err(e) = result {
+            log::error!("failed to write to motion log, logging stopped: {}", e);
+            return;
+        }
+
+        // keep a fixed sampling grid regardless of the time spent writing
+        next_sample += interval;
+        let now = Instant::now();
+        if next_sample > now {
+            std::thread::sleep(next_sample - now);
+        } else {
+            next_sample = now;
+        }
+    }
+}
+
+/// Converts motion log `input` to CSV file `output`.
+pub fn convert_motion_log(input: &Path, output: &Path) -> Result<(), String> {
+    let mut reader = BufReader::new(File::open(input).map_err(|e| e.to_string())?);
+    let mut magic = [0u8; MOTION_LOG_MAGIC.len()];
+    reader.read_exact(&mut magic).map_err(|e| e.to_string())?;
+    if &magic != MOTION_LOG_MAGIC { return Err("not a motion log".into()); }
+
+    let mut writer = BufWriter::new(File::create(output).map_err(|e| e.to_string())?);
+    let mut header = vec!["time_s".to_string()];
+    for axis in 1..=2 {
+        for name in ["cmd_pos_deg", "cmd_spd_deg_s", "pos_deg", "spd_deg_s", "accel_deg_s2"] {
+            header.push(format!("axis{}_{}", axis, name));
+        }
+    }
+    writeln!(writer, "{}", header.join(",")).map_err(|e| e.to_string())?;
+
+    let mut record = [0u8; 8 * (1 + 2 * NUM_AXIS_VALUES)];
+    loop {
+        match reader.read_exact(&mut record) {
+            Ok(()) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.to_string())
+        }
+        let mut fields = record.chunks_exact(8).map(|chunk| <[u8; 8]>::try_from(chunk).unwrap());
+        let mut line = format!("{:.6}", u64::from_le_bytes(fields.next().unwrap()) as f64 * 1.0e-6);
+        for value in fields.map(f64::from_le_bytes) {
+            line += &format!(",{}", value);
+        }
+        writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
+    }
+
+    writer.flush().map_err(|e| e.to_string())
+}
@@ -0,0 +1,73 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Errors of mount commands.
+//!
+//! A failed request is answered with `MountSimulatorMessage::Reply(Err(<text>))`, where the text is
+//! `<code>;<name>;<details>`, e.g., `1;limit_violation;axis 1 limit reached`. Clients should branch on the numeric
+//! code (see `ErrorCode`); the name and details are informative only.
+
+/// Cause of a command's failure. The numeric values are part of the protocol and must not change.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ErrorCode {
+    /// The command would move an axis beyond its position limits.
+    LimitViolation = 1,
+    /// The mount is parked.
+    Parked = 2,
+    /// The requested rate exceeded the axis' max. rate; the command has been executed with the rate clamped.
+    RateClamped = 3,
+    /// The command cannot be executed at the moment (e.g., a guide pulse is in progress).
+    Busy = 4,
+    /// The client is not allowed to control the mount (e.g., it mirrors another mount).
+    Unauthorized = 5,
+    /// The command is not supported in the current mount mode.
+    InvalidMode = 6,
+    /// A command parameter is invalid (e.g., a rate or position is not a finite number).
+    InvalidArgument = 7
+}
+
+impl ErrorCode {
+    fn name(&self) -> &'static str {
+        match self {
+            ErrorCode::LimitViolation => "limit_violation",
+            ErrorCode::Parked => "parked",
+            ErrorCode::RateClamped => "rate_clamped",
+            ErrorCode::Busy => "busy",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::InvalidMode => "invalid_mode",
+            ErrorCode::InvalidArgument => "invalid_argument"
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MountError {
+    pub code: ErrorCode,
+    pub details: String
+}
+
+impl MountError {
+    pub fn new(code: ErrorCode, details: &str) -> MountError {
+        MountError{ code, details: details.to_string() }
+    }
+
+    /// Returns the text sent in `MountSimulatorMessage::Reply`.
+    pub fn to_reply_text(&self) -> String {
+        format!("{};{};{}", self.code as u32, self.code.name(), self.details)
+    }
+}
+
+impl std::fmt::Display for MountError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl From<MountError> for String {
+    fn from(error: MountError) -> String { error.to_string() }
+}
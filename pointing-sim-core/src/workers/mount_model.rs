@@ -0,0 +1,1065 @@
+use crate::{clock, clock::Clock, config::{AxisConfig, MountConfig}};
+use crate::workers::{
+    derotator,
+    derotator::Derotator,
+    disturbance::{WindDisturbance, WindSettings},
+    drive_train::DriveTrain,
+    equatorial,
+    equatorial::{MountMode, PierSide},
+    ext_protocol::GuideDirection,
+    filter_wheel::FilterWheel,
+    focuser::Focuser,
+    motion_log::AxisMotion,
+    mount_error::{ErrorCode, MountError},
+    mount_persistence,
+    mount_persistence::PersistentMountState,
+    mount_protocol::{TextCodec, serve_client},
+    protocol_trace::{TraceFile, Traced},
+    pointing_model::PointingErrors,
+    status::{StatusSender, StatusTimer, StatusUpdate},
+    structural_mode::StructuralMode,
+    traffic_monitor,
+    traffic_monitor::{Tapped, TrafficDirection, TrafficMonitor}
+};
+use pointing_utils::uom;
+use std::{net::TcpListener, path::PathBuf, sync::{Arc, Mutex, RwLock}};
+use uom::{si::f64, si::{angle, angular_acceleration, angular_velocity, time}};
+
+pub const MOUNT_SERVER_PORT: u16 = 45501;
+
+/// Sidereal rate in arcseconds per second.
+const SIDEREAL_RATE: f64 = 15.041;
+
+const LIMIT_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+const STATE_AUTOSAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+mod axis {
+    use super::*;
+
+    /// ST-4 style guide pulse; superimposed on the regular motion of the axis, with no acceleration limit.
+    struct GuidePulse {
+        start: std::time::Instant,
+        duration: std::time::Duration,
+        rate: f64::AngularVelocity
+    }
+
+    impl GuidePulse {
+        fn offset(&self, now: std::time::Instant) -> f64::Angle {
+            Into::<f64::Angle>::into(self.rate * time(now.saturating_duration_since(self.start).min(self.duration)))
+        }
+
+        fn is_active(&self, now: std::time::Instant) -> bool {
+            now.saturating_duration_since(self.start) < self.duration
+        }
+    }
+
+    pub struct Axis {
+        t0: std::time::Instant,
+        pos0: f64::Angle,
+        spd0: f64::AngularVelocity,
+        target_spd: f64::AngularVelocity,
+        accel_dt: f64::Time,
+        accel: f64::AngularAcceleration,
+        guide_pulses: Vec<GuidePulse>,
+        /// Total offset caused by completed guide pulses.
+        guide_offset: f64::Angle,
+        clock: Arc<dyn Clock>
+    }
+
+    impl Axis {
+        pub fn new(
+            pos: f64::Angle,
+            speed: f64::AngularVelocity,
+            accel: f64::AngularAcceleration,
+            clock: Arc<dyn Clock>
+        ) -> Axis {
+            Axis{
+                t0: clock.now(),
+                pos0: pos,
+                spd0: speed,
+                target_spd: speed,
+                accel_dt: time(std::time::Duration::from_secs(0)),
+                accel,
+                guide_pulses: vec![],
+                guide_offset: deg(0.0),
+                clock
+            }
+        }
+
+        pub fn state(&self) -> (f64::Angle, f64::AngularVelocity) {
+            let (mut pos, mut speed) = self.base_state();
+
+            let now = self.clock.now();
+            pos += self.guide_offset;
+            for pulse in &self.guide_pulses {
+                pos += pulse.offset(now);
+                if pulse.is_active(now) { speed += pulse.rate; }
+            }
+
+            (pos, speed)
+        }
+
+        /// Returns position and speed resulting from commanded speeds only (without guide pulses).
+        fn base_state(&self) -> (f64::Angle, f64::AngularVelocity) {
+            let dt = time(self.clock.since(self.t0));
+
+            let accel_sign = (self.target_spd - self.spd0).get::<angular_velocity::degree_per_second>().signum();
+            let accel = accel_sign * self.accel;
+
+            let speed = if dt < self.accel_dt {
+                self.spd0 + Into::<f64::AngularVelocity>::into(dt * accel)
+            } else {
+                self.target_spd
+            };
+
+            let pos_during_accel = |dt| {
+                self.pos0 + Into::<f64::Angle>::into(self.spd0 * dt) + Into::<f64::Angle>::into(accel * dt * dt / 2.0)
+            };
+
+            let pos = if dt < self.accel_dt {
+                pos_during_accel(dt)
+            } else {
+                pos_during_accel(self.accel_dt) + Into::<f64::Angle>::into((dt - self.accel_dt) * self.target_spd)
+            };
+
+            (pos, speed)
+        }
+
+        pub fn target_speed(&self) -> f64::AngularVelocity { self.target_spd }
+
+        /// Returns the target speed plus the rates of guide pulses in progress.
+        pub fn commanded_speed(&self) -> f64::AngularVelocity {
+            let now = self.clock.now();
+            self.guide_pulses.iter().filter(|p| p.is_active(now)).fold(self.target_spd, |spd, p| spd + p.rate)
+        }
+
+        /// Returns start, acceleration (deg/s²) and duration of the current acceleration phase.
+        pub fn acceleration_profile(&self) -> (std::time::Instant, f64, std::time::Duration) {
+            let accel_sign = (self.target_spd - self.spd0).get::<angular_velocity::degree_per_second>().signum();
+            (
+                self.t0,
+                accel_sign * self.accel.get::<angular_acceleration::degree_per_second_squared>(),
+                std::time::Duration::try_from_secs_f64(self.accel_dt.get::<time::second>())
+                    .unwrap_or(std::time::Duration::MAX)
+            )
+        }
+
+        pub fn motion(&self) -> AxisMotion {
+            let now = self.clock.now();
+            let dt = time(now.saturating_duration_since(self.t0));
+            let (pos, spd) = self.state();
+            let accel = if dt < self.accel_dt {
+                (self.target_spd - self.spd0).get::<angular_velocity::degree_per_second>().signum() * self.accel
+            } else {
+                deg_per_s_sq(0.0)
+            };
+
+            let mut commanded_pos = self.pos0 + Into::<f64::Angle>::into(self.target_spd * dt) + self.guide_offset;
+            let mut commanded_spd = self.target_spd;
+            for pulse in &self.guide_pulses {
+                commanded_pos += pulse.offset(now);
+                if pulse.is_active(now) { commanded_spd += pulse.rate; }
+            }
+
+            AxisMotion{
+                commanded_pos: commanded_pos.get::<angle::degree>(),
+                commanded_spd: commanded_spd.get::<angular_velocity::degree_per_second>(),
+                pos: pos.get::<angle::degree>(),
+                spd: spd.get::<angular_velocity::degree_per_second>(),
+                accel: accel.get::<angular_acceleration::degree_per_second_squared>()
+            }
+        }
+
+        pub fn set_target_speed(&mut self, target_spd: f64::AngularVelocity) {
+            let (pos0, spd0) = self.base_state();
+
+            self.t0 = self.clock.now();
+            self.pos0 = pos0;
+            self.spd0 = spd0;
+            self.target_spd = target_spd;
+            self.accel_dt = (self.target_spd - self.spd0).abs() / self.accel;
+        }
+
+        /// Moves the axis instantly to `pos`; if `mirror` is set, direction of motion is reversed.
+        /// Guide pulses in progress are cancelled.
+        pub fn relocate(&mut self, pos: f64::Angle, mirror: bool) {
+            let (_, spd0) = self.base_state();
+            let sign = if mirror { -1.0 } else { 1.0 };
+
+            self.t0 = self.clock.now();
+            self.pos0 = pos;
+            self.spd0 = spd0 * sign;
+            self.target_spd = self.target_spd * sign;
+            self.accel_dt = (self.target_spd - self.spd0).abs() / self.accel;
+            self.guide_pulses.clear();
+            self.guide_offset = deg(0.0);
+        }
+
+        /// Moves the axis instantly to `pos` and sets its speed to `speed` (without acceleration).
+        /// Guide pulses in progress are cancelled.
+        pub fn set_state(&mut self, pos: f64::Angle, speed: f64::AngularVelocity) {
+            self.t0 = self.clock.now();
+            self.pos0 = pos;
+            self.spd0 = speed;
+            self.target_spd = speed;
+            self.accel_dt = time(std::time::Duration::from_secs(0));
+            self.guide_pulses.clear();
+            self.guide_offset = deg(0.0);
+        }
+
+        pub fn is_guiding(&self) -> bool {
+            let now = self.clock.now();
+            self.guide_pulses.iter().any(|p| p.is_active(now))
+        }
+
+        pub fn guide_pulse(&mut self, rate: f64::AngularVelocity, duration: std::time::Duration) {
+            let now = self.clock.now();
+            let (completed, active): (Vec<_>, Vec<_>) =
+                std::mem::take(&mut self.guide_pulses).into_iter().partition(|p| !p.is_active(now));
+            for pulse in &completed {
+                self.guide_offset += pulse.offset(now);
+            }
+            self.guide_pulses = active;
+
+            self.guide_pulses.push(GuidePulse{ start: now, duration, rate });
+        }
+
+        /// Ends guide pulses in progress; the offset they have caused so far is kept.
+        pub fn cancel_guide_pulses(&mut self) {
+            let now = self.clock.now();
+            for pulse in std::mem::take(&mut self.guide_pulses) {
+                self.guide_offset += pulse.offset(now);
+            }
+        }
+    }
+}
+use axis::Axis;
+
+pub struct MountState {
+    pub axis1_pos: f64::Angle,
+    pub axis2_pos: f64::Angle,
+    pub axis1_spd: f64::AngularVelocity,
+    pub axis2_spd: f64::AngularVelocity,
+    /// True boresight azimuth (computed from axis positions with pointing errors applied).
+    pub boresight_az: f64::Angle,
+    /// True boresight altitude (computed from axis positions with pointing errors applied).
+    pub boresight_alt: f64::Angle,
+    /// Pier side (equatorial mode only).
+    pub pier_side: Option<PierSide>,
+    /// Parallactic angle at the boresight.
+    pub parallactic_angle: f64::Angle,
+    /// Counter-clockwise rotation of the camera image relative to the alt-az frame (zenith up); in equatorial mode
+    /// the camera follows the equatorial frame, otherwise it is determined by the derotator.
+    pub camera_roll: f64::Angle,
+    /// Diameter of the defocused image of a point source.
+    pub defocus: f64::Angle,
+    /// Transmission of the selected filter in the red, green and blue channels.
+    pub filter_transmission: [f32; 3]
+}
+
+struct PrivState {
+    axis1: Axis,
+    axis2: Axis
+}
+
+impl PrivState {
+    pub fn new(config: &MountConfig, clock: &Arc<dyn Clock>) -> PrivState {
+        let accel1 = deg_per_s_sq(config.axis1.acceleration_deg_per_s2);
+        let accel2 = deg_per_s_sq(config.axis2.acceleration_deg_per_s2);
+        PrivState {
+            axis1: Axis::new(deg(0.0), deg_per_s(0.0), accel1, Arc::clone(clock)),
+            axis2: Axis::new(deg(0.0), deg_per_s(0.0), accel2, Arc::clone(clock)),
+        }
+    }
+}
+
+/// Returns true if moving the axis at `speed` would (further) exceed its position limits.
+fn violates_limits(pos: f64::Angle, speed: f64::AngularVelocity, config: &AxisConfig) -> bool {
+    let speed = speed.get::<angular_velocity::degree_per_second>();
+    config.min_pos_deg.map_or(false, |min| pos <= deg(min) && speed < 0.0)
+        || config.max_pos_deg.map_or(false, |max| pos >= deg(max) && speed > 0.0)
+}
+
+/// Clamps `speed` to the maximum rate of the axis.
+fn clamp_rate(speed: f64::AngularVelocity, config: &AxisConfig, axis_name: &str) -> f64::AngularVelocity {
+    let max_rate = deg_per_s(config.max_rate_deg_per_s);
+    if speed.abs() > max_rate {
+        log::debug!("{} rate clamped to {:.2}°/s", axis_name, config.max_rate_deg_per_s);
+        max_rate * speed.get::<angular_velocity::degree_per_second>().signum()
+    } else {
+        speed
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClientStatus {
+    NotConnected,
+    Connected,
+    /// The client stopped sending messages for longer than the heartbeat timeout; axes have been stopped.
+    TimedOut
+}
+
+impl std::fmt::Display for ClientStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ClientStatus::NotConnected => "not connected",
+            ClientStatus::Connected => "connected",
+            ClientStatus::TimedOut => "timed out (axes stopped)"
+        })
+    }
+}
+
+/// Identifies a client connection (of any transport) of a mount.
+pub(super) type ClientId = u64;
+
+/// Activity of a single client connection.
+struct ClientConnection {
+    /// Not set until the client has sent something (e.g., a gRPC client which has not sent any request yet).
+    last_activity: Option<std::time::Instant>,
+    /// Set if the client has been silent for longer than the heartbeat timeout.
+    timed_out: bool
+}
+
+/// Tracks client activity for the heartbeat failsafe; each connection is monitored separately, so that one client's
+/// messages do not keep the failsafe from firing for another one.
+struct ClientLink {
+    connections: std::collections::HashMap<ClientId, ClientConnection>,
+    next_id: ClientId,
+    heartbeat_timeout: Option<std::time::Duration>,
+    /// Number of messages received since the last status update.
+    num_messages: usize
+}
+
+impl ClientLink {
+    fn status(&self) -> ClientStatus {
+        if self.connections.values().any(|c| c.timed_out) {
+            ClientStatus::TimedOut
+        } else if self.connections.values().any(|c| c.last_activity.is_some()) {
+            ClientStatus::Connected
+        } else {
+            ClientStatus::NotConnected
+        }
+    }
+}
+
+pub struct Mount {
+    /// Number of the mount instance (0-based).
+    instance: usize,
+    config: MountConfig,
+    priv_state: RwLock<PrivState>,
+    pointing_errors: RwLock<PointingErrors>,
+    mode: RwLock<MountMode>,
+    /// Used for field rotation in alt-az mode (equatorial mode uses its own setting).
+    site_latitude: RwLock<f64::Angle>,
+    derotator: RwLock<Derotator>,
+    wind: Mutex<WindDisturbance>,
+    drive_trains: Mutex<[DriveTrain; 2]>,
+    structural_modes: Mutex<[StructuralMode; 2]>,
+    focuser: Mutex<Focuser>,
+    filter_wheel: Mutex<FilterWheel>,
+    guide_rate: RwLock<f64::AngularVelocity>,
+    parked: RwLock<bool>,
+    client: Mutex<ClientLink>,
+    /// Mount receiving copies of all commands (e.g., one with different parameters, for comparison).
+    mirror: RwLock<Option<Arc<Mount>>>,
+    /// Set if this mount is the mirror of another one.
+    is_mirror: RwLock<bool>,
+    /// Time source of the axis motion, wind disturbance, focuser and filter wheel.
+    clock: Arc<dyn Clock>
+}
+
+impl Mount {
+    pub fn new(config: MountConfig, instance: usize) -> Mount {
+        Mount::with_clock(config, instance, clock::system_clock())
+    }
+
+    /// Creates a mount whose axes move according to `clock` (e.g., a manually advanced one in tests).
+    pub fn with_clock(config: MountConfig, instance: usize, clock: Arc<dyn Clock>) -> Mount {
+        Mount{
+            instance,
+            priv_state: RwLock::new(PrivState::new(&config, &clock)),
+            pointing_errors: RwLock::new(PointingErrors{
+                cone: arcsec(config.cone_error_arcsec),
+                non_perpendicularity: arcsec(config.non_perpendicularity_arcsec),
+                tube_flexure: arcsec(config.tube_flexure_arcsec)
+            }),
+            mode: RwLock::new(MountMode::AltAz),
+            site_latitude: RwLock::new(deg(0.0)),
+            derotator: RwLock::new(Derotator::Off),
+            wind: Mutex::new(WindDisturbance::new(WindSettings::default(), Arc::clone(&clock))),
+            drive_trains: Mutex::new([DriveTrain::new(config.axis1.clone()), DriveTrain::new(config.axis2.clone())]),
+            structural_modes: Mutex::new([StructuralMode::new(&config.axis1), StructuralMode::new(&config.axis2)]),
+            focuser: Mutex::new(Focuser::new(config.focuser.clone(), Arc::clone(&clock))),
+            filter_wheel: Mutex::new(FilterWheel::new(config.filter_wheel.clone(), Arc::clone(&clock))),
+            guide_rate: RwLock::new(deg_per_s(config.guide_rate_sidereal * SIDEREAL_RATE / 3600.0)),
+            parked: RwLock::new(false),
+            client: Mutex::new(ClientLink{
+                connections: Default::default(),
+                next_id: 0,
+                heartbeat_timeout: config.heartbeat_timeout_s
+                    .filter(|timeout| *timeout > 0.0)
+                    .and_then(|timeout| std::time::Duration::try_from_secs_f64(timeout).ok()),
+                num_messages: 0
+            }),
+            mirror: RwLock::new(None),
+            is_mirror: RwLock::new(false),
+            config,
+            clock
+        }
+    }
+
+    /// Sets the mount to receive copies of all subsequent commands; it is first moved to this mount's axis
+    /// positions and speeds and park state.
+    pub fn set_mirror(&self, mirror: Option<Arc<Mount>>) {
+        if let Some(mirror) = &mirror {
+            let state = self.priv_state.read().unwrap();
+            let mut mirror_state = mirror.priv_state.write().unwrap();
+            let (axis1_pos, axis1_spd) = state.axis1.state();
+            let (axis2_pos, axis2_spd) = state.axis2.state();
+            mirror_state.axis1.set_state(axis1_pos, axis1_spd);
+            mirror_state.axis2.set_state(axis2_pos, axis2_spd);
+            *mirror.parked.write().unwrap() = self.is_parked();
+            *mirror.derotator.write().unwrap() = self.derotator();
+            *mirror.is_mirror.write().unwrap() = true;
+        }
+        let prev_mirror = std::mem::replace(&mut *self.mirror.write().unwrap(), mirror);
+        if let Some(prev_mirror) = prev_mirror { *prev_mirror.is_mirror.write().unwrap() = false; }
+    }
+
+    /// Returns true if the mount mirrors another one (and so must not be commanded by clients directly).
+    pub fn is_mirror(&self) -> bool { *self.is_mirror.read().unwrap() }
+
+    /// Passes a command to the mirror mount (if any).
+    fn mirrored<F: FnOnce(&Mount) -> Result<(), MountError>>(&self, command: F) {
+        if let Some(mirror) = &*self.mirror.read().unwrap() {
+            if let Err(e) = command(mirror) {
+                log::debug!("mirror mount: {}", e);
+            }
+        }
+    }
+
+    pub fn config(&self) -> &MountConfig { &self.config }
+
+    pub fn instance(&self) -> usize { self.instance }
+
+    /// Returns commanded and actual motion of both axes.
+    pub fn axis_motion(&self) -> [AxisMotion; 2] {
+        let priv_state = self.priv_state.read().unwrap();
+        [priv_state.axis1.motion(), priv_state.axis2.motion()]
+    }
+
+    pub fn slew(&self, axis1: f64::AngularVelocity, axis2: f64::AngularVelocity) -> Result<(), MountError> {
+        if !axis1.value.is_finite() || !axis2.value.is_finite() {
+            return Err(MountError::new(ErrorCode::InvalidArgument, "rate is not a finite number"));
+        }
+        self.mirrored(|mirror| mirror.slew(axis1, axis2));
+        if self.is_parked() {
+            return Err(MountError::new(ErrorCode::Parked, "mount is parked"));
+        }
+
+        let axis1 = clamp_rate(axis1, &self.config.axis1, "axis 1");
+        let axis2 = clamp_rate(axis2, &self.config.axis2, "axis 2");
+
+        let mut state = self.priv_state.write().unwrap();
+        if violates_limits(state.axis1.state().0, axis1, &self.config.axis1) {
+            return Err(MountError::new(ErrorCode::LimitViolation, "axis 1 limit reached"));
+        }
+        if violates_limits(state.axis2.state().0, axis2, &self.config.axis2) {
+            return Err(MountError::new(ErrorCode::LimitViolation, "axis 2 limit reached"));
+        }
+        state.axis1.set_target_speed(axis1);
+        state.axis2.set_target_speed(axis2);
+
+        Ok(())
+    }
+
+    /// Returns an error if any of the rates exceeds the axis' max. rate (and so would be clamped by `slew`).
+    pub fn check_max_rates(&self, axis1: f64::AngularVelocity, axis2: f64::AngularVelocity) -> Result<(), MountError> {
+        for (rate, config, name) in [(axis1, &self.config.axis1, "axis 1"), (axis2, &self.config.axis2, "axis 2")] {
+            if rate.abs() > deg_per_s(config.max_rate_deg_per_s) {
+                return Err(MountError{
+                    code: ErrorCode::RateClamped,
+                    details: format!("{} rate clamped to {:.2}°/s", name, config.max_rate_deg_per_s)
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves the axes instantly to the given positions and sets their speeds (e.g., to center and follow a target
+    /// without waiting for a slew).
+    pub fn snap_to(
+        &self,
+        axis1: (f64::Angle, f64::AngularVelocity),
+        axis2: (f64::Angle, f64::AngularVelocity)
+    ) -> Result<(), MountError> {
+        if [axis1.0.value, axis1.1.value, axis2.0.value, axis2.1.value].iter().any(|x| !x.is_finite()) {
+            return Err(MountError::new(ErrorCode::InvalidArgument, "position or rate is not a finite number"));
+        }
+        self.mirrored(|mirror| mirror.snap_to(axis1, axis2));
+        if self.is_parked() {
+            return Err(MountError::new(ErrorCode::Parked, "mount is parked"));
+        }
+
+        let outside_limits = |pos: f64::Angle, config: &AxisConfig| {
+            config.min_pos_deg.map_or(false, |min| pos < deg(min))
+                || config.max_pos_deg.map_or(false, |max| pos > deg(max))
+        };
+        if outside_limits(axis1.0, &self.config.axis1) {
+            return Err(MountError::new(ErrorCode::LimitViolation, "axis 1 position outside limits"));
+        }
+        if outside_limits(axis2.0, &self.config.axis2) {
+            return Err(MountError::new(ErrorCode::LimitViolation, "axis 2 position outside limits"));
+        }
+
+        let mut state = self.priv_state.write().unwrap();
+        state.axis1.set_state(axis1.0, clamp_rate(axis1.1, &self.config.axis1, "axis 1"));
+        state.axis2.set_state(axis2.0, clamp_rate(axis2.1, &self.config.axis2, "axis 2"));
+
+        Ok(())
+    }
+
+    /// Stops the axes; guide pulses in progress are cancelled.
+    pub fn stop(&self) {
+        self.mirrored(|mirror| { mirror.stop(); Ok(()) });
+        let mut state = self.priv_state.write().unwrap();
+        let PrivState{ axis1, axis2 } = &mut *state;
+        for axis in [axis1, axis2] {
+            axis.cancel_guide_pulses();
+            axis.set_target_speed(deg_per_s(0.0));
+        }
+    }
+
+    pub fn is_parked(&self) -> bool { *self.parked.read().unwrap() }
+
+    /// Stops the axes; further slews are rejected until the mount is unparked.
+    pub fn park(&self) {
+        self.mirrored(|mirror| { mirror.park(); Ok(()) });
+        self.stop();
+        *self.parked.write().unwrap() = true;
+        log::info!("mount parked");
+    }
+
+    pub fn unpark(&self) {
+        self.mirrored(|mirror| { mirror.unpark(); Ok(()) });
+        *self.parked.write().unwrap() = false;
+        log::info!("mount unparked");
+    }
+
+    fn persistent_state(&self) -> PersistentMountState {
+        let priv_state = self.priv_state.read().unwrap();
+        PersistentMountState{
+            axis1_pos_deg: priv_state.axis1.state().0.get::<angle::degree>(),
+            axis2_pos_deg: priv_state.axis2.state().0.get::<angle::degree>(),
+            parked: self.is_parked()
+        }
+    }
+
+    /// Restores axis positions and park state saved by a previous run, if any.
+    pub fn restore_state(&self) {
+        if let Some(state) = mount_persistence::load(self.instance) {
+            log::info!(
+                "restoring mount state: axis 1: {:.4}°, axis 2: {:.4}°, parked: {}",
+                state.axis1_pos_deg, state.axis2_pos_deg, state.parked
+            );
+            let mut priv_state = self.priv_state.write().unwrap();
+            priv_state.axis1 = Axis::new(
+                deg(state.axis1_pos_deg),
+                deg_per_s(0.0),
+                deg_per_s_sq(self.config.axis1.acceleration_deg_per_s2),
+                Arc::clone(&self.clock)
+            );
+            priv_state.axis2 = Axis::new(
+                deg(state.axis2_pos_deg),
+                deg_per_s(0.0),
+                deg_per_s_sq(self.config.axis2.acceleration_deg_per_s2),
+                Arc::clone(&self.clock)
+            );
+            *self.parked.write().unwrap() = state.parked;
+        }
+    }
+
+    /// Saves axis positions and park state; does nothing for a mirror mount, whose state follows the mirrored one
+    /// and must not overwrite the state saved for its instance.
+    pub fn save_state(&self) {
+        if self.is_mirror() { return; }
+        if let Err(e) = mount_persistence::save(self.instance, &self.persistent_state()) {
+            log::error!("failed to save mount state: {}", e);
+        }
+    }
+
+    pub fn get(&self) -> MountState {
+        let priv_state = self.priv_state.read().unwrap();
+        let (motor1_pos, axis1_spd) = priv_state.axis1.state();
+        let (motor2_pos, axis2_spd) = priv_state.axis2.state();
+
+        let (wind_dev1, wind_dev2) = self.wind.lock().unwrap().update();
+
+        // encoders are on the motor side and do not register backlash, periodic error and structural oscillation
+        let mut drive_trains = self.drive_trains.lock().unwrap();
+        let axis1_pos = drive_trains[0].encoder_reading(motor1_pos + wind_dev1);
+        let axis2_pos = drive_trains[1].encoder_reading(motor2_pos + wind_dev2);
+        let mut structural_modes = self.structural_modes.lock().unwrap();
+        let now = self.clock.now();
+        for (mode, axis) in structural_modes.iter_mut().zip([&priv_state.axis1, &priv_state.axis2]) {
+            let (start, accel, duration) = axis.acceleration_profile();
+            mode.update(now, start, accel, duration);
+        }
+        let true_axis1_pos = drive_trains[0].output(motor1_pos) + wind_dev1 + structural_modes[0].offset(now);
+        let true_axis2_pos = drive_trains[1].output(motor2_pos) + wind_dev2 + structural_modes[1].offset(now);
+
+        let errors = self.pointing_errors.read().unwrap();
+
+        let (boresight_az, boresight_alt, pier_side, latitude) = match &*self.mode.read().unwrap() {
+            MountMode::AltAz => {
+                let (az, alt) = errors.boresight(true_axis1_pos, true_axis2_pos);
+                (az, alt, None, *self.site_latitude.read().unwrap())
+            },
+
+            MountMode::Equatorial(settings) => {
+                let (ha, dec, pier_side) = equatorial::to_ha_dec(true_axis1_pos, true_axis2_pos);
+                let (ha, dec) = errors.apply_axis_terms(ha, dec);
+                let (az, alt) = equatorial::to_az_alt(ha, dec, settings.latitude);
+                (az, errors.apply_flexure(alt), Some(pier_side), settings.latitude)
+            }
+        };
+
+        let parallactic_angle = derotator::parallactic_angle(boresight_az, boresight_alt, latitude);
+        let camera_roll = match pier_side {
+            None => self.derotator().rotation(parallactic_angle),
+            Some(PierSide::East) => parallactic_angle,
+            // the camera is upside down after a meridian flip
+            Some(PierSide::West) => parallactic_angle + deg(180.0)
+        };
+
+        MountState{
+            axis1_pos,
+            axis2_pos,
+            axis1_spd,
+            axis2_spd,
+            boresight_az,
+            boresight_alt,
+            pier_side,
+            parallactic_angle,
+            camera_roll,
+            defocus: self.focuser.lock().unwrap().defocus(),
+            filter_transmission: self.filter_wheel.lock().unwrap().transmission()
+        }
+    }
+
+    pub fn set_site_latitude(&self, latitude: f64::Angle) {
+        *self.site_latitude.write().unwrap() = latitude;
+    }
+
+    pub fn derotator(&self) -> Derotator { *self.derotator.read().unwrap() }
+
+    pub fn set_derotator(&self, derotator: Derotator) {
+        self.mirrored(|mirror| { mirror.set_derotator(derotator); Ok(()) });
+        *self.derotator.write().unwrap() = derotator;
+    }
+
+    /// Returns focuser position, max. position and whether it is moving.
+    pub fn focuser(&self) -> (u32, u32, bool) {
+        let focuser = self.focuser.lock().unwrap();
+        (focuser.position(), focuser.max_position(), focuser.is_moving())
+    }
+
+    pub fn move_focuser(&self, position: u32) -> Result<(), MountError> {
+        self.focuser.lock().unwrap().move_to(position)
+    }
+
+    pub fn halt_focuser(&self) {
+        self.focuser.lock().unwrap().halt();
+    }
+
+    /// Returns the current filter wheel slot (`None` while moving).
+    pub fn filter(&self) -> Option<usize> { self.filter_wheel.lock().unwrap().position() }
+
+    pub fn set_filter(&self, position: usize) -> Result<(), MountError> {
+        self.filter_wheel.lock().unwrap().set_position(position)
+    }
+
+    pub fn filter_names(&self) -> Vec<String> { self.filter_wheel.lock().unwrap().filter_names() }
+
+    pub fn wind_settings(&self) -> WindSettings {
+        self.wind.lock().unwrap().settings().clone()
+    }
+
+    pub fn set_wind_settings(&self, settings: WindSettings) {
+        self.wind.lock().unwrap().set_settings(settings);
+    }
+
+    pub fn guide_rate(&self) -> f64::AngularVelocity {
+        *self.guide_rate.read().unwrap()
+    }
+
+    pub fn set_guide_rate(&self, rate: f64::AngularVelocity) {
+        self.mirrored(|mirror| { mirror.set_guide_rate(rate); Ok(()) });
+        *self.guide_rate.write().unwrap() = rate.abs();
+    }
+
+    pub fn pulse_guide(&self, direction: GuideDirection, duration: std::time::Duration) -> Result<(), MountError> {
+        self.mirrored(|mirror| mirror.pulse_guide(direction, duration));
+        if self.is_parked() {
+            return Err(MountError::new(ErrorCode::Parked, "mount is parked"));
+        }
+
+        let rate = self.guide_rate();
+        let mut priv_state = self.priv_state.write().unwrap();
+        match direction {
+            GuideDirection::North => priv_state.axis2.guide_pulse(rate, duration),
+            GuideDirection::South => priv_state.axis2.guide_pulse(-rate, duration),
+            GuideDirection::West => priv_state.axis1.guide_pulse(rate, duration),
+            GuideDirection::East => priv_state.axis1.guide_pulse(-rate, duration)
+        }
+
+        Ok(())
+    }
+
+    pub fn mode(&self) -> MountMode {
+        self.mode.read().unwrap().clone()
+    }
+
+    pub fn set_mode(&self, mode: MountMode) {
+        *self.mode.write().unwrap() = mode;
+    }
+
+    /// Moves the mount to the other pier side, keeping the same sky position (equatorial mode only).
+    pub fn meridian_flip(&self) -> Result<PierSide, MountError> {
+        self.mirrored(|mirror| mirror.meridian_flip().map(|_| ()));
+        if !matches!(self.mode(), MountMode::Equatorial(_)) {
+            return Err(MountError::new(ErrorCode::InvalidMode, "meridian flip requires equatorial mode"));
+        }
+
+        let mut priv_state = self.priv_state.write().unwrap();
+        if priv_state.axis1.is_guiding() || priv_state.axis2.is_guiding() {
+            return Err(MountError::new(ErrorCode::Busy, "guide pulse in progress"));
+        }
+        let (axis1_pos, _) = priv_state.axis1.state();
+        let (axis2_pos, _) = priv_state.axis2.state();
+        let (ha, dec, pier_side) = equatorial::to_ha_dec(axis1_pos, axis2_pos);
+        let new_pier_side = match pier_side { PierSide::East => PierSide::West, PierSide::West => PierSide::East };
+        let (new_axis1_pos, new_axis2_pos) = equatorial::to_mechanical(ha, dec, new_pier_side);
+        priv_state.axis1.relocate(new_axis1_pos, false);
+        priv_state.axis2.relocate(new_axis2_pos, true);
+        log::info!("meridian flip performed; pier side: {}", new_pier_side);
+
+        Ok(new_pier_side)
+    }
+
+    /// Performs an automatic meridian flip if enabled and the meridian limit has been exceeded.
+    fn check_meridian_limit(&self) {
+        if let MountMode::Equatorial(settings) = self.mode() {
+            if !settings.auto_flip { return; }
+
+            let state = self.get();
+            let (ha, _, pier_side) = equatorial::to_ha_dec(state.axis1_pos, state.axis2_pos);
+            if pier_side == PierSide::East && ha > settings.meridian_limit {
+                log::info!("meridian limit exceeded");
+                let _ = self.meridian_flip();
+            }
+        }
+    }
+
+    /// Stops axes (including their guide pulses) which have exceeded their position limits.
+    fn check_axis_limits(&self) {
+        let mut priv_state = self.priv_state.write().unwrap();
+        let PrivState{ axis1, axis2 } = &mut *priv_state;
+        for (axis, config, name) in [(axis1, &self.config.axis1, "axis 1"), (axis2, &self.config.axis2, "axis 2")] {
+            if violates_limits(axis.state().0, axis.commanded_speed(), config) {
+                log::warn!("{} limit reached; stopping", name);
+                axis.cancel_guide_pulses();
+                axis.set_target_speed(deg_per_s(0.0));
+            }
+        }
+    }
+
+    pub fn client_status(&self) -> ClientStatus { self.client.lock().unwrap().status() }
+
+    pub fn heartbeat_timeout(&self) -> Option<std::time::Duration> { self.client.lock().unwrap().heartbeat_timeout }
+
+    pub fn set_heartbeat_timeout(&self, timeout: Option<std::time::Duration>) {
+        let mut client = self.client.lock().unwrap();
+        client.heartbeat_timeout = timeout;
+        let now = self.clock.now();
+        for connection in client.connections.values_mut() {
+            if connection.last_activity.is_some() { connection.last_activity = Some(now); }
+        }
+    }
+
+    /// Registers a new client connection; it is monitored by the heartbeat failsafe after its first activity.
+    pub(super) fn register_client_connection(&self) -> ClientId {
+        let mut client = self.client.lock().unwrap();
+        let id = client.next_id;
+        client.next_id += 1;
+        client.connections.insert(id, ClientConnection{ last_activity: None, timed_out: false });
+        id
+    }
+
+    /// Must be called for every message received from the client.
+    pub(super) fn register_client_message(&self, id: ClientId) {
+        self.register_client_activity(id);
+        self.client.lock().unwrap().num_messages += 1;
+    }
+
+    pub(super) fn register_client_activity(&self, id: ClientId) {
+        let mut client = self.client.lock().unwrap();
+        if let Some(connection) = client.connections.get_mut(&id) {
+            if connection.timed_out {
+                log::info!("client resumed communication");
+                connection.timed_out = false;
+            }
+            connection.last_activity = Some(self.clock.now());
+        }
+    }
+
+    pub(super) fn register_client_disconnection(&self, id: ClientId) {
+        self.client.lock().unwrap().connections.remove(&id);
+    }
+
+    /// Returns the clients' status and the number of messages received since the previous call.
+    fn take_client_stats(&self) -> (ClientStatus, usize) {
+        let mut client = self.client.lock().unwrap();
+        (client.status(), std::mem::take(&mut client.num_messages))
+    }
+
+    /// Stops the axes and cancels guide pulses if any client has been silent for longer than the heartbeat timeout.
+    fn check_heartbeat(&self) {
+        let mut client = self.client.lock().unwrap();
+        let timeout = match client.heartbeat_timeout {
+            Some(timeout) => timeout,
+            None => return
+        };
+        let mut timed_out = false;
+        for connection in client.connections.values_mut().filter(|c| !c.timed_out) {
+            if connection.last_activity.map_or(false, |t| self.clock.since(t) > timeout) {
+                connection.timed_out = true;
+                timed_out = true;
+            }
+        }
+        drop(client);
+        if timed_out {
+            log::warn!("no message from client for {:.1} s; stopping axes", timeout.as_secs_f64());
+            self.stop();
+        }
+    }
+
+    pub fn pointing_errors(&self) -> PointingErrors {
+        self.pointing_errors.read().unwrap().clone()
+    }
+
+    pub fn set_pointing_errors(&self, pointing_errors: PointingErrors) {
+        *self.pointing_errors.write().unwrap() = pointing_errors;
+    }
+}
+
+fn time(duration: std::time::Duration) -> f64::Time { f64::Time::new::<time::second>(duration.as_secs_f64()) }
+
+fn deg(value: f64) -> f64::Angle { f64::Angle::new::<angle::degree>(value) }
+
+fn arcsec(value: f64) -> f64::Angle { f64::Angle::new::<angle::second>(value) }
+
+fn deg_per_s(value: f64) -> f64::AngularVelocity {
+    f64::AngularVelocity::new::<angular_velocity::degree_per_second>(value)
+}
+
+fn deg_per_s_sq(value: f64) -> f64::AngularAcceleration {
+    f64::AngularAcceleration::new::<angular_acceleration::degree_per_second_squared>(value)
+}
+
+/// Serves the mount protocol on TCP `port`; if `trace_dir` is set, each connection is recorded there.
+/// If `status` is set, the client connection state is reported to it; if `traffic` is set, exchanged messages
+/// are captured there.
+pub fn mount_model(
+    mount: Arc<Mount>,
+    port: u16,
+    trace_dir: Option<PathBuf>,
+    status: Option<StatusSender>,
+    traffic: Option<TrafficMonitor>
+) {
+    let mount2 = Arc::clone(&mount);
+    std::thread::spawn(move || {
+        let mut t_last_save = std::time::Instant::now();
+        let mut status_timer = StatusTimer::new();
+        loop {
+            mount2.check_axis_limits();
+            mount2.check_meridian_limit();
+            mount2.check_heartbeat();
+            if t_last_save.elapsed() >= STATE_AUTOSAVE_INTERVAL {
+                mount2.save_state();
+                t_last_save = std::time::Instant::now();
+            }
+            if let (Some(status), Some(elapsed)) = (&status, status_timer.due()) {
+                let (client, num_messages) = mount2.take_client_stats();
+                let _ = status.send(StatusUpdate::Mount{
+                    instance: mount2.instance(),
+                    client,
+                    messages_per_s: num_messages as f64 / elapsed.as_secs_f64()
+                });
+            }
+            std::thread::sleep(LIMIT_CHECK_INTERVAL);
+        }
+    });
+
+    loop {
+        let (stream, _) = {
+            log::info!("waiting for client on port {}", port);
+            let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).unwrap();
+            let stream = listener.accept().unwrap();
+            log::info!("client connected");
+            stream
+        };
+
+        let trace = trace_dir.as_ref().and_then(|dir| TraceFile::create(dir, &format!("tcp-{}", port)));
+        let link = format!("mount {}", port);
+        let mut reader = std::io::BufReader::new(Traced::new(
+            Tapped::new(
+                stream.try_clone().unwrap(),
+                traffic.as_ref(),
+                &link,
+                TrafficDirection::FromClient,
+                traffic_monitor::parse_mount_message
+            ),
+            trace.clone()
+        ));
+        let mut writer = Traced::new(
+            Tapped::new(
+                stream,
+                traffic.as_ref(),
+                &link,
+                TrafficDirection::ToClient,
+                traffic_monitor::parse_mount_message
+            ),
+            trace
+        );
+        serve_client(&mut reader, &mut writer, &mount, &mut TextCodec);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use std::time::Duration;
+
+    const TOLERANCE_DEG: f64 = 1.0e-9;
+
+    fn mount_with_manual_clock() -> (Mount, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::default());
+        (Mount::with_clock(MountConfig::default(), 0, Arc::clone(&clock) as Arc<dyn Clock>), clock)
+    }
+
+    #[test]
+    fn slew_follows_acceleration_ramp() {
+        let (mount, clock) = mount_with_manual_clock();
+        let accel = mount.config().axis1.acceleration_deg_per_s2;
+        let rate = accel / 2.0; // reached after 0.5 s
+        mount.slew(deg_per_s(rate), deg_per_s(0.0)).unwrap();
+
+        clock.advance(Duration::from_millis(250));
+        let [axis1, axis2] = mount.axis_motion();
+        assert!((axis1.pos - 0.5 * accel * 0.25 * 0.25).abs() < TOLERANCE_DEG);
+        assert!((axis1.spd - accel * 0.25).abs() < TOLERANCE_DEG);
+        assert!((axis1.accel - accel).abs() < TOLERANCE_DEG);
+        assert!(axis2.pos.abs() < TOLERANCE_DEG);
+
+        clock.advance(Duration::from_millis(750));
+        let [axis1, _] = mount.axis_motion();
+        assert!((axis1.pos - (0.5 * accel * 0.5 * 0.5 + rate * 0.5)).abs() < TOLERANCE_DEG);
+        assert!((axis1.spd - rate).abs() < TOLERANCE_DEG);
+        assert!(axis1.accel.abs() < TOLERANCE_DEG);
+        assert!((axis1.commanded_pos - rate).abs() < TOLERANCE_DEG);
+    }
+
+    #[test]
+    fn guide_pulse_offsets_axis_by_rate_times_duration() {
+        let (mount, clock) = mount_with_manual_clock();
+        let guide_rate = mount.guide_rate().get::<angular_velocity::degree_per_second>();
+        mount.pulse_guide(GuideDirection::North, Duration::from_millis(400)).unwrap();
+
+        clock.advance(Duration::from_millis(100));
+        let [_, axis2] = mount.axis_motion();
+        assert!((axis2.pos - guide_rate * 0.1).abs() < TOLERANCE_DEG);
+        assert!((axis2.spd - guide_rate).abs() < TOLERANCE_DEG);
+        assert!(mount.priv_state.read().unwrap().axis2.is_guiding());
+
+        clock.advance(Duration::from_secs(1));
+        let [_, axis2] = mount.axis_motion();
+        assert!((axis2.pos - guide_rate * 0.4).abs() < TOLERANCE_DEG);
+        assert!(axis2.spd.abs() < TOLERANCE_DEG);
+        assert!(!mount.priv_state.read().unwrap().axis2.is_guiding());
+    }
+
+    #[test]
+    fn park_cancels_and_rejects_guide_pulses() {
+        let (mount, clock) = mount_with_manual_clock();
+        let guide_rate = mount.guide_rate().get::<angular_velocity::degree_per_second>();
+        mount.pulse_guide(GuideDirection::North, Duration::from_millis(400)).unwrap();
+
+        clock.advance(Duration::from_millis(100));
+        mount.park();
+        clock.advance(Duration::from_secs(1));
+        let [_, axis2] = mount.axis_motion();
+        assert!((axis2.pos - guide_rate * 0.1).abs() < TOLERANCE_DEG);
+        assert!(axis2.spd.abs() < TOLERANCE_DEG);
+
+        let result = mount.pulse_guide(GuideDirection::North, Duration::from_millis(400));
+        assert_eq!(result.unwrap_err().code, ErrorCode::Parked);
+    }
+
+    #[test]
+    fn heartbeat_timeout_cancels_guide_pulses() {
+        let (mount, clock) = mount_with_manual_clock();
+        mount.set_heartbeat_timeout(Some(Duration::from_millis(50)));
+        let id = mount.register_client_connection();
+        mount.register_client_activity(id);
+        mount.pulse_guide(GuideDirection::West, Duration::from_secs(10)).unwrap();
+
+        clock.advance(Duration::from_millis(100));
+        mount.check_heartbeat();
+        assert_eq!(mount.client_status(), ClientStatus::TimedOut);
+        assert!(!mount.priv_state.read().unwrap().axis1.is_guiding());
+    }
+
+    #[test]
+    fn focuser_and_filter_wheel_move_with_clock() {
+        let (mount, clock) = mount_with_manual_clock();
+        let config = mount.config().clone();
+
+        mount.set_filter(1).unwrap();
+        assert_eq!(mount.filter(), None);
+        clock.advance(Duration::from_secs_f64(config.filter_wheel.slot_change_time_s));
+        assert_eq!(mount.filter(), Some(1));
+
+        let start = mount.focuser().0;
+        let target = start / 2;
+        mount.move_focuser(target).unwrap();
+        assert!(mount.focuser().2);
+        clock.advance(Duration::from_secs_f64((start - target) as f64 / config.focuser.speed / 2.0));
+        assert_eq!(mount.focuser().0, start - (start - target) / 2);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(mount.focuser().0, target);
+        assert!(!mount.focuser().2);
+    }
+
+    #[test]
+    fn heartbeat_of_one_client_does_not_cover_another() {
+        let (mount, clock) = mount_with_manual_clock();
+        mount.set_heartbeat_timeout(Some(Duration::from_millis(50)));
+        let active = mount.register_client_connection();
+        let silent = mount.register_client_connection();
+        mount.register_client_activity(active);
+        mount.register_client_activity(silent);
+        assert_eq!(mount.client_status(), ClientStatus::Connected);
+
+        clock.advance(Duration::from_millis(100));
+        mount.register_client_message(active);
+        mount.check_heartbeat();
+        assert_eq!(mount.client_status(), ClientStatus::TimedOut);
+
+        mount.register_client_disconnection(silent);
+        assert_eq!(mount.client_status(), ClientStatus::Connected);
+    }
+}
@@ -0,0 +1,54 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use serde::{Deserialize, Serialize};
+use std::{error::Error, path::PathBuf};
+
+/// Mount state preserved across program restarts.
+#[derive(Serialize, Deserialize)]
+pub struct PersistentMountState {
+    pub axis1_pos_deg: f64,
+    pub axis2_pos_deg: f64,
+    pub parked: bool
+}
+
+/// Returns path of the state file of mount number `instance` (0-based).
+fn state_file_path(instance: usize) -> Option<PathBuf> {
+    let file_name = if instance == 0 {
+        "mount_state.toml".to_string()
+    } else {
+        format!("mount_state_{}.toml", instance + 1)
+    };
+    dirs::data_local_dir().map(|dir| dir.join("pointing-sim").join(file_name))
+}
+
+pub fn load(instance: usize) -> Option<PersistentMountState> {
+    let path = state_file_path(instance)?;
+    let contents = std::fs::read_to_string(&path).ok()?;
+    match toml::from_str::<PersistentMountState>(&contents) {
+        Ok(state) if state.axis1_pos_deg.is_finite() && state.axis2_pos_deg.is_finite() => Some(state),
+        Ok(_) => {
+            log::error!("invalid axis position in mount state file {}", path.display());
+            None
+        },
+        Err(e) => {
+            log::error!("failed to parse mount state file {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub fn save(instance: usize, state: &PersistentMountState) -> Result<(), Box<dyn Error>> {
+    let path = state_file_path(instance).ok_or("cannot determine data directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, toml::to_string(state)?)?;
+
+    Ok(())
+}
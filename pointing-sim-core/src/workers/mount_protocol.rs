@@ -0,0 +1,305 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Protocol-independent handling of mount requests.
+//!
+//! Each wire protocol is a `Codec` translating between its messages and `Request`/`Response`; requests are
+//! executed in a single place (`execute`), so all protocols behave identically. The legacy newline-delimited
+//! text protocol (`TextCodec`) is served on the original ports regardless of other protocols being enabled.
+//! Failures are reported with error codes (see `mount_error`).
+
+use crate::workers::{
+    ext_protocol::{ExtMessage, GuideDirection},
+    mount_error::{ErrorCode, MountError},
+    mount_model::Mount,
+    protocol_diagnostics::SessionDiagnostics
+};
+use pointing_utils::{MountSimulatorMessage, uom};
+use std::io::{BufRead, BufReader, Read, Write};
+use uom::{si::f64, si::angular_velocity};
+
+pub enum Request {
+    GetPosition,
+    Slew{ axis1: f64::AngularVelocity, axis2: f64::AngularVelocity },
+    Stop,
+    /// Simulator-specific request.
+    Ext(ExtMessage)
+}
+
+pub enum Response {
+    Position(f64::Angle, f64::Angle),
+    Reply(Result<(), MountError>),
+    /// Simulator-specific reply.
+    Ext(ExtMessage)
+}
+
+pub trait Codec {
+    /// Reads the next request; returns `Ok(None)` if the client has disconnected, `Ok(Some(Err(_)))` if
+    /// the request is invalid (but the connection can continue).
+    fn read_request(&mut self, reader: &mut dyn BufRead) -> std::io::Result<Option<Result<Request, String>>>;
+
+    fn write_response(&mut self, response: Response, writer: &mut dyn Write) -> std::io::Result<()>;
+}
+
+/// Legacy text protocol: one `pointing_utils::MountSimulatorMessage` or `ExtMessage` per line.
+pub struct TextCodec;
+
+impl Codec for TextCodec {
+    fn read_request(&mut self, reader: &mut dyn BufRead) -> std::io::Result<Option<Result<Request, String>>> {
+        type Msg = MountSimulatorMessage;
+
+        let mut msg_s = String::new();
+        if reader.read_line(&mut msg_s)? == 0 { return Ok(None); }
+        let msg_s = msg_s.trim_end();
+
+        Ok(Some(match msg_s.parse::<Msg>() {
+            Err(e) => match msg_s.parse::<ExtMessage>() {
+                Ok(ext_msg) => Ok(Request::Ext(ext_msg)),
+                Err(_) => Err(format!("error parsing mount message: {}", e))
+            },
+
+            Ok(msg) => match msg {
+                Msg::GetPosition => Ok(Request::GetPosition),
+                Msg::Slew{axis1, axis2} => if axis1.value.is_finite() && axis2.value.is_finite() {
+                    Ok(Request::Slew{ axis1, axis2 })
+                } else {
+                    Err("invalid rate".to_string())
+                },
+                Msg::Stop => Ok(Request::Stop),
+                _ => Err(format!("unexpected message: {}", msg_s))
+            }
+        }))
+    }
+
+    fn write_response(&mut self, response: Response, writer: &mut dyn Write) -> std::io::Result<()> {
+        type Msg = MountSimulatorMessage;
+
+        let contents = match response {
+            Response::Position(axis1, axis2) => Msg::Position(Ok((axis1, axis2))).to_string(),
+            Response::Reply(result) => Msg::Reply(result.map_err(|e| e.to_reply_text())).to_string(),
+            Response::Ext(msg) => msg.to_string()
+        };
+        writer.write_all(contents.as_bytes())
+    }
+}
+
+/// Returns true if the request changes the mount's state (as opposed to a query).
+fn is_command(request: &Request) -> bool {
+    match request {
+        Request::GetPosition => false,
+        Request::Slew{ .. } | Request::Stop => true,
+        Request::Ext(msg) => matches!(
+            msg,
+            ExtMessage::MeridianFlip
+                | ExtMessage::PulseGuide{ .. }
+                | ExtMessage::SetGuideRate(_)
+                | ExtMessage::Park
+                | ExtMessage::Unpark
+                | ExtMessage::SetHeartbeatTimeout(_)
+                | ExtMessage::SetDerotator(_)
+                | ExtMessage::MoveFocuser(_)
+                | ExtMessage::HaltFocuser
+                | ExtMessage::SetFilter(_)
+        )
+    }
+}
+
+/// Executes a request; returns `None` if it does not warrant a response.
+pub(super) fn execute(request: Request, mount: &Mount) -> Option<Response> {
+    if mount.is_mirror() && is_command(&request) {
+        return Some(Response::Reply(Err(
+            MountError::new(ErrorCode::Unauthorized, "mount mirrors another one and accepts only queries")
+        )));
+    }
+
+    match request {
+        Request::GetPosition => {
+            let state = mount.get();
+            Some(Response::Position(state.axis1_pos, state.axis2_pos))
+        },
+
+        // clamped rates are reported, even though the slew is executed
+        Request::Slew{ axis1, axis2 } =>
+            Some(Response::Reply(mount.slew(axis1, axis2).and_then(|_| mount.check_max_rates(axis1, axis2)))),
+
+        Request::Stop => {
+            mount.stop();
+            Some(Response::Reply(Ok(())))
+        },
+
+        Request::Ext(msg) => execute_ext(msg, mount)
+    }
+}
+
+fn execute_ext(msg: ExtMessage, mount: &Mount) -> Option<Response> {
+    match msg {
+        ExtMessage::GetPierSide => Some(Response::Ext(ExtMessage::PierSide(mount.get().pier_side))),
+
+        ExtMessage::MeridianFlip => Some(Response::Reply(mount.meridian_flip().map(|_| ()))),
+
+        ExtMessage::PulseGuide{ direction, duration } =>
+            Some(Response::Reply(mount.pulse_guide(direction, duration))),
+
+        ExtMessage::GetGuideRate => Some(Response::Ext(ExtMessage::GuideRate(mount.guide_rate()))),
+
+        ExtMessage::SetGuideRate(rate) => {
+            mount.set_guide_rate(rate);
+            Some(Response::Reply(Ok(())))
+        },
+
+        ExtMessage::Park => {
+            mount.park();
+            Some(Response::Reply(Ok(())))
+        },
+
+        ExtMessage::Unpark => {
+            mount.unpark();
+            Some(Response::Reply(Ok(())))
+        },
+
+        ExtMessage::GetParked => Some(Response::Ext(ExtMessage::Parked(mount.is_parked()))),
+
+        ExtMessage::Heartbeat => Some(Response::Reply(Ok(()))),
+
+        ExtMessage::SetHeartbeatTimeout(timeout) => {
+            mount.set_heartbeat_timeout(timeout);
+            Some(Response::Reply(Ok(())))
+        },
+
+        ExtMessage::SetDerotator(derotator) => {
+            mount.set_derotator(derotator);
+            Some(Response::Reply(Ok(())))
+        },
+
+        ExtMessage::GetFieldRotation => {
+            let state = mount.get();
+            Some(Response::Ext(ExtMessage::FieldRotation{
+                parallactic_angle: state.parallactic_angle,
+                image_rotation: state.camera_roll
+            }))
+        },
+
+        ExtMessage::MoveFocuser(position) => Some(Response::Reply(mount.move_focuser(position))),
+
+        ExtMessage::HaltFocuser => {
+            mount.halt_focuser();
+            Some(Response::Reply(Ok(())))
+        },
+
+        ExtMessage::GetFocuser => {
+            let (position, _, moving) = mount.focuser();
+            Some(Response::Ext(ExtMessage::Focuser{ position, moving }))
+        },
+
+        ExtMessage::SetFilter(position) => Some(Response::Reply(mount.set_filter(position))),
+
+        ExtMessage::GetFilter => Some(Response::Ext(ExtMessage::Filter(mount.filter()))),
+
+        ExtMessage::GetFilterNames => Some(Response::Ext(ExtMessage::FilterNames(mount.filter_names()))),
+
+        _ => {
+            log::error!("unexpected message: {}", msg);
+            None
+        }
+    }
+}
+
+/// Handles mount protocol messages until the client disconnects.
+pub fn serve_client<R: Read>(reader: &mut BufReader<R>, writer: &mut dyn Write, mount: &Mount, codec: &mut dyn Codec) {
+    let client = mount.register_client_connection();
+    mount.register_client_activity(client);
+    let mut diagnostics = SessionDiagnostics::default();
+
+    loop {
+        let request = match codec.read_request(reader) {
+            Ok(Some(request)) => request,
+            Ok(None) => {
+                log::info!("client disconnected");
+                break;
+            },
+            Err(e) => {
+                log::info!("error receiving message ({}); disconnecting from client", e);
+                break;
+            }
+        };
+        mount.register_client_message(client);
+
+        let response = match request {
+            Ok(request) => {
+                // the client should wait for the response before sending the next request
+                diagnostics.request(&request, !reader.buffer().is_empty());
+                let response = execute(request, mount);
+                diagnostics.response(response.as_ref());
+                response
+            },
+            Err(e) => {
+                log::error!("{}", e);
+                diagnostics.invalid_message(&e);
+                None
+            }
+        };
+
+        if let Some(response) = response {
+            if let Err(e) = codec.write_response(response, writer) {
+                log::info!("error sending message ({}); disconnecting from client", e);
+                break;
+            }
+        }
+    }
+
+    diagnostics.log_summary();
+    mount.register_client_disconnection(client);
+}
+
+/// Executes a single message of the text protocol as if it was sent by a client; returns the response
+/// (`None` if the message does not warrant one).
+pub fn execute_message(message: &str, mount: &Mount) -> Result<Option<String>, String> {
+    let mut codec = TextCodec;
+    let mut reader = std::io::Cursor::new(format!("{}\n", message.trim()));
+    let request = codec.read_request(&mut reader)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "empty message".to_string())??;
+
+    match execute(request, mount) {
+        Some(response) => {
+            let mut contents = vec![];
+            codec.write_response(response, &mut contents).map_err(|e| e.to_string())?;
+            Ok(Some(String::from_utf8_lossy(&contents).trim_end().to_string()))
+        },
+        None => Ok(None)
+    }
+}
+
+/// Returns examples of requests of the text protocol.
+pub fn message_templates() -> Vec<String> {
+    type Msg = MountSimulatorMessage;
+
+    let slew_rate = f64::AngularVelocity::new::<angular_velocity::degree_per_second>(0.5);
+    let messages = [
+        Msg::GetPosition.to_string(),
+        Msg::Slew{ axis1: slew_rate, axis2: -slew_rate }.to_string(),
+        Msg::Stop.to_string(),
+        ExtMessage::GetPierSide.to_string(),
+        ExtMessage::MeridianFlip.to_string(),
+        ExtMessage::PulseGuide{
+            direction: GuideDirection::North,
+            duration: std::time::Duration::from_millis(500)
+        }.to_string(),
+        ExtMessage::GetGuideRate.to_string(),
+        ExtMessage::Park.to_string(),
+        ExtMessage::Unpark.to_string(),
+        ExtMessage::GetParked.to_string(),
+        ExtMessage::Heartbeat.to_string(),
+        ExtMessage::GetFieldRotation.to_string(),
+        ExtMessage::GetFocuser.to_string(),
+        ExtMessage::GetFilter.to_string(),
+        ExtMessage::GetFilterNames.to_string()
+    ];
+
+    messages.iter().map(|msg| msg.trim_end().to_string()).collect()
+}
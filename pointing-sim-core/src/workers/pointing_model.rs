@@ -0,0 +1,122 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+/// Minimum value of cos(secondary axis) used by terms which diverge at the axis pole.
+const MIN_COS_SECONDARY: f64 = 1.0e-6;
+
+/// Classical (TPoint-style) pointing error terms.
+///
+/// Error terms describe how the true boresight direction differs from the one implied by the axis positions.
+#[derive(Clone)]
+pub struct PointingErrors {
+    /// Collimation (cone) error; optical axis not perpendicular to the secondary axis (TPoint: CA/CH).
+    pub cone: f64::Angle,
+    /// Non-perpendicularity of the primary and secondary axes (TPoint: NPAE/NP).
+    pub non_perpendicularity: f64::Angle,
+    /// Tube flexure; sag proportional to cos(altitude) (TPoint: TF).
+    pub tube_flexure: f64::Angle,
+}
+
+impl Default for PointingErrors {
+    fn default() -> PointingErrors {
+        PointingErrors{
+            cone: f64::Angle::new::<angle::degree>(0.0),
+            non_perpendicularity: f64::Angle::new::<angle::degree>(0.0),
+            tube_flexure: f64::Angle::new::<angle::degree>(0.0)
+        }
+    }
+}
+
+impl PointingErrors {
+    /// Returns true boresight direction (azimuth, altitude) corresponding to the given alt-az axis positions.
+    pub fn boresight(&self, axis1_pos: f64::Angle, axis2_pos: f64::Angle) -> (f64::Angle, f64::Angle) {
+        let (az, alt) = self.apply_axis_terms(axis1_pos, axis2_pos);
+        (az, self.apply_flexure(alt))
+    }
+
+    /// Applies cone and non-perpendicularity errors to the primary/secondary axis positions (azimuth/altitude
+    /// or hour angle/declination).
+    pub fn apply_axis_terms(&self, primary: f64::Angle, secondary: f64::Angle) -> (f64::Angle, f64::Angle) {
+        let sec = secondary.get::<angle::radian>();
+        let cos_sec = sec.cos().abs().max(MIN_COS_SECONDARY).copysign(sec.cos());
+        let tan_sec = sec.sin() / cos_sec;
+
+        (primary + self.cone / cos_sec + self.non_perpendicularity * tan_sec, secondary)
+    }
+
+    /// Applies tube flexure to the true altitude.
+    pub fn apply_flexure(&self, altitude: f64::Angle) -> f64::Angle {
+        altitude - self.tube_flexure * altitude.get::<angle::radian>().cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Required accuracy of the error terms.
+    const TOLERANCE_ARCSEC: f64 = 1.0e-3;
+
+    fn deg(value: f64) -> f64::Angle { f64::Angle::new::<angle::degree>(value) }
+
+    fn arcsec(value: f64) -> f64::Angle { f64::Angle::new::<angle::second>(value) }
+
+    fn assert_close(actual: f64::Angle, expected: f64::Angle) {
+        let error = (actual - expected).get::<angle::second>().abs();
+        assert!(error < TOLERANCE_ARCSEC, "error of {}\" exceeds {}\"", error, TOLERANCE_ARCSEC);
+    }
+
+    #[test]
+    fn no_errors_preserve_axis_positions() {
+        let errors = PointingErrors::default();
+        for (az, alt) in [(0.0, 0.0), (123.456789, 45.0), (359.9999, 89.5)] {
+            let (true_az, true_alt) = errors.boresight(deg(az), deg(alt));
+            assert_close(true_az, deg(az));
+            assert_close(true_alt, deg(alt));
+        }
+    }
+
+    #[test]
+    fn cone_error_scales_with_secant_of_altitude() {
+        let errors = PointingErrors{ cone: arcsec(0.5), ..Default::default() };
+        for alt in [0.0, 30.0, 60.0, 80.0] {
+            let (az, true_alt) = errors.boresight(deg(100.0), deg(alt));
+            assert_close(az, deg(100.0) + arcsec(0.5 / alt.to_radians().cos()));
+            assert_close(true_alt, deg(alt));
+        }
+    }
+
+    #[test]
+    fn non_perpendicularity_scales_with_tangent_of_altitude() {
+        let errors = PointingErrors{ non_perpendicularity: arcsec(0.25), ..Default::default() };
+        for alt in [0.0, 30.0, 45.0, 75.0] {
+            let (az, _) = errors.boresight(deg(200.0), deg(alt));
+            assert_close(az, deg(200.0) + arcsec(0.25 * alt.to_radians().tan()));
+        }
+    }
+
+    #[test]
+    fn tube_flexure_scales_with_cosine_of_altitude() {
+        let errors = PointingErrors{ tube_flexure: arcsec(0.8), ..Default::default() };
+        for alt in [0.0, 20.0, 60.0, 90.0] {
+            let (az, true_alt) = errors.boresight(deg(10.0), deg(alt));
+            assert_close(az, deg(10.0));
+            assert_close(true_alt, deg(alt) - arcsec(0.8 * alt.to_radians().cos()));
+        }
+    }
+
+    #[test]
+    fn terms_near_pole_stay_finite() {
+        let errors = PointingErrors{ cone: arcsec(1.0), non_perpendicularity: arcsec(1.0), ..Default::default() };
+        let (az, _) = errors.boresight(deg(0.0), deg(90.0));
+        assert!(az.get::<angle::degree>().is_finite());
+    }
+}
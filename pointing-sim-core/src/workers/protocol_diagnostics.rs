@@ -0,0 +1,169 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Detection of suspicious client behavior (to help developers of client software find bugs in it).
+//!
+//! The first occurrence of each kind of issue is logged as a warning; all occurrences are summarized when
+//! the client disconnects.
+
+use crate::workers::{
+    mount_error::ErrorCode,
+    mount_protocol::{Request, Response}
+};
+use std::time::{Duration, Instant};
+
+/// Position queries sent more often than this are reported.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Issue {
+    /// Position queried more often than every `MIN_POLL_INTERVAL`.
+    FastPolling,
+    /// Slew rate above the axis' max. rate.
+    RateAboveLimit,
+    /// Slew towards an axis which has reached its position limit.
+    SlewIntoLimit,
+    /// A command failed with the same error as the preceding one.
+    IgnoredError,
+    /// A request was sent before the response to the previous one had been received.
+    Pipelining,
+    InvalidMessage
+}
+
+impl Issue {
+    const ALL: [Issue; 6] = [
+        Issue::FastPolling,
+        Issue::RateAboveLimit,
+        Issue::SlewIntoLimit,
+        Issue::IgnoredError,
+        Issue::Pipelining,
+        Issue::InvalidMessage
+    ];
+
+    fn description(&self) -> String {
+        match self {
+            Issue::FastPolling =>
+                format!("position polled faster than every {} ms", MIN_POLL_INTERVAL.as_millis()),
+            Issue::RateAboveLimit => "slew rate above axis limit".into(),
+            Issue::SlewIntoLimit => "slew into axis position limit".into(),
+            Issue::IgnoredError => "failed command repeated without handling the error".into(),
+            Issue::Pipelining => "request sent without waiting for the response to the previous one".into(),
+            Issue::InvalidMessage => "invalid message".into()
+        }
+    }
+}
+
+fn request_name(request: &Request) -> String {
+    match request {
+        Request::GetPosition => "get_position".into(),
+        Request::Slew{ .. } => "slew".into(),
+        Request::Stop => "stop".into(),
+        Request::Ext(msg) => msg.to_string().trim_end().split(';').next().unwrap_or("").to_string()
+    }
+}
+
+/// Diagnostics of a single client session.
+pub struct SessionDiagnostics {
+    start: Instant,
+    num_requests: usize,
+    counts: [usize; Issue::ALL.len()],
+    last_poll: Option<Instant>,
+    /// Name of the request being executed.
+    current_request: String,
+    /// Name and error code of the last failed command.
+    last_failure: Option<(String, ErrorCode)>
+}
+
+impl Default for SessionDiagnostics {
+    fn default() -> SessionDiagnostics {
+        SessionDiagnostics{
+            start: Instant::now(),
+            num_requests: 0,
+            counts: [0; Issue::ALL.len()],
+            last_poll: None,
+            current_request: String::new(),
+            last_failure: None
+        }
+    }
+}
+
+impl SessionDiagnostics {
+    fn report(&mut self, issue: Issue, details: &str) {
+        let count = &mut self.counts[Issue::ALL.iter().position(|i| *i == issue).unwrap()];
+        if *count == 0 {
+            log::warn!(
+                "client protocol issue: {} ({}); further occurrences will be counted in the session summary",
+                issue.description(), details
+            );
+        }
+        *count += 1;
+    }
+
+    /// To be called for a request before it is executed; `pending_input`: whether data following the request
+    /// has already been received.
+    pub fn request(&mut self, request: &Request, pending_input: bool) {
+        self.num_requests += 1;
+        self.current_request = request_name(request);
+
+        if pending_input {
+            self.report(Issue::Pipelining, &format!("after \"{}\"", self.current_request));
+        }
+
+        if matches!(request, Request::GetPosition) {
+            let now = Instant::now();
+            if let Some(interval) = self.last_poll.map(|t| now - t).filter(|dt| *dt < MIN_POLL_INTERVAL) {
+                self.report(Issue::FastPolling, &format!("interval: {:.1} ms", interval.as_secs_f64() * 1000.0));
+            }
+            self.last_poll = Some(now);
+        }
+    }
+
+    /// To be called with the response to the request passed to the last call of `request`.
+    pub fn response(&mut self, response: Option<&Response>) {
+        let error = match response {
+            Some(Response::Reply(Err(error))) => error,
+            Some(Response::Reply(Ok(()))) => { self.last_failure = None; return; },
+            _ => return
+        };
+
+        match error.code {
+            ErrorCode::RateClamped => self.report(Issue::RateAboveLimit, &error.details),
+            ErrorCode::LimitViolation if self.current_request == "slew" =>
+                self.report(Issue::SlewIntoLimit, &error.details),
+            _ => ()
+        }
+
+        let failure = (self.current_request.clone(), error.code);
+        if self.last_failure.as_ref() == Some(&failure) {
+            self.report(Issue::IgnoredError, &format!("\"{}\": {}", failure.0, error.details));
+        }
+        self.last_failure = Some(failure);
+    }
+
+    pub fn invalid_message(&mut self, error: &str) {
+        self.report(Issue::InvalidMessage, error);
+    }
+
+    /// Logs a summary of the session's issues.
+    pub fn log_summary(&self) {
+        let issues: Vec<String> = Issue::ALL.iter().zip(&self.counts)
+            .filter(|(_, count)| **count > 0)
+            .map(|(issue, count)| format!("  {}: {}", issue.description(), count))
+            .collect();
+
+        let header = format!(
+            "client session summary: {:.1} s, {} requests",
+            self.start.elapsed().as_secs_f64(), self.num_requests
+        );
+        if issues.is_empty() {
+            log::info!("{}, no protocol issues", header);
+        } else {
+            log::warn!("{}, protocol issues:\n{}", header, issues.join("\n"));
+        }
+    }
+}
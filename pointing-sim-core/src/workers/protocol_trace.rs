@@ -0,0 +1,195 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Recording of raw traffic of mount and target feed client connections to trace files, and replaying them.
+//!
+//! Trace file format (integers are little-endian):
+//!
+//!   - magic bytes `TRACE_MAGIC`
+//!   - records, each consisting of:
+//!     - time since the start of connection (µs): u64
+//!     - direction: u8 (0: from client, 1: to client)
+//!     - data length: u32
+//!     - data
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    net::TcpStream,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+
+const TRACE_MAGIC: &[u8; 8] = b"PSTRACE1";
+
+/// Time to wait for server responses after replaying the last record.
+const REPLAY_LINGER: Duration = Duration::from_secs(1);
+
+#[derive(Copy, Clone, PartialEq)]
+enum Direction {
+    FromClient = 0,
+    ToClient = 1
+}
+
+struct Record {
+    timestamp: Duration,
+    direction: Direction,
+    data: Vec<u8>
+}
+
+pub struct TraceFile {
+    file: BufWriter<File>,
+    start: Instant
+}
+
+impl TraceFile {
+    /// Creates a trace file in `dir` for a new connection; `connection` identifies the server (e.g., "tcp-45501",
+    /// "feed-45500").
+    pub fn create(dir: &Path, connection: &str) -> Option<Arc<Mutex<TraceFile>>> {
+        let path = dir.join(format!("{}-{}.trace", connection, chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")));
+        let result = std::fs::create_dir_all(dir).and_then(|_| File::create(&path)).and_then(|file| {
+            let mut file = BufWriter::new(file);
+            file.write_all(TRACE_MAGIC)?;
+            Ok(file)
+        });
+        match result {
+            Ok(file) => {
+                log::info!("recording connection trace to {}", path.display());
+                Some(Arc::new(Mutex::new(TraceFile{ file, start: Instant::now() })))
+            },
+            Err(e) => { log::error!("failed to create trace file {}: {}", path.display(), e); None }
+        }
+    }
+
+    fn record(&mut self, direction: Direction, data: &[u8]) {
+        let timestamp = self.start.elapsed().as_micros() as u64;
+        let result = self.file.write_all(&timestamp.to_le_bytes())
+            .and_then(|_| self.file.write_all(&[direction as u8]))
+            .and_then(|_| self.file.write_all(&(data.len() as u32).to_le_bytes()))
+            .and_then(|_| self.file.write_all(data))
+            // flush every record, so that the trace is complete even if the program crashes
+            .and_then(|_| self.file.flush());
+        if let Err(e) = result { log::error!("failed to write to trace file: {}", e); }
+    }
+}
+
+/// Reader or writer of a client connection which records the transferred bytes if a trace file is given.
+pub struct Traced<T> {
+    inner: T,
+    trace: Option<Arc<Mutex<TraceFile>>>
+}
+
+impl<T> Traced<T> {
+    pub fn new(inner: T, trace: Option<Arc<Mutex<TraceFile>>>) -> Traced<T> {
+        Traced{ inner, trace }
+    }
+}
+
+impl<T: Read> Read for Traced<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let num_read = self.inner.read(buf)?;
+        if let (Some(trace), true) = (&self.trace, num_read > 0) {
+            trace.lock().unwrap().record(Direction::FromClient, &buf[..num_read]);
+        }
+        Ok(num_read)
+    }
+}
+
+impl<T: Write> Write for Traced<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_written = self.inner.write(buf)?;
+        if let Some(trace) = &self.trace {
+            trace.lock().unwrap().record(Direction::ToClient, &buf[..num_written]);
+        }
+        Ok(num_written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> { self.inner.flush() }
+}
+
+fn read_trace(path: &Path) -> std::io::Result<Vec<Record>> {
+    let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg.to_string());
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; TRACE_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if &magic != TRACE_MAGIC { return Err(invalid("not a trace file")); }
+
+    let mut records = vec![];
+    loop {
+        let mut timestamp = [0u8; 8];
+        match reader.read_exact(&mut timestamp) {
+            Ok(()) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e)
+        }
+        let mut direction = [0u8; 1];
+        reader.read_exact(&mut direction)?;
+        let mut length = [0u8; 4];
+        reader.read_exact(&mut length)?;
+        let mut data = vec![0u8; u32::from_le_bytes(length) as usize];
+        reader.read_exact(&mut data)?;
+
+        records.push(Record{
+            timestamp: Duration::from_micros(u64::from_le_bytes(timestamp)),
+            direction: match direction[0] {
+                0 => Direction::FromClient,
+                1 => Direction::ToClient,
+                _ => return Err(invalid("invalid record direction"))
+            },
+            data
+        });
+    }
+
+    Ok(records)
+}
+
+/// Connects to the mount server at `address` and sends it the client's part of the trace at `path` with
+/// the original timing; then compares the server's responses with the recorded ones.
+pub fn replay_trace(path: &Path, address: &str) -> std::io::Result<()> {
+    let records = read_trace(path)?;
+    log::info!("replaying {} records from {} to {}", records.len(), path.display(), address);
+
+    let mut stream = TcpStream::connect(address)?;
+    let mut response_reader = stream.try_clone()?;
+    let responses = std::thread::spawn(move || {
+        let mut received = vec![];
+        let _ = response_reader.read_to_end(&mut received);
+        received
+    });
+
+    let start = Instant::now();
+    for record in records.iter().filter(|r| r.direction == Direction::FromClient) {
+        if let Some(wait) = record.timestamp.checked_sub(start.elapsed()) { std::thread::sleep(wait); }
+        stream.write_all(&record.data)?;
+    }
+    std::thread::sleep(REPLAY_LINGER);
+    stream.shutdown(std::net::Shutdown::Both)?;
+
+    let received = responses.join().unwrap();
+    let recorded: Vec<u8> = records.iter()
+        .filter(|r| r.direction == Direction::ToClient)
+        .flat_map(|r| r.data.iter().cloned())
+        .collect();
+    match received.iter().zip(&recorded).position(|(a, b)| a != b) {
+        None if received.len() == recorded.len() => log::info!("server responses identical to the recorded ones"),
+        None => log::info!(
+            "server responses match the recorded ones, but lengths differ ({} vs. {} bytes)",
+            received.len(), recorded.len()
+        ),
+        Some(offset) => log::info!(
+            "server responses differ from the recorded ones starting at byte {}:\n  received: {}\n  recorded: {}",
+            offset,
+            String::from_utf8_lossy(&received[offset..received.len().min(offset + 80)]),
+            String::from_utf8_lossy(&recorded[offset..recorded.len().min(offset + 80)])
+        )
+    }
+
+    Ok(())
+}
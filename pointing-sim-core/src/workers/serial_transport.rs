@@ -0,0 +1,49 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Exposes the mount protocol on a pseudo-terminal, for clients which can talk only to a serial port.
+
+use crate::workers::{
+    mount_model::Mount,
+    mount_protocol::{TextCodec, serve_client},
+    protocol_trace::{TraceFile, Traced}
+};
+use nix::{pty, sys::termios, unistd};
+use std::{os::fd::AsRawFd, path::PathBuf, sync::Arc};
+
+pub fn mount_model_pty(mount: Arc<Mount>, trace_dir: Option<PathBuf>) {
+    let pty = match pty::openpty(None, None) {
+        Ok(pty) => pty,
+        Err(e) => { log::error!("failed to open pseudo-terminal: {}", e); return; }
+    };
+
+    // disable echo and line editing; the protocol is line-based, but must be passed through verbatim
+    match termios::tcgetattr(&pty.slave) {
+        Ok(mut attrs) => {
+            termios::cfmakeraw(&mut attrs);
+            if let Err(e) = termios::tcsetattr(&pty.slave, termios::SetArg::TCSANOW, &attrs) {
+                log::error!("failed to configure pseudo-terminal: {}", e);
+            }
+        },
+        Err(e) => log::error!("failed to configure pseudo-terminal: {}", e)
+    }
+
+    match unistd::ttyname(pty.slave.as_raw_fd()) {
+        Ok(path) => log::info!("mount protocol available on serial device {}", path.display()),
+        Err(e) => log::error!("cannot determine pseudo-terminal name: {}", e)
+    }
+
+    // `pty.slave` stays open for the lifetime of this function, so that reading from the master does not fail
+    // after a client closes the device; the next client simply continues the conversation
+    let master = std::fs::File::from(pty.master);
+    let trace = trace_dir.as_ref().and_then(|dir| TraceFile::create(dir, "pty"));
+    let mut reader = std::io::BufReader::new(Traced::new(master.try_clone().unwrap(), trace.clone()));
+    let mut writer = Traced::new(master, trace);
+    serve_client(&mut reader, &mut writer, &mount, &mut TextCodec);
+    drop(pty.slave);
+}
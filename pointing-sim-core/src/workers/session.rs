@@ -0,0 +1,440 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Recording of a whole run (protocol traffic, mount states, target truth) for post-mortem analysis, and its
+//! replay.
+//!
+//! Session file format: one JSON object per line, distinguished by `type`; times `t` are seconds since the start
+//! of recording:
+//!
+//! ```text
+//! {"type": "header", "version": 1, "start": <RFC 3339>, "num_stations": <N>}
+//! {"type": "traffic", "t", "link", "to_client", "text"}
+//! {"type": "mount", "t", "station", "axis1_deg", "axis2_deg", "axis1_speed_deg_per_s", "axis2_speed_deg_per_s",
+//!     "azimuth_deg", "altitude_deg"}
+//! {"type": "targets", "t", "targets": [{"id", "kind", "lat_deg", "lon_deg", "elevation_m", "track_deg",
+//!     "speed_m_per_s", "vertical_speed_m_per_s"}, ...]}
+//! ```
+//!
+//! Traffic records cover only the text protocols on TCP ports (mount servers and target feeds), i.e., what the
+//! traffic monitor captures. Clients using the binary protocol, gRPC, a local socket or the pseudo-terminal are
+//! not recorded; their effect is still visible in the mount samples.
+//!
+//! During replay the recorded targets are published by the target source and the mounts are moved to the
+//! recorded axis positions; the playback position is shared with the GUI (see `SessionReplay`).
+
+use cgmath::{Deg, EuclideanSpace, InnerSpace};
+use crate::workers::{
+    Mount,
+    source_manager::{TargetSource, TargetState},
+    target_source::{lat_lon, TargetControl},
+    target_subscription::TargetKind,
+    traffic_monitor::{TrafficDirection, TrafficEntry, TrafficMonitor}
+};
+use pointing_utils::{GeoPos, LatLon, uom};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+use uom::{si::f64, si::{angle, angular_velocity, length}};
+
+const SESSION_FORMAT_VERSION: u32 = 1;
+
+/// Rate (Hz) of recorded mount and target samples.
+const SESSION_SAMPLE_RATE: f64 = 20.0;
+
+/// Records are flushed to disk at this interval.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval of updating the mounts during replay.
+const REPLAY_STEP: Duration = Duration::from_millis(20);
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SessionHeader {
+    pub version: u32,
+    pub start: String,
+    pub num_stations: usize
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TrafficSample {
+    pub t: f64,
+    pub link: String,
+    pub to_client: bool,
+    pub text: String
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct MountSample {
+    pub t: f64,
+    pub station: usize,
+    pub axis1_deg: f64,
+    pub axis2_deg: f64,
+    pub axis1_speed_deg_per_s: f64,
+    pub axis2_speed_deg_per_s: f64,
+    pub azimuth_deg: f64,
+    pub altitude_deg: f64
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RecordedTarget {
+    pub id: u32,
+    pub kind: TargetKind,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub elevation_m: f64,
+    pub track_deg: f64,
+    /// Ground speed.
+    pub speed_m_per_s: f64,
+    pub vertical_speed_m_per_s: f64
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TargetsSample {
+    pub t: f64,
+    pub targets: Vec<RecordedTarget>
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SessionRecord {
+    Header(SessionHeader),
+    Traffic(TrafficSample),
+    Mount(MountSample),
+    Targets(TargetsSample)
+}
+
+impl RecordedTarget {
+    fn new(target: &TargetState) -> RecordedTarget {
+        let lat_lon = lat_lon(&target.pos);
+        let up = target.pos.0.to_vec().normalize();
+        let vertical_speed = target.velocity.0.dot(up);
+        RecordedTarget{
+            id: target.id,
+            kind: target.kind,
+            lat_deg: lat_lon.lat.0,
+            lon_deg: lat_lon.lon.0,
+            elevation_m: target.elevation.get::<length::meter>(),
+            track_deg: target.track.0,
+            speed_m_per_s: (target.velocity.0 - up * vertical_speed).magnitude(),
+            vertical_speed_m_per_s: vertical_speed
+        }
+    }
+
+    /// Interpolates linearly between `self` (`f` = 0) and `other` (`f` = 1), which describe the same target.
+    fn interpolate(&self, other: &RecordedTarget, f: f64) -> RecordedTarget {
+        let lerp = |a: f64, b: f64| a + (b - a) * f;
+        // take the shorter way around if the target crosses the antimeridian
+        let lon_diff = (other.lon_deg - self.lon_deg + 180.0).rem_euclid(360.0) - 180.0;
+        let lon_deg = (self.lon_deg + lon_diff * f + 180.0).rem_euclid(360.0) - 180.0;
+        RecordedTarget{
+            lat_deg: lerp(self.lat_deg, other.lat_deg),
+            lon_deg,
+            elevation_m: lerp(self.elevation_m, other.elevation_m),
+            ..self.clone()
+        }
+    }
+
+    fn state(&self) -> TargetState {
+        let geo_pos = GeoPos{
+            lat_lon: LatLon::new(Deg(self.lat_deg), Deg(self.lon_deg)),
+            elevation: f64::Length::new::<length::meter>(self.elevation_m)
+        };
+        TargetState::from_geo(
+            self.id,
+            self.kind,
+            &geo_pos,
+            Deg(self.track_deg),
+            self.speed_m_per_s,
+            self.vertical_speed_m_per_s
+        )
+    }
+}
+
+impl MountSample {
+    fn new(t: f64, station: usize, mount: &Mount) -> MountSample {
+        let state = mount.get();
+        MountSample{
+            t,
+            station,
+            axis1_deg: state.axis1_pos.get::<angle::degree>(),
+            axis2_deg: state.axis2_pos.get::<angle::degree>(),
+            axis1_speed_deg_per_s: state.axis1_spd.get::<angular_velocity::degree_per_second>(),
+            axis2_speed_deg_per_s: state.axis2_spd.get::<angular_velocity::degree_per_second>(),
+            azimuth_deg: state.boresight_az.get::<angle::degree>(),
+            altitude_deg: state.boresight_alt.get::<angle::degree>()
+        }
+    }
+}
+
+fn write_record(file: &mut BufWriter<File>, record: &SessionRecord) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *file, record)?;
+    file.write_all(b"\n")
+}
+
+/// Records the traffic of `traffic_monitor`, states of `mounts` (of all stations) and the target truth (from
+/// `target_control`) to a new session file at `path`; does not return.
+pub fn session_recorder(
+    path: &Path,
+    mounts: Vec<Arc<Mount>>,
+    target_control: Arc<Mutex<TargetControl>>,
+    traffic_monitor: TrafficMonitor
+) {
+    let mut file = match File::create(path) {
+        Ok(file) => BufWriter::new(file),
+        Err(e) => { log::error!("failed to create session file {}: {}", path.display(), e); return; }
+    };
+    let start_time = chrono::Local::now();
+    let header = SessionRecord::Header(SessionHeader{
+        version: SESSION_FORMAT_VERSION,
+        start: start_time.to_rfc3339(),
+        num_stations: mounts.len()
+    });
+    if let Err(e) = write_record(&mut file, &header) {
+        log::error!("failed to write to session file: {}", e);
+        return;
+    }
+    let (sender, receiver) = crossbeam::channel::unbounded::<TrafficEntry>();
+    traffic_monitor.add_listener(sender);
+    log::info!("recording session to {}", path.display());
+
+    let interval = Duration::from_secs_f64(1.0 / SESSION_SAMPLE_RATE);
+    let start = Instant::now();
+    let mut next_sample = start;
+    let mut last_flush = start;
+    loop {
+        let t = start.elapsed().as_secs_f64();
+        let mut records: Vec<SessionRecord> = receiver.try_iter().map(|entry| {
+            SessionRecord::Traffic(TrafficSample{
+                t: (entry.time - start_time).num_microseconds().unwrap_or(0) as f64 * 1.0e-6,
+                link: entry.link,
+                to_client: entry.direction == TrafficDirection::ToClient,
+                text: entry.text
+            })
+        }).collect();
+        for (station, mount) in mounts.iter().enumerate() {
+            records.push(SessionRecord::Mount(MountSample::new(t, station, mount)));
+        }
+        let targets = target_control.lock().unwrap().targets.iter().map(RecordedTarget::new).collect();
+        records.push(SessionRecord::Targets(TargetsSample{ t, targets }));
+
+        let mut result = records.iter().try_for_each(|record| write_record(&mut file, record));
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            result = result.and_then(|_| file.flush());
+            last_flush = Instant::now();
+        }
+        if let Err(e) = result {
+            log::error!("failed to write to session file, recording stopped: {}", e);
+            return;
+        }
+
+        next_sample += interval;
+        let now = Instant::now();
+        if next_sample > now { std::thread::sleep(next_sample - now); } else { next_sample = now; }
+    }
+}
+
+/// Recorded session.
+pub struct Session {
+    pub header: SessionHeader,
+    /// Sorted by time.
+    pub traffic: Vec<TrafficSample>,
+    /// Samples of each station, sorted by time.
+    mounts: Vec<Vec<MountSample>>,
+    /// Sorted by time.
+    targets: Vec<TargetsSample>,
+    /// Time of the last record (s).
+    pub duration: f64
+}
+
+impl Session {
+    pub fn load(path: &Path) -> Result<Session, String> {
+        let reader = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+        let mut header = None;
+        let mut traffic = vec![];
+        let mut mounts = vec![];
+        let mut targets = vec![];
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            // the last line may be incomplete if recording was interrupted
+            let record = match serde_json::from_str::<SessionRecord>(&line) {
+                Ok(record) => record,
+                Err(e) => { log::warn!("{}: skipping line {}: {}", path.display(), i + 1, e); continue; }
+            };
+            match record {
+                SessionRecord::Header(h) => {
+                    if h.version != SESSION_FORMAT_VERSION {
+                        return Err(format!("unsupported session format version {}", h.version));
+                    }
+                    mounts.resize_with(h.num_stations, Vec::new);
+                    header = Some(h);
+                },
+                SessionRecord::Traffic(sample) => traffic.push(sample),
+                SessionRecord::Mount(sample) => match mounts.get_mut(sample.station) {
+                    Some(samples) => samples.push(sample),
+                    None => return Err(format!("line {}: invalid station {}", i + 1, sample.station))
+                },
+                SessionRecord::Targets(sample) => targets.push(sample)
+            }
+        }
+        let header = header.ok_or("not a session file (no header)")?;
+
+        traffic.sort_by(|a, b| a.t.total_cmp(&b.t));
+        for samples in &mut mounts { samples.sort_by(|a, b| a.t.total_cmp(&b.t)); }
+        targets.sort_by(|a, b| a.t.total_cmp(&b.t));
+        let duration = mounts.iter().filter_map(|samples| samples.last().map(|s| s.t))
+            .chain(targets.last().map(|s| s.t))
+            .chain(traffic.last().map(|s| s.t))
+            .fold(0.0, |a: f64, b| a.max(b));
+
+        Ok(Session{ header, traffic, mounts, targets, duration })
+    }
+
+    /// Returns the last mount sample of `station` at or before `t`.
+    pub fn mount_sample(&self, station: usize, t: f64) -> Option<&MountSample> {
+        let samples = self.mounts.get(station)?;
+        let i = samples.partition_point(|s| s.t <= t);
+        if i == 0 { None } else { Some(&samples[i - 1]) }
+    }
+
+    /// Returns targets at `t` (interpolated between the neighboring samples).
+    fn targets(&self, t: f64) -> Vec<TargetState> {
+        let i = self.targets.partition_point(|s| s.t <= t);
+        if i == 0 { return vec![]; }
+        let s0 = &self.targets[i - 1];
+        let s1 = match self.targets.get(i) {
+            Some(s1) => s1,
+            None => return s0.targets.iter().map(RecordedTarget::state).collect()
+        };
+        let f = (t - s0.t) / (s1.t - s0.t);
+        s0.targets.iter().map(|target| {
+            match s1.targets.iter().find(|other| other.id == target.id) {
+                Some(other) => target.interpolate(other, f).state(),
+                None => target.state()
+            }
+        }).collect()
+    }
+}
+
+/// Playback state of a replayed session.
+pub struct Playback {
+    /// Time since the start of the session (s).
+    pub position: f64,
+    pub playing: bool,
+    /// Playback speed relative to real time.
+    pub speed: f64
+}
+
+/// Replayed session; shared by the replay workers and the GUI, which controls the playback.
+#[derive(Clone)]
+pub struct SessionReplay {
+    pub session: Arc<Session>,
+    pub playback: Arc<Mutex<Playback>>
+}
+
+impl SessionReplay {
+    pub fn new(session: Session) -> SessionReplay {
+        SessionReplay{
+            session: Arc::new(session),
+            playback: Arc::new(Mutex::new(Playback{ position: 0.0, playing: true, speed: 1.0 }))
+        }
+    }
+
+    pub fn position(&self) -> f64 { self.playback.lock().unwrap().position }
+}
+
+impl TargetSource for SessionReplay {
+    fn name(&self) -> String { "session replay".into() }
+
+    // the playback position is advanced by `session_replay`
+    fn update(&mut self, _dt: Duration) -> Vec<TargetState> { self.session.targets(self.position()) }
+}
+
+/// Advances the playback of `replay` and moves `mounts` (of all stations) to the recorded positions; does
+/// not return.
+pub fn session_replay(replay: SessionReplay, mounts: Vec<Arc<Mount>>) {
+    if mounts.len() != replay.session.header.num_stations {
+        log::warn!(
+            "session has {} station(s), the simulation {}; only the common ones are replayed",
+            replay.session.header.num_stations, mounts.len()
+        );
+    }
+    for mount in &mounts { mount.unpark(); }
+    // errors are logged once per mount
+    let mut failed = vec![false; mounts.len()];
+    let mut t_last_update = Instant::now();
+    loop {
+        std::thread::sleep(REPLAY_STEP);
+        let position = {
+            let mut playback = replay.playback.lock().unwrap();
+            if playback.playing {
+                playback.position += t_last_update.elapsed().as_secs_f64() * playback.speed;
+                if playback.position >= replay.session.duration {
+                    playback.position = replay.session.duration;
+                    playback.playing = false;
+                }
+            }
+            playback.position
+        };
+        t_last_update = Instant::now();
+
+        for (station, mount) in mounts.iter().enumerate() {
+            let sample = match replay.session.mount_sample(station, position) {
+                Some(sample) => sample,
+                None => continue
+            };
+            // continue with the recorded speeds since the sample
+            let dt = position - sample.t;
+            let axis = |pos_deg: f64, speed_deg_per_s: f64| (
+                f64::Angle::new::<angle::degree>(pos_deg + speed_deg_per_s * dt),
+                f64::AngularVelocity::new::<angular_velocity::degree_per_second>(speed_deg_per_s)
+            );
+            let result = mount.snap_to(
+                axis(sample.axis1_deg, sample.axis1_speed_deg_per_s),
+                axis(sample.axis2_deg, sample.axis2_speed_deg_per_s)
+            );
+            match result {
+                Ok(()) => failed[station] = false,
+                Err(e) => if !failed[station] {
+                    log::error!("session replay: cannot move mount {}: {}", station + 1, e);
+                    failed[station] = true;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(lon_deg: f64) -> RecordedTarget {
+        RecordedTarget{
+            id: 1,
+            kind: TargetKind::Aircraft,
+            lat_deg: 10.0,
+            lon_deg,
+            elevation_m: 1000.0,
+            track_deg: 90.0,
+            speed_m_per_s: 200.0,
+            vertical_speed_m_per_s: 0.0
+        }
+    }
+
+    #[test]
+    fn interpolation_crosses_antimeridian() {
+        let (t0, t1) = (target(179.0), target(-179.0));
+        assert!((t0.interpolate(&t1, 0.25).lon_deg - 179.5).abs() < 1.0e-9);
+        assert!((t0.interpolate(&t1, 0.75).lon_deg + 179.5).abs() < 1.0e-9);
+        assert!((t1.interpolate(&t0, 0.25).lon_deg + 179.5).abs() < 1.0e-9);
+    }
+}
@@ -0,0 +1,88 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Pluggable sources of target truth. The target source worker combines the registered sources and publishes
+//! their targets in its feeds; a new kind of source only needs to implement `TargetSource`.
+
+use cgmath::{Basis3, Deg, EuclideanSpace, InnerSpace, Rotation, Rotation3};
+use crate::workers::TargetKind;
+use pointing_utils::{EARTH_RADIUS_M, GeoPos, Global, Point3, Vector3, to_global, uom};
+use std::{collections::HashSet, time::Duration};
+use uom::si::f64;
+
+/// True state of a target at the current time.
+#[derive(Clone)]
+pub struct TargetState {
+    pub id: u32,
+    pub kind: TargetKind,
+    pub pos: Point3<f64, Global>,
+    pub elevation: f64::Length,
+    /// Velocity in m/s.
+    pub velocity: Vector3<f64, Global>,
+    /// Track (clockwise from north).
+    pub track: Deg<f64>
+}
+
+impl TargetState {
+    /// Creates the state of a target at `geo_pos`; speeds in m/s.
+    pub fn from_geo(
+        id: u32,
+        kind: TargetKind,
+        geo_pos: &GeoPos,
+        track: Deg<f64>,
+        ground_speed: f64,
+        vertical_speed: f64
+    ) -> TargetState {
+        let pos = to_global(geo_pos);
+        let up = pos.0.to_vec().normalize();
+        let velocity = Vector3::from(track_direction(&pos, track).0 * ground_speed + up * vertical_speed);
+        TargetState{ id, kind, pos, elevation: geo_pos.elevation, velocity, track }
+    }
+}
+
+/// Returns the unit vector pointing along `track` (clockwise from north) at `pos`.
+pub(super) fn track_direction(pos: &Point3<f64, Global>, track: Deg<f64>) -> Vector3<f64, Global> {
+    let north_pole = Point3::<f64, Global>::from_xyz(0.0, 0.0, EARTH_RADIUS_M);
+    let to_north_pole = north_pole.0 - pos.0;
+    let west = pos.0.to_vec().cross(to_north_pole);
+    let north = west.cross(pos.0.to_vec()).normalize();
+    Vector3::from(Basis3::from_axis_angle(pos.0.to_vec().normalize(), -track).rotate_vector(north))
+}
+
+/// Producer of target states over time.
+pub trait TargetSource: Send {
+    /// Name used in log messages.
+    fn name(&self) -> String;
+
+    /// Advances the source by `dt`; returns the current states of its targets.
+    fn update(&mut self, dt: Duration) -> Vec<TargetState>;
+}
+
+/// Combines the targets of the registered sources.
+#[derive(Default)]
+pub struct SourceManager {
+    sources: Vec<Box<dyn TargetSource>>
+}
+
+impl SourceManager {
+    pub fn register(&mut self, source: Box<dyn TargetSource>) {
+        log::info!("registered target source: {}", source.name());
+        self.sources.push(source);
+    }
+
+    /// Advances all sources by `dt`; returns the states of their targets. If several sources report a target
+    /// with the same ID, the one registered first takes precedence.
+    pub fn update(&mut self, dt: Duration) -> Vec<TargetState> {
+        let mut ids = HashSet::new();
+        let mut states = vec![];
+        for source in &mut self.sources {
+            states.extend(source.update(dt).into_iter().filter(|state| ids.insert(state.id)));
+        }
+        states
+    }
+}
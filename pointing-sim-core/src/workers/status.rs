@@ -0,0 +1,40 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Status updates sent periodically by worker threads (e.g., for display in the GUI's status bar).
+
+use crate::workers::ClientStatus;
+use std::time::{Duration, Instant};
+
+pub const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+pub enum StatusUpdate {
+    /// State of the target feed served on `port`.
+    TargetFeed{ port: u16, clients: usize, messages_per_s: f64 },
+    /// State of the client connection of the given mount instance.
+    Mount{ instance: usize, client: ClientStatus, messages_per_s: f64 }
+}
+
+pub type StatusSender = crossbeam::channel::Sender<StatusUpdate>;
+
+/// Determines when the next status update is due.
+pub(super) struct StatusTimer {
+    last_update: Instant
+}
+
+impl StatusTimer {
+    pub fn new() -> StatusTimer { StatusTimer{ last_update: Instant::now() } }
+
+    /// Returns the time since the last update if the next one is due.
+    pub fn due(&mut self) -> Option<Duration> {
+        let elapsed = self.last_update.elapsed();
+        if elapsed < STATUS_INTERVAL { return None; }
+        self.last_update = Instant::now();
+        Some(elapsed)
+    }
+}
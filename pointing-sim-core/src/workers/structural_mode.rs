@@ -0,0 +1,88 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Lightly damped oscillatory mode of the gearbox and mount structure, excited by changes of axis acceleration
+//! (most noticeably by rapid stops).
+
+use crate::config::AxisConfig;
+use pointing_utils::uom;
+use std::time::{Duration, Instant};
+use uom::{si::f64, si::angle};
+
+/// Response to an acceleration change is considered decayed when its envelope falls below `exp(-DECAY_EXPONENT)`.
+const DECAY_EXPONENT: f64 = 14.0;
+
+/// Models the output side of an axis as a second-order system driven by the motor side's acceleration:
+/// `x'' + 2ζω x' + ω² x = -a(t)`, where `x` is the deflection of the output relative to the motor.
+pub struct StructuralMode {
+    /// Natural angular frequency (rad/s) and damping ratio; `None` if the mode is disabled.
+    params: Option<(f64, f64)>,
+    /// Changes of acceleration (deg/s²) whose response has not decayed yet; may include the scheduled end
+    /// of the current acceleration phase.
+    steps: Vec<(Instant, f64)>,
+    /// Sum of acceleration changes whose response has decayed (deg/s²).
+    settled_accel: f64,
+    /// Start of the last registered acceleration phase.
+    profile_start: Option<Instant>
+}
+
+impl StructuralMode {
+    pub fn new(config: &AxisConfig) -> StructuralMode {
+        let params = config.resonance_frequency_hz.filter(|f| *f > 0.0).map(|frequency| (
+            2.0 * std::f64::consts::PI * frequency,
+            config.resonance_damping_ratio.clamp(1.0e-3, 0.999)
+        ));
+        StructuralMode{ params, steps: vec![], settled_accel: 0.0, profile_start: None }
+    }
+
+    /// Registers the motor side's acceleration profile: `accel` (deg/s²) from `start` for `duration`,
+    /// zero afterwards.
+    pub fn update(&mut self, now: Instant, start: Instant, accel: f64, duration: Duration) {
+        let (omega, zeta) = match self.params {
+            Some(params) => params,
+            None => return
+        };
+        if self.profile_start == Some(start) { return; }
+        self.profile_start = Some(start);
+
+        // cancel the scheduled end of the previous acceleration phase
+        self.steps.retain(|(t, _)| *t <= start);
+        let current = self.settled_accel + self.steps.iter().map(|(_, value)| value).sum::<f64>();
+        if accel != current { self.steps.push((start, accel - current)); }
+        if accel != 0.0 {
+            // a phase too long to represent (e.g., `Duration::MAX`) never ends
+            if let Some(end) = start.checked_add(duration) { self.steps.push((end, -accel)); }
+        }
+
+        let settled_accel = &mut self.settled_accel;
+        self.steps.retain(|(t, value)| {
+            let decayed = *t <= now && (now - *t).as_secs_f64() * zeta * omega > DECAY_EXPONENT;
+            if decayed { *settled_accel += value; }
+            !decayed
+        });
+    }
+
+    /// Returns the deflection of the axis output relative to the motor side at `now`.
+    pub fn offset(&self, now: Instant) -> f64::Angle {
+        let (omega, zeta) = match self.params {
+            Some(params) => params,
+            None => return f64::Angle::new::<angle::degree>(0.0)
+        };
+
+        let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+        let mut offset = -self.settled_accel / (omega * omega);
+        for (t, value) in self.steps.iter().filter(|(t, _)| *t <= now) {
+            let dt = (now - *t).as_secs_f64();
+            let transient = (-zeta * omega * dt).exp()
+                * ((omega_d * dt).cos() + zeta / (1.0 - zeta * zeta).sqrt() * (omega_d * dt).sin());
+            offset -= value / (omega * omega) * (1.0 - transient);
+        }
+
+        f64::Angle::new::<angle::degree>(offset)
+    }
+}
@@ -0,0 +1,207 @@
+//
+// Pointing Simulator
+// Copyright (c) 2023-2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Simulated targets in level flight: explicitly defined ones and randomly generated ones.
+
+use cgmath::{Basis3, Deg, EuclideanSpace, InnerSpace, Rad, Rotation, Rotation3};
+use crate::workers::{
+    source_manager::{TargetSource, TargetState, track_direction},
+    target_source::{Site, TargetControl, TargetDefinition, TargetMotion},
+    target_subscription::TargetKind
+};
+use pointing_utils::{EARTH_RADIUS_M, GeoPos, Global, LatLon, Point3, Vector3, to_global, uom};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, StandardNormal};
+use rayon::prelude::*;
+use std::{sync::{Arc, Mutex}, time::Duration};
+use uom::{si::f64, si::length};
+
+/// Random-walk intensity of track changes of generated targets (degrees per √s).
+const GENERATED_TARGET_TRACK_NOISE: f64 = 0.5;
+
+/// Generated targets are placed within this distance (in latitude and longitude) from the first station's site.
+const GENERATED_TARGET_MAX_OFFSET: Deg<f64> = Deg(0.5);
+
+fn meters(value: f64) -> f64::Length {
+    f64::Length::new::<length::meter>(value)
+}
+
+/// Simulated target in level flight.
+struct SimTarget {
+    id: u32,
+    kind: TargetKind,
+    pos: Point3<f64, Global>,
+    elevation: f64::Length,
+    track: Deg<f64>,
+    /// Speed in m/s.
+    speed: f64,
+    /// Random-walk intensity of track changes (degrees per √s).
+    track_noise: f64,
+    /// Per-target random number generator; keeps results independent of how targets are distributed
+    /// between threads.
+    rng: StdRng
+}
+
+impl SimTarget {
+    fn from_definition(definition: &TargetDefinition, id: u32, seed: u64) -> SimTarget {
+        let elevation = meters(definition.elevation_m);
+        let lat_lon = LatLon::new(Deg(definition.lat_deg), Deg(definition.lon_deg));
+        SimTarget{
+            id,
+            kind: definition.kind,
+            pos: to_global(&GeoPos{ lat_lon, elevation }),
+            elevation,
+            track: Deg(definition.track_deg),
+            speed: definition.speed_m_per_s,
+            track_noise: definition.track_noise,
+            rng: target_rng(seed, id)
+        }
+    }
+
+    fn step(&mut self, dt: Duration) {
+        if self.track_noise > 0.0 {
+            let noise: f64 = StandardNormal.sample(&mut self.rng);
+            self.track += Deg(self.track_noise * dt.as_secs_f64().sqrt() * noise);
+        }
+
+        // assume level flight
+        let arc_length = dt.as_secs_f64() * self.speed;
+        let travel_angle = Rad(arc_length / (EARTH_RADIUS_M + self.elevation.get::<length::meter>()));
+        let fwd_axis = self.pos.0.to_vec().cross(track_direction(&self.pos, self.track).0).normalize();
+        self.pos = Point3::from(Basis3::from_axis_angle(fwd_axis, travel_angle).rotate_point(self.pos.0));
+    }
+
+    fn state(&self) -> TargetState {
+        TargetState{
+            id: self.id,
+            kind: self.kind,
+            pos: self.pos.clone(),
+            elevation: self.elevation,
+            velocity: Vector3::from(track_direction(&self.pos, self.track).0 * self.speed),
+            track: self.track
+        }
+    }
+}
+
+/// Explicitly defined targets followed by randomly generated ones and those spawned while the simulation runs;
+/// motion of the first defined target is adjustable while the simulation runs.
+pub struct SyntheticTargets {
+    targets: Vec<SimTarget>,
+    num_defined: usize,
+    seed: u64,
+    target_motion: Arc<Mutex<TargetMotion>>,
+    control: Arc<Mutex<TargetControl>>
+}
+
+impl SyntheticTargets {
+    /// Generated targets are placed around `origin`.
+    pub fn new(
+        definitions: &[TargetDefinition],
+        num_generated: usize,
+        origin: &Site,
+        seed: u64,
+        target_motion: Arc<Mutex<TargetMotion>>,
+        control: Arc<Mutex<TargetControl>>
+    ) -> SyntheticTargets {
+        let mut targets: Vec<SimTarget> = definitions.iter().map(|definition| {
+            SimTarget::from_definition(definition, definition.id, seed)
+        }).collect();
+        match next_free_id(&targets) {
+            Some(first_id) => targets.extend(generate_targets(num_generated, seed, first_id, origin)),
+            None if num_generated > 0 => log::error!("cannot generate targets: no free target IDs"),
+            None => ()
+        }
+
+        SyntheticTargets{ targets, num_defined: definitions.len(), seed, target_motion, control }
+    }
+
+    /// Adds the targets requested via `TargetControl`.
+    fn spawn_requested(&mut self) {
+        let requested = std::mem::take(&mut self.control.lock().unwrap().spawn);
+        for definition in &requested {
+            // the successor of every target's ID must exist, so that IDs can be assigned to further targets
+            if definition.id == u32::MAX {
+                log::error!("cannot spawn target: invalid ID {}", definition.id);
+                continue;
+            }
+            let id = if definition.id == 0 || self.targets.iter().any(|target| target.id == definition.id) {
+                match next_free_id(&self.targets) {
+                    Some(id) => id,
+                    None => { log::error!("cannot spawn target: no free target IDs"); continue; }
+                }
+            } else {
+                definition.id
+            };
+            log::info!("spawned target {} ({:?})", id, definition.kind);
+            self.targets.push(SimTarget::from_definition(definition, id, self.seed));
+        }
+    }
+}
+
+impl TargetSource for SyntheticTargets {
+    fn name(&self) -> String {
+        format!("synthetic ({} defined, {} generated)", self.num_defined, self.targets.len() - self.num_defined)
+    }
+
+    fn update(&mut self, dt: Duration) -> Vec<TargetState> {
+        self.spawn_requested();
+        if self.num_defined > 0 {
+            let motion = *self.target_motion.lock().unwrap();
+            self.targets[0].speed = motion.speed;
+            self.targets[0].track_noise = motion.track_noise;
+        }
+        self.targets.par_iter_mut().for_each(|target| target.step(dt));
+        self.targets.par_iter().map(|target| target.state()).collect()
+    }
+}
+
+fn target_rng(seed: u64, id: u32) -> StdRng {
+    StdRng::seed_from_u64(seed ^ (id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Returns the ID following the greatest one of `targets` (1 if there are none); `None` if it would overflow.
+fn next_free_id(targets: &[SimTarget]) -> Option<u32> {
+    targets.iter().map(|target| target.id).max().map_or(Some(1), |id| id.checked_add(1))
+}
+
+/// Generates `count` targets with random positions, altitudes, tracks and speeds; IDs start at `first_id` (fewer
+/// targets are generated if the IDs would overflow).
+fn generate_targets(count: usize, seed: u64, first_id: u32, origin: &Site) -> Vec<SimTarget> {
+    let ids = (0..count).map_while(|i| u32::try_from(i).ok().and_then(|i| first_id.checked_add(i)));
+    let targets: Vec<SimTarget> = ids.map(|id| {
+        let mut rng = target_rng(seed, id);
+        let max_offset = GENERATED_TARGET_MAX_OFFSET.0;
+        let elevation = meters(rng.gen_range(300.0..11000.0));
+        let lat_lon = LatLon::new(
+            origin.lat + Deg(rng.gen_range(-max_offset..max_offset)),
+            origin.lon + Deg(rng.gen_range(-max_offset..max_offset))
+        );
+        let (kind, speed) = match rng.gen_range(0..4) {
+            0 => (TargetKind::Helicopter, rng.gen_range(30.0..70.0)),
+            1 => (TargetKind::Balloon, rng.gen_range(1.0..10.0)),
+            2 => (TargetKind::Drone, rng.gen_range(5.0..30.0)),
+            _ => (TargetKind::Aircraft, rng.gen_range(80.0..250.0))
+        };
+
+        SimTarget{
+            id,
+            kind,
+            pos: to_global(&GeoPos{ lat_lon, elevation }),
+            elevation,
+            track: Deg(rng.gen_range(0.0..360.0)),
+            speed,
+            track_noise: GENERATED_TARGET_TRACK_NOISE,
+            rng
+        }
+    }).collect();
+    if targets.len() < count {
+        log::error!("generated {} of {} targets: no more free target IDs", targets.len(), count);
+    }
+
+    targets
+}
@@ -6,18 +6,17 @@
 // (see the LICENSE file for details).
 //
 
-use crate::workers;
 use pointing_utils::TargetInfoMessage;
 use std::{
     io::BufRead,
     net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream}
 };
 
-pub fn target_receiver(sender: crossbeam::channel::Sender<TargetInfoMessage>) {
+pub fn target_receiver(sender: crossbeam::channel::Sender<TargetInfoMessage>, port: u16) {
     let stream;
     loop {
         if let Ok(s) = TcpStream::connect_timeout(
-            &SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), workers::target_source::TARGET_SOURCE_PORT),
+            &SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port),
             std::time::Duration::from_millis(50)
         ) {
             stream = s;
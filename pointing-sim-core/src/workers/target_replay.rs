@@ -0,0 +1,155 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Replay of recorded target trajectories.
+//!
+//! The replay file is a CSV file with lines:
+//!
+//! `<time (s)>,<target ID>,<kind>,<latitude (deg)>,<longitude (deg)>,<elevation (m)>,<track (deg)>,<speed (m/s)>`
+//!
+//! Empty lines and lines starting with `#` are ignored. Positions are interpolated linearly between the records
+//! of each target; the replay restarts after the last record.
+
+use cgmath::Deg;
+use crate::workers::{source_manager::{TargetSource, TargetState}, target_subscription::TargetKind};
+use pointing_utils::{GeoPos, LatLon, uom};
+use std::{collections::BTreeMap, path::Path, time::Duration};
+use uom::{si::f64, si::length};
+
+#[derive(Clone)]
+struct Record {
+    /// Time since the start of the replay (s).
+    t: f64,
+    lat_deg: f64,
+    lon_deg: f64,
+    elevation_m: f64,
+    track_deg: f64,
+    speed: f64
+}
+
+impl Record {
+    fn interpolate(&self, other: &Record, f: f64) -> Record {
+        let lerp = |a: f64, b: f64| a + (b - a) * f;
+        Record{
+            t: lerp(self.t, other.t),
+            lat_deg: lerp(self.lat_deg, other.lat_deg),
+            lon_deg: lerp(self.lon_deg, other.lon_deg),
+            elevation_m: lerp(self.elevation_m, other.elevation_m),
+            track_deg: if f < 0.5 { self.track_deg } else { other.track_deg },
+            speed: lerp(self.speed, other.speed)
+        }
+    }
+}
+
+/// Recorded trajectory of a target.
+struct Track {
+    kind: TargetKind,
+    /// Sorted by time.
+    records: Vec<Record>
+}
+
+pub struct TargetReplay {
+    name: String,
+    /// Keyed by target ID.
+    tracks: BTreeMap<u32, Track>,
+    duration: f64,
+    /// Set if all records share one timestamp; the replay then shows them continuously instead of restarting.
+    is_snapshot: bool,
+    /// Time since the start of the replay (s).
+    t: f64
+}
+
+fn parse_line(line: &str) -> Result<(u32, TargetKind, Record), String> {
+    let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+    if fields.len() != 8 { return Err(format!("expected 8 fields, got {}", fields.len())); }
+    let value = |i: usize| fields[i].parse::<f64>().map_err(|e| format!("field {}: {}", i + 1, e));
+    Ok((
+        fields[1].parse::<u32>().map_err(|e| format!("invalid target ID: {}", e))?,
+        fields[2].parse::<TargetKind>()?,
+        Record{
+            t: value(0)?,
+            lat_deg: value(3)?,
+            lon_deg: value(4)?,
+            elevation_m: value(5)?,
+            track_deg: value(6)?,
+            speed: value(7)?
+        }
+    ))
+}
+
+impl TargetReplay {
+    pub fn load(path: &Path) -> Result<TargetReplay, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut tracks = BTreeMap::<u32, Track>::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let (id, kind, record) = parse_line(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+            tracks.entry(id).or_insert_with(|| Track{ kind, records: vec![] }).records.push(record);
+        }
+        if tracks.is_empty() { return Err("no target records".into()); }
+
+        for track in tracks.values_mut() { track.records.sort_by(|a, b| a.t.total_cmp(&b.t)); }
+        let duration = tracks.values()
+            .filter_map(|track| track.records.last())
+            .fold(0.0, |duration: f64, r| duration.max(r.t));
+        let is_snapshot = tracks.values().flat_map(|track| &track.records).all(|r| r.t == duration);
+
+        Ok(TargetReplay{
+            name: format!("replay of {}", path.display()),
+            tracks,
+            duration,
+            is_snapshot,
+            t: if is_snapshot { duration } else { 0.0 }
+        })
+    }
+}
+
+impl TargetSource for TargetReplay {
+    fn name(&self) -> String { self.name.clone() }
+
+    fn update(&mut self, dt: Duration) -> Vec<TargetState> {
+        if !self.is_snapshot {
+            self.t += dt.as_secs_f64();
+            if self.t > self.duration {
+                log::info!("{}: restarting", self.name);
+                self.t = 0.0;
+            }
+        }
+
+        let mut states = vec![];
+        for (id, track) in &self.tracks {
+            // targets are present only between their first and last records
+            let i = track.records.partition_point(|r| r.t <= self.t);
+            if i == 0 || (i == track.records.len() && track.records[i - 1].t < self.t) { continue; }
+            let r0 = &track.records[i - 1];
+            let record = match track.records.get(i) {
+                Some(r1) => r0.interpolate(r1, (self.t - r0.t) / (r1.t - r0.t)),
+                None => r0.clone()
+            };
+            let geo_pos = GeoPos{
+                lat_lon: LatLon::new(Deg(record.lat_deg), Deg(record.lon_deg)),
+                elevation: f64::Length::new::<length::meter>(record.elevation_m)
+            };
+            let vertical_speed = match track.records.get(i) {
+                Some(r1) => (r1.elevation_m - r0.elevation_m) / (r1.t - r0.t),
+                None => 0.0
+            };
+            states.push(TargetState::from_geo(
+                *id,
+                track.kind,
+                &geo_pos,
+                Deg(record.track_deg),
+                record.speed,
+                vertical_speed
+            ));
+        }
+
+        states
+    }
+}
@@ -0,0 +1,838 @@
+//
+// Pointing Simulator
+// Copyright (c) 2023-2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Rad};
+use crate::{
+    clock,
+    clock::Clock,
+    refraction::Atmosphere,
+    units,
+    workers::{
+        adsb_cpr::CprQuantizer,
+        binary_protocol,
+        protocol_trace::{TraceFile, Traced},
+        source_manager::{SourceManager, TargetSource, TargetState},
+        status::{StatusSender, StatusTimer, StatusUpdate},
+        synthetic_targets::SyntheticTargets,
+        target_subscription::{Subscription, TargetKind},
+        target_swap::SwapInjector,
+        traffic_monitor,
+        traffic_monitor::{Tapped, TrafficDirection, TrafficMonitor}
+    }
+};
+#[cfg(unix)]
+use crate::workers::local_socket;
+use pointing_utils::{
+    GeoPos,
+    Global,
+    LatLon,
+    Point3,
+    TargetInfoMessage,
+    to_global,
+    to_local_point,
+    to_local_vec,
+    Vector3,
+    uom
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{BufRead, Read, Write},
+    net::TcpListener,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+use uom::{si::f64, si::{angle, length}};
+
+/// Time step of the target truth simulation.
+const TRUTH_DELTA_T: Duration = Duration::from_millis(20);
+
+pub const TARGET_SOURCE_PORT: u16 = 45500;
+
+/// Motion parameters of the default target (ID 1), adjustable while the simulation runs.
+#[derive(Copy, Clone, Debug)]
+pub struct TargetMotion {
+    /// Speed in m/s.
+    pub speed: f64,
+    /// Random-walk intensity of track changes (degrees per √s).
+    pub track_noise: f64
+}
+
+pub const DEFAULT_TARGET_MOTION: TargetMotion = TargetMotion{ speed: 200.0, track_noise: 0.0 };
+
+/// Requests to the target source while the simulation runs, and its current targets.
+#[derive(Default)]
+pub struct TargetControl {
+    /// Targets to be added to the simulated ones; an ID of 0 or one already in use is replaced by a free one.
+    pub spawn: Vec<TargetDefinition>,
+    /// If set, the feeds publish nothing until then (system time, regardless of the target source's clock).
+    pub feed_outage_until: Option<Instant>,
+    /// Current target truth.
+    pub targets: Vec<TargetState>,
+    /// Listeners to be attached to the feeds.
+    pub listeners: Vec<FeedListener>
+}
+
+impl TargetControl {
+    pub fn feed_dropped(&self) -> bool {
+        self.feed_outage_until.map_or(false, |t| Instant::now() < t)
+    }
+}
+
+/// Message published by a feed.
+#[derive(Clone)]
+pub struct FeedMessage {
+    /// ID and kind of the target the message is published as (they differ from those of the target whose data
+    /// it contains if targets have been swapped).
+    pub id: u32,
+    pub kind: TargetKind,
+    /// Published position (e.g., after CPR encoding and decoding).
+    pub lat_lon: LatLon,
+    pub message: TargetInfoMessage,
+    /// Apparent (refracted) azimuth and altitude (deg); set if requested in the subscription.
+    pub apparent_position: Option<(f64, f64)>
+}
+
+/// In-process client of a feed (e.g., the gRPC server); it receives the messages of every update, like a feed's
+/// network clients (subject to the feed's latency, outages etc.).
+pub struct FeedListener {
+    /// Port of the feed.
+    pub port: u16,
+    pub subscription: Subscription,
+    /// Receives the messages matching `subscription`; the listener is detached once the receiver is dropped.
+    pub sender: crossbeam::channel::Sender<Vec<FeedMessage>>
+}
+
+/// Initial state of a simulated target in level flight.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TargetDefinition {
+    pub id: u32,
+    pub kind: TargetKind,
+    pub lat_deg: f64,
+    pub lon_deg: f64,
+    pub elevation_m: f64,
+    /// Initial track (clockwise from north).
+    pub track_deg: f64,
+    pub speed_m_per_s: f64,
+    /// Random-walk intensity of track changes (degrees per √s).
+    pub track_noise: f64
+}
+
+impl Default for TargetDefinition {
+    fn default() -> TargetDefinition {
+        TargetDefinition{
+            id: 1,
+            kind: TargetKind::Aircraft,
+            lat_deg: 0.05,
+            lon_deg: 0.1,
+            elevation_m: 5000.0,
+            track_deg: -90.0,
+            speed_m_per_s: DEFAULT_TARGET_MOTION.speed,
+            track_noise: DEFAULT_TARGET_MOTION.track_noise
+        }
+    }
+}
+
+impl TargetDefinition {
+    /// Returns the default target, placed relative to `site` instead of (0°, 0°).
+    pub fn default_near(site: &Site) -> TargetDefinition {
+        let target = TargetDefinition::default();
+        TargetDefinition{ lat_deg: site.lat.0 + target.lat_deg, lon_deg: site.lon.0 + target.lon_deg, ..target }
+    }
+
+    pub fn motion(&self) -> TargetMotion {
+        TargetMotion{ speed: self.speed_m_per_s, track_noise: self.track_noise }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FeedKind {
+    AdsB,
+    Radar,
+    ImageDetections
+}
+
+/// Observer's location; positions and velocities of targets are published relative to it.
+#[derive(Copy, Clone, Debug)]
+pub struct Site {
+    pub lat: Deg<f64>,
+    pub lon: Deg<f64>,
+    pub elevation: f64::Length
+}
+
+impl Default for Site {
+    fn default() -> Site {
+        Site{ lat: Deg(0.0), lon: Deg(0.0), elevation: meters(0.0) }
+    }
+}
+
+impl Site {
+    pub(super) fn global_pos(&self) -> P3G {
+        to_global(&GeoPos{ lat_lon: LatLon::new(self.lat, self.lon), elevation: self.elevation })
+    }
+
+    /// Returns the target's truth in the local frame of the site (as an ideal feed would publish it).
+    pub fn local_target(&self, target: &TargetState) -> TargetInfoMessage {
+        let observer_pos = self.global_pos();
+        TargetInfoMessage{
+            position: to_local_point(&observer_pos, &target.pos),
+            velocity: to_local_vec(&observer_pos, &target.velocity),
+            track: target.track,
+            altitude: target.elevation
+        }
+    }
+}
+
+/// Target feed served on a dedicated port.
+#[derive(Clone, Debug)]
+pub struct FeedSettings {
+    pub kind: FeedKind,
+    pub port: u16,
+    /// If set, the feed is additionally served on a Unix domain socket at this path.
+    pub socket_path: Option<PathBuf>,
+    /// If set, the feed is additionally served with binary framing (see `binary_protocol`) on this port.
+    pub binary_port: Option<u16>,
+    pub site: Site,
+    /// Age of the published target data.
+    pub latency: Duration,
+    pub update_interval: Duration
+}
+
+pub struct TargetSourceOptions {
+    pub feeds: Vec<FeedSettings>,
+    /// Explicitly defined targets; motion of the first one is controlled by `target_motion`.
+    pub targets: Vec<TargetDefinition>,
+    /// If set, ADS-B feed positions are passed through CPR encoding/decoding with the given probability
+    /// of an ambiguous decoding.
+    pub adsb_cpr_glitch_probability: Option<f64>,
+    /// Number of randomly generated targets simulated in addition to the default one.
+    pub num_generated_targets: usize,
+    /// Seed of the random number generators; runs with the same seed produce the same trajectories.
+    pub seed: u64,
+    /// If set, apparent (refracted) positions are available to subscribers.
+    pub refraction: Option<Atmosphere>,
+    /// If set, data of nearby targets is swapped in each feed, starting with the given probability per update
+    /// and pair of targets.
+    pub target_swap_probability: Option<f64>,
+    pub target_motion: Arc<Mutex<TargetMotion>>,
+    pub target_control: Arc<Mutex<TargetControl>>,
+    /// Sources of targets in addition to the simulated ones (`targets` and the generated targets).
+    pub sources: Vec<Box<dyn TargetSource>>,
+    /// If set, the feeds' client counts and message rates are reported to it.
+    pub status: Option<StatusSender>,
+    /// If set, messages exchanged with feed clients are captured there.
+    pub traffic: Option<TrafficMonitor>,
+    /// If set, each feed connection is recorded there.
+    pub trace_dir: Option<PathBuf>,
+    /// Time source of the target motion and feed timing.
+    pub clock: Arc<dyn Clock>
+}
+
+impl Default for TargetSourceOptions {
+    fn default() -> TargetSourceOptions {
+        TargetSourceOptions{
+            feeds: vec![
+                FeedSettings{
+                    kind: FeedKind::AdsB,
+                    port: TARGET_SOURCE_PORT,
+                    socket_path: None,
+                    binary_port: None,
+                    site: Site::default(),
+                    latency: Duration::from_millis(0),
+                    update_interval: Duration::from_millis(250)
+                },
+                FeedSettings{
+                    kind: FeedKind::Radar,
+                    port: TARGET_SOURCE_PORT + 2,
+                    socket_path: None,
+                    binary_port: None,
+                    site: Site::default(),
+                    latency: Duration::from_millis(1500),
+                    update_interval: Duration::from_secs(4)
+                },
+                FeedSettings{
+                    kind: FeedKind::ImageDetections,
+                    port: TARGET_SOURCE_PORT + 3,
+                    socket_path: None,
+                    binary_port: None,
+                    site: Site::default(),
+                    latency: Duration::from_millis(100),
+                    update_interval: Duration::from_millis(40)
+                }
+            ],
+            targets: vec![TargetDefinition::default()],
+            adsb_cpr_glitch_probability: None,
+            num_generated_targets: 0,
+            seed: 0,
+            refraction: None,
+            target_swap_probability: None,
+            target_motion: Arc::new(Mutex::new(DEFAULT_TARGET_MOTION)),
+            target_control: Arc::new(Mutex::new(TargetControl::default())),
+            sources: vec![],
+            status: None,
+            traffic: None,
+            trace_dir: None,
+            clock: clock::system_clock()
+        }
+    }
+}
+
+impl TargetSourceOptions {
+    /// Adds an ADS-B feed for an additional observer located at `site`.
+    pub fn add_station_feed(&mut self, port: u16, site: Site) {
+        self.feeds.push(FeedSettings{
+            kind: FeedKind::AdsB,
+            port,
+            socket_path: None,
+            binary_port: None,
+            site,
+            latency: Duration::from_millis(0),
+            update_interval: Duration::from_millis(250)
+        });
+    }
+}
+
+type P3G = Point3<f64, Global>;
+type V3G = Vector3<f64, Global>;
+
+fn meters(value: f64) -> f64::Length {
+    f64::Length::new::<length::meter>(value)
+}
+
+/// True target state at a given time.
+#[derive(Clone)]
+struct TruthSample {
+    id: u32,
+    kind: TargetKind,
+    pos: P3G,
+    lat_lon: LatLon,
+    elevation: f64::Length,
+    velocity: V3G,
+    track: Deg<f64>
+}
+
+impl From<TargetState> for TruthSample {
+    fn from(state: TargetState) -> TruthSample {
+        TruthSample{
+            id: state.id,
+            kind: state.kind,
+            lat_lon: lat_lon(&state.pos),
+            pos: state.pos,
+            elevation: state.elevation,
+            velocity: state.velocity,
+            track: state.track
+        }
+    }
+}
+
+pub(super) fn lat_lon(pos: &P3G) -> LatLon {
+    let p = pos.0.to_vec();
+    LatLon::new(Deg::from(Rad((p.z / p.magnitude()).asin())), Deg::from(Rad(p.y.atan2(p.x))))
+}
+
+impl TruthSample {
+    /// Interpolates linearly between `self` (`f` = 0) and `other` (`f` = 1), which describe the same target.
+    fn interpolate(&self, other: &TruthSample, f: f64) -> TruthSample {
+        let pos = P3G::from(self.pos.0 + (other.pos.0 - self.pos.0) * f);
+        TruthSample{
+            id: self.id,
+            kind: self.kind,
+            lat_lon: lat_lon(&pos),
+            pos,
+            elevation: self.elevation + (other.elevation - self.elevation) * f,
+            velocity: V3G::from(self.velocity.0 + (other.velocity.0 - self.velocity.0) * f),
+            track: if f < 0.5 { self.track } else { other.track }
+        }
+    }
+}
+
+type TruthHistory = VecDeque<(Instant, Vec<TruthSample>)>;
+
+/// Returns truth at `t`, interpolated between the nearest recorded samples.
+fn truth_at(history: &TruthHistory, t: Instant) -> Option<Vec<TruthSample>> {
+    match history.iter().position(|(time, _)| *time >= t) {
+        None => history.back().map(|(_, samples)| samples.clone()),
+        Some(0) => history.front().map(|(_, samples)| samples.clone()),
+        Some(i) => {
+            let (t0, samples0) = &history[i - 1];
+            let (t1, samples1) = &history[i];
+            let f = (t - *t0).as_secs_f64() / (*t1 - *t0).as_secs_f64();
+            // the set of targets may change between samples
+            let samples0: HashMap<u32, &TruthSample> = samples0.iter().map(|s0| (s0.id, s0)).collect();
+            Some(samples1.iter()
+                .map(|s1| match samples0.get(&s1.id) {
+                    Some(s0) => s0.interpolate(s1, f),
+                    None => s1.clone()
+                })
+                .collect())
+        }
+    }
+}
+
+/// Encoding of messages exchanged with a feed client.
+#[derive(Copy, Clone, PartialEq)]
+enum Framing {
+    /// Newline-delimited text messages.
+    Text,
+    /// See `binary_protocol`.
+    Binary
+}
+
+/// Where the messages of a feed client go.
+enum Output {
+    Stream{ stream: Box<dyn Write + Send>, framing: Framing },
+    Listener(crossbeam::channel::Sender<Vec<FeedMessage>>)
+}
+
+struct Client {
+    output: Output,
+    subscription: Arc<Mutex<Subscription>>,
+    last_sent: Option<Instant>,
+    /// Time of the next message (for clients requesting a fixed output rate).
+    next_due: Option<Instant>,
+    /// Number of messages sent since the last status update.
+    num_sent: usize
+}
+
+/// Sends those of `messages` which match the client's subscription at `now`; returns false if the client has
+/// disconnected.
+fn send_to_client(
+    client: &mut Client,
+    subscription: &Subscription,
+    messages: &[(&TruthSample, FeedMessage)],
+    refraction: Option<Atmosphere>,
+    now: Instant
+) -> bool {
+    let matching = messages.iter()
+        .filter(|(s, _)| subscription.matches(s.id, s.kind, &s.lat_lon))
+        .map(|(_, msg)| FeedMessage{
+            apparent_position: refraction.as_ref()
+                .filter(|_| subscription.apparent_position)
+                .map(|atmosphere| apparent_direction(&msg.message, atmosphere)),
+            ..msg.clone()
+        });
+
+    match &mut client.output {
+        Output::Stream{ stream, framing } => for msg in matching {
+            let contents = match framing {
+                Framing::Text => {
+                    let mut contents = msg.message.to_string();
+                    if let Some((azimuth, altitude)) = msg.apparent_position {
+                        contents += &format!("apparent_position;{};{}\n", azimuth, altitude);
+                    }
+                    contents.into_bytes()
+                },
+                Framing::Binary => binary_protocol::target_frame(&msg.message, msg.apparent_position)
+            };
+            if let Err(e) = stream.write_all(&contents) {
+                log::info!("error sending data ({}), disconnecting from client", e);
+                return false;
+            }
+            client.num_sent += 1;
+        },
+
+        Output::Listener(sender) => {
+            let matching: Vec<FeedMessage> = matching.collect();
+            client.num_sent += matching.len();
+            // fails once the listener has been dropped
+            if sender.send(matching).is_err() { return false; }
+        }
+    }
+    client.last_sent = Some(now);
+
+    true
+}
+
+fn set_subscription(received: Result<Subscription, String>, subscription: &Mutex<Subscription>) {
+    match received {
+        Ok(s) => {
+            log::info!("client subscribed: {:?}", s);
+            *subscription.lock().unwrap() = s;
+        },
+        Err(e) => log::error!("error parsing subscription message: {}", e)
+    }
+}
+
+/// Receives subscription messages from a target feed client.
+fn subscription_receiver<R: Read>(stream: R, subscription: Arc<Mutex<Subscription>>, framing: Framing) {
+    let mut reader = std::io::BufReader::new(stream);
+    match framing {
+        Framing::Text => for line in reader.lines() {
+            match line {
+                Ok(line) => set_subscription(line.parse::<Subscription>(), &subscription),
+                Err(_) => break
+            }
+        },
+
+        Framing::Binary => while let Ok(Some(payload)) = binary_protocol::read_frame(&mut reader) {
+            set_subscription(binary_protocol::decode_subscription(&payload), &subscription);
+        }
+    }
+}
+
+/// Registers a newly connected client of the feed on `port`; `reader` (a clone of `stream`) receives its
+/// subscription messages. If `trace_dir` is set, the connection is recorded there as `connection`.
+fn add_client<S: Read + Write + Send + 'static>(
+    clients: &Mutex<Vec<Client>>,
+    stream: S,
+    reader: std::io::Result<S>,
+    port: u16,
+    traffic: Option<&TrafficMonitor>,
+    framing: Framing,
+    trace_dir: Option<&PathBuf>,
+    connection: &str
+) {
+    let link = format!("target feed {}", port);
+    let trace = trace_dir.and_then(|dir| TraceFile::create(dir, connection));
+    // binary messages are not captured
+    let traffic = traffic.filter(|_| framing == Framing::Text);
+    let subscription = Arc::new(Mutex::new(Subscription::default()));
+    match reader {
+        Ok(reader) => {
+            let reader = Tapped::new(
+                Traced::new(reader, trace.clone()),
+                traffic,
+                &link,
+                TrafficDirection::FromClient,
+                traffic_monitor::parse_subscription
+            );
+            let subscription = Arc::clone(&subscription);
+            std::thread::spawn(move || subscription_receiver(reader, subscription, framing));
+        },
+        Err(e) => log::error!("cannot receive subscriptions from client: {}", e)
+    }
+    let stream = Box::new(Tapped::new(
+        Traced::new(stream, trace),
+        traffic,
+        &link,
+        TrafficDirection::ToClient,
+        traffic_monitor::parse_target_message
+    ));
+    clients.lock().unwrap().push(Client::new(Output::Stream{ stream, framing }, subscription));
+}
+
+impl Client {
+    fn new(output: Output, subscription: Arc<Mutex<Subscription>>) -> Client {
+        Client{ output, subscription, last_sent: None, next_due: None, num_sent: 0 }
+    }
+}
+
+struct Feed {
+    settings: FeedSettings,
+    observer_pos: P3G,
+    clients: Arc<Mutex<Vec<Client>>>,
+    last_update: Option<Instant>,
+    /// Per-target CPR state (ADS-B feed only).
+    cpr: Option<HashMap<u32, CprQuantizer>>,
+    swap_injector: Option<SwapInjector>
+}
+
+impl Feed {
+    fn new(
+        settings: FeedSettings,
+        adsb_cpr_glitch_probability: Option<f64>,
+        swap_injector: Option<SwapInjector>,
+        traffic: Option<TrafficMonitor>,
+        trace_dir: Option<PathBuf>
+    ) -> Feed {
+        let clients = Arc::new(Mutex::new(Vec::<Client>::new()));
+
+        let clients2 = Arc::clone(&clients);
+        let traffic2 = traffic.clone();
+        let trace_dir2 = trace_dir.clone();
+        let port = settings.port;
+        let kind = settings.kind;
+        std::thread::spawn(move || {
+            log::info!("waiting for clients of {:?} feed", kind);
+            let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).unwrap();
+            loop {
+                let (stream, _) = listener.accept().unwrap();
+                log::info!("client of {:?} feed connected", kind);
+                let reader = stream.try_clone();
+                add_client(
+                    &clients2, stream, reader, port, traffic2.as_ref(), Framing::Text, trace_dir2.as_ref(),
+                    &format!("feed-{}", port)
+                );
+            }
+        });
+
+        if let Some(binary_port) = settings.binary_port {
+            let clients2 = Arc::clone(&clients);
+            let trace_dir2 = trace_dir.clone();
+            std::thread::spawn(move || {
+                let listener = match TcpListener::bind(("127.0.0.1", binary_port)) {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        log::error!("cannot serve binary {:?} feed on port {}: {}", kind, binary_port, e);
+                        return;
+                    }
+                };
+                log::info!("waiting for binary protocol clients of {:?} feed", kind);
+                loop {
+                    let stream = match listener.accept() {
+                        Ok((stream, _)) => stream,
+                        Err(e) => { log::error!("error accepting client of {:?} feed: {}", kind, e); continue; }
+                    };
+                    log::info!("binary protocol client of {:?} feed connected", kind);
+                    let reader = stream.try_clone();
+                    add_client(
+                        &clients2, stream, reader, port, None, Framing::Binary, trace_dir2.as_ref(),
+                        &format!("feed-binary-{}", binary_port)
+                    );
+                }
+            });
+        }
+
+        if let Some(path) = settings.socket_path.clone() {
+            #[cfg(unix)]
+            {
+                let clients2 = Arc::clone(&clients);
+                let traffic2 = traffic.clone();
+                let trace_dir2 = trace_dir.clone();
+                std::thread::spawn(move || {
+                    let listener = match local_socket::bind(&path) {
+                        Ok(listener) => listener,
+                        Err(e) => { log::error!("cannot listen on {}: {}", path.display(), e); return; }
+                    };
+                    log::info!("waiting for clients of {:?} feed on {}", kind, path.display());
+                    loop {
+                        let stream = match listener.accept() {
+                            Ok((stream, _)) => stream,
+                            Err(e) => { log::error!("error accepting client of {:?} feed: {}", kind, e); continue; }
+                        };
+                        log::info!("client of {:?} feed connected via local socket", kind);
+                        let reader = stream.try_clone();
+                        add_client(
+                            &clients2, stream, reader, port, traffic2.as_ref(), Framing::Text, trace_dir2.as_ref(),
+                            &format!("feed-local-socket-{}", port)
+                        );
+                    }
+                });
+            }
+            #[cfg(not(unix))]
+            log::error!("local socket transport is not supported on this platform ({})", path.display());
+        }
+
+        let cpr = if settings.kind == FeedKind::AdsB && adsb_cpr_glitch_probability.is_some() {
+            Some(HashMap::new())
+        } else {
+            None
+        };
+
+        let observer_pos = settings.site.global_pos();
+
+        Feed{ settings, observer_pos, clients, last_update: None, cpr, swap_injector }
+    }
+
+    /// Returns the message to publish and the published position; `None` if the position is not available
+    /// (e.g. an ADS-B position cannot be decoded yet).
+    fn message(
+        &mut self,
+        sample: &TruthSample,
+        adsb_cpr_glitch_probability: Option<f64>
+    ) -> Option<(TargetInfoMessage, LatLon)> {
+        let (pos, altitude) = match (&mut self.cpr, adsb_cpr_glitch_probability) {
+            (Some(cpr), Some(glitch_probability)) => {
+                let quantizer = cpr.entry(sample.id).or_insert_with(|| CprQuantizer::new(glitch_probability));
+                let (lat_lon, altitude) = quantizer.quantize(&sample.lat_lon, sample.elevation)?;
+                (to_global(&GeoPos{ lat_lon, elevation: altitude }), altitude)
+            },
+
+            _ => (sample.pos.clone(), sample.elevation)
+        };
+
+        Some((
+            TargetInfoMessage{
+                position: to_local_point(&self.observer_pos, &pos),
+                velocity: to_local_vec(&self.observer_pos, &sample.velocity),
+                track: sample.track,
+                altitude
+            },
+            lat_lon(&pos)
+        ))
+    }
+
+    /// Returns messages to publish for `samples`, each paired with the sample of the target it is published as.
+    fn messages<'a>(
+        &mut self,
+        samples: &'a [TruthSample],
+        adsb_cpr_glitch_probability: Option<f64>
+    ) -> Vec<(&'a TruthSample, FeedMessage)> {
+        let permutation = match &mut self.swap_injector {
+            Some(injector) => injector.permutation(&samples.iter().map(|s| (s.id, s.pos.clone())).collect::<Vec<_>>()),
+            None => (0..samples.len()).collect()
+        };
+        // a swapped target is published with the other one's data
+        samples.iter().zip(permutation)
+            .filter_map(|(s, source)| {
+                let (message, lat_lon) = self.message(&samples[source], adsb_cpr_glitch_probability)?;
+                Some((s, FeedMessage{ id: s.id, kind: s.kind, lat_lon, message, apparent_position: None }))
+            })
+            .collect()
+    }
+
+    /// Publishes `samples` to clients not requesting a fixed output rate.
+    fn publish(
+        &mut self,
+        samples: &[TruthSample],
+        adsb_cpr_glitch_probability: Option<f64>,
+        refraction: Option<Atmosphere>,
+        now: Instant
+    ) {
+        let messages = self.messages(samples, adsb_cpr_glitch_probability);
+
+        self.clients.lock().unwrap().retain_mut(|client| {
+            let subscription = client.subscription.lock().unwrap().clone();
+            if subscription.rate.is_some() { return true; }
+            if let (Some(last_sent), Some(min_interval)) = (client.last_sent, subscription.min_interval()) {
+                if now - last_sent < min_interval { return true; }
+            }
+
+            send_to_client(client, &subscription, &messages, refraction, now)
+        });
+    }
+
+    /// Returns the number of clients and of messages sent since the previous call.
+    fn take_stats(&self) -> (usize, usize) {
+        let mut clients = self.clients.lock().unwrap();
+        let num_sent = clients.iter_mut().map(|client| std::mem::take(&mut client.num_sent)).sum();
+        (clients.len(), num_sent)
+    }
+
+    /// Publishes truth resampled at fixed intervals to clients requesting a fixed output rate; to be called
+    /// after every truth update.
+    fn publish_resampled(
+        &mut self,
+        history: &TruthHistory,
+        adsb_cpr_glitch_probability: Option<f64>,
+        refraction: Option<Atmosphere>,
+        now: Instant
+    ) {
+        let clients = Arc::clone(&self.clients);
+        clients.lock().unwrap().retain_mut(|client| {
+            let subscription = client.subscription.lock().unwrap().clone();
+            let interval = match subscription.output_interval() {
+                Some(interval) => interval,
+                None => return true
+            };
+            let due = client.next_due.unwrap_or(now);
+            if due > now { return true; }
+
+            // if lagging by more than one interval (e.g., the rate exceeds that of the truth), resynchronize
+            client.next_due = Some(if now - due > interval { now + interval } else { due + interval });
+
+            let samples = match truth_at(history, due.checked_sub(self.settings.latency).unwrap_or(due)) {
+                Some(samples) => samples,
+                None => return true
+            };
+            let messages = self.messages(&samples, adsb_cpr_glitch_probability);
+            send_to_client(client, &subscription, &messages, refraction, now)
+        });
+    }
+}
+
+/// Returns the apparent azimuth and altitude of the target (degrees).
+fn apparent_direction(msg: &TargetInfoMessage, atmosphere: &Atmosphere) -> (f64, f64) {
+    let p = atmosphere.apparent_position(msg.position.0.to_vec());
+    let azimuth = units::azimuth(p).get::<angle::degree>().rem_euclid(360.0);
+    let altitude = p.z.atan2(p.x.hypot(p.y)).to_degrees();
+    (azimuth, altitude)
+}
+
+pub fn target_source(mut options: TargetSourceOptions) {
+    let mut feeds: Vec<Feed> = options.feeds.iter().enumerate()
+        .map(|(i, settings)| Feed::new(
+            settings.clone(),
+            options.adsb_cpr_glitch_probability,
+            options.target_swap_probability.map(|p| SwapInjector::new(p, options.seed ^ (i as u64 + 1))),
+            options.traffic.clone(),
+            options.trace_dir.clone()
+        ))
+        .collect();
+    let max_latency = options.feeds.iter().map(|f| f.latency).max().unwrap_or(Duration::ZERO);
+
+    // the simulated targets take precedence in case of conflicting IDs
+    let mut sources = SourceManager::default();
+    sources.register(Box::new(SyntheticTargets::new(
+        &options.targets,
+        options.num_generated_targets,
+        &options.feeds.first().map_or(Site::default(), |feed| feed.site),
+        options.seed,
+        Arc::clone(&options.target_motion),
+        Arc::clone(&options.target_control)
+    )));
+    for source in std::mem::take(&mut options.sources) { sources.register(source); }
+
+    let mut history = TruthHistory::new();
+
+    let clock = Arc::clone(&options.clock);
+    let mut t_last_update = clock.now();
+    let mut status_timer = StatusTimer::new();
+    loop {
+        let dt = clock.since(t_last_update);
+        t_last_update = clock.now();
+        let states = sources.update(dt);
+        let feed_dropped = {
+            let mut control = options.target_control.lock().unwrap();
+            control.targets = states.clone();
+            for listener in control.listeners.drain(..) {
+                match feeds.iter_mut().find(|feed| feed.settings.port == listener.port) {
+                    Some(feed) => feed.clients.lock().unwrap().push(Client::new(
+                        Output::Listener(listener.sender),
+                        Arc::new(Mutex::new(listener.subscription))
+                    )),
+                    None => log::error!("cannot attach listener: no target feed on port {}", listener.port)
+                }
+            }
+            control.feed_dropped()
+        };
+        let samples = states.into_iter().map(TruthSample::from).collect();
+        history.push_back((t_last_update, samples));
+
+        let step_time = clock.since(t_last_update);
+        if step_time > TRUTH_DELTA_T {
+            log::warn!("target simulation step took {:.1} ms", step_time.as_secs_f64() * 1000.0);
+        }
+
+        while history.len() > 1 && t_last_update.duration_since(history[1].0) > max_latency {
+            history.pop_front();
+        }
+
+        // during a feed outage the targets keep being simulated, but nothing is published
+        if !feed_dropped {
+            for feed in &mut feeds {
+                let now = clock.now();
+                feed.publish_resampled(&history, options.adsb_cpr_glitch_probability, options.refraction, now);
+
+                if feed.last_update.map_or(false, |t| now - t < feed.settings.update_interval) { continue; }
+
+                // use the newest truth which is at least as old as the feed's latency
+                let delayed = history.iter().rev().find(|(t, _)| now - *t >= feed.settings.latency);
+                if let Some((_, samples)) = delayed {
+                    feed.publish(samples, options.adsb_cpr_glitch_probability, options.refraction, now);
+                    feed.last_update = Some(now);
+                }
+            }
+        }
+
+        if let (Some(status), Some(elapsed)) = (&options.status, status_timer.due()) {
+            for feed in &feeds {
+                let (clients, num_sent) = feed.take_stats();
+                let _ = status.send(StatusUpdate::TargetFeed{
+                    port: feed.settings.port,
+                    clients,
+                    messages_per_s: num_sent as f64 / elapsed.as_secs_f64()
+                });
+            }
+        }
+
+        std::thread::sleep(TRUTH_DELTA_T);
+    }
+}
@@ -0,0 +1,167 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use cgmath::Deg;
+use pointing_utils::LatLon;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Rates (Hz) requested in subscriptions are clamped to at least this value.
+const MIN_RATE: f64 = 1.0e-3;
+
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    Aircraft,
+    Helicopter,
+    Balloon,
+    Drone
+}
+
+impl TargetKind {
+    pub const ALL: [TargetKind; 4] = [
+        TargetKind::Aircraft,
+        TargetKind::Helicopter,
+        TargetKind::Balloon,
+        TargetKind::Drone
+    ];
+}
+
+impl FromStr for TargetKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TargetKind, String> {
+        match s {
+            "aircraft" => Ok(TargetKind::Aircraft),
+            "helicopter" => Ok(TargetKind::Helicopter),
+            "balloon" => Ok(TargetKind::Balloon),
+            "drone" => Ok(TargetKind::Drone),
+            _ => Err(format!("unknown target kind: {}", s))
+        }
+    }
+}
+
+/// Geographic region (inclusive).
+#[derive(Clone, Debug)]
+pub struct Region {
+    pub lat_min: Deg<f64>,
+    pub lon_min: Deg<f64>,
+    pub lat_max: Deg<f64>,
+    pub lon_max: Deg<f64>
+}
+
+impl Region {
+    pub fn contains(&self, lat_lon: &LatLon) -> bool {
+        lat_lon.lat >= self.lat_min && lat_lon.lat <= self.lat_max &&
+            lat_lon.lon >= self.lon_min && lat_lon.lon <= self.lon_max
+    }
+}
+
+/// Target feed subscription of a single client.
+///
+/// Sent by the client as a single line:
+///
+/// `subscribe [ids=<id>,...] [types=<kind>,...] [region=<lat_min>,<lon_min>,<lat_max>,<lon_max>] [max_rate=<Hz>]
+/// [rate=<Hz>] [apparent_position=<true|false>]`
+///
+/// Omitted criteria do not restrict the feed; angles are in degrees.
+#[derive(Clone, Debug, Default)]
+pub struct Subscription {
+    pub ids: Option<Vec<u32>>,
+    pub kinds: Option<Vec<TargetKind>>,
+    pub region: Option<Region>,
+    pub max_rate: Option<f64>,
+    /// Fixed output rate, independent of the feed's update interval (up to the truth simulation rate of 50 Hz);
+    /// the truth is decimated or interpolated accordingly. Overrides `max_rate`.
+    pub rate: Option<f64>,
+    /// If set (and refraction is enabled), each target message is followed by
+    /// `apparent_position;<azimuth>;<altitude>` (degrees).
+    pub apparent_position: bool
+}
+
+impl Subscription {
+    pub fn matches(&self, id: u32, kind: TargetKind, lat_lon: &LatLon) -> bool {
+        self.ids.as_ref().map_or(true, |ids| ids.contains(&id))
+            && self.kinds.as_ref().map_or(true, |kinds| kinds.contains(&kind))
+            && self.region.as_ref().map_or(true, |region| region.contains(lat_lon))
+    }
+
+    /// Returns minimum interval between consecutive messages sent to the client.
+    pub fn min_interval(&self) -> Option<std::time::Duration> {
+        self.max_rate.and_then(|rate| std::time::Duration::try_from_secs_f64(1.0 / rate.max(MIN_RATE)).ok())
+    }
+
+    /// Returns interval between consecutive messages if a fixed output rate is requested.
+    pub fn output_interval(&self) -> Option<std::time::Duration> {
+        self.rate.and_then(|rate| std::time::Duration::try_from_secs_f64(1.0 / rate.max(MIN_RATE)).ok())
+    }
+}
+
+/// Returns `rate` if it is positive; `name` identifies it in the error message.
+pub(super) fn positive_rate(rate: f64, name: &str) -> Result<f64, String> {
+    if rate.is_nan() || rate <= 0.0 {
+        Err(format!("{} must be positive: {}", name, rate))
+    } else {
+        Ok(rate)
+    }
+}
+
+fn parse_list<T: FromStr>(s: &str) -> Result<Vec<T>, String> where T::Err: std::fmt::Display {
+    s.split(',').map(|item| item.parse::<T>().map_err(|e| format!("invalid value \"{}\": {}", item, e))).collect()
+}
+
+impl FromStr for Subscription {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Subscription, String> {
+        let mut tokens = s.split_whitespace();
+        if tokens.next() != Some("subscribe") {
+            return Err(format!("not a subscription message: {}", s));
+        }
+
+        let mut subscription = Subscription::default();
+        for token in tokens {
+            let (key, value) = token.split_once('=').ok_or(format!("malformed criterion: {}", token))?;
+            match key {
+                "ids" => subscription.ids = Some(parse_list::<u32>(value)?),
+
+                "types" => subscription.kinds = Some(parse_list::<TargetKind>(value)?),
+
+                "region" => {
+                    let bounds = parse_list::<f64>(value)?;
+                    if bounds.len() != 4 {
+                        return Err(format!("expected 4 region bounds, got {}", bounds.len()));
+                    }
+                    subscription.region = Some(Region{
+                        lat_min: Deg(bounds[0]),
+                        lon_min: Deg(bounds[1]),
+                        lat_max: Deg(bounds[2]),
+                        lon_max: Deg(bounds[3])
+                    });
+                },
+
+                "max_rate" => {
+                    let rate = value.parse::<f64>().map_err(|e| format!("invalid max. rate: {}", e))?;
+                    subscription.max_rate = Some(positive_rate(rate, "max. rate")?);
+                },
+
+                "rate" => {
+                    let rate = value.parse::<f64>().map_err(|e| format!("invalid rate: {}", e))?;
+                    subscription.rate = Some(positive_rate(rate, "rate")?);
+                },
+
+                "apparent_position" => subscription.apparent_position =
+                    value.parse::<bool>().map_err(|e| format!("invalid value \"{}\": {}", value, e))?,
+
+                _ => return Err(format!("unknown criterion: {}", key))
+            }
+        }
+
+        Ok(subscription)
+    }
+}
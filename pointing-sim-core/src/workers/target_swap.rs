@@ -0,0 +1,74 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Injection of target swaps: the published data of two nearby targets is intermittently exchanged, as happens
+//! with real surveillance sources associating reports with the wrong track.
+
+use cgmath::InnerSpace;
+use pointing_utils::{Global, Point3};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::time::{Duration, Instant};
+
+/// Targets closer than this are candidates for swapping.
+const MAX_SEPARATION: f64 = 3000.0; // m
+
+const MIN_SWAP_DURATION: Duration = Duration::from_secs(2);
+const MAX_SWAP_DURATION: Duration = Duration::from_secs(10);
+
+struct Swap {
+    ids: (u32, u32),
+    end: Instant
+}
+
+pub struct SwapInjector {
+    /// Probability of a swap starting for a given pair of nearby targets on each feed update.
+    probability: f64,
+    active: Vec<Swap>,
+    rng: StdRng
+}
+
+impl SwapInjector {
+    pub fn new(probability: f64, seed: u64) -> SwapInjector {
+        let probability = if probability.is_nan() {
+            log::error!("invalid target swap probability: {}; disabling swaps", probability);
+            0.0
+        } else {
+            probability.clamp(0.0, 1.0)
+        };
+        SwapInjector{ probability, active: vec![], rng: StdRng::seed_from_u64(seed) }
+    }
+
+    /// Returns, for each of `targets` (id and position), the index of the target whose data is to be published
+    /// in its place.
+    pub fn permutation(&mut self, targets: &[(u32, Point3<f64, Global>)]) -> Vec<usize> {
+        let now = Instant::now();
+        self.active.retain(|swap| swap.end > now);
+
+        let is_swapped = |active: &[Swap], id| active.iter().any(|s| s.ids.0 == id || s.ids.1 == id);
+        for (i, (id1, pos1)) in targets.iter().enumerate() {
+            for (id2, pos2) in &targets[i + 1..] {
+                if is_swapped(&self.active, *id1) || is_swapped(&self.active, *id2) { continue; }
+                if (pos2.0 - pos1.0).magnitude() > MAX_SEPARATION || !self.rng.gen_bool(self.probability) { continue; }
+
+                let duration = self.rng.gen_range(MIN_SWAP_DURATION..MAX_SWAP_DURATION);
+                log::info!("swapping targets {} and {} for {:.1} s", id1, id2, duration.as_secs_f64());
+                self.active.push(Swap{ ids: (*id1, *id2), end: now + duration });
+            }
+        }
+
+        let index_of = |id| targets.iter().position(|(i, _)| *i == id);
+        let mut permutation: Vec<usize> = (0..targets.len()).collect();
+        for swap in &self.active {
+            if let (Some(i), Some(j)) = (index_of(swap.ids.0), index_of(swap.ids.1)) {
+                permutation.swap(i, j);
+            }
+        }
+
+        permutation
+    }
+}
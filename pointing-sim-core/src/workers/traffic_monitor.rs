@@ -0,0 +1,195 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Live capture of textual messages exchanged with mount and target feed clients (shown in the GUI's traffic
+//! inspector and passed to listeners, e.g. the session recorder).
+
+use crate::workers::{ext_protocol::ExtMessage, target_subscription::Subscription};
+use pointing_utils::{MountSimulatorMessage, TargetInfoMessage};
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    sync::{Arc, Mutex, MutexGuard, atomic::{AtomicBool, Ordering}}
+};
+
+/// The oldest entries are discarded above this number.
+const MAX_ENTRIES: usize = 2000;
+
+/// Longer incomplete lines are recorded as they are.
+const MAX_LINE_LEN: usize = 4096;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum TrafficDirection {
+    FromClient,
+    ToClient
+}
+
+impl std::fmt::Display for TrafficDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self { TrafficDirection::FromClient => "<-", TrafficDirection::ToClient => "->" })
+    }
+}
+
+#[derive(Clone)]
+pub struct TrafficEntry {
+    pub time: chrono::DateTime<chrono::Local>,
+    /// Server the message was exchanged with (e.g., "mount 45501").
+    pub link: String,
+    pub direction: TrafficDirection,
+    pub text: String,
+    /// Set if the message could not be parsed.
+    pub parse_error: Option<String>
+}
+
+/// Returns the error if `line` is not a valid message.
+pub type Parser = fn(&str) -> Option<String>;
+
+/// Parses messages of the mount protocol (in both directions).
+pub fn parse_mount_message(line: &str) -> Option<String> {
+    match line.parse::<MountSimulatorMessage>() {
+        Ok(_) => None,
+        Err(e) => line.parse::<ExtMessage>().err().map(|_| e.to_string())
+    }
+}
+
+/// Parses messages sent to target feed clients.
+pub fn parse_target_message(line: &str) -> Option<String> {
+    if line.starts_with("apparent_position;") { return None; }
+    line.parse::<TargetInfoMessage>().err().map(|e| e.to_string())
+}
+
+/// Parses subscription messages received from target feed clients.
+pub fn parse_subscription(line: &str) -> Option<String> {
+    line.parse::<Subscription>().err().map(|e| e.to_string())
+}
+
+#[derive(Default)]
+struct Inner {
+    enabled: AtomicBool,
+    entries: Mutex<VecDeque<TrafficEntry>>,
+    /// Receive every message, regardless of `enabled`.
+    listeners: Mutex<Vec<crossbeam::channel::Sender<TrafficEntry>>>
+}
+
+/// Captured messages; capturing is disabled initially.
+#[derive(Clone, Default)]
+pub struct TrafficMonitor(Arc<Inner>);
+
+impl TrafficMonitor {
+    pub fn enabled(&self) -> bool { self.0.enabled.load(Ordering::Relaxed) }
+
+    pub fn set_enabled(&self, enabled: bool) { self.0.enabled.store(enabled, Ordering::Relaxed); }
+
+    /// Returns captured messages, oldest first.
+    pub fn entries(&self) -> MutexGuard<VecDeque<TrafficEntry>> { self.0.entries.lock().unwrap() }
+
+    pub fn clear(&self) { self.entries().clear(); }
+
+    /// Passes all subsequent messages to `listener` (until it is disconnected).
+    pub fn add_listener(&self, listener: crossbeam::channel::Sender<TrafficEntry>) {
+        self.0.listeners.lock().unwrap().push(listener);
+    }
+
+    /// Returns true if messages are captured (for the traffic inspector or any listener).
+    fn active(&self) -> bool { self.enabled() || !self.0.listeners.lock().unwrap().is_empty() }
+
+    fn record(&self, link: &str, direction: TrafficDirection, text: String, parser: Parser) {
+        let parse_error = parser(&text);
+        let entry = TrafficEntry{
+            time: chrono::Local::now(),
+            link: link.to_string(),
+            direction,
+            text,
+            parse_error
+        };
+        self.0.listeners.lock().unwrap().retain(|listener| listener.send(entry.clone()).is_ok());
+        if !self.enabled() { return; }
+
+        let mut entries = self.entries();
+        if entries.len() == MAX_ENTRIES { entries.pop_front(); }
+        entries.push_back(entry);
+    }
+}
+
+struct LineTap {
+    monitor: TrafficMonitor,
+    link: String,
+    direction: TrafficDirection,
+    parser: Parser,
+    /// Data of the incomplete last line.
+    buffer: Vec<u8>
+}
+
+impl LineTap {
+    fn process(&mut self, data: &[u8]) {
+        if !self.monitor.active() {
+            self.buffer.clear();
+            return;
+        }
+
+        self.buffer.extend_from_slice(data);
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.record(&line);
+        }
+        if self.buffer.len() > MAX_LINE_LEN {
+            let line = std::mem::take(&mut self.buffer);
+            self.record(&line);
+        }
+    }
+
+    fn record(&self, line: &[u8]) {
+        let text = String::from_utf8_lossy(line).trim_end().to_string();
+        if !text.is_empty() { self.monitor.record(&self.link, self.direction, text, self.parser); }
+    }
+}
+
+/// Reader or writer of a client connection which passes complete lines to a traffic monitor (if given).
+pub struct Tapped<T> {
+    inner: T,
+    tap: Option<LineTap>
+}
+
+impl<T> Tapped<T> {
+    pub fn new(
+        inner: T,
+        monitor: Option<&TrafficMonitor>,
+        link: &str,
+        direction: TrafficDirection,
+        parser: Parser
+    ) -> Tapped<T> {
+        Tapped{
+            inner,
+            tap: monitor.map(|monitor| LineTap{
+                monitor: monitor.clone(),
+                link: link.to_string(),
+                direction,
+                parser,
+                buffer: vec![]
+            })
+        }
+    }
+}
+
+impl<T: Read> Read for Tapped<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let num_read = self.inner.read(buf)?;
+        if let Some(tap) = &mut self.tap { tap.process(&buf[..num_read]); }
+        Ok(num_read)
+    }
+}
+
+impl<T: Write> Write for Tapped<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_written = self.inner.write(buf)?;
+        if let Some(tap) = &mut self.tap { tap.process(&buf[..num_written]); }
+        Ok(num_written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> { self.inner.flush() }
+}
@@ -0,0 +1,188 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! HTTP-MJPEG stream of the camera view, for external video trackers expecting a network camera.
+
+use crate::color_mode::ColorMode;
+use crossbeam::channel::Receiver;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+
+pub const VIDEO_STREAM_PORT: u16 = 45505;
+
+const JPEG_QUALITY: u8 = 85;
+
+const BOUNDARY: &str = "frame";
+
+/// Lower frame rates (Hz) are rejected.
+const MIN_FRAME_RATE: f64 = 0.01;
+
+/// Camera image to be streamed.
+pub struct VideoFrame {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA, top row first.
+    pub data: Vec<u8>,
+    /// Color mode of the streamed image.
+    pub color_mode: ColorMode
+}
+
+#[derive(Clone, Debug)]
+pub struct VideoStreamSettings {
+    /// Output resolution; if not set, frames are streamed at the camera view's resolution.
+    pub size: Option<(u32, u32)>,
+    /// Max. frame rate (Hz).
+    pub frame_rate: f64
+}
+
+impl Default for VideoStreamSettings {
+    fn default() -> VideoStreamSettings {
+        VideoStreamSettings{ size: None, frame_rate: 25.0 }
+    }
+}
+
+impl std::str::FromStr for VideoStreamSettings {
+    type Err = String;
+
+    /// Parses `[<width>x<height>][@<frame rate>]`, e.g., `640x480@25`.
+    fn from_str(s: &str) -> Result<VideoStreamSettings, String> {
+        let mut settings = VideoStreamSettings::default();
+        let (size, frame_rate) = match s.split_once('@') {
+            Some((size, frame_rate)) => (size.trim(), Some(frame_rate.trim())),
+            None => (s.trim(), None)
+        };
+
+        if !size.is_empty() {
+            let (width, height) = size.split_once('x').ok_or(format!("expected <width>x<height>: {}", size))?;
+            let width = width.parse::<u32>().map_err(|e| format!("invalid width: {}", e))?;
+            let height = height.parse::<u32>().map_err(|e| format!("invalid height: {}", e))?;
+            if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+                return Err(format!("invalid resolution: {}x{}", width, height));
+            }
+            settings.size = Some((width, height));
+        }
+
+        if let Some(frame_rate) = frame_rate {
+            let frame_rate = frame_rate.parse::<f64>().map_err(|e| format!("invalid frame rate: {}", e))?;
+            if frame_rate.is_nan() || frame_rate < MIN_FRAME_RATE {
+                return Err(format!("frame rate must be at least {} Hz: {}", MIN_FRAME_RATE, frame_rate));
+            }
+            settings.frame_rate = frame_rate;
+        }
+
+        Ok(settings)
+    }
+}
+
+/// Resizes `frame` to `width`×`height` (nearest neighbor).
+fn resize(frame: &VideoFrame, width: u32, height: u32) -> VideoFrame {
+    let mut data = Vec::with_capacity((4 * width * height) as usize);
+    for y in 0..height {
+        let src_y = (y as u64 * frame.height as u64 / height as u64) as usize;
+        for x in 0..width {
+            let src_x = (x as u64 * frame.width as u64 / width as u64) as usize;
+            let offset = 4 * (src_y * frame.width as usize + src_x);
+            data.extend_from_slice(&frame.data[offset..offset + 4]);
+        }
+    }
+    VideoFrame{ width, height, data, color_mode: frame.color_mode }
+}
+
+fn encode_jpeg(frame: &VideoFrame) -> Result<Vec<u8>, String> {
+    let data = frame.color_mode.convert(frame.width, frame.height, &frame.data);
+    let color_type = if frame.color_mode.bytes_per_pixel() == 1 {
+        jpeg_encoder::ColorType::Luma
+    } else {
+        jpeg_encoder::ColorType::Rgba
+    };
+    let mut jpeg = vec![];
+    jpeg_encoder::Encoder::new(&mut jpeg, JPEG_QUALITY)
+        .encode(&data, frame.width as u16, frame.height as u16, color_type)
+        .map_err(|e| e.to_string())?;
+    Ok(jpeg)
+}
+
+/// Reads the client's HTTP request (ignoring its contents) and responds with the header of the MJPEG stream.
+fn start_stream(stream: &mut TcpStream) -> Result<(), String> {
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Err("connection closed".into());
+        }
+        if line.trim().is_empty() { break; }
+    }
+
+    stream.write_all(format!(
+        "HTTP/1.0 200 OK\r\n\
+        Cache-Control: no-cache\r\n\
+        Connection: close\r\n\
+        Content-Type: multipart/x-mixed-replace; boundary={}\r\n\r\n",
+        BOUNDARY
+    ).as_bytes()).map_err(|e| e.to_string())
+}
+
+/// Serves frames received via `frames` as an MJPEG stream (any HTTP path can be requested).
+pub fn video_stream(settings: VideoStreamSettings, port: u16, frames: Receiver<VideoFrame>) {
+    let clients = Arc::new(Mutex::new(Vec::<TcpStream>::new()));
+
+    let clients2 = Arc::clone(&clients);
+    std::thread::spawn(move || {
+        let listener = match TcpListener::bind(format!("127.0.0.1:{}", port)) {
+            Ok(listener) => listener,
+            Err(e) => { log::error!("cannot listen on port {}: {}", port, e); return; }
+        };
+        log::info!("waiting for clients of video stream on port {}", port);
+        for stream in listener.incoming() {
+            match stream {
+                Ok(mut stream) => {
+                    let clients = Arc::clone(&clients2);
+                    // the request is read in a separate thread, so that a silent client does not block others
+                    std::thread::spawn(move || match start_stream(&mut stream) {
+                        Ok(()) => {
+                            log::info!("client of video stream connected");
+                            clients.lock().unwrap().push(stream);
+                        },
+                        Err(e) => log::error!("error starting video stream: {}", e)
+                    });
+                },
+                Err(e) => log::error!("error accepting video stream client: {}", e)
+            }
+        }
+    });
+
+    let frame_interval = Duration::from_secs_f64(1.0 / settings.frame_rate);
+    let mut last_sent: Option<Instant> = None;
+    while let Ok(frame) = frames.recv() {
+        if last_sent.map_or(false, |t| t.elapsed() < frame_interval) { continue; }
+        if clients.lock().unwrap().is_empty() { continue; }
+
+        let frame = match settings.size {
+            Some((width, height)) if (width, height) != (frame.width, frame.height) => resize(&frame, width, height),
+            _ => frame
+        };
+        let jpeg = match encode_jpeg(&frame) {
+            Ok(jpeg) => jpeg,
+            Err(e) => { log::error!("failed to encode video frame: {}", e); continue; }
+        };
+
+        let header = format!("--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n", BOUNDARY, jpeg.len());
+        clients.lock().unwrap().retain_mut(|client| {
+            let result = client.write_all(header.as_bytes())
+                .and_then(|_| client.write_all(&jpeg))
+                .and_then(|_| client.write_all(b"\r\n"));
+            if let Err(e) = &result { log::info!("error sending video frame ({}), disconnecting from client", e); }
+            result.is_ok()
+        });
+        last_sent = Some(Instant::now());
+    }
+}
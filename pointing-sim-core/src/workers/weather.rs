@@ -0,0 +1,150 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Synthetic weather (cloud cover and visibility) and a short-term forecast feed derived from it.
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, StandardNormal};
+use std::{
+    io::Write,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+
+pub const WEATHER_FORECAST_PORT: u16 = 45504;
+
+/// Interval between consecutive forecasts.
+const FORECAST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Lead times of each forecast.
+const FORECAST_LEAD_TIMES: [Duration; 5] = [
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(15 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(60 * 60),
+    Duration::from_secs(120 * 60)
+];
+
+/// Standard deviation of the cloud cover forecast error per √hour of lead time.
+const FORECAST_ERROR_PER_SQRT_HOUR: f64 = 0.15;
+
+const NUM_HARMONICS: usize = 4;
+
+/// Period range of the harmonics making up the cloud cover variations.
+const MIN_PERIOD: f64 = 15.0 * 60.0; // s
+const MAX_PERIOD: f64 = 3.0 * 3600.0; // s
+
+#[derive(Copy, Clone, Debug)]
+pub struct WeatherConditions {
+    /// Fraction of the sky covered by clouds (0-1).
+    pub cloud_cover: f64,
+    /// Meteorological visibility (km).
+    pub visibility_km: f64
+}
+
+impl WeatherConditions {
+    /// Visibility is assumed to drop from 50 km under clear sky to 5 km under overcast.
+    fn from_cloud_cover(cloud_cover: f64) -> WeatherConditions {
+        let cloud_cover = cloud_cover.clamp(0.0, 1.0);
+        WeatherConditions{ cloud_cover, visibility_km: 5.0 + 45.0 * (1.0 - cloud_cover).powi(2) }
+    }
+}
+
+/// Weather as a deterministic function of simulation time, so that conditions at any future time
+/// (the "truth" for forecasts) are known in advance.
+pub struct Weather {
+    start: Instant,
+    mean_cloud_cover: f64,
+    /// (amplitude, angular frequency (rad/s), phase)
+    harmonics: [(f64, f64, f64); NUM_HARMONICS],
+    seed: u64
+}
+
+impl Weather {
+    pub fn new(seed: u64) -> Weather {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let harmonics = [(); NUM_HARMONICS].map(|_| {
+            let period = rng.gen_range(MIN_PERIOD..MAX_PERIOD);
+            (rng.gen_range(0.1..0.3), 2.0 * std::f64::consts::PI / period, rng.gen_range(0.0..2.0 * std::f64::consts::PI))
+        });
+        Weather{ start: Instant::now(), mean_cloud_cover: rng.gen_range(0.2..0.6), harmonics, seed }
+    }
+
+    /// Returns conditions at `t` (seconds since start).
+    fn conditions_at(&self, t: f64) -> WeatherConditions {
+        let variation: f64 = self.harmonics.iter().map(|(a, w, phi)| a * (w * t + phi).sin()).sum();
+        WeatherConditions::from_cloud_cover(self.mean_cloud_cover + variation)
+    }
+
+    /// Returns current conditions.
+    pub fn current(&self) -> WeatherConditions {
+        self.conditions_at(self.start.elapsed().as_secs_f64())
+    }
+
+    /// Returns the forecast issued at `t_issue` (seconds since start) for `lead` later. Forecast errors grow with
+    /// lead time and are reproducible for given issue time and lead.
+    fn forecast(&self, t_issue: f64, lead: Duration) -> WeatherConditions {
+        let truth = self.conditions_at(t_issue + lead.as_secs_f64());
+        let mut rng = StdRng::seed_from_u64(
+            self.seed ^ (t_issue.round() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ lead.as_secs()
+        );
+        let noise: f64 = StandardNormal.sample(&mut rng);
+        let error = FORECAST_ERROR_PER_SQRT_HOUR * (lead.as_secs_f64() / 3600.0).sqrt() * noise;
+        WeatherConditions::from_cloud_cover(truth.cloud_cover + error)
+    }
+}
+
+/// Publishes forecasts to all connected clients every `FORECAST_INTERVAL`; a newly connected client immediately
+/// receives the latest forecast.
+///
+/// Each forecast is sent as one line per lead time:
+///
+/// `forecast;<issue time (s since start)>;<lead time (s)>;<cloud cover (0-1)>;<visibility (km)>`
+pub fn weather_forecast_feed(weather: Arc<Weather>, port: u16) {
+    // latest forecast and connected clients
+    let feed = Arc::new(Mutex::new((String::new(), Vec::<TcpStream>::new())));
+
+    let feed2 = Arc::clone(&feed);
+    std::thread::spawn(move || {
+        let listener = match TcpListener::bind(format!("127.0.0.1:{}", port)) {
+            Ok(listener) => listener,
+            Err(e) => { log::error!("cannot listen on port {}: {}", port, e); return; }
+        };
+        log::info!("waiting for clients of weather forecast feed on port {}", port);
+        for stream in listener.incoming() {
+            match stream {
+                Ok(mut stream) => {
+                    log::info!("client of weather forecast feed connected");
+                    let mut feed = feed2.lock().unwrap();
+                    if stream.write_all(feed.0.as_bytes()).is_ok() { feed.1.push(stream); }
+                },
+                Err(e) => log::error!("error accepting weather forecast feed client: {}", e)
+            }
+        }
+    });
+
+    loop {
+        let t_issue = weather.start.elapsed().as_secs_f64();
+        let forecast: String = FORECAST_LEAD_TIMES.iter().map(|lead| {
+            let conditions = weather.forecast(t_issue, *lead);
+            format!(
+                "forecast;{:.0};{};{:.3};{:.1}\n",
+                t_issue, lead.as_secs(), conditions.cloud_cover, conditions.visibility_km
+            )
+        }).collect();
+
+        {
+            let mut feed = feed.lock().unwrap();
+            feed.1.retain_mut(|client| client.write_all(forecast.as_bytes()).is_ok());
+            feed.0 = forecast;
+        }
+
+        std::thread::sleep(FORECAST_INTERVAL);
+    }
+}
@@ -0,0 +1,193 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Telemetry broadcast over WebSocket for dashboards and analysis tools.
+//!
+//! Every client receives (at a fixed rate) text messages with a JSON object:
+//!
+//! ```text
+//! {
+//!     "time": <RFC 3339>,
+//!     "targets": [{"id", "kind", "lat_deg", "lon_deg", "elevation_m", "track_deg", "speed_m_per_s"}, ...],
+//!     "stations": [{
+//!         "mount": <as in the control API's state>,
+//!         "targets": [{"id", "azimuth_deg", "altitude_deg", "range_m"}, ...],
+//!         "nearest_target": <ID or null>,
+//!         "pointing_error_deg": <angle between the boresight and the target followed by the station's tracking
+//!             controller, or null>
+//!     }, ...]
+//! }
+//! ```
+//!
+//! Messages sent by clients are ignored.
+
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Vector3};
+use crate::units;
+use crate::workers::{
+    control_api,
+    Mount,
+    source_manager::TargetState,
+    target_source::{lat_lon, Site, TargetControl}
+};
+use pointing_utils::{TargetInfoMessage, to_local_point, uom};
+use serde_json::json;
+use std::{
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+use tungstenite::{Message, WebSocket};
+use uom::si::{angle, length};
+
+pub const WEBSOCKET_TELEMETRY_PORT: u16 = 45507;
+
+pub const DEFAULT_WEBSOCKET_TELEMETRY_RATE: f64 = 10.0;
+
+/// Lower rates (Hz) are rejected.
+pub const MIN_WEBSOCKET_TELEMETRY_RATE: f64 = 0.01;
+
+/// Clients not completing the handshake or not accepting data within this time are disconnected.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Most recent target (in the station's local frame) followed by a station's tracking controller; not set if
+/// there is none.
+pub type TrackedTarget = Arc<Mutex<Option<TargetInfoMessage>>>;
+
+/// Station whose telemetry is broadcast.
+pub struct TelemetryStation {
+    pub mount: Arc<Mount>,
+    pub site: Site,
+    pub tracked_target: TrackedTarget
+}
+
+/// Direction and distance of a target as seen from a station.
+struct Observation {
+    id: u32,
+    azimuth: Deg<f64>,
+    altitude: Deg<f64>,
+    range_m: f64,
+    /// Unit vector in the local frame (x points north, y west, z up).
+    direction: Vector3<f64>
+}
+
+fn observe(site: &Site, target: &TargetState) -> Observation {
+    let p = to_local_point(&site.global_pos(), &target.pos).0.to_vec();
+    Observation{
+        id: target.id,
+        azimuth: Deg(units::azimuth(p).get::<angle::degree>().rem_euclid(360.0)),
+        altitude: Deg(p.z.atan2(p.x.hypot(p.y)).to_degrees()),
+        range_m: p.magnitude(),
+        direction: p.normalize()
+    }
+}
+
+fn station_telemetry(station: &TelemetryStation, targets: &[TargetState]) -> serde_json::Value {
+    let state = station.mount.get();
+    let (az, alt) = (state.boresight_az.get::<angle::radian>(), state.boresight_alt.get::<angle::radian>());
+    let boresight = Vector3::new(alt.cos() * az.cos(), -alt.cos() * az.sin(), alt.sin());
+
+    let observations: Vec<Observation> = targets.iter().map(|target| observe(&station.site, target)).collect();
+    // angle between the boresight and a direction (radians)
+    let error = |direction: Vector3<f64>| boresight.cross(direction).magnitude().atan2(boresight.dot(direction));
+    let nearest = observations.iter().min_by(|a, b| error(a.direction).total_cmp(&error(b.direction)));
+    let tracked_error = station.tracked_target.lock().unwrap().as_ref()
+        .map(|target| target.position.0.to_vec())
+        .filter(|p| p.magnitude() > 0.0)
+        .map(|p| error(p.normalize()));
+
+    json!({
+        "mount": control_api::mount_state(&station.mount),
+        "targets": observations.iter().map(|o| json!({
+            "id": o.id,
+            "azimuth_deg": o.azimuth.0,
+            "altitude_deg": o.altitude.0,
+            "range_m": o.range_m
+        })).collect::<Vec<_>>(),
+        "nearest_target": nearest.map(|o| o.id),
+        "pointing_error_deg": tracked_error.map(|e| e.to_degrees())
+    })
+}
+
+fn telemetry_message(stations: &[TelemetryStation], targets: &[TargetState]) -> String {
+    json!({
+        "time": chrono::Utc::now().to_rfc3339(),
+        "targets": targets.iter().map(|target| {
+            let lat_lon = lat_lon(&target.pos);
+            json!({
+                "id": target.id,
+                "kind": target.kind,
+                "lat_deg": lat_lon.lat.0,
+                "lon_deg": lat_lon.lon.0,
+                "elevation_m": target.elevation.get::<length::meter>(),
+                "track_deg": target.track.0,
+                "speed_m_per_s": target.velocity.0.magnitude()
+            })
+        }).collect::<Vec<_>>(),
+        "stations": stations.iter()
+            .map(|station| station_telemetry(station, targets))
+            .collect::<Vec<_>>()
+    }).to_string()
+}
+
+fn accept_clients(listener: TcpListener, clients: Arc<Mutex<Vec<WebSocket<TcpStream>>>>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => { log::error!("error accepting telemetry client: {}", e); continue; }
+        };
+        let timeouts = stream.set_read_timeout(Some(CLIENT_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(CLIENT_TIMEOUT)));
+        if let Err(e) = timeouts {
+            log::error!("cannot set timeout of telemetry client: {}", e);
+        }
+        match tungstenite::accept(stream) {
+            Ok(websocket) => {
+                log::info!("telemetry client connected");
+                clients.lock().unwrap().push(websocket);
+            },
+            Err(e) => log::error!("WebSocket handshake with telemetry client failed: {}", e)
+        }
+    }
+}
+
+/// Broadcasts telemetry of `stations` and of targets simulated by the target source (controlled via
+/// `target_control`) to WebSocket clients on `port`; `rate` in Hz (at least `MIN_WEBSOCKET_TELEMETRY_RATE`).
+pub fn websocket_telemetry(
+    stations: Vec<TelemetryStation>,
+    target_control: Arc<Mutex<TargetControl>>,
+    port: u16,
+    rate: f64
+) {
+    let listener = match TcpListener::bind(format!("127.0.0.1:{}", port)) {
+        Ok(listener) => listener,
+        Err(e) => { log::error!("cannot serve WebSocket telemetry on port {}: {}", port, e); return; }
+    };
+    log::info!("serving WebSocket telemetry on port {}", port);
+    let clients = Arc::new(Mutex::new(Vec::<WebSocket<TcpStream>>::new()));
+    let clients2 = Arc::clone(&clients);
+    std::thread::spawn(move || accept_clients(listener, clients2));
+
+    let interval = Duration::from_secs_f64(1.0 / rate.max(MIN_WEBSOCKET_TELEMETRY_RATE));
+    let mut next_due = Instant::now();
+    loop {
+        let mut clients = clients.lock().unwrap();
+        if !clients.is_empty() {
+            let targets = target_control.lock().unwrap().targets.clone();
+            let message = telemetry_message(&stations, &targets);
+            clients.retain_mut(|client| match client.send(Message::Text(message.clone())) {
+                Ok(()) => true,
+                Err(e) => { log::info!("telemetry client disconnected ({})", e); false }
+            });
+        }
+        drop(clients);
+
+        next_due += interval;
+        let now = Instant::now();
+        if next_due > now { std::thread::sleep(next_due - now); } else { next_due = now; }
+    }
+}
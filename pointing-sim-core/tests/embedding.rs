@@ -0,0 +1,64 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Uses the simulation core the way another project's integration tests would: without the GUI, with a manually
+//! advanced clock and without network connections.
+
+use pointing_sim_core::{
+    clock::{Clock, ManualClock},
+    config::MountConfig,
+    target_interpolator::TargetInterpolator,
+    workers::{Mount, execute_message}
+};
+use pointing_utils::{MountSimulatorMessage, Point3, TargetInfoMessage, Vector3, uom};
+use std::{sync::Arc, time::Duration};
+use subscriber_rs::Subscriber;
+use uom::si::{angular_velocity, f64, length};
+
+const TOLERANCE: f64 = 1.0e-9;
+
+#[test]
+fn mount_follows_text_protocol_commands() {
+    let clock = Arc::new(ManualClock::default());
+    let mount = Mount::with_clock(MountConfig::default(), 0, Arc::clone(&clock) as Arc<dyn Clock>);
+    let accel = mount.config().axis1.acceleration_deg_per_s2;
+    let rate = accel / 2.0; // reached after 0.5 s
+
+    let slew = MountSimulatorMessage::Slew{
+        axis1: f64::AngularVelocity::new::<angular_velocity::degree_per_second>(rate),
+        axis2: f64::AngularVelocity::new::<angular_velocity::degree_per_second>(0.0)
+    };
+    execute_message(&slew.to_string(), &mount).unwrap();
+    clock.advance(Duration::from_secs(1));
+    let [axis1, axis2] = mount.axis_motion();
+    assert!((axis1.spd - rate).abs() < TOLERANCE);
+    assert!(axis2.pos.abs() < TOLERANCE);
+
+    execute_message(&MountSimulatorMessage::Stop.to_string(), &mount).unwrap();
+    clock.advance(Duration::from_secs(1));
+    let [axis1, _] = mount.axis_motion();
+    assert!(axis1.spd.abs() < TOLERANCE);
+    assert!(execute_message(&MountSimulatorMessage::GetPosition.to_string(), &mount).unwrap().is_some());
+}
+
+#[test]
+fn target_is_extrapolated_between_messages() {
+    let clock = Arc::new(ManualClock::default());
+    let mut interpolator = TargetInterpolator::with_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+    interpolator.notify(&TargetInfoMessage{
+        position: Point3::from(cgmath::Point3::new(5_000.0, 0.0, 1_000.0)),
+        velocity: Vector3::from(cgmath::Vector3::new(0.0, 100.0, 0.0)),
+        track: cgmath::Deg(0.0),
+        altitude: f64::Length::new::<length::meter>(1_000.0)
+    });
+
+    clock.advance(Duration::from_secs(2));
+    interpolator.interpolate();
+    let target = interpolator.current().unwrap();
+    assert!((target.position.0.y - 200.0).abs() < TOLERANCE);
+}
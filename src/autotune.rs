@@ -0,0 +1,137 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use crate::{
+    target_geometry::TargetDirection,
+    tracking_controller::{control_rates, normalize, ControllerKind, ControllerSettings, COMMAND_INTERVAL},
+    workers::Mount
+};
+use pointing_sim_core::clock::{Clock, ManualClock};
+use pointing_utils::{Local, Point3, TargetInfoMessage, uom};
+use std::{sync::Arc, time::Duration};
+use uom::{si::f64, si::{angle, angular_velocity}};
+
+/// Duration of a single simulated run.
+const RUN_DURATION: f64 = 30.0; // s
+
+/// Initial part of a run (target acquisition) excluded from the RMS error.
+const SETTLING_TIME: f64 = 5.0; // s
+
+/// RMS error above which the target is considered not trackable with the current mount configuration.
+const MAX_TRACKABLE_RMS_ERROR: f64 = 60.0; // arcsec
+
+const KP_CANDIDATES: [f64; 8] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 12.0, 16.0];
+
+const KI_CANDIDATES: [f64; 6] = [0.02, 0.05, 0.1, 0.2, 0.5, 1.0];
+
+pub struct AutotuneResult {
+    /// Settings which achieved the lowest RMS error.
+    pub settings: ControllerSettings,
+    pub rms_error: f64::Angle,
+    /// False if even the best settings did not keep the error below `MAX_TRACKABLE_RMS_ERROR`.
+    pub trackable: bool
+}
+
+/// Finds controller gains minimizing the RMS tracking error (alt-az mode).
+///
+/// Runs a grid search over simulated runs, in which the target continues along a straight line from its last
+/// known position and is seen by the controller with the feed's `latency`. Each run uses a copy of `mount`'s model
+/// (configuration including the drive train, and wind disturbance) driven by a simulated clock. Returns `None` if
+/// the target is not suitable (e.g., at zenith or beyond axis limits).
+pub fn autotune(target: &TargetInfoMessage, mount: &Mount, latency: Duration) -> Option<AutotuneResult> {
+    let mut candidates = vec![];
+    for feed_forward in [true, false] {
+        for kp in KP_CANDIDATES {
+            candidates.push(ControllerSettings{
+                kind: ControllerKind::P, kp, ki: ControllerSettings::default().ki, feed_forward
+            });
+            for ki in KI_CANDIDATES {
+                candidates.push(ControllerSettings{ kind: ControllerKind::PI, kp, ki, feed_forward });
+            }
+        }
+    }
+
+    let num_runs = candidates.len();
+    let (settings, rms_error) = candidates.into_iter()
+        .filter_map(|settings| simulate_run(&settings, target, mount, latency).map(|rms| (settings, rms)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    let rms_arcsec = rms_error.to_degrees() * 3600.0;
+    log::info!("autotune: best settings {} (RMS error {:.1}\" after {} runs)", settings, rms_arcsec, num_runs);
+
+    Some(AutotuneResult{
+        settings,
+        rms_error: f64::Angle::new::<angle::radian>(rms_error),
+        trackable: rms_arcsec <= MAX_TRACKABLE_RMS_ERROR
+    })
+}
+
+/// Returns the RMS pointing error (radians) after settling, or `None` if the target passed through zenith or
+/// the mount could not follow it.
+fn simulate_run(
+    settings: &ControllerSettings,
+    target: &TargetInfoMessage,
+    mount: &Mount,
+    latency: Duration
+) -> Option<f64> {
+    let target_at = |t: f64| TargetDirection::from_message(&TargetInfoMessage{
+        position: Point3::<f64, Local>::from(target.position.0 + target.velocity.0 * t),
+        velocity: target.velocity.clone(),
+        track: target.track,
+        altitude: target.altitude
+    });
+
+    let clock = Arc::new(ManualClock::default());
+    let sim_mount = Mount::with_clock(mount.config().clone(), mount.instance(), Arc::clone(&clock) as Arc<dyn Clock>);
+    sim_mount.set_wind_settings(mount.wind_settings());
+    let initial = target_at(0.0)?;
+    let zero_rate = f64::AngularVelocity::new::<angular_velocity::radian_per_second>(0.0);
+    sim_mount.snap_to((initial.az, zero_rate), (initial.alt, zero_rate)).ok()?;
+
+    let command_interval = COMMAND_INTERVAL.as_secs_f64();
+    let mut integral = (0.0, 0.0);
+    let mut sum_sq_error = 0.0;
+    let mut num_samples = 0;
+
+    let mut t = 0.0;
+    while t < RUN_DURATION {
+        let state = sim_mount.get();
+
+        if t >= SETTLING_TIME {
+            // true pointing error (of the boresight, not as reported by the encoders)
+            let actual = target_at(t)?;
+            let az_error = normalize((actual.az - state.boresight_az).get::<angle::radian>());
+            let alt_error = (actual.alt - state.boresight_alt).get::<angle::radian>();
+            sum_sq_error += (az_error * actual.alt.get::<angle::radian>().cos()).powi(2) + alt_error.powi(2);
+            num_samples += 1;
+        }
+
+        let seen = target_at(t - latency.as_secs_f64())?;
+        let error = (
+            normalize((seen.az - state.axis1_pos).get::<angle::radian>()),
+            (seen.alt - state.axis2_pos).get::<angle::radian>()
+        );
+        let feed_forward = (
+            seen.az_rate.get::<angular_velocity::radian_per_second>(),
+            seen.alt_rate.get::<angular_velocity::radian_per_second>()
+        );
+        let dt = if t > 0.0 { command_interval } else { 0.0 };
+        let rates = control_rates(settings, error, &mut integral, dt, feed_forward);
+        sim_mount.slew(
+            f64::AngularVelocity::new::<angular_velocity::radian_per_second>(rates.0),
+            f64::AngularVelocity::new::<angular_velocity::radian_per_second>(rates.1)
+        ).ok()?;
+
+        clock.advance(COMMAND_INTERVAL);
+        t += command_interval;
+    }
+
+    if num_samples == 0 { return None; }
+
+    Some((sum_sq_error / num_samples as f64).sqrt())
+}
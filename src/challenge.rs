@@ -0,0 +1,207 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Timed manual tracking challenge: rounds of increasing target speed, maneuvering and wind disturbance, each
+//! scored by `scoring::Scoring`.
+
+use crate::{
+    data::Station,
+    scoring::ScoringSummary,
+    workers::{DEFAULT_TARGET_MOTION, TargetMotion, WindSettings}
+};
+use pointing_utils::uom;
+use serde::Serialize;
+use std::{io::Write, path::PathBuf, sync::{Arc, Mutex}, time::{Duration, Instant}};
+use uom::{si::f64, si::angle};
+
+pub const ROUND_DURATION: Duration = Duration::from_secs(60);
+
+pub const MAX_ROUNDS: usize = 10;
+
+/// The challenge ends after a round scored below this.
+pub const MIN_PASSING_SCORE: f64 = 50.0;
+
+/// Difficulty of a challenge round.
+#[derive(Clone, Debug, Serialize)]
+pub struct RoundSettings {
+    pub target_speed_m_per_s: f64,
+    pub track_noise_deg_per_sqrt_s: f64,
+    pub turbulence_rms_arcsec: f64,
+    pub gusts_per_minute: f64,
+    pub gust_amplitude_arcsec: f64
+}
+
+impl RoundSettings {
+    /// Returns settings of round `round` (starting at 0).
+    pub fn for_round(round: usize) -> RoundSettings {
+        let r = round as f64;
+        RoundSettings{
+            target_speed_m_per_s: 100.0 * 1.25f64.powf(r),
+            track_noise_deg_per_sqrt_s: 1.5 * r,
+            turbulence_rms_arcsec: 5.0 * r,
+            gusts_per_minute: 2.0 * r,
+            gust_amplitude_arcsec: 30.0 * r
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct RoundResult {
+    pub round: usize,
+    pub settings: RoundSettings,
+    pub summary: ScoringSummary
+}
+
+/// Results of a whole challenge.
+#[derive(Serialize)]
+pub struct ChallengeResults {
+    pub start_time: String,
+    pub rounds: Vec<RoundResult>,
+    pub rounds_passed: usize,
+    pub total_score: f64
+}
+
+struct CurrentRound {
+    index: usize,
+    start: Instant
+}
+
+pub struct Challenge {
+    target_motion: Arc<Mutex<TargetMotion>>,
+    start_time: chrono::DateTime<chrono::Local>,
+    round: Option<CurrentRound>,
+    results: Vec<RoundResult>,
+    /// Wind settings to restore after the challenge.
+    saved_wind: Option<WindSettings>
+}
+
+impl Challenge {
+    pub fn new(target_motion: Arc<Mutex<TargetMotion>>) -> Challenge {
+        Challenge{ target_motion, start_time: chrono::Local::now(), round: None, results: vec![], saved_wind: None }
+    }
+
+    pub fn is_running(&self) -> bool { self.round.is_some() }
+
+    /// Returns current round (starting at 0) and its remaining time.
+    pub fn current_round(&self) -> Option<(usize, Duration)> {
+        self.round.as_ref().map(|r| (r.index, ROUND_DURATION.saturating_sub(r.start.elapsed())))
+    }
+
+    pub fn results(&self) -> &[RoundResult] { &self.results }
+
+    pub fn start(&mut self, station: &Station) {
+        self.start_time = chrono::Local::now();
+        self.results.clear();
+        self.saved_wind = Some(station.mount.wind_settings());
+        self.start_round(0, station);
+    }
+
+    fn start_round(&mut self, index: usize, station: &Station) {
+        let settings = RoundSettings::for_round(index);
+        log::info!("challenge round {}: {:?}", index + 1, settings);
+
+        *self.target_motion.lock().unwrap() = TargetMotion{
+            speed: settings.target_speed_m_per_s,
+            track_noise: settings.track_noise_deg_per_sqrt_s
+        };
+
+        let mut wind = self.saved_wind.clone().unwrap_or_default();
+        wind.enabled = index > 0;
+        wind.turbulence_rms = f64::Angle::new::<angle::second>(settings.turbulence_rms_arcsec);
+        wind.gusts_per_minute = settings.gusts_per_minute;
+        wind.gust_amplitude = f64::Angle::new::<angle::second>(settings.gust_amplitude_arcsec);
+        station.mount.set_wind_settings(wind);
+
+        station.scoring.borrow_mut().start();
+        self.round = Some(CurrentRound{ index, start: Instant::now() });
+    }
+
+    /// Advances the challenge; to be called periodically.
+    pub fn update(&mut self, station: &Station) {
+        let (index, remaining) = match self.current_round() {
+            Some(round) => round,
+            None => return
+        };
+        if remaining > Duration::ZERO { return; }
+
+        let mut scoring = station.scoring.borrow_mut();
+        scoring.stop();
+        let summary = match scoring.summary() {
+            Some(summary) => summary,
+            None => { self.finish(station); return; }
+        };
+        drop(scoring);
+
+        let passed = summary.score >= MIN_PASSING_SCORE;
+        self.results.push(RoundResult{ round: index + 1, settings: RoundSettings::for_round(index), summary });
+
+        if passed && index + 1 < MAX_ROUNDS {
+            self.start_round(index + 1, station);
+        } else {
+            self.finish(station);
+        }
+    }
+
+    /// Ends the challenge (keeping results of the completed rounds).
+    pub fn abort(&mut self, station: &Station) {
+        if self.is_running() {
+            station.scoring.borrow_mut().stop();
+            self.finish(station);
+        }
+    }
+
+    fn finish(&mut self, station: &Station) {
+        self.round = None;
+        *self.target_motion.lock().unwrap() = DEFAULT_TARGET_MOTION;
+        if let Some(wind) = self.saved_wind.take() { station.mount.set_wind_settings(wind); }
+
+        let results = self.challenge_results();
+        log::info!(
+            "challenge finished: {} round(s) passed, total score {:.0}", results.rounds_passed, results.total_score
+        );
+        if let Err(e) = record_progression(&results) {
+            log::error!("failed to record challenge progression: {}", e);
+        }
+    }
+
+    fn challenge_results(&self) -> ChallengeResults {
+        ChallengeResults{
+            start_time: self.start_time.to_rfc3339(),
+            rounds: self.results.clone(),
+            rounds_passed: self.results.iter().filter(|r| r.summary.score >= MIN_PASSING_SCORE).count(),
+            total_score: self.results.iter().map(|r| r.summary.score).sum()
+        }
+    }
+}
+
+fn challenges_dir() -> Result<PathBuf, String> {
+    crate::config::config_dir()
+        .map(|dir| dir.join("challenges"))
+        .ok_or_else(|| "cannot determine configuration directory".to_string())
+}
+
+/// Saves full results of a challenge and appends a line to the progression history (`progression.csv`).
+fn record_progression(results: &ChallengeResults) -> Result<(), String> {
+    let dir = challenges_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let start_time = chrono::DateTime::parse_from_rfc3339(&results.start_time).map_err(|e| e.to_string())?;
+    let contents = serde_json::to_string_pretty(results).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(format!("{}.json", start_time.format("%Y%m%d-%H%M%S"))), contents)
+        .map_err(|e| e.to_string())?;
+
+    let history_path = dir.join("progression.csv");
+    let is_new = !history_path.exists();
+    let mut history = std::fs::OpenOptions::new().create(true).append(true).open(&history_path)
+        .map_err(|e| e.to_string())?;
+    if is_new {
+        writeln!(history, "start_time,rounds_passed,total_score").map_err(|e| e.to_string())?;
+    }
+    writeln!(history, "{},{},{:.1}", results.start_time, results.rounds_passed, results.total_score)
+        .map_err(|e| e.to_string())
+}
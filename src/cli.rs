@@ -0,0 +1,308 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Command-line arguments.
+
+use crate::{color_mode::ColorMode, gui::Misalignment, refraction::Atmosphere, workers};
+use clap::Parser;
+use std::path::PathBuf;
+
+/// Highest first station's port; leaves room for the ports at offsets from it (further feeds and stations, binary
+/// framing).
+const MAX_BASE_PORT: i64 = 65400;
+
+/// Parses `<width>x<height>`.
+fn parse_resolution(s: &str) -> Result<[u32; 2], String> {
+    match s.split_once('x').and_then(|(w, h)| Some([w.parse::<u32>().ok()?, h.parse::<u32>().ok()?])) {
+        Some(resolution) if resolution.iter().all(|value| *value > 0) => Ok(resolution),
+        _ => Err("expected <width>x<height>".into())
+    }
+}
+
+fn parse_motion_log_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(value) if (workers::MIN_MOTION_LOG_RATE..=workers::MAX_MOTION_LOG_RATE).contains(&value) => Ok(value),
+        Ok(_) => Err(format!(
+            "must be between {} and {} Hz", workers::MIN_MOTION_LOG_RATE, workers::MAX_MOTION_LOG_RATE
+        )),
+        Err(e) => Err(e.to_string())
+    }
+}
+
+fn parse_websocket_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= workers::MIN_WEBSOCKET_TELEMETRY_RATE => Ok(value),
+        Ok(_) => Err(format!("must be at least {} Hz", workers::MIN_WEBSOCKET_TELEMETRY_RATE)),
+        Err(e) => Err(e.to_string())
+    }
+}
+
+fn parse_probability(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+        Ok(_) => Err("must be between 0 and 1".into()),
+        Err(e) => Err(e.to_string())
+    }
+}
+
+#[derive(Parser)]
+#[command(version, about = "Simulator of a telescope mount tracking aircraft and other targets")]
+pub struct Args {
+    /// Settings file [default: settings.toml in the configuration directory]
+    #[arg(long, value_name = "FILE", help_heading = "Configuration")]
+    pub config: Option<PathBuf>,
+
+    /// Mount profile (from mount_profiles.toml in the configuration directory) used instead of the settings' mount
+    #[arg(long, value_name = "NAME", help_heading = "Configuration")]
+    pub mount_profile: Option<String>,
+
+    /// Scenario file (observer site, targets, faults); overrides the corresponding options
+    #[arg(long, value_name = "FILE", help_heading = "Configuration")]
+    pub scenario: Option<PathBuf>,
+
+    /// Event hooks file [default: hooks file in the configuration directory, if present]
+    #[arg(long, value_name = "FILE", help_heading = "Configuration")]
+    pub event_hooks: Option<PathBuf>,
+
+    /// Scenario script (Rhai) driving target maneuvers and faults and checking conditions during the run; the program
+    /// exits with status 1 if any check fails [default: the scenario's script, if any]
+    #[arg(long, value_name = "FILE", help_heading = "Configuration")]
+    pub script: Option<PathBuf>,
+
+    /// Minimum level of logged messages (error, warn, info, debug, trace)
+    #[arg(long, value_name = "LEVEL", default_value_t = log::LevelFilter::Debug, help_heading = "Configuration")]
+    pub log_level: log::LevelFilter,
+
+    /// Port of the first station's mount server [default: from the settings]
+    #[arg(
+        long,
+        value_name = "PORT",
+        value_parser = clap::value_parser!(u16).range(1..=MAX_BASE_PORT),
+        help_heading = "Ports and connections"
+    )]
+    pub mount_port: Option<u16>,
+
+    /// Port of the first station's ADS-B target feed (other feeds use the following ports) [default: from the
+    /// settings]
+    #[arg(
+        long,
+        value_name = "PORT",
+        value_parser = clap::value_parser!(u16).range(1..=MAX_BASE_PORT),
+        help_heading = "Ports and connections"
+    )]
+    pub target_port: Option<u16>,
+
+    /// Port of the weather forecast feed
+    #[arg(
+        long,
+        value_name = "PORT",
+        default_value_t = workers::WEATHER_FORECAST_PORT,
+        help_heading = "Ports and connections"
+    )]
+    pub weather_port: u16,
+
+    /// Port of the video stream (see --video-stream)
+    #[arg(
+        long,
+        value_name = "PORT",
+        default_value_t = workers::VIDEO_STREAM_PORT,
+        help_heading = "Ports and connections"
+    )]
+    pub video_stream_port: u16,
+
+    /// Port of the HTTP control API (see --control-api)
+    #[arg(
+        long,
+        value_name = "PORT",
+        default_value_t = workers::CONTROL_API_PORT,
+        help_heading = "Ports and connections"
+    )]
+    pub control_port: u16,
+
+    /// Port of the WebSocket telemetry (see --websocket-telemetry)
+    #[arg(
+        long,
+        value_name = "PORT",
+        default_value_t = workers::WEBSOCKET_TELEMETRY_PORT,
+        help_heading = "Ports and connections"
+    )]
+    pub websocket_port: u16,
+
+    /// Port of the gRPC server (see --grpc)
+    #[cfg(feature = "grpc")]
+    #[arg(long, value_name = "PORT", default_value_t = workers::GRPC_PORT, help_heading = "Ports and connections")]
+    pub grpc_port: u16,
+
+    /// Also serve the mount protocol and target feeds with binary, length-prefixed messages, on ports 100 above
+    /// the text ones
+    #[arg(long, help_heading = "Ports and connections")]
+    pub binary_framing: bool,
+
+    /// Also serve the mount protocol on a pseudo-terminal (Unix only)
+    #[arg(long, help_heading = "Ports and connections")]
+    pub mount_pty: bool,
+
+    /// Also serve the mount protocol on a Unix domain socket (Unix only; further stations: numbered suffix)
+    #[arg(long, value_name = "PATH", help_heading = "Ports and connections")]
+    pub mount_socket: Option<String>,
+
+    /// Also serve the ADS-B target feed on a Unix domain socket (Unix only)
+    #[arg(long, value_name = "PATH", help_heading = "Ports and connections")]
+    pub target_socket: Option<PathBuf>,
+
+    /// Show the first station's camera view in a separate window
+    #[arg(long, help_heading = "Modes")]
+    pub external_display: bool,
+
+    /// Run without any window (the GUI is processed and camera views are rendered off-screen, for frame capture and
+    /// video streaming); uses a headless EGL context, so no display server is needed
+    #[arg(long, conflicts_with = "external_display", help_heading = "Modes")]
+    pub offscreen: bool,
+
+    /// Run without a GUI, serving only the mount and target feeds (camera views are not rendered; for off-screen
+    /// rendering use --offscreen instead)
+    #[arg(
+        long,
+        conflicts_with_all = ["offscreen", "external_display", "video_stream", "replay_session"],
+        help_heading = "Modes"
+    )]
+    pub headless: bool,
+
+    /// Replay a session recorded with --record-session: its targets are published and the mounts follow the
+    /// recorded motion (controlled in the "Session playback" window)
+    #[arg(long, value_name = "FILE", help_heading = "Modes")]
+    pub replay_session: Option<PathBuf>,
+
+    /// Serve an HTTP control API (JSON; spawning targets, setting mount parameters, injecting faults, querying
+    /// the state) for external test harnesses
+    #[arg(long, help_heading = "Modes")]
+    pub control_api: bool,
+
+    /// Broadcast target truth, mount states and pointing errors as JSON over WebSocket
+    #[arg(long, help_heading = "Modes")]
+    pub websocket_telemetry: bool,
+
+    /// Rate of the WebSocket telemetry (Hz)
+    #[arg(
+        long,
+        value_name = "HZ",
+        value_parser = parse_websocket_rate,
+        default_value_t = workers::DEFAULT_WEBSOCKET_TELEMETRY_RATE,
+        help_heading = "Modes"
+    )]
+    pub websocket_rate: f64,
+
+    /// Serve mount commands and target telemetry via gRPC (schemas in pointing-sim-core/proto), alongside the
+    /// text protocols
+    #[cfg(feature = "grpc")]
+    #[arg(long, help_heading = "Modes")]
+    pub grpc: bool,
+
+    /// Add a second station (ca. 17 km east of the first one)
+    #[arg(long, help_heading = "Modes")]
+    pub second_mount: bool,
+
+    /// Add a mirror mount with this profile, following the first one's commands (for comparison)
+    #[arg(long, value_name = "NAME", help_heading = "Modes")]
+    pub compare_mount_profile: Option<String>,
+
+    /// Stream camera images of the first station: [<width>x<height>][@<frame rate>]
+    #[arg(long, value_name = "SETTINGS", help_heading = "Camera")]
+    pub video_stream: Option<workers::VideoStreamSettings>,
+
+    /// Fixed camera sensor resolution: <width>x<height>
+    #[arg(long, value_name = "RESOLUTION", value_parser = parse_resolution, help_heading = "Camera")]
+    pub sensor_resolution: Option<[u32; 2]>,
+
+    /// Camera misalignment relative to the boresight: <horizontal>,<vertical>,<roll> (arcminutes)
+    #[arg(long, value_name = "OFFSETS", allow_hyphen_values = true, help_heading = "Camera")]
+    pub camera_misalignment: Option<Misalignment>,
+
+    /// Color mode of recorded and streamed frames
+    #[arg(long, value_name = "MODE", help_heading = "Camera")]
+    pub color_mode: Option<ColorMode>,
+
+    /// Cloud layer: <coverage (0-1) or "weather">,<altitude (m)>,<drift north (m/s)>,<drift west (m/s)>
+    #[arg(long, value_name = "SETTINGS", allow_hyphen_values = true, help_heading = "Camera")]
+    pub clouds: Option<String>,
+
+    /// Atmospheric refraction: <temperature (°C)>,<pressure (hPa)>
+    #[arg(long, value_name = "CONDITIONS", allow_hyphen_values = true, help_heading = "Simulation")]
+    pub refraction: Option<Atmosphere>,
+
+    /// Sky model time (RFC 3339 or "now")
+    #[arg(long, value_name = "TIME", help_heading = "Simulation")]
+    pub sky_time: Option<String>,
+
+    /// Number of additional generated targets
+    #[arg(long, value_name = "N", default_value_t = 0, help_heading = "Simulation")]
+    pub targets: usize,
+
+    /// Seed of the target and weather simulation (overrides the scenario's) [default: random, logged at startup]
+    #[arg(long, value_name = "SEED", help_heading = "Simulation")]
+    pub seed: Option<u64>,
+
+    /// Simulate ADS-B CPR decoding glitches
+    #[arg(long, help_heading = "Simulation")]
+    pub adsb_cpr: bool,
+
+    /// Replay recorded target trajectories from a CSV file (may be given multiple times)
+    #[arg(long, value_name = "FILE", help_heading = "Simulation")]
+    pub replay_targets: Vec<PathBuf>,
+
+    /// Publish aircraft received from an ADS-B decoder in the SBS-1 format, e.g. dump1090's port 30003 (may be
+    /// given multiple times)
+    #[arg(long, value_name = "ADDRESS", help_heading = "Simulation")]
+    pub adsb_input: Vec<String>,
+
+    /// Probability of target identity swaps (per message)
+    #[arg(long, value_name = "PROBABILITY", value_parser = parse_probability, help_heading = "Simulation")]
+    pub target_swap: Option<f64>,
+
+    /// Record mount protocol and target feed traces in this directory
+    #[arg(long, value_name = "DIR", help_heading = "Recording")]
+    pub trace_dir: Option<PathBuf>,
+
+    /// Log axis motion in this directory
+    #[arg(long, value_name = "DIR", help_heading = "Recording")]
+    pub motion_log: Option<PathBuf>,
+
+    /// Motion log rate (Hz)
+    #[arg(
+        long,
+        value_name = "HZ",
+        value_parser = parse_motion_log_rate,
+        default_value_t = workers::DEFAULT_MOTION_LOG_RATE,
+        help_heading = "Recording"
+    )]
+    pub motion_log_rate: f64,
+
+    /// Record protocol traffic, mount states and target truth of the whole run to FILE
+    #[arg(long, value_name = "FILE", help_heading = "Recording")]
+    pub record_session: Option<PathBuf>,
+
+    /// Save the mount dynamics model to FILE and exit
+    #[arg(long, value_name = "FILE", help_heading = "Utilities")]
+    pub export_dynamics: Option<PathBuf>,
+
+    /// Command latency included in the exported dynamics model (ms)
+    #[arg(long, value_name = "MS", default_value_t = 0, help_heading = "Utilities")]
+    pub dynamics_latency_ms: u64,
+
+    /// Replay the client's part of a mount protocol trace against a running mount server and exit
+    #[arg(long, value_name = "FILE", help_heading = "Utilities")]
+    pub replay_trace: Option<PathBuf>,
+
+    /// Address of the mount server for --replay-trace [default: localhost, the mount port]
+    #[arg(long, value_name = "ADDRESS", help_heading = "Utilities")]
+    pub replay_address: Option<String>,
+
+    /// Convert a motion log to CSV (saved next to it) and exit
+    #[arg(long, value_name = "FILE", help_heading = "Utilities")]
+    pub convert_motion_log: Option<PathBuf>
+}
@@ -0,0 +1,122 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Drifting cloud layer at a fixed altitude.
+//!
+//! The cloud pattern is evaluated on the GPU (`resources/shaders/cloud_layer.glsl`) for rendering, and here for
+//! determining whether a target is obscured; both implementations must be kept in sync.
+
+use cgmath::{InnerSpace, Vector2, Vector3};
+use crate::workers::Weather;
+use pointing_utils::uom;
+use std::{sync::Arc, time::Instant};
+use uom::si::{f64, length};
+
+/// Size of the cells of the cloud pattern's coarsest octave (m).
+const CELL_SIZE: f64 = 2000.0;
+
+/// Width of the transition between clear sky and an opaque cloud (in units of pattern density).
+const EDGE_SOFTNESS: f64 = 0.05;
+
+pub enum Coverage {
+    /// Fraction of the sky covered (0-1).
+    Fixed(f64),
+    /// Follows the cloud cover of the weather model.
+    Weather(Arc<Weather>)
+}
+
+pub struct CloudLayer {
+    pub coverage: Coverage,
+    pub altitude: f64::Length,
+    /// Velocity (m/s) of the cloud pattern in the local frame (x: north, y: west).
+    pub drift: Vector2<f64>,
+    start: Instant
+}
+
+impl CloudLayer {
+    pub fn new(coverage: Coverage, altitude: f64::Length, drift: Vector2<f64>) -> CloudLayer {
+        CloudLayer{ coverage, altitude, drift, start: Instant::now() }
+    }
+
+    /// Parses `<coverage (0-1) or "weather">,<altitude (m)>,<drift north (m/s)>,<drift west (m/s)>`.
+    pub fn parse(s: &str, weather: &Arc<Weather>) -> Result<CloudLayer, String> {
+        let fields: Vec<&str> = s.split(',').map(|f| f.trim()).collect();
+        if fields.len() != 4 {
+            return Err(format!("expected 4 comma-separated values, got \"{}\"", s));
+        }
+        let number = |f: &str| f.parse::<f64>().map_err(|e| format!("invalid value \"{}\": {}", f, e));
+
+        let coverage = if fields[0] == "weather" {
+            Coverage::Weather(Arc::clone(weather))
+        } else {
+            Coverage::Fixed(number(fields[0])?.clamp(0.0, 1.0))
+        };
+
+        Ok(CloudLayer::new(
+            coverage,
+            f64::Length::new::<length::meter>(number(fields[1])?),
+            Vector2{ x: number(fields[2])?, y: number(fields[3])? }
+        ))
+    }
+
+    /// Returns current coverage (0-1).
+    pub fn coverage(&self) -> f64 {
+        match &self.coverage {
+            Coverage::Fixed(coverage) => *coverage,
+            Coverage::Weather(weather) => weather.current().cloud_cover
+        }
+    }
+
+    /// Current displacement (m) of the cloud pattern.
+    pub fn offset(&self) -> Vector2<f64> {
+        self.drift * self.start.elapsed().as_secs_f64()
+    }
+
+    /// Returns opacity (0-1) of the clouds between the observer and a point at `pos` (local frame).
+    pub fn opacity(&self, pos: Vector3<f64>) -> f64 {
+        let altitude = self.altitude.get::<length::meter>();
+        if pos.z <= altitude || altitude <= 0.0 { return 0.0; }
+
+        let dir = pos.normalize();
+        let crossing = Vector2{ x: dir.x, y: dir.y } * (altitude / dir.z);
+        let threshold = 1.0 + EDGE_SOFTNESS - (1.0 + 2.0 * EDGE_SOFTNESS) * self.coverage();
+        smoothstep(
+            threshold - EDGE_SOFTNESS,
+            threshold + EDGE_SOFTNESS,
+            density((crossing - self.offset()) / CELL_SIZE)
+        )
+    }
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Returns a pseudorandom value (0-1) for a pattern cell.
+fn hash(x: i32, y: i32) -> f64 {
+    let mut h = (x as u32).wrapping_mul(374761393).wrapping_add((y as u32).wrapping_mul(668265263));
+    h = (h ^ (h >> 13)).wrapping_mul(1274126177);
+    h ^= h >> 16;
+    h as f64 / u32::MAX as f64
+}
+
+fn value_noise(p: Vector2<f64>) -> f64 {
+    let (ix, iy) = (p.x.floor() as i32, p.y.floor() as i32);
+    let (fx, fy) = (p.x - p.x.floor(), p.y - p.y.floor());
+    let (ux, uy) = (fx * fx * (3.0 - 2.0 * fx), fy * fy * (3.0 - 2.0 * fy));
+
+    let bottom = hash(ix, iy) + (hash(ix + 1, iy) - hash(ix, iy)) * ux;
+    let top = hash(ix, iy + 1) + (hash(ix + 1, iy + 1) - hash(ix, iy + 1)) * ux;
+    bottom + (top - bottom) * uy
+}
+
+/// Returns cloud density (0-1) at `p` (in units of `CELL_SIZE`).
+fn density(p: Vector2<f64>) -> f64 {
+    0.5 * value_noise(p) + 0.3 * value_noise(p * 2.03) + 0.2 * value_noise(p * 4.01)
+}
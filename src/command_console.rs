@@ -0,0 +1,54 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Sending of raw text protocol messages to a mount (as if by an external client), for probing its behavior.
+
+use crate::workers::{Mount, execute_message, message_templates};
+use std::{collections::VecDeque, sync::Arc};
+
+/// The oldest exchanges are discarded above this number.
+const MAX_HISTORY: usize = 200;
+
+pub struct Exchange {
+    pub time: chrono::DateTime<chrono::Local>,
+    pub message: String,
+    /// Response of the mount (`None` if there was none) or the error.
+    pub result: Result<Option<String>, String>
+}
+
+pub struct CommandConsole {
+    mount: Arc<Mount>,
+    /// Message entered by the user.
+    pub input: String,
+    templates: Vec<String>,
+    /// Sent messages, oldest first.
+    history: VecDeque<Exchange>
+}
+
+impl CommandConsole {
+    pub fn new(mount: Arc<Mount>) -> CommandConsole {
+        CommandConsole{ mount, input: String::new(), templates: message_templates(), history: VecDeque::new() }
+    }
+
+    pub fn templates(&self) -> &[String] { &self.templates }
+
+    pub fn history(&self) -> &VecDeque<Exchange> { &self.history }
+
+    pub fn clear_history(&mut self) { self.history.clear(); }
+
+    /// Sends the entered message.
+    pub fn send(&mut self) {
+        let message = self.input.trim().to_string();
+        if message.is_empty() { return; }
+
+        let result = execute_message(&message, &self.mount);
+        if let Err(e) = &result { log::error!("command console: {}", e); }
+        if self.history.len() == MAX_HISTORY { self.history.pop_front(); }
+        self.history.push_back(Exchange{ time: chrono::Local::now(), message, result });
+    }
+}
@@ -0,0 +1,257 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Program settings (stored in the settings file in the configuration directory) and GUI layout.
+//!
+//! Re-exports the simulation core's configuration (mount profiles, location), which the settings include.
+
+pub use pointing_sim_core::config::*;
+use crate::{units, workers};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::{Path, PathBuf}};
+
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+const LAYOUT_FILE_NAME: &str = "layout.ini";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RenderConfig {
+    /// 1 = multisampling disabled.
+    pub msaa_samples: u32,
+    pub supersampling: f32,
+    pub adaptive_quality: bool
+}
+
+impl Default for RenderConfig {
+    fn default() -> RenderConfig {
+        RenderConfig{ msaa_samples: 8, supersampling: 1.0, adaptive_quality: false }
+    }
+}
+
+/// Field of view of an instrument, shown as a ring in camera views.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InstrumentFov {
+    pub name: String,
+    /// Diameter of the field of view.
+    pub fov_deg: f64,
+    pub color: [f32; 4],
+    pub shown: bool
+}
+
+impl Default for InstrumentFov {
+    fn default() -> InstrumentFov {
+        InstrumentFov{ name: "instrument".into(), fov_deg: 1.0, color: [0.3, 0.8, 1.0, 0.8], shown: true }
+    }
+}
+
+fn default_instruments() -> Vec<InstrumentFov> {
+    vec![
+        InstrumentFov{ name: "finder".into(), fov_deg: 5.0, color: [0.3, 0.8, 1.0, 0.8], shown: true },
+        InstrumentFov{ name: "main camera".into(), fov_deg: 0.5, color: [1.0, 0.8, 0.2, 0.8], shown: true },
+        InstrumentFov{ name: "eyepiece".into(), fov_deg: 0.2, color: [0.8, 0.4, 1.0, 0.8], shown: true }
+    ]
+}
+
+/// Named camera view configuration (recalled in the camera view's context menu).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CameraPreset {
+    pub name: String,
+    pub fov_y_deg: f64,
+    /// Reticle style (as shown in the GUI, e.g., "crosshair").
+    pub reticle: String,
+    pub reticle_spacing_arcmin: f64,
+    pub target_path: bool,
+    pub bounding_box: bool,
+    pub error_hud: bool,
+    pub zoom_inset: bool,
+    /// Fixed sensor resolution; if not set, the image matches the window.
+    pub sensor_resolution: Option<[u32; 2]>
+}
+
+impl Default for CameraPreset {
+    fn default() -> CameraPreset {
+        CameraPreset{
+            name: String::new(),
+            fov_y_deg: 1.0,
+            reticle: "crosshair".into(),
+            reticle_spacing_arcmin: 3.44,
+            target_path: false,
+            bounding_box: false,
+            error_hud: true,
+            zoom_inset: false,
+            sensor_resolution: None
+        }
+    }
+}
+
+/// Base color scheme of the GUI.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeBase {
+    #[default]
+    Dark,
+    Light,
+    Classic,
+    /// Dim red on black; preserves dark adaptation.
+    Night
+}
+
+impl ThemeBase {
+    pub const ALL: [ThemeBase; 4] = [ThemeBase::Dark, ThemeBase::Light, ThemeBase::Classic, ThemeBase::Night];
+}
+
+impl std::fmt::Display for ThemeBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ThemeBase::Dark => "dark",
+            ThemeBase::Light => "light",
+            ThemeBase::Classic => "classic",
+            ThemeBase::Night => "night (red)"
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub base: ThemeBase,
+    pub window_rounding: f32,
+    pub frame_rounding: f32,
+    /// Colors overriding those of the base scheme, keyed by imgui style color name (e.g., "WindowBg").
+    pub colors: BTreeMap<String, [f32; 4]>
+}
+
+/// Structured (JSON) log files, in addition to the terminal output.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Directory of the log files; if empty, no log files are written.
+    pub directory: String,
+    /// Minimum level of messages written to the log files (error, warn, info, debug, trace).
+    pub level: String,
+    /// Levels overriding `level` for modules and their submodules, e.g., `"pointing_sim_core::workers" = "trace"`.
+    pub module_levels: BTreeMap<String, String>,
+    /// The current log file is rotated when it exceeds this size.
+    pub max_file_size_mb: f64,
+    /// Number of rotated log files kept in addition to the current one.
+    pub max_files: usize
+}
+
+impl Default for LoggingConfig {
+    fn default() -> LoggingConfig {
+        LoggingConfig{
+            directory: String::new(),
+            level: "info".into(),
+            module_levels: BTreeMap::new(),
+            max_file_size_mb: 10.0,
+            max_files: 5
+        }
+    }
+}
+
+/// Program settings (edited in the Settings window), loaded at startup from the settings file in the configuration
+/// directory. Font size, render options, instrument FOVs, display units, theme and map tiles take effect immediately,
+/// the rest after restart.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Logical font size (pixels).
+    pub font_size: f32,
+    /// Port of the first station's mount server.
+    pub mount_port: u16,
+    /// Port of the first station's ADS-B target feed (other feeds use the following ports).
+    pub target_port: u16,
+    pub render: RenderConfig,
+    /// Used unless a mount profile is selected with `--mount-profile`.
+    pub mount: MountConfig,
+    pub location: LocationConfig,
+    pub instruments: Vec<InstrumentFov>,
+    pub camera_presets: Vec<CameraPreset>,
+    pub units: units::DisplayUnits,
+    pub theme: ThemeConfig,
+    /// Path of map tile images (PNG, "XYZ" scheme) with `{z}`, `{x}`, `{y}` placeholders, e.g.,
+    /// `/data/tiles/{z}/{x}/{y}.png`; if empty, the map window shows no imagery.
+    pub map_tiles: String,
+    pub logging: LoggingConfig
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings{
+            font_size: 15.0,
+            mount_port: workers::MOUNT_SERVER_PORT,
+            target_port: workers::TARGET_SOURCE_PORT,
+            render: RenderConfig::default(),
+            mount: MountConfig::default(),
+            location: LocationConfig::default(),
+            instruments: default_instruments(),
+            camera_presets: vec![],
+            units: units::DisplayUnits::default(),
+            theme: ThemeConfig::default(),
+            map_tiles: String::new(),
+            logging: LoggingConfig::default()
+        }
+    }
+}
+
+/// Returns path of the file storing the GUI's window layout (creating the configuration directory if needed).
+pub fn layout_file() -> Option<PathBuf> {
+    let dir = config_dir()?;
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        log::error!("failed to create configuration directory {}: {}", dir.display(), e);
+        return None;
+    }
+    Some(dir.join(LAYOUT_FILE_NAME))
+}
+
+/// Returns path of the default settings file (in the configuration directory).
+fn default_settings_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(SETTINGS_FILE_NAME))
+}
+
+/// Loads settings from `path` (default: the configuration directory); defaults are used if there is no settings
+/// file.
+pub fn load_settings(path: Option<&Path>) -> Settings {
+    let path = match path.map(Path::to_path_buf).or_else(default_settings_file) {
+        Some(path) => path,
+        None => {
+            log::error!("cannot determine configuration directory; using default settings");
+            return Settings::default();
+        }
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => match toml::from_str::<Settings>(&contents) {
+            Ok(settings) => {
+                log::info!("loaded settings from {}", path.display());
+                Settings{ mount: settings.mount.validated(), ..settings }
+            },
+            Err(e) => {
+                log::error!("failed to parse {}: {}", path.display(), e);
+                Settings::default()
+            }
+        },
+        Err(_) => Settings::default()
+    }
+}
+
+/// Saves settings in `path` (default: the configuration directory); returns the file's path.
+pub fn save_settings(settings: &Settings, path: Option<&Path>) -> Result<PathBuf, String> {
+    let path = path.map(Path::to_path_buf)
+        .or_else(default_settings_file)
+        .ok_or_else(|| "cannot determine configuration directory".to_string())?;
+    let contents = toml::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, contents).map_err(|e| e.to_string())?;
+    Ok(path)
+}
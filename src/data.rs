@@ -7,10 +7,37 @@
 //
 
 use cgmath::{Basis3, Deg, EuclideanSpace, InnerSpace, Rad, Rotation, Rotation3};
-use crate::{gui::CameraView, workers::Mount, target_interpolator::TargetInterpolator};
-use glium::{glutin::surface::WindowSurface, program};
+use crate::{
+    challenge::Challenge,
+    command_console::CommandConsole,
+    event_hooks::{EventHooks, Hook},
+    frame_capture::FrameCapture,
+    mount_comparison::MountComparison,
+    mount_control::MountControl,
+    gui::{CameraView, SkyChart},
+    horizon::{HorizonProfile, load_horizon},
+    refraction::Atmosphere,
+    runner::GlContext,
+    scoring::Scoring,
+    workers::{Mount, Site, TargetControl, TargetMotion, TrackedTarget},
+    target_interpolator::TargetInterpolator,
+    telemetry::Telemetry,
+    tracking_controller::TrackingController,
+    zenith_keyhole::KeyholeMonitor
+};
+use glium::program;
 use pointing_utils::{TargetInfoMessage, LatLon, to_global_unit};
-use std::{cell::RefCell, error::Error, rc::Rc, sync::Arc};
+use std::{cell::RefCell, error::Error, rc::Rc, sync::{Arc, Mutex}};
+
+/// Shared shader functions (see `with_library`).
+const CLOUD_LAYER_GLSL: &str = include_str!("resources/shaders/cloud_layer.glsl");
+const SENSOR_NOISE_GLSL: &str = include_str!("resources/shaders/sensor_noise.glsl");
+const LENS_DISTORTION_GLSL: &str = include_str!("resources/shaders/lens_distortion.glsl");
+const SEEING_GLSL: &str = include_str!("resources/shaders/seeing.glsl");
+
+/// Spacing of the sky mesh's grid lines and number of line segments between neighboring grid lines.
+pub const SKY_MESH_STEP: Deg<f64> = Deg(10.0);
+pub const SKY_MESH_SUBSTEPS: usize = 10;
 
 #[derive(Copy, Clone)]
 pub struct Vertex2 {
@@ -35,35 +62,143 @@ glium::implement_vertex!(MeshVertex, position, normal);
 pub struct MeshBuffers<T: Copy> {
     pub vertices: Rc<glium::VertexBuffer<T>>,
     pub indices: Rc<glium::IndexBuffer<u32>>,
+    /// Radius of the bounding sphere centered at the origin of model coordinates.
+    pub bounding_radius: f64
 }
 
 pub struct OpenGlObjects {
     pub sky_mesh: MeshBuffers<Vertex3>,
+    pub ground_mesh: MeshBuffers<Vertex3>,
+    /// Unit disc facing the -X direction, centered at (1, 0, 0).
+    pub disc_mesh: MeshBuffers<Vertex3>,
     pub sky_mesh_prog: Rc<glium::Program>,
     pub texture_copy_single: Rc<glium::Program>,
     pub texture_copy_multi: Rc<glium::Program>,
     pub unit_quad: Rc<glium::VertexBuffer<Vertex2>>,
     pub target_mesh: MeshBuffers<MeshVertex>,
-    pub target_prog: Rc<glium::Program>
+    /// Vertex positions of `target_mesh` (for computing its image-space bounds).
+    pub target_mesh_points: Rc<Vec<[f32; 3]>>,
+    pub target_prog: Rc<glium::Program>,
+    /// Renders the cloud layer over the whole view (drawn on `unit_quad`).
+    pub clouds_prog: Rc<glium::Program>
 }
 
-pub struct ProgramData {
+/// Worker-side resources of a simulated station: a mount and the target feed observed from its site.
+pub struct StationLink {
+    pub target_receiver: crossbeam::channel::Receiver<TargetInfoMessage>,
+    pub mount: Arc<Mount>,
+    pub site: Site,
+    /// Age of the target data received from `target_receiver`.
+    pub feed_latency: std::time::Duration,
+    pub target_control: Arc<Mutex<TargetControl>>,
+    /// Updated with the target followed by the station's tracking controller.
+    pub tracked_target: TrackedTarget
+}
+
+/// Simulated station (mount with its own target feed and camera view).
+pub struct Station {
+    pub name: String,
     pub camera_view: Rc<RefCell<CameraView>>,
-    gl_objects: OpenGlObjects,
-    pub gui_state: crate::gui::GuiState,
     pub target_receiver: crossbeam::channel::Receiver<TargetInfoMessage>,
     pub target_subscribers: subscriber_rs::SubscriberCollection<TargetInfoMessage>,
     pub target_interpolator: Rc<RefCell<TargetInterpolator>>,
-    pub mount: Arc<Mount>
+    pub tracking_controller: Rc<RefCell<TrackingController>>,
+    pub keyhole_monitor: Rc<RefCell<KeyholeMonitor>>,
+    pub event_hooks: Rc<RefCell<EventHooks>>,
+    pub scoring: Rc<RefCell<Scoring>>,
+    pub telemetry: Rc<RefCell<Telemetry>>,
+    pub frame_capture: RefCell<FrameCapture>,
+    pub mount_control: RefCell<MountControl>,
+    pub sky_chart: RefCell<SkyChart>,
+    pub command_console: RefCell<CommandConsole>,
+    pub mount: Arc<Mount>,
+    /// Observer location (origin of the local frame of target positions).
+    pub site: Site
+}
+
+impl Station {
+    fn new(
+        name: String,
+        link: StationLink,
+        gl_objects: &OpenGlObjects,
+        horizon: &Rc<HorizonProfile>,
+        hooks: Vec<Hook>,
+        renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
+        display: &GlContext
+    ) -> Station {
+        let camera_view = Rc::new(RefCell::new(CameraView::new(gl_objects, Rc::clone(horizon), renderer, display)));
+
+        let target_interpolator = Rc::new(RefCell::new(TargetInterpolator::new()));
+        target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&camera_view) as _);
+
+        let tracking_controller = Rc::new(RefCell::new(
+            TrackingController::new(Arc::clone(&link.mount), link.feed_latency, Arc::clone(&link.tracked_target))
+        ));
+        target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&tracking_controller) as _);
+
+        let keyhole_monitor = Rc::new(RefCell::new(KeyholeMonitor::new(Arc::clone(&link.mount))));
+        target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&keyhole_monitor) as _);
+
+        let event_hooks = Rc::new(RefCell::new(
+            EventHooks::new(hooks, Arc::clone(&link.mount), Rc::downgrade(&camera_view))
+        ));
+        target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&event_hooks) as _);
+
+        let scoring = Rc::new(RefCell::new(Scoring::new(name.clone(), Arc::clone(&link.mount))));
+        target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&scoring) as _);
+
+        let telemetry = Rc::new(RefCell::new(Telemetry::new(name.clone(), Arc::clone(&link.mount))));
+        target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&telemetry) as _);
+
+        let frame_capture = RefCell::new(FrameCapture::new(
+            name.clone(), Rc::clone(&camera_view), Arc::clone(&link.mount), link.site, link.target_control
+        ));
+
+        let mount_control = RefCell::new(MountControl::new(Arc::clone(&link.mount), Rc::clone(&tracking_controller)));
+
+        let mut target_subscribers = subscriber_rs::SubscriberCollection::<TargetInfoMessage>::new();
+        target_subscribers.add(Rc::downgrade(&target_interpolator) as _);
+
+        Station{
+            name,
+            camera_view,
+            target_receiver: link.target_receiver,
+            target_subscribers,
+            target_interpolator,
+            tracking_controller,
+            keyhole_monitor,
+            event_hooks,
+            scoring,
+            telemetry,
+            frame_capture,
+            mount_control,
+            sky_chart: RefCell::new(SkyChart::default()),
+            command_console: RefCell::new(CommandConsole::new(Arc::clone(&link.mount))),
+            mount: link.mount,
+            site: link.site
+        }
+    }
+}
+
+pub struct ProgramData {
+    gl_objects: OpenGlObjects,
+    pub gui_state: crate::gui::GuiState,
+    pub stations: Vec<Station>,
+    /// Runs on the first station.
+    pub challenge: Challenge,
+    /// Compares the first station's mount with the second one, which mirrors it.
+    pub comparison: Option<Rc<RefCell<MountComparison>>>
 }
 
 impl ProgramData {
     pub fn new(
         renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
-        display: &glium::Display<WindowSurface>,
+        display: &GlContext,
         gui_state: crate::gui::GuiState,
-        target_receiver: crossbeam::channel::Receiver<TargetInfoMessage>,
-        mount: Arc<Mount>
+        station_links: Vec<StationLink>,
+        hooks: Vec<Hook>,
+        target_motion: Arc<Mutex<TargetMotion>>,
+        comparison: bool
     ) -> ProgramData {
         let create_gl_program = |result| -> glium::Program {
             match result {
@@ -82,14 +217,20 @@ impl ProgramData {
         let texture_copy_single = Rc::new(create_gl_program(program!(display,
             330 => {
                 vertex: include_str!("resources/shaders/pass-through.vert"),
-                fragment: include_str!("resources/shaders/texturing.frag"),
+                fragment: &with_libraries(
+                    include_str!("resources/shaders/texturing.frag"),
+                    &[SENSOR_NOISE_GLSL, LENS_DISTORTION_GLSL, SEEING_GLSL]
+                ),
             }
         )));
 
         let texture_copy_multi = Rc::new(create_gl_program(program!(display,
             330 => {
                 vertex: include_str!("resources/shaders/pass-through.vert"),
-                fragment: include_str!("resources/shaders/texturing_multi-sample.frag"),
+                fragment: &with_libraries(
+                    include_str!("resources/shaders/texturing_multi-sample.frag"),
+                    &[SENSOR_NOISE_GLSL, LENS_DISTORTION_GLSL, SEEING_GLSL]
+                ),
             }
         )));
 
@@ -104,43 +245,84 @@ impl ProgramData {
         let target_prog = Rc::new(create_gl_program(program!(display,
             330 => {
                 vertex: include_str!("resources/shaders/3d_view.vert"),
-                fragment: include_str!("resources/shaders/surface.frag"),
+                fragment: &with_library(include_str!("resources/shaders/surface.frag"), CLOUD_LAYER_GLSL),
             }
         )));
 
+        let clouds_prog = Rc::new(create_gl_program(program!(display,
+            330 => {
+                vertex: include_str!("resources/shaders/pass-through.vert"),
+                fragment: &with_library(include_str!("resources/shaders/clouds.frag"), CLOUD_LAYER_GLSL),
+            }
+        )));
+
+        let horizon = Rc::new(load_horizon());
+        let (target_mesh, target_mesh_points) = create_target_mesh(display);
+
         let gl_objects = OpenGlObjects{
-            sky_mesh: create_sky_mesh(Deg(10.0), 10, display),
+            sky_mesh: create_sky_mesh(SKY_MESH_STEP, SKY_MESH_SUBSTEPS, None, display),
+            ground_mesh: create_ground_mesh(&horizon, display),
+            disc_mesh: create_disc_mesh(32, display),
             sky_mesh_prog,
             texture_copy_single,
             texture_copy_multi,
             unit_quad,
-            target_mesh: create_target_mesh(display),
-            target_prog
+            target_mesh,
+            target_mesh_points: Rc::new(target_mesh_points),
+            target_prog,
+            clouds_prog
         };
 
-        let camera_view = Rc::new(RefCell::new(CameraView::new(&gl_objects, renderer, display)));
-
-        let target_interpolator = Rc::new(RefCell::new(TargetInterpolator::new()));
-        target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&camera_view) as _);
-
-        let mut target_subscribers = subscriber_rs::SubscriberCollection::<TargetInfoMessage>::new();
-        target_subscribers.add(Rc::downgrade(&target_interpolator) as _);
+        let stations: Vec<Station> = station_links.into_iter().enumerate()
+            .map(|(i, link)| Station::new(
+                format!("mount {}", i + 1), link, &gl_objects, &horizon, hooks.clone(), renderer, display
+            ))
+            .collect();
+
+        let comparison = if comparison {
+            let comparison = Rc::new(RefCell::new(MountComparison::new(
+                (stations[0].name.clone(), Arc::clone(&stations[0].mount)),
+                (stations[1].name.clone(), Arc::clone(&stations[1].mount))
+            )));
+            stations[0].target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&comparison) as _);
+            Some(comparison)
+        } else {
+            None
+        };
 
         ProgramData{
-            camera_view,
             gl_objects,
             gui_state,
-            target_receiver,
-            target_subscribers,
-            target_interpolator,
-            mount
+            stations,
+            challenge: Challenge::new(target_motion),
+            comparison
+        }
+    }
+}
+
+impl Drop for ProgramData {
+    fn drop(&mut self) {
+        for station in &self.stations {
+            station.mount.save_state();
         }
     }
 }
 
+/// Inserts `library` (shared shader functions) after the `#version` directive of `shader`.
+fn with_library(shader: &str, library: &str) -> String {
+    with_libraries(shader, &[library])
+}
+
+/// Inserts `libraries` (shared shader functions) after the `#version` directive of `shader`.
+fn with_libraries(shader: &str, libraries: &[&str]) -> String {
+    let (version, body) = shader.split_once('\n').unwrap();
+    format!("{}\n{}\n{}", version, libraries.join("\n"), body)
+}
+
+/// Returns mesh buffers and vertex positions.
 fn create_target_mesh(
-    display: &glium::Display<WindowSurface>
-) -> MeshBuffers<MeshVertex> {
+    display: &GlContext
+) -> (MeshBuffers<MeshVertex>, Vec<[f32; 3]>) {
     use cgmath::Point3 as Point3;
     use cgmath::Vector3 as Vector3;
 
@@ -207,58 +389,124 @@ fn create_target_mesh(
 
     let vertices = Rc::new(glium::VertexBuffer::new(display, &vertex_data).unwrap());
     let indices = Rc::new(glium::IndexBuffer::new(display, glium::index::PrimitiveType::TrianglesList, &index_data).unwrap());
+    let bounding_radius = vertex_data.iter()
+        .map(|v| cgmath::Vector3::from(v.position).cast::<f64>().unwrap().magnitude())
+        .fold(0.0, f64::max);
 
-    MeshBuffers{ vertices, indices }
+    (MeshBuffers{ vertices, indices, bounding_radius }, vertex_data.iter().map(|v| v.position).collect())
 }
 
-fn create_sky_mesh(
+/// Index which splits the sky mesh's line strips (requires `DrawParameters::primitive_restart_index`).
+const PRIMITIVE_RESTART_INDEX: u32 = u32::MAX;
+
+/// Creates a grid of meridians and parallels spaced by `step`, each consisting of `num_substeps` segments
+/// between intersections.
+///
+/// Vertices are shared between meridians and parallels (and the poles are single vertices); lines are drawn
+/// as line strips separated by `PRIMITIVE_RESTART_INDEX`.
+/// Creates the grid of altitude and azimuth lines on the unit sphere (local frame); if `refraction` is set, the lines
+/// are shown at their apparent (refracted) altitudes.
+pub fn create_sky_mesh(
     step: cgmath::Deg<f64>,
     num_substeps: usize,
-    display: &glium::Display<WindowSurface>
+    refraction: Option<&Atmosphere>,
+    display: &GlContext
 ) -> MeshBuffers<Vertex3> {
+    let num_lat_steps = (180.0 / step.0).round() as usize * num_substeps;
+    let num_lon_steps = (360.0 / step.0).round() as usize * num_substeps;
+    let lat_substep = cgmath::Deg(180.0) / num_lat_steps as f64;
+    let lon_substep = cgmath::Deg(360.0) / num_lon_steps as f64;
+
     let mut vertex_data: Vec<Vertex3> = vec![];
+    let mut push_vertex = |latitude, longitude| {
+        let p = to_global_unit(&LatLon{ lat: latitude, lon: longitude }).0;
+        let p = cgmath::Vector3::new(p.x, p.y, p.z);
+        let p = refraction.map_or(p, |atmosphere| atmosphere.apparent_position(p));
+        vertex_data.push(Vertex3{ position: *p.cast::<f32>().unwrap().as_ref() });
+    };
+
+    push_vertex(cgmath::Deg(-90.0), cgmath::Deg(0.0));
+    for i in 1..num_lat_steps {
+        for j in 0..num_lon_steps {
+            push_vertex(cgmath::Deg(-90.0) + lat_substep * i as f64, cgmath::Deg(-180.0) + lon_substep * j as f64);
+        }
+    }
+    push_vertex(cgmath::Deg(90.0), cgmath::Deg(0.0));
+
+    let north_pole = (vertex_data.len() - 1) as u32;
+    let vertex_index = |i: usize, j: usize| -> u32 {
+        if i == 0 {
+            0
+        } else if i == num_lat_steps {
+            north_pole
+        } else {
+            (1 + (i - 1) * num_lon_steps + j % num_lon_steps) as u32
+        }
+    };
+
     let mut index_data: Vec<u32> = vec![];
 
-    let mut longitude = cgmath::Deg(-180.0);
-    while longitude <= cgmath::Deg(180.0) {
-        let mut latitude = cgmath::Deg(-90.0);
-        let mut parallel_starts = true;
-        while latitude <= cgmath::Deg(90.0) {
-            vertex_data.push(Vertex3{
-                position: *to_global_unit(&LatLon{ lat: latitude, lon: longitude }).0.cast::<f32>().unwrap().as_ref()
-            });
-            if !parallel_starts {
-                index_data.push((vertex_data.len() - 2) as u32);
-                index_data.push((vertex_data.len() - 1) as u32);
-            }
-            latitude += step / num_substeps as f64;
-            parallel_starts = false;
-        }
+    for j in (0..num_lon_steps).step_by(num_substeps) {
+        index_data.extend((0..=num_lat_steps).map(|i| vertex_index(i, j)));
+        index_data.push(PRIMITIVE_RESTART_INDEX);
+    }
 
-        longitude += step;
+    for i in (num_substeps..num_lat_steps).step_by(num_substeps) {
+        index_data.extend((0..=num_lon_steps).map(|j| vertex_index(i, j)));
+        index_data.push(PRIMITIVE_RESTART_INDEX);
     }
 
-    let mut latitude = cgmath::Deg(-90.0);
-    while latitude <= cgmath::Deg(90.0) {
-        let mut longitude = cgmath::Deg(-180.0);
-        let mut meridian_starts = true;
-        while longitude <= cgmath::Deg(180.0) {
-            vertex_data.push(Vertex3{
-                position: *to_global_unit(&LatLon{ lat: latitude, lon: longitude }).0.cast::<f32>().unwrap().as_ref()
-            });
-            if !meridian_starts {
-                index_data.push((vertex_data.len() - 2) as u32);
-                index_data.push((vertex_data.len() - 1) as u32);
-            }
-            longitude += step / num_substeps as f64;
-            meridian_starts = false;
-        }
+    log::info!(
+        "sky mesh: {} vertices ({} KiB), {} indices ({} KiB)",
+        vertex_data.len(), vertex_data.len() * std::mem::size_of::<Vertex3>() / 1024,
+        index_data.len(), index_data.len() * std::mem::size_of::<u32>() / 1024
+    );
 
-        latitude += step;
+    let vertices = Rc::new(glium::VertexBuffer::new(display, &vertex_data).unwrap());
+    let indices = Rc::new(glium::IndexBuffer::new(display, glium::index::PrimitiveType::LineStrip, &index_data).unwrap());
+
+    MeshBuffers{ vertices, indices, bounding_radius: 1.0 }
+}
+
+/// Creates the ground (everything below the horizon profile) as a fan of triangles on the unit sphere
+/// (local frame), converging at nadir.
+fn create_ground_mesh(horizon: &HorizonProfile, display: &GlContext) -> MeshBuffers<Vertex3> {
+    let direction = |azimuth: f64, altitude: f64| {
+        let (az, alt) = (azimuth.to_radians(), altitude.to_radians());
+        // x points north, y west, z up
+        Vertex3{ position: [(alt.cos() * az.cos()) as f32, (-alt.cos() * az.sin()) as f32, alt.sin() as f32] }
+    };
+
+    let mut vertex_data = vec![direction(0.0, -90.0)];
+    vertex_data.extend(horizon.samples().map(|(az, alt)| direction(az, alt)));
+    let num_samples = vertex_data.len() - 1;
+
+    let mut index_data: Vec<u32> = vec![];
+    for i in 0..num_samples {
+        index_data.extend([0, 1 + i as u32, 1 + ((i + 1) % num_samples) as u32]);
     }
 
     let vertices = Rc::new(glium::VertexBuffer::new(display, &vertex_data).unwrap());
-    let indices = Rc::new(glium::IndexBuffer::new(display, glium::index::PrimitiveType::LinesList, &index_data).unwrap());
+    let indices = Rc::new(glium::IndexBuffer::new(display, glium::index::PrimitiveType::TrianglesList, &index_data).unwrap());
+
+    MeshBuffers{ vertices, indices, bounding_radius: 1.0 }
+}
+
+/// Creates a unit disc in the plane x = 1 as a fan of `num_segments` triangles.
+fn create_disc_mesh(num_segments: usize, display: &GlContext) -> MeshBuffers<Vertex3> {
+    let mut vertex_data = vec![Vertex3{ position: [1.0, 0.0, 0.0] }];
+    for i in 0..num_segments {
+        let angle = i as f32 * 2.0 * std::f32::consts::PI / num_segments as f32;
+        vertex_data.push(Vertex3{ position: [1.0, angle.cos(), angle.sin()] });
+    }
+
+    let mut index_data: Vec<u32> = vec![];
+    for i in 0..num_segments {
+        index_data.extend([0, 1 + i as u32, 1 + ((i + 1) % num_segments) as u32]);
+    }
+
+    let vertices = Rc::new(glium::VertexBuffer::new(display, &vertex_data).unwrap());
+    let indices = Rc::new(glium::IndexBuffer::new(display, glium::index::PrimitiveType::TrianglesList, &index_data).unwrap());
 
-    MeshBuffers{ vertices, indices }
+    MeshBuffers{ vertices, indices, bounding_radius: 2.0f64.sqrt() }
 }
@@ -0,0 +1,208 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Event hooks: simulator actions triggered by conditions, for closed-loop (e.g. adversarial) scenarios.
+//!
+//! Hooks are read from a TOML file, e.g.:
+//!
+//! ```toml
+//! # drop the feed whenever tracking error falls below 1 arcmin
+//! [[hooks]]
+//! on = { event = "on_error_threshold", below_arcsec = 60.0 }
+//! then = { action = "drop_feed", duration_s = 3.0 }
+//! cooldown_s = 10.0
+//!
+//! [[hooks]]
+//! on = { event = "on_connect" }
+//! then = { action = "log_marker", text = "client connected" }
+//! ```
+
+use crate::{gui::CameraView, target_geometry::{separation, TargetDirection}, workers::{ClientStatus, Mount}};
+use pointing_utils::{TargetInfoMessage, uom};
+use serde::Deserialize;
+use std::{cell::RefCell, path::Path, rc::Weak, sync::Arc, time::{Duration, Instant}};
+use subscriber_rs::Subscriber;
+use uom::{si::f64, si::angle};
+
+const EVENT_HOOKS_FILE_NAME: &str = "event_hooks.toml";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A client has connected to the mount.
+    OnConnect,
+    /// The target has entered the camera's field of view.
+    OnTargetEnterFov,
+    /// Angle between the boresight and the target has crossed a threshold.
+    OnErrorThreshold{ below_arcsec: Option<f64>, above_arcsec: Option<f64> }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    LogMarker{ text: String },
+    /// Stops delivering target messages (the last one keeps being extrapolated).
+    DropFeed{ duration_s: f64 },
+    StopMount,
+    Park,
+    /// Unspecified values are left unchanged.
+    SetWind{ enabled: bool, turbulence_rms_arcsec: Option<f64>, gust_amplitude_arcsec: Option<f64> }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Hook {
+    pub on: Event,
+    pub then: Action,
+    /// Minimum time between consecutive activations.
+    #[serde(default)]
+    pub cooldown_s: f64
+}
+
+#[derive(Deserialize)]
+struct HooksFile {
+    #[serde(default)]
+    hooks: Vec<Hook>
+}
+
+/// Loads hooks from `path` or, if not specified, from the configuration directory (if present there).
+pub fn load_hooks(path: Option<&Path>) -> Vec<Hook> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match crate::config::config_dir() {
+            Some(dir) if dir.join(EVENT_HOOKS_FILE_NAME).exists() => dir.join(EVENT_HOOKS_FILE_NAME),
+            _ => return vec![]
+        }
+    };
+
+    let result = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| toml::from_str::<HooksFile>(&contents).map_err(|e| e.to_string()));
+    match result {
+        Ok(file) => {
+            log::info!("loaded {} event hook(s) from {}", file.hooks.len(), path.display());
+            file.hooks
+        },
+        Err(e) => {
+            log::error!("failed to load event hooks from {}: {}", path.display(), e);
+            vec![]
+        }
+    }
+}
+
+struct HookState {
+    hook: Hook,
+    /// Hooks fire when their condition becomes true (not while it stays true).
+    condition_met: bool,
+    last_fired: Option<Instant>
+}
+
+/// Evaluates hooks of a station on every target update.
+pub struct EventHooks {
+    mount: Arc<Mount>,
+    camera_view: Weak<RefCell<CameraView>>,
+    hooks: Vec<HookState>,
+    feed_dropped_until: Option<Instant>
+}
+
+impl EventHooks {
+    pub fn new(hooks: Vec<Hook>, mount: Arc<Mount>, camera_view: Weak<RefCell<CameraView>>) -> EventHooks {
+        EventHooks{
+            mount,
+            camera_view,
+            hooks: hooks.into_iter().map(|hook| HookState{ hook, condition_met: false, last_fired: None }).collect(),
+            feed_dropped_until: None
+        }
+    }
+
+    /// Returns true if target messages shall be discarded.
+    pub fn feed_dropped(&self) -> bool {
+        self.feed_dropped_until.map_or(false, |t| Instant::now() < t)
+    }
+
+    /// Performs `action` (also used by scenario scripts).
+    pub fn execute(&mut self, action: &Action) {
+        match action {
+            Action::LogMarker{ text } => log::info!("marker: {}", text),
+
+            Action::DropFeed{ duration_s } => {
+                let until = Duration::try_from_secs_f64(*duration_s).ok().and_then(|d| Instant::now().checked_add(d));
+                match until {
+                    Some(until) => {
+                        log::info!("event hook: dropping target feed for {:.1} s", duration_s);
+                        self.feed_dropped_until = Some(until);
+                    },
+                    None => log::error!("invalid feed drop duration: {}", duration_s)
+                }
+            },
+
+            Action::StopMount => {
+                log::info!("event hook: stopping mount");
+                self.mount.stop();
+            },
+
+            Action::Park => self.mount.park(),
+
+            Action::SetWind{ enabled, turbulence_rms_arcsec, gust_amplitude_arcsec } => {
+                let mut settings = self.mount.wind_settings();
+                settings.enabled = *enabled;
+                if let Some(value) = turbulence_rms_arcsec {
+                    settings.turbulence_rms = f64::Angle::new::<angle::second>(*value);
+                }
+                if let Some(value) = gust_amplitude_arcsec {
+                    settings.gust_amplitude = f64::Angle::new::<angle::second>(*value);
+                }
+                log::info!("event hook: changing wind settings");
+                self.mount.set_wind_settings(settings);
+            }
+        }
+    }
+}
+
+impl Subscriber<TargetInfoMessage> for EventHooks {
+    fn notify(&mut self, value: &TargetInfoMessage) {
+        if self.hooks.is_empty() { return; }
+
+        let state = self.mount.get();
+        let error = TargetDirection::from_message(value)
+            .map(|target| separation(state.boresight_az, state.boresight_alt, target.az, target.alt));
+        let half_fov = self.camera_view.upgrade().map(|view| view.borrow().field_of_view_y() / 2.0);
+        let client_connected = self.mount.client_status() == ClientStatus::Connected;
+
+        let mut actions = vec![];
+        for hook_state in &mut self.hooks {
+            let condition_met = match &hook_state.hook.on {
+                Event::OnConnect => client_connected,
+
+                Event::OnTargetEnterFov => match (error, half_fov) {
+                    (Some(error), Some(half_fov)) => error < half_fov,
+                    _ => false
+                },
+
+                Event::OnErrorThreshold{ below_arcsec, above_arcsec } => match error {
+                    Some(error) => {
+                        let error = error.get::<angle::second>();
+                        below_arcsec.map_or(true, |t| error < t) && above_arcsec.map_or(true, |t| error > t)
+                    },
+                    None => false
+                }
+            };
+
+            let cooled_down = hook_state.last_fired
+                .map_or(true, |t| t.elapsed().as_secs_f64() >= hook_state.hook.cooldown_s);
+            if condition_met && !hook_state.condition_met && cooled_down {
+                hook_state.last_fired = Some(Instant::now());
+                actions.push(hook_state.hook.then.clone());
+            }
+            hook_state.condition_met = condition_met;
+        }
+
+        for action in &actions {
+            self.execute(action);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Saving of camera images as PNG, individually or as timed sequences with a sidecar CSV of mount and target
+//! truth (e.g., for building labeled datasets for detection and tracking algorithms).
+//!
+//! Optionally, the target's bounding box in each frame is exported as CSV: `frame,file,class,x_min,y_min,x_max,y_max`
+//! (pixels; no row if the target is not visible).
+
+use cgmath::{EuclideanSpace, InnerSpace};
+use crate::{
+    color_mode::ColorMode,
+    gui::CameraView,
+    target_geometry::TargetDirection,
+    workers::{Mount, Site, TargetControl}
+};
+use pointing_utils::uom;
+use std::{
+    cell::RefCell,
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant}
+};
+use uom::si::angle;
+
+pub const DEFAULT_SEQUENCE_INTERVAL: Duration = Duration::from_millis(100);
+
+const TRUTH_FILE_NAME: &str = "truth.csv";
+
+const BOXES_FILE_NAME: &str = "boxes.csv";
+
+const BOXES_HEADER: &str = "frame,file,class,x_min,y_min,x_max,y_max";
+
+/// Class label of the target in exported bounding boxes.
+pub const TARGET_CLASS: &str = "aircraft";
+
+struct Sequence {
+    dir: PathBuf,
+    truth: std::io::BufWriter<std::fs::File>,
+    boxes: Option<std::io::BufWriter<std::fs::File>>,
+    start: Instant,
+    last_frame: Option<Instant>,
+    num_frames: usize
+}
+
+pub struct FrameCapture {
+    station: String,
+    camera_view: Rc<RefCell<CameraView>>,
+    mount: Arc<Mount>,
+    site: Site,
+    /// Target truth; the first target is recorded.
+    target_control: Arc<Mutex<TargetControl>>,
+    /// Interval between frames of recorded sequences.
+    pub sequence_interval: Duration,
+    /// If set, the target's bounding boxes are exported along with frames.
+    pub export_boxes: bool,
+    sequence: Option<Sequence>
+}
+
+fn captures_dir() -> Result<PathBuf, String> {
+    crate::config::config_dir()
+        .map(|dir| dir.join("captures"))
+        .ok_or_else(|| "cannot determine configuration directory".to_string())
+}
+
+/// Saves RGBA `data` (top row first) converted to `color_mode`.
+fn save_png(path: &Path, width: u32, height: u32, data: &[u8], color_mode: ColorMode) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+    encoder.set_color(if color_mode.bytes_per_pixel() == 1 { png::ColorType::Grayscale } else { png::ColorType::Rgba });
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(&color_mode.convert(width, height, data)).map_err(|e| e.to_string())
+}
+
+/// Writes a bounding box row (see module description); `bounding_box` as in `CameraView::target_bounding_box`.
+fn write_box(
+    writer: &mut impl Write,
+    frame: usize,
+    file_name: &str,
+    bounding_box: [f64; 4],
+    width: u32,
+    height: u32
+) -> Result<(), String> {
+    let [x_min, y_min, x_max, y_max] = bounding_box;
+    let (width, height) = (width as f64, height as f64);
+    writeln!(
+        writer,
+        "{},{},{},{:.2},{:.2},{:.2},{:.2}",
+        frame, file_name, TARGET_CLASS, x_min * width, y_min * height, x_max * width, y_max * height
+    ).map_err(|e| e.to_string())
+}
+
+fn create_boxes_file(path: &Path) -> Result<std::io::BufWriter<std::fs::File>, String> {
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path).map_err(|e| e.to_string())?);
+    writeln!(writer, "{}", BOXES_HEADER).map_err(|e| e.to_string())?;
+    Ok(writer)
+}
+
+impl FrameCapture {
+    pub fn new(
+        station: String,
+        camera_view: Rc<RefCell<CameraView>>,
+        mount: Arc<Mount>,
+        site: Site,
+        target_control: Arc<Mutex<TargetControl>>
+    ) -> FrameCapture {
+        FrameCapture{
+            station,
+            camera_view,
+            mount,
+            site,
+            target_control,
+            sequence_interval: DEFAULT_SEQUENCE_INTERVAL,
+            export_boxes: false,
+            sequence: None
+        }
+    }
+
+    fn file_stem(&self) -> String {
+        format!("{}-{}", self.station.replace(' ', "_"), chrono::Local::now().format("%Y%m%d-%H%M%S%.3f"))
+    }
+
+    /// Saves the current camera image in the captures directory (and its bounding box in a CSV file of the same
+    /// name, if `export_boxes` is set); returns its path.
+    pub fn save_frame(&self) -> Result<PathBuf, String> {
+        let dir = captures_dir()?;
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let stem = self.file_stem();
+        let path = dir.join(format!("{}.png", stem));
+        let camera_view = self.camera_view.borrow();
+        let image = camera_view.read_frame();
+        save_png(&path, image.width, image.height, &image.data, camera_view.color_mode())?;
+
+        if self.export_boxes {
+            let mut boxes = create_boxes_file(&dir.join(format!("{}.csv", stem)))?;
+            if let Some(bounding_box) = camera_view.target_bounding_box() {
+                write_box(&mut boxes, 0, &format!("{}.png", stem), bounding_box, image.width, image.height)?;
+            }
+            boxes.flush().map_err(|e| e.to_string())?;
+        }
+
+        Ok(path)
+    }
+
+    /// Starts recording a sequence into a new subdirectory of the captures directory; returns its path.
+    pub fn start_sequence(&mut self) -> Result<PathBuf, String> {
+        let dir = captures_dir()?.join(self.file_stem());
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let mut truth = std::io::BufWriter::new(
+            std::fs::File::create(dir.join(TRUTH_FILE_NAME)).map_err(|e| e.to_string())?
+        );
+        writeln!(
+            truth,
+            "frame,file,time_s,axis1_deg,axis2_deg,boresight_az_deg,boresight_alt_deg,\
+            target_az_deg,target_alt_deg,target_distance_m,target_x_px,target_y_px"
+        ).map_err(|e| e.to_string())?;
+        let boxes = if self.export_boxes { Some(create_boxes_file(&dir.join(BOXES_FILE_NAME))?) } else { None };
+
+        self.sequence = Some(Sequence{
+            dir: dir.clone(), truth, boxes, start: Instant::now(), last_frame: None, num_frames: 0
+        });
+        Ok(dir)
+    }
+
+    pub fn stop_sequence(&mut self) {
+        if let Some(mut sequence) = self.sequence.take() {
+            if let Err(e) = sequence.truth.flush() {
+                log::error!("failed to save {}: {}", sequence.dir.join(TRUTH_FILE_NAME).display(), e);
+            }
+            if let Some(Err(e)) = sequence.boxes.as_mut().map(|boxes| boxes.flush()) {
+                log::error!("failed to save {}: {}", sequence.dir.join(BOXES_FILE_NAME).display(), e);
+            }
+        }
+    }
+
+    /// Returns the directory and number of frames of the sequence being recorded.
+    pub fn sequence_status(&self) -> Option<(&Path, usize)> {
+        self.sequence.as_ref().map(|s| (s.dir.as_path(), s.num_frames))
+    }
+
+    /// Records the next frame of the sequence (if due); to be called periodically.
+    pub fn update(&mut self) {
+        let due = match &self.sequence {
+            Some(sequence) => sequence.last_frame.map_or(true, |t| t.elapsed() >= self.sequence_interval),
+            None => false
+        };
+        if !due { return; }
+
+        if let Err(e) = self.record_frame() {
+            log::error!("failed to record frame, stopping sequence: {}", e);
+            self.stop_sequence();
+        }
+    }
+
+    fn record_frame(&mut self) -> Result<(), String> {
+        let camera_view = self.camera_view.borrow();
+        let image = camera_view.read_frame();
+        let mount_state = self.mount.get();
+        // the truth (not the feed, which may be delayed, erroneous or swapped)
+        let truth = self.target_control.lock().unwrap().targets.first().map(|target| self.site.local_target(target));
+        let target = truth.as_ref().and_then(|msg| {
+            TargetDirection::from_message(msg).map(|dir| (dir, msg.position.0.to_vec().magnitude()))
+        });
+        let image_pos = camera_view.target_image_position();
+        let bounding_box = camera_view.target_bounding_box();
+
+        let sequence = self.sequence.as_mut().unwrap();
+        let file_name = format!("frame_{:06}.png", sequence.num_frames);
+        save_png(&sequence.dir.join(&file_name), image.width, image.height, &image.data, camera_view.color_mode())?;
+
+        let opt = |value: Option<f64>, precision: usize| value.map_or(String::new(), |v| format!("{:.*}", precision, v));
+        writeln!(
+            sequence.truth,
+            "{},{},{:.3},{:.6},{:.6},{:.6},{:.6},{},{},{},{},{}",
+            sequence.num_frames,
+            file_name,
+            sequence.start.elapsed().as_secs_f64(),
+            mount_state.axis1_pos.get::<angle::degree>(),
+            mount_state.axis2_pos.get::<angle::degree>(),
+            mount_state.boresight_az.get::<angle::degree>(),
+            mount_state.boresight_alt.get::<angle::degree>(),
+            opt(target.as_ref().map(|(dir, _)| dir.az.get::<angle::degree>()), 6),
+            opt(target.as_ref().map(|(dir, _)| dir.alt.get::<angle::degree>()), 6),
+            opt(target.as_ref().map(|(_, distance)| *distance), 1),
+            opt(image_pos.map(|pos| pos[0] * image.width as f64), 2),
+            opt(image_pos.map(|pos| pos[1] * image.height as f64), 2)
+        ).map_err(|e| e.to_string())?;
+
+        if let (Some(boxes), Some(bounding_box)) = (sequence.boxes.as_mut(), bounding_box) {
+            write_box(boxes, sequence.num_frames, &file_name, bounding_box, image.width, image.height)?;
+        }
+
+        sequence.last_frame = Some(Instant::now());
+        sequence.num_frames += 1;
+
+        Ok(())
+    }
+}
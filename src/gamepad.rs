@@ -0,0 +1,103 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Hand-controller-style operation of a mount with a gamepad.
+//!
+//! Mapping:
+//!   - left stick: slew proportionally to the deflection, up to the selected rate (right: axis 1 positive,
+//!     up: axis 2 positive)
+//!   - right/left bumper: next/previous slew rate
+//!   - south button (A/cross): stop
+//!   - east button (B/circle): park/unpark
+
+use crate::mount_control::MountControl;
+
+/// Stick deflections below this value are ignored.
+const DEAD_ZONE: f64 = 0.15;
+
+/// Returns the deflection with the dead zone removed; the response is quadratic to allow fine control.
+fn shape(value: f32) -> f64 {
+    let magnitude = ((value.abs() as f64 - DEAD_ZONE) / (1.0 - DEAD_ZONE)).clamp(0.0, 1.0);
+    (value as f64).signum() * magnitude * magnitude
+}
+
+pub struct Gamepad {
+    /// `None` if gamepad support could not be initialized.
+    gilrs: Option<gilrs::Gilrs>,
+    pub enabled: bool,
+    /// Index of the controlled station.
+    pub station_idx: usize,
+    /// Gamepad from which input was last received.
+    active: Option<gilrs::GamepadId>,
+    /// True while the stick is deflected (zero deflection is sent once, so as not to stop slews from other sources).
+    deflected: bool
+}
+
+impl Default for Gamepad {
+    fn default() -> Gamepad {
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => { log::error!("gamepad support unavailable: {}", e); None }
+        };
+        Gamepad{ gilrs, enabled: true, station_idx: 0, active: None, deflected: false }
+    }
+}
+
+impl Gamepad {
+    pub fn available(&self) -> bool { self.gilrs.is_some() }
+
+    /// Returns names of the connected gamepads.
+    pub fn connected(&self) -> Vec<String> {
+        self.gilrs.as_ref().map_or(vec![], |gilrs| gilrs.gamepads().map(|(_, gp)| gp.name().to_string()).collect())
+    }
+
+    /// Processes gamepad input and commands the mount via `control`; to be called every frame.
+    pub fn update(&mut self, control: &mut MountControl) {
+        let gilrs = match &mut self.gilrs {
+            Some(gilrs) => gilrs,
+            None => return
+        };
+
+        while let Some(gilrs::Event{ id, event, .. }) = gilrs.next_event() {
+            match event {
+                gilrs::EventType::Connected => log::info!("gamepad connected: {}", gilrs.gamepad(id).name()),
+
+                gilrs::EventType::Disconnected => {
+                    log::info!("gamepad disconnected: {}", gilrs.gamepad(id).name());
+                    if self.active == Some(id) { self.active = None; }
+                },
+
+                gilrs::EventType::ButtonPressed(button, _) if self.enabled => {
+                    self.active = Some(id);
+                    match button {
+                        gilrs::Button::South => control.stop(),
+                        gilrs::Button::East => control.toggle_park(),
+                        gilrs::Button::RightTrigger => control.change_rate(1),
+                        gilrs::Button::LeftTrigger => control.change_rate(-1),
+                        _ => ()
+                    }
+                },
+
+                gilrs::EventType::AxisChanged(..) => self.active = Some(id),
+
+                _ => ()
+            }
+        }
+
+        let deflection = match self.active.filter(|_| self.enabled) {
+            Some(id) => {
+                let gamepad = gilrs.gamepad(id);
+                [shape(gamepad.value(gilrs::Axis::LeftStickX)), shape(gamepad.value(gilrs::Axis::LeftStickY))]
+            },
+            None => [0.0; 2]
+        };
+        let deflected = deflection != [0.0; 2];
+        if deflected || self.deflected { control.slew_proportional(deflection); }
+        self.deflected = deflected;
+    }
+}
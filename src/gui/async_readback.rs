@@ -0,0 +1,59 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use glium::texture::{pixel_buffer::PixelBuffer, RawImage2d, Texture2d};
+use std::collections::VecDeque;
+
+/// Number of frames between starting a readback and accessing its contents. Reading a pixel buffer
+/// which the GPU has not finished filling would stall the pipeline.
+pub const DEFAULT_READBACK_LATENCY: usize = 3;
+
+/// Asynchronous texture readback using pixel buffer objects (PBOs).
+///
+/// Each call to `push` schedules a GPU-side copy of the texture into a PBO; the contents of a PBO are mapped
+/// into RAM only `latency` frames later, when the copy has (most likely) completed.
+pub struct AsyncReadback {
+    latency: usize,
+    pending: VecDeque<PixelBuffer<(u8, u8, u8, u8)>>,
+    latest: Option<RawImage2d<'static, u8>>,
+    num_frames: u64
+}
+
+impl AsyncReadback {
+    pub fn new(latency: usize) -> AsyncReadback {
+        AsyncReadback{
+            latency: latency.max(1),
+            pending: VecDeque::new(),
+            latest: None,
+            num_frames: 0
+        }
+    }
+
+    pub fn push(&mut self, texture: &Texture2d) {
+        self.pending.push_back(texture.read_to_pixel_buffer());
+
+        while self.pending.len() > self.latency {
+            let buffer = self.pending.pop_front().unwrap();
+            match buffer.read_as_texture_2d::<RawImage2d<u8>>() {
+                Ok(image) => {
+                    self.latest = Some(image);
+                    self.num_frames += 1;
+                },
+                Err(e) => log::error!("failed to read pixel buffer: {:?}", e)
+            }
+        }
+    }
+
+    pub fn latency(&self) -> usize { self.latency }
+
+    /// Returns the most recently completed frame (RGBA, bottom row first).
+    pub fn latest(&self) -> Option<&RawImage2d<'static, u8>> { self.latest.as_ref() }
+
+    /// Returns the number of frames read back so far.
+    pub fn num_frames(&self) -> u64 { self.num_frames }
+}
@@ -0,0 +1,52 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Saving and recalling of named camera view configurations (stored with the settings).
+
+use crate::{config::CameraPreset, gui::{CameraView, GuiState, reticle::ReticleStyle}};
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+/// State of the preset controls in the camera view's context menu.
+#[derive(Default)]
+pub struct PresetSelection {
+    /// Index in `Settings::camera_presets`.
+    pub selected: usize,
+    /// Name of the preset to be saved.
+    pub name: String
+}
+
+/// Returns the current configuration of the camera view.
+pub fn capture(name: &str, gui_state: &GuiState, camera_view: &CameraView) -> CameraPreset {
+    CameraPreset{
+        name: name.to_string(),
+        fov_y_deg: camera_view.field_of_view_y().get::<angle::degree>(),
+        reticle: gui_state.reticle.style.to_string(),
+        reticle_spacing_arcmin: gui_state.reticle.spacing.get::<angle::minute>(),
+        target_path: gui_state.target_path,
+        bounding_box: gui_state.bounding_box,
+        error_hud: gui_state.error_hud,
+        zoom_inset: gui_state.zoom_inset.enabled,
+        sensor_resolution: gui_state.sensor_resolution
+    }
+}
+
+pub fn apply(preset: &CameraPreset, gui_state: &mut GuiState, camera_view: &mut CameraView) {
+    camera_view.set_field_of_view_y(f64::Angle::new::<angle::degree>(preset.fov_y_deg));
+    match ReticleStyle::ALL.iter().find(|style| style.to_string() == preset.reticle) {
+        Some(style) => gui_state.reticle.style = *style,
+        None => log::error!("unknown reticle style in camera preset \"{}\": {}", preset.name, preset.reticle)
+    }
+    gui_state.reticle.spacing = f64::Angle::new::<angle::minute>(preset.reticle_spacing_arcmin);
+    gui_state.target_path = preset.target_path;
+    gui_state.bounding_box = preset.bounding_box;
+    gui_state.error_hud = preset.error_hud;
+    gui_state.zoom_inset.enabled = preset.zoom_inset;
+    gui_state.sensor_resolution = preset.sensor_resolution;
+    log::info!("camera preset \"{}\" applied", preset.name);
+}
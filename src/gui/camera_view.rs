@@ -7,47 +7,204 @@
 //
 
 use cgmath::{
-    Basis3, Deg, EuclideanSpace, InnerSpace, Matrix3, Matrix4, Point3, Rotation, Rotation3, SquareMatrix, Vector3
+    Basis3, EuclideanSpace, InnerSpace, Matrix3, Matrix4, Point3, Rotation, Rotation3, SquareMatrix, Vector3
 };
-use crate::{data, data::{MeshVertex, Vertex3}, gui::draw_buffer::{DrawBuffer, Sampling}, workers::MountState};
-use glium::{glutin::surface::WindowSurface, Surface, uniform};
+use crate::{
+    cloud_layer::CloudLayer,
+    color_mode::ColorMode,
+    data,
+    data::{MeshVertex, Vertex3},
+    gui::async_readback::{AsyncReadback, DEFAULT_READBACK_LATENCY},
+    gui::draw_buffer::{DEFAULT_NUM_SAMPLES, DrawBuffer, Sampling},
+    gui::frustum::Frustum,
+    gui::lens_distortion::LensDistortion,
+    gui::motion_blur::{AccumulationBuffer, Exposure, Pose, PoseHistory, streak_length},
+    gui::nav_lights,
+    gui::seeing::Seeing,
+    gui::sensor_noise::SensorNoise,
+    horizon::HorizonProfile,
+    refraction::Atmosphere,
+    runner::GlContext,
+    sky_model::{AzAlt, SUN_MOON_ANGULAR_RADIUS, SkyModel},
+    units,
+    workers::{MountState, Weather}
+};
+use glium::{Surface, texture::RawImage2d, uniform};
 use pointing_utils::{TargetInfoMessage, uom};
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::{Cell, Ref, RefCell}, rc::Rc, sync::Arc};
 use subscriber_rs::Subscriber;
-use uom::{si::f64, si::angle};
+use uom::{si::f64, si::{angle, length}};
+
+/// Default meteorological visibility (distance at which contrast drops to 2%).
+const DEFAULT_VISIBILITY: f64 = 50_000.0; // m
+
+/// Direction towards the light source (in the local frame) used if no sky model is set.
+const FIXED_TO_LIGHT_DIR: Vector3<f64> = Vector3{ x: -0.5, y: -1.0, z: -0.2 };
+
+/// Solar altitudes (degrees) between which direct sunlight fades in (refraction and the target's own altitude
+/// let it be sunlit slightly after sunset).
+const SUNLIGHT_FADE_ALTITUDES: (f64, f64) = (-4.0, 4.0);
+
+/// Intensity of diffuse lighting by the daytime sky.
+const DAYLIGHT_AMBIENT: f32 = 0.15;
+
+/// Converts a matrix to the single-precision representation used by OpenGL.
+fn to_gl(matrix: &Matrix4<f64>) -> [[f32; 4]; 4] {
+    matrix.cast::<f32>().unwrap().into()
+}
+
+/// Returns the relative image position (as in `CameraView::direction_at`) of `point` transformed by
+/// `view_projection`; `None` if the point is behind the camera.
+fn projected_position(view_projection: &Matrix4<f64>, point: Point3<f64>) -> Option<[f64; 2]> {
+    let clip = view_projection * point.to_homogeneous();
+    if clip.w <= 0.0 { return None; }
+    Some([(1.0 + clip.x / clip.w) / 2.0, (1.0 - clip.y / clip.w) / 2.0])
+}
+
+/// Numbers of objects drawn, skipped by frustum culling and hidden below the horizon during the last rendering.
+#[derive(Copy, Clone, Default)]
+pub struct RenderStats {
+    pub drawn: usize,
+    pub culled: usize,
+    pub occluded: usize,
+    /// Drawn objects mostly (opacity ≥ 0.5) hidden by clouds.
+    pub obscured: usize,
+    /// Angular length of the target's motion blur streak (if exposure simulation is enabled).
+    pub streak: Option<f64::Angle>
+}
+
+/// Misalignment of the camera relative to the mount's boresight.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Misalignment {
+    /// Offset of the camera's optical axis to the right of the boresight (as seen in the image).
+    pub horizontal: f64::Angle,
+    /// Offset of the camera's optical axis above the boresight (as seen in the image).
+    pub vertical: f64::Angle,
+    /// Counter-clockwise rotation of the image around the optical axis.
+    pub roll: f64::Angle
+}
+
+impl Default for Misalignment {
+    fn default() -> Misalignment {
+        Misalignment{ horizontal: units::deg(0.0), vertical: units::deg(0.0), roll: units::deg(0.0) }
+    }
+}
+
+impl std::str::FromStr for Misalignment {
+    type Err = String;
+
+    /// Parses `<horizontal>,<vertical>,<roll>` (arcminutes).
+    fn from_str(s: &str) -> Result<Misalignment, String> {
+        let values = s.split(',')
+            .map(|value| value.trim().parse::<f64>().map(f64::Angle::new::<angle::minute>))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        match values[..] {
+            [horizontal, vertical, roll] => Ok(Misalignment{ horizontal, vertical, roll }),
+            _ => Err(format!("expected <horizontal>,<vertical>,<roll>: {}", s))
+        }
+    }
+}
+
+/// Meteorological visibility (distance at which contrast drops to 2%); determines haze and extinction.
+pub enum Visibility {
+    Fixed(f64::Length),
+    /// Follows the visibility of the weather model (which drops as the cloud cover increases).
+    Weather(Arc<Weather>)
+}
+
+impl Visibility {
+    pub fn current(&self) -> f64::Length {
+        match self {
+            Visibility::Fixed(visibility) => *visibility,
+            Visibility::Weather(weather) => f64::Length::new::<length::kilometer>(weather.current().visibility_km)
+        }
+    }
+}
+
+/// Lighting values passed to `surface.frag`.
+struct LightUniforms {
+    to_light_dir: [f32; 3],
+    intensity: f32,
+    ambient: f32
+}
+
+/// Cloud layer values passed to shaders using `cloud_layer.glsl`.
+struct CloudUniforms {
+    enabled: bool,
+    altitude: f32,
+    coverage: f32,
+    offset: [f32; 2],
+    color: [f32; 3]
+}
 
+/// All geometry is processed in double precision; conversion to single precision happens only when passing
+/// matrices to OpenGL.
 pub struct CameraView {
-    dir: Vector3<f32>,
-    up: Vector3<f32>,
-    field_of_view_y: Deg<f32>,
+    display: GlContext,
+    dir: Vector3<f64>,
+    up: Vector3<f64>,
+    /// Counter-clockwise rotation of the image around the boresight (field rotation).
+    roll: f64::Angle,
+    misalignment: Misalignment,
+    /// Color mode of recorded and streamed frames.
+    color_mode: ColorMode,
+    field_of_view_y: f64::Angle,
     draw_buf: DrawBuffer,
-    gl_view: Matrix4<f32>,
+    gl_view: Matrix4<f64>,
     sky_mesh: data::MeshBuffers<Vertex3>,
     sky_mesh_prog: Rc<glium::Program>,
+    ground_mesh: data::MeshBuffers<Vertex3>,
+    disc_mesh: data::MeshBuffers<Vertex3>,
+    horizon: Rc<HorizonProfile>,
     target_mesh: data::MeshBuffers<MeshVertex>,
+    target_mesh_points: Rc<Vec<[f32; 3]>>,
     target_prog: Rc<glium::Program>,
-    target_pos: Point3<f32>,
-    target_heading: Deg<f32>,
-    wh_ratio: f32
+    clouds_prog: Rc<glium::Program>,
+    texture_copy_prog: Rc<glium::Program>,
+    unit_quad: Rc<glium::VertexBuffer<data::Vertex2>>,
+    /// Apparent target position (i.e., affected by refraction, if enabled).
+    target_pos: Point3<f64>,
+    target_heading: f64::Angle,
+    wh_ratio: f64,
+    render_stats: Cell<RenderStats>,
+    refraction: Option<Atmosphere>,
+    /// If not set, a fixed daytime background is used and the Sun and Moon are not shown.
+    sky_model: Option<SkyModel>,
+    /// `None` means a perfectly clear atmosphere.
+    visibility: Option<Visibility>,
+    cloud_layer: Option<CloudLayer>,
+    /// Weather model which haze and clouds can be set to follow.
+    weather: Option<Arc<Weather>>,
+    /// If set, the target is rendered with motion blur accumulated over the exposure time.
+    exposure: Option<Exposure>,
+    pose_history: PoseHistory,
+    /// Exists only if `exposure` is set.
+    accum_buf: Option<AccumulationBuffer>
 }
 
 impl CameraView {
     pub fn new(
         gl_objects: &data::OpenGlObjects,
+        horizon: Rc<HorizonProfile>,
         renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
-        display: &glium::Display<WindowSurface>
+        display: &GlContext
     ) -> CameraView {
-        let field_of_view_y = Deg(20.0);
+        let field_of_view_y = units::deg(20.0);
         let target_pos = Point3{ x: 2000.0, y: 0.0, z: 500.0 };
         let dir = target_pos.to_vec();
         let up = Vector3{ x: 0.0, y: 0.0, z: 1.0 };
 
         CameraView{
+            display: display.clone(),
             dir,
             up,
+            roll: units::deg(0.0),
+            misalignment: Misalignment::default(),
+            color_mode: ColorMode::default(),
             field_of_view_y,
             draw_buf: DrawBuffer::new(
-                Sampling::Multi,
+                Sampling::Multi(DEFAULT_NUM_SAMPLES),
                 &gl_objects.texture_copy_single,
                 &gl_objects.texture_copy_multi,
                 &gl_objects.unit_quad,
@@ -57,50 +214,245 @@ impl CameraView {
             gl_view: Matrix4::look_to_rh(Point3::origin(), dir, up),
             sky_mesh: gl_objects.sky_mesh.clone(),
             sky_mesh_prog: gl_objects.sky_mesh_prog.clone(),
+            ground_mesh: gl_objects.ground_mesh.clone(),
+            disc_mesh: gl_objects.disc_mesh.clone(),
+            horizon,
             target_mesh: gl_objects.target_mesh.clone(),
+            target_mesh_points: gl_objects.target_mesh_points.clone(),
             target_prog: gl_objects.target_prog.clone(),
+            clouds_prog: gl_objects.clouds_prog.clone(),
+            texture_copy_prog: gl_objects.texture_copy_single.clone(),
+            unit_quad: gl_objects.unit_quad.clone(),
             target_pos,
-            target_heading: Deg(-45.0),
-            wh_ratio: 1.0
+            target_heading: units::deg(-45.0),
+            wh_ratio: 1.0,
+            render_stats: Cell::new(RenderStats::default()),
+            refraction: None,
+            sky_model: None,
+            visibility: Some(Visibility::Fixed(f64::Length::new::<length::meter>(DEFAULT_VISIBILITY))),
+            cloud_layer: None,
+            weather: None,
+            exposure: None,
+            pose_history: PoseHistory::default(),
+            accum_buf: None
         }
     }
 
-    fn gl_projection(&self, near: f32, far: f32) -> Matrix4<f32> {
-        cgmath::perspective(self.field_of_view_y, self.wh_ratio, near, far)
+    /// Returns the view matrix of the camera for mount boresight direction `dir`.
+    fn view_matrix(&self, dir: Vector3<f64>) -> Matrix4<f64> {
+        let m = &self.misalignment;
+        Matrix4::from_angle_z(units::to_rad(self.roll + m.roll))
+            * Matrix4::from_angle_x(-units::to_rad(m.vertical))
+            * Matrix4::from_angle_y(units::to_rad(m.horizontal))
+            * Matrix4::look_to_rh(Point3::origin(), dir, self.up)
+    }
+
+    pub fn misalignment(&self) -> Misalignment { self.misalignment }
+
+    pub fn set_misalignment(&mut self, misalignment: Misalignment) {
+        self.misalignment = misalignment;
+        self.gl_view = self.view_matrix(self.dir);
+        self.render();
+    }
+
+    pub fn color_mode(&self) -> ColorMode { self.color_mode }
+
+    pub fn set_color_mode(&mut self, color_mode: ColorMode) { self.color_mode = color_mode; }
+
+    fn gl_projection(&self, near: f64, far: f64) -> Matrix4<f64> {
+        cgmath::perspective(units::to_rad(self.field_of_view_y), self.wh_ratio, near, far)
     }
 
     pub fn update_size(&mut self, width: u32, height: u32) {
         if self.draw_buf.update_size(width, height) {
-            self.wh_ratio = width as f32 / height as f32;
+            self.wh_ratio = width as f64 / height as f64;
+            if self.accum_buf.is_some() {
+                self.accum_buf = Some(AccumulationBuffer::new(&self.display, width, height));
+            }
             self.render()
         }
     }
 
     pub fn set_mount_state(&mut self, mount_state: &MountState) {
         let x_unit = Vector3{ x: 1.0, y: 0.0, z: 0.0 };
-        let azimuth = mount_state.axis1_pos;
-        let altitude = mount_state.axis2_pos;
-        let dir = Basis3::from_angle_z(-Deg(azimuth.get::<angle::degree>())).rotate_vector(
-            Basis3::from_angle_y(-Deg(altitude.get::<angle::degree>())).rotate_vector(x_unit)
+        let azimuth = mount_state.boresight_az;
+        let altitude = mount_state.boresight_alt;
+        let dir = Basis3::from_angle_z(-units::to_rad(azimuth)).rotate_vector(
+            Basis3::from_angle_y(-units::to_rad(altitude)).rotate_vector(x_unit)
         );
-        self.dir = dir.cast::<f32>().unwrap();
-        self.gl_view = Matrix4::look_to_rh(Point3::origin(), self.dir, self.up);
+        self.dir = dir;
+        self.roll = mount_state.camera_roll;
+        self.gl_view = self.view_matrix(self.dir);
+        self.draw_buf.set_defocus(mount_state.defocus);
+        self.draw_buf.set_filter_transmission(mount_state.filter_transmission);
+        self.pose_history.record(self.pose());
+        self.render();
+    }
+
+    pub fn set_sampling(&mut self, sampling: Sampling) {
+        if self.draw_buf.sampling() != sampling {
+            self.draw_buf.set_sampling(sampling);
+            self.render();
+        }
+    }
+
+    pub fn sensor_noise(&self) -> Option<&SensorNoise> { self.draw_buf.sensor_noise() }
+
+    pub fn set_sensor_noise(&mut self, sensor_noise: Option<SensorNoise>) {
+        self.draw_buf.set_sensor_noise(sensor_noise);
+        self.render();
+    }
+
+    pub fn lens_distortion(&self) -> Option<&LensDistortion> { self.draw_buf.lens_distortion() }
+
+    pub fn set_lens_distortion(&mut self, lens_distortion: Option<LensDistortion>) {
+        self.draw_buf.set_lens_distortion(lens_distortion);
+        self.render();
+    }
+
+    pub fn seeing(&self) -> Option<&Seeing> { self.draw_buf.seeing() }
+
+    pub fn set_seeing(&mut self, seeing: Option<Seeing>) {
+        self.draw_buf.set_seeing(seeing);
+        self.render();
+    }
+
+    pub fn set_refraction(&mut self, refraction: Option<Atmosphere>) {
+        self.refraction = refraction;
+        // the sky is refracted like the target, so that both agree near the horizon
+        self.sky_mesh = data::create_sky_mesh(
+            data::SKY_MESH_STEP,
+            data::SKY_MESH_SUBSTEPS,
+            self.refraction.as_ref(),
+            &self.display
+        );
+        self.render();
+    }
+
+    /// Returns the apparent direction of a celestial body (i.e., affected by refraction, if enabled).
+    fn apparent_direction(&self, direction: AzAlt) -> AzAlt {
+        match &self.refraction {
+            Some(atmosphere) => AzAlt{
+                alt: (direction.alt + atmosphere.refraction(direction.alt)).min(90.0),
+                ..direction
+            },
+            None => direction
+        }
+    }
+
+    pub fn set_sky_model(&mut self, sky_model: Option<SkyModel>) {
+        self.sky_model = sky_model;
+        self.render();
+    }
+
+    pub fn sky_model(&self) -> Option<&SkyModel> { self.sky_model.as_ref() }
+
+    pub fn visibility(&self) -> Option<&Visibility> { self.visibility.as_ref() }
+
+    pub fn set_visibility(&mut self, visibility: Option<Visibility>) {
+        self.visibility = visibility;
+        self.render();
+    }
+
+    pub fn weather(&self) -> Option<&Arc<Weather>> { self.weather.as_ref() }
+
+    /// Sets the weather model; haze (if enabled) follows its visibility from now on.
+    pub fn set_weather(&mut self, weather: Option<Arc<Weather>>) {
+        if let Some(weather) = &weather {
+            if self.visibility.is_some() { self.visibility = Some(Visibility::Weather(Arc::clone(weather))); }
+        }
+        self.weather = weather;
+        self.render();
+    }
+
+    pub fn cloud_layer(&self) -> Option<&CloudLayer> { self.cloud_layer.as_ref() }
+
+    pub fn cloud_layer_mut(&mut self) -> Option<&mut CloudLayer> { self.cloud_layer.as_mut() }
+
+    pub fn set_cloud_layer(&mut self, cloud_layer: Option<CloudLayer>) {
+        self.cloud_layer = cloud_layer;
+        self.render();
+    }
+
+    pub fn exposure(&self) -> Option<&Exposure> { self.exposure.as_ref() }
+
+    pub fn set_exposure(&mut self, exposure: Option<Exposure>) {
+        self.accum_buf = exposure.as_ref().map(|_| AccumulationBuffer::new(
+            &self.display, self.draw_buf.width(), self.draw_buf.height()
+        ));
+        self.exposure = exposure;
+        self.render();
+    }
+
+    fn pose(&self) -> Pose {
+        Pose{ dir: self.dir, target_pos: self.target_pos, target_heading: self.target_heading }
+    }
+
+    pub fn set_field_of_view_y(&mut self, field_of_view_y: f64::Angle) {
+        self.field_of_view_y = field_of_view_y;
         self.render();
     }
 
     pub fn zoom_by(&mut self, factor: f32) {
-        self.field_of_view_y /= factor;
+        self.field_of_view_y /= factor as f64;
         self.render();
     }
 
+    fn extinction_coeff(&self) -> f32 {
+        // Koschmieder's relation for a 2% contrast threshold
+        self.visibility.as_ref().map_or(0.0, |v| 3.912 / v.current().get::<length::meter>()) as f32
+    }
+
+    fn cloud_uniforms(&self, background: [f32; 3]) -> CloudUniforms {
+        match &self.cloud_layer {
+            Some(layer) => {
+                let brightness = background.iter().cloned().fold(0.0, f32::max);
+                CloudUniforms{
+                    enabled: true,
+                    altitude: layer.altitude.get::<length::meter>() as f32,
+                    coverage: layer.coverage() as f32,
+                    offset: layer.offset().cast::<f32>().unwrap().into(),
+                    color: background.map(|c| (0.3 * c + 0.8 * brightness).min(1.0))
+                }
+            },
+            None => CloudUniforms{ enabled: false, altitude: 0.0, coverage: 0.0, offset: [0.0; 2], color: [0.0; 3] }
+        }
+    }
+
+    /// Returns target lighting by the Sun (or fixed lighting if there is no sky model).
+    fn light_uniforms(&self) -> LightUniforms {
+        match &self.sky_model {
+            Some(sky) => {
+                let sun = sky.sun();
+                let (az, alt) = (sun.az.to_radians(), sun.alt.to_radians());
+                let to_sun = Vector3{ x: alt.cos() * az.cos(), y: -alt.cos() * az.sin(), z: alt.sin() };
+                let (alt0, alt1) = SUNLIGHT_FADE_ALTITUDES;
+                let intensity = ((sun.alt - alt0) / (alt1 - alt0)).clamp(0.0, 1.0) as f32;
+                LightUniforms{
+                    to_light_dir: to_sun.cast::<f32>().unwrap().into(),
+                    intensity,
+                    ambient: DAYLIGHT_AMBIENT * intensity
+                }
+            },
+            None => LightUniforms{
+                to_light_dir: FIXED_TO_LIGHT_DIR.normalize().cast::<f32>().unwrap().into(),
+                intensity: 1.0,
+                ambient: 0.0
+            }
+        }
+    }
+
     fn render(&self) {
         let mut target = self.draw_buf.frame_buf();
-        target.clear_color_and_depth((0.2, 0.2, 0.7, 1.0), 1.0);
+        let background = self.sky_model.as_ref().map_or([0.2, 0.2, 0.7], |sky| sky.background_color());
+        let clouds = self.cloud_uniforms(background);
+        target.clear_color_and_depth((background[0], background[1], background[2], 1.0), 1.0);
 
         let uniforms = uniform! {
-            model: Into::<[[f32; 4]; 4]>::into(Matrix4::<f32>::identity()),
-            view: Into::<[[f32; 4]; 4]>::into(self.gl_view),
-            projection: Into::<[[f32; 4]; 4]>::into(self.gl_projection(0.1, 5.0)),
+            model: to_gl(&Matrix4::<f64>::identity()),
+            view: to_gl(&self.gl_view),
+            view_model: to_gl(&self.gl_view),
+            projection: to_gl(&self.gl_projection(0.1, 5.0)),
             draw_color: [0.0f32, 0.0f32, 0.0f32, 1.0f32]
         };
         target.draw(
@@ -114,54 +466,425 @@ impl CameraView {
                     write: false,
                     ..Default::default()
                 },
+                primitive_restart_index: true,
                 ..Default::default()
             }
         ).unwrap();
 
+        if let Some(sky) = &self.sky_model {
+            self.render_disc(&mut target, self.apparent_direction(sky.moon()), [0.85, 0.85, 0.8, 1.0]);
+            self.render_disc(&mut target, self.apparent_direction(sky.sun()), [1.0, 1.0, 0.9, 1.0]);
+        }
+
+        if clouds.enabled {
+            self.render_clouds(&mut target, &clouds, background);
+        }
 
-        let target_dist = self.target_pos.to_vec().magnitude();
+        let ground_uniforms = uniform! {
+            model: to_gl(&Matrix4::<f64>::identity()),
+            view: to_gl(&self.gl_view),
+            view_model: to_gl(&self.gl_view),
+            projection: to_gl(&self.gl_projection(0.1, 5.0)),
+            draw_color: [0.25f32, 0.3f32, 0.15f32, 1.0f32]
+        };
+        target.draw(
+            &*self.ground_mesh.vertices,
+            &*self.ground_mesh.indices,
+            &self.sky_mesh_prog,
+            &ground_uniforms,
+            &glium::DrawParameters{
+                depth: glium::Depth{
+                    test: glium::DepthTest::Overwrite,
+                    write: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        ).unwrap();
+
+        let mut stats = RenderStats::default();
+        let frustum = Frustum::from_matrix(&(self.gl_projection(0.1, 1.0e7) * self.gl_view));
+
+        if self.horizon.occludes(self.target_pos.to_vec()) {
+            stats.occluded += 1;
+        } else if frustum.intersects_sphere(self.target_pos, self.target_mesh.bounding_radius) {
+            match (&self.exposure, &self.accum_buf) {
+                (Some(exposure), Some(accum_buf)) => {
+                    let poses = self.pose_history.samples(exposure);
+                    self.render_target_blurred(&mut target, accum_buf, &poses, &clouds, background);
+                    stats.streak = Some(streak_length(&poses, self.up));
+                },
+
+                _ => self.render_target(&mut target, &self.pose(), &clouds, background, &glium::DrawParameters{
+                    depth: glium::Depth{
+                        test: glium::DepthTest::IfLess,
+                        write: true,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+            }
+            stats.drawn += 1;
+            if self.cloud_layer.as_ref().map_or(false, |layer| layer.opacity(self.target_pos.to_vec()) >= 0.5) {
+                stats.obscured += 1;
+            }
+        } else {
+            stats.culled += 1;
+        }
+
+        self.render_stats.set(stats);
+        let focal_length_px = self.draw_buf.height() as f64 / 2.0 / (units::to_rad(self.field_of_view_y).0 / 2.0).tan();
+        self.draw_buf.update_storage_buf(focal_length_px as f32);
+    }
+
+    /// Renders the Sun or Moon (as a uniformly lit disc) in the given direction.
+    fn render_disc<S: Surface>(&self, target: &mut S, direction: AzAlt, color: [f32; 4]) {
+        let radius = cgmath::Rad::from(SUN_MOON_ANGULAR_RADIUS).0.tan();
+        let rotation = Basis3::from_angle_z(-cgmath::Deg(direction.az))
+            * Basis3::from_angle_y(-cgmath::Deg(direction.alt));
+        let model = Matrix4::from(Matrix3::from(rotation)) * Matrix4::from_nonuniform_scale(1.0, radius, radius);
+        let uniforms = uniform! {
+            model: to_gl(&model),
+            view: to_gl(&self.gl_view),
+            view_model: to_gl(&(self.gl_view * model)),
+            projection: to_gl(&self.gl_projection(0.1, 5.0)),
+            draw_color: color
+        };
+        target.draw(
+            &*self.disc_mesh.vertices,
+            &*self.disc_mesh.indices,
+            &self.sky_mesh_prog,
+            &uniforms,
+            &glium::DrawParameters{
+                depth: glium::Depth{
+                    test: glium::DepthTest::Overwrite,
+                    write: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        ).unwrap();
+    }
+
+    /// Renders the cloud layer above the observer (as seen from below).
+    fn render_clouds<S: Surface>(&self, target: &mut S, clouds: &CloudUniforms, haze_color: [f32; 3]) {
+        let inv_view_projection = (self.gl_projection(0.1, 5.0) * self.gl_view).invert().unwrap();
+        let uniforms = uniform! {
+            inv_view_projection: to_gl(&inv_view_projection),
+            haze_color: haze_color,
+            extinction_coeff: self.extinction_coeff(),
+            cloud_layer_enabled: clouds.enabled,
+            cloud_altitude: clouds.altitude,
+            cloud_coverage: clouds.coverage,
+            cloud_offset: clouds.offset,
+            cloud_color: clouds.color
+        };
+        target.draw(
+            &*self.unit_quad,
+            &glium::index::NoIndices(glium::index::PrimitiveType::TriangleFan),
+            &self.clouds_prog,
+            &uniforms,
+            &glium::DrawParameters{
+                depth: glium::Depth{
+                    test: glium::DepthTest::Overwrite,
+                    write: false,
+                    ..Default::default()
+                },
+                blend: glium::Blend::alpha_blending(),
+                ..Default::default()
+            }
+        ).unwrap();
+    }
+
+    /// Renders the target as seen in `pose`, i.e., the camera direction may differ from the current one.
+    fn render_target<S: Surface>(
+        &self,
+        target: &mut S,
+        pose: &Pose,
+        clouds: &CloudUniforms,
+        haze_color: [f32; 3],
+        draw_parameters: &glium::DrawParameters
+    ) {
+        let target_dist = pose.target_pos.to_vec().magnitude();
         assert!(target_dist > 500.0);
-        let t_dist_proj = cgmath::dot(self.dir.normalize(), self.target_pos.to_vec());
-        let target_model = Matrix4::<f32>::from_translation(self.target_pos.to_vec())
-            * Matrix4::from(Matrix3::from(Basis3::from_angle_z(-self.target_heading)));
+        let t_dist_proj = cgmath::dot(pose.dir.normalize(), pose.target_pos.to_vec());
+        let target_model = Matrix4::<f64>::from_translation(pose.target_pos.to_vec())
+            * Matrix4::from(Matrix3::from(Basis3::from_angle_z(-units::to_rad(pose.target_heading))));
+        let view_model = self.view_matrix(pose.dir) * target_model;
+        // model matrix equivalent to `target_model` for the current view
+        let model = self.gl_view.invert().unwrap() * view_model;
+        let light = self.light_uniforms();
         let uniforms = uniform! {
-            model: Into::<[[f32; 4]; 4]>::into(target_model),
-            view: Into::<[[f32; 4]; 4]>::into(self.gl_view),
-            projection: Into::<[[f32; 4]; 4]>::into(self.gl_projection(t_dist_proj - 70.0, t_dist_proj + 70.0)),
-            draw_color: [1.0f32, 1.0f32, 1.0f32]
+            model: to_gl(&model),
+            view: to_gl(&self.gl_view),
+            view_model: to_gl(&view_model),
+            projection: to_gl(&self.gl_projection(t_dist_proj - 70.0, t_dist_proj + 70.0)),
+            draw_color: [1.0f32, 1.0f32, 1.0f32],
+            haze_color: haze_color,
+            extinction_coeff: self.extinction_coeff(),
+            to_light_dir: light.to_light_dir,
+            light_intensity: light.intensity,
+            ambient_intensity: light.ambient,
+            cloud_layer_enabled: clouds.enabled,
+            cloud_altitude: clouds.altitude,
+            cloud_coverage: clouds.coverage,
+            cloud_offset: clouds.offset,
+            cloud_color: clouds.color
         };
         match target.draw(
             &*self.target_mesh.vertices,
             &*self.target_mesh.indices,
             &self.target_prog,
             &uniforms,
+            draw_parameters
+        ) {
+            Err(e) => { log::error!("failed to render: {}", e); panic!(); },
+            _ => ()
+        }
+
+        self.render_nav_lights(target, &model, &view_model, t_dist_proj, draw_parameters);
+    }
+
+    /// Renders the target's navigation lights (only at night).
+    fn render_nav_lights<S: Surface>(
+        &self,
+        target: &mut S,
+        model: &Matrix4<f64>,
+        view_model: &Matrix4<f64>,
+        t_dist_proj: f64,
+        draw_parameters: &glium::DrawParameters
+    ) {
+        let sky = match &self.sky_model {
+            Some(sky) if sky.sun().alt < nav_lights::MAX_SUN_ALTITUDE => sky,
+            _ => return
+        };
+
+        let draw_parameters = glium::DrawParameters{
+            point_size: Some(nav_lights::LIGHT_SIZE),
+            ..draw_parameters.clone()
+        };
+        let t = sky.time().timestamp_millis() as f64 / 1000.0;
+        for (position, color) in nav_lights::lit_lights(t) {
+            let vertices = glium::VertexBuffer::new(&self.display, &[Vertex3{ position }]).unwrap();
+            let uniforms = uniform! {
+                model: to_gl(model),
+                view: to_gl(&self.gl_view),
+                view_model: to_gl(view_model),
+                projection: to_gl(&self.gl_projection(t_dist_proj - 70.0, t_dist_proj + 70.0)),
+                draw_color: color
+            };
+            target.draw(
+                &vertices,
+                &glium::index::NoIndices(glium::index::PrimitiveType::Points),
+                &self.sky_mesh_prog,
+                &uniforms,
+                &draw_parameters
+            ).unwrap();
+        }
+    }
+
+    /// Renders the target in all `poses` into `accum_buf` (averaging them) and blends the result over `target`.
+    fn render_target_blurred<S: Surface>(
+        &self,
+        target: &mut S,
+        accum_buf: &AccumulationBuffer,
+        poses: &[Pose],
+        clouds: &CloudUniforms,
+        haze_color: [f32; 3]
+    ) {
+        let mut accum_target = accum_buf.frame_buf(&self.display);
+        accum_target.clear_color(0.0, 0.0, 0.0, 0.0);
+
+        // each sample adds its color and coverage (alpha) with weight 1/N
+        let weight = 1.0 / poses.len().max(1) as f32;
+        let accumulate = glium::BlendingFunction::Addition{
+            source: glium::LinearBlendingFactor::ConstantAlpha,
+            destination: glium::LinearBlendingFactor::One
+        };
+        let draw_parameters = glium::DrawParameters{
+            depth: glium::Depth{
+                test: glium::DepthTest::IfLess,
+                write: true,
+                ..Default::default()
+            },
+            blend: glium::Blend{ color: accumulate, alpha: accumulate, constant_value: (0.0, 0.0, 0.0, weight) },
+            ..Default::default()
+        };
+        for pose in poses {
+            // every sample is depth-tested only against itself
+            accum_target.clear_depth(1.0);
+            self.render_target(&mut accum_target, pose, clouds, haze_color, &draw_parameters);
+        }
+
+        // the accumulated color is premultiplied by coverage
+        let composite = glium::BlendingFunction::Addition{
+            source: glium::LinearBlendingFactor::One,
+            destination: glium::LinearBlendingFactor::OneMinusSourceAlpha
+        };
+        let uniforms = uniform! {
+            source_texture: accum_buf.texture().sampled(),
+            brightness: 1.0f32,
+            sensor_noise_enabled: false,
+            lens_distortion_enabled: false,
+            seeing_enabled: false,
+            filter_enabled: false
+        };
+        target.draw(
+            &*self.unit_quad,
+            &glium::index::NoIndices(glium::index::PrimitiveType::TriangleFan),
+            &self.texture_copy_prog,
+            &uniforms,
             &glium::DrawParameters{
                 depth: glium::Depth{
-                    test: glium::DepthTest::IfLess,
-                    write: true,
+                    test: glium::DepthTest::Overwrite,
+                    write: false,
                     ..Default::default()
                 },
+                blend: glium::Blend{ color: composite, alpha: composite, constant_value: (0.0, 0.0, 0.0, 0.0) },
                 ..Default::default()
             }
-        ) {
-            Err(e) => { log::error!("failed to render: {}", e); panic!(); },
-            _ => ()
+        ).unwrap();
+    }
+
+    /// Returns the direction shown at `position` (relative to the image: (0, 0) = top left, (1, 1) = bottom right),
+    /// ignoring lens distortion.
+    pub fn direction_at(&self, position: [f32; 2]) -> AzAlt {
+        let inv_view_projection = (self.gl_projection(0.1, 5.0) * self.gl_view).invert().unwrap();
+        let far_point = inv_view_projection * cgmath::Vector4{
+            x: 2.0 * position[0] as f64 - 1.0,
+            y: 1.0 - 2.0 * position[1] as f64,
+            z: 1.0,
+            w: 1.0
+        };
+        let dir = (far_point.truncate() / far_point.w).normalize();
+
+        // local frame: x points north, y west, z up
+        AzAlt{ az: units::azimuth(dir).get::<angle::degree>().rem_euclid(360.0), alt: dir.z.asin().to_degrees() }
+    }
+
+    /// Returns position of the target in the image (relative, as in `direction_at`), ignoring lens distortion;
+    /// `None` if the target is behind the camera.
+    pub fn target_image_position(&self) -> Option<[f64; 2]> { self.image_position(self.target_pos) }
+
+    /// Returns the bounding box of the target's image as relative (x_min, y_min, x_max, y_max), clipped to the image
+    /// (coordinates as in `direction_at`), ignoring lens distortion; `None` if the target is not in view, is behind
+    /// the camera (even partially) or is hidden by terrain.
+    pub fn target_bounding_box(&self) -> Option<[f64; 4]> {
+        if self.horizon.occludes(self.target_pos.to_vec()) { return None; }
+
+        let model = Matrix4::<f64>::from_translation(self.target_pos.to_vec())
+            * Matrix4::from(Matrix3::from(Basis3::from_angle_z(-units::to_rad(self.target_heading))));
+        let mut bounds: Option<[f64; 4]> = None;
+        for point in self.target_mesh_points.iter() {
+            let world = model * Point3::from(*point).cast::<f64>().unwrap().to_homogeneous();
+            let [x, y] = self.image_position(Point3::from_homogeneous(world))?;
+            bounds = Some(match bounds {
+                None => [x, y, x, y],
+                Some(b) => [b[0].min(x), b[1].min(y), b[2].max(x), b[3].max(y)]
+            });
         }
+        let bounds = bounds?;
 
-        self.draw_buf.update_storage_buf();
+        if bounds[2] < 0.0 || bounds[0] > 1.0 || bounds[3] < 0.0 || bounds[1] > 1.0 { return None; }
+        Some(bounds.map(|value| value.clamp(0.0, 1.0)))
     }
 
+    /// Returns position in the image (as in `target_image_position`) of the given direction.
+    pub fn direction_image_position(&self, az: f64::Angle, alt: f64::Angle) -> Option<[f64; 2]> {
+        let (az, alt) = (units::to_rad(az).0, units::to_rad(alt).0);
+        // local frame: x points north, y west, z up
+        self.image_position(Point3{ x: alt.cos() * az.cos(), y: -alt.cos() * az.sin(), z: alt.sin() })
+    }
+
+    /// Returns position in the image (as in `target_image_position`) of the given point of the local frame
+    /// (refraction is applied, if enabled).
+    pub fn point_image_position(&self, point: Point3<f64>) -> Option<[f64; 2]> {
+        self.image_position(match &self.refraction {
+            Some(atmosphere) => Point3::from_vec(atmosphere.apparent_position(point.to_vec())),
+            None => point
+        })
+    }
+
+    fn image_position(&self, point: Point3<f64>) -> Option<[f64; 2]> {
+        projected_position(&(self.gl_projection(0.1, 5.0) * self.gl_view), point)
+    }
+
+    /// Reads the current image synchronously (RGBA, top row first).
+    pub fn read_frame(&self) -> RawImage2d<'static, u8> { self.draw_buf.storage_buf().read() }
+
     pub fn draw_buf_id(&self) -> imgui::TextureId { self.draw_buf.id() }
 
-    pub fn field_of_view_y(&self) -> Deg<f32> { self.field_of_view_y }
+    pub fn field_of_view_y(&self) -> f64::Angle { self.field_of_view_y }
+
+    pub fn render_stats(&self) -> RenderStats { self.render_stats.get() }
+
+    pub fn set_readback_enabled(&mut self, enabled: bool) {
+        self.draw_buf.set_readback(if enabled { Some(DEFAULT_READBACK_LATENCY) } else { None });
+    }
+
+    pub fn readback(&self) -> Ref<'_, Option<AsyncReadback>> { self.draw_buf.readback() }
 }
 
 impl Subscriber<TargetInfoMessage> for CameraView {
     fn notify(&mut self, value: &TargetInfoMessage) {
         // we need to use track (actual azimuth of travel), as we
         // do not get heading (aircraft orientation) from ADS-B messages
-        self.target_heading = Deg(value.track.0 as f32);
-        self.target_pos = value.position.0.cast::<f32>().unwrap();
+        self.target_heading = units::from_deg(value.track);
+        self.target_pos = match &self.refraction {
+            Some(atmosphere) => Point3::from_vec(atmosphere.apparent_position(value.position.0.to_vec())),
+            None => value.position.0
+        };
+        self.pose_history.record(self.pose());
         self.render();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Narrow field of view of a long focal length camera.
+    const FIELD_OF_VIEW_Y_DEG: f64 = 0.1;
+
+    const WH_RATIO: f64 = 1.5;
+
+    /// Returns the image position of a target at `distance` (m) offset from the boresight (pointing north at
+    /// altitude 30°) by `d_az`, `d_alt` (arcseconds).
+    fn image_position(d_az: f64, d_alt: f64, distance: f64) -> [f64; 2] {
+        let boresight_alt = 30.0f64.to_radians();
+        let dir = Vector3{ x: boresight_alt.cos(), y: 0.0, z: boresight_alt.sin() };
+        let view = Matrix4::look_to_rh(Point3::origin(), dir, Vector3{ x: 0.0, y: 0.0, z: 1.0 });
+        let projection = cgmath::perspective(cgmath::Deg(FIELD_OF_VIEW_Y_DEG), WH_RATIO, 0.1, 5.0);
+
+        let (az, alt) = ((-d_az / 3600.0).to_radians(), boresight_alt + (d_alt / 3600.0).to_radians());
+        let point = Point3{ x: alt.cos() * az.cos(), y: alt.cos() * az.sin(), z: alt.sin() } * distance;
+        projected_position(&(projection * view), point).unwrap()
+    }
+
+    /// Returns the angle (arcseconds) corresponding to an offset in the image.
+    fn offset_to_arcsec(offset: f64, field_of_view_deg: f64) -> f64 {
+        (2.0 * offset * (field_of_view_deg / 2.0).to_radians().tan()).atan().to_degrees() * 3600.0
+    }
+
+    #[test]
+    fn boresight_is_at_image_center() {
+        let [x, y] = image_position(0.0, 0.0, 20_000.0);
+        assert!((x - 0.5).abs() < 1.0e-12 && (y - 0.5).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn sub_arcsecond_offsets_are_preserved() {
+        let fov_x = 2.0 * ((FIELD_OF_VIEW_Y_DEG / 2.0).to_radians().tan() * WH_RATIO).atan().to_degrees();
+        for distance in [1_000.0, 20_000.0, 100_000.0] {
+            for offset in [0.1, 0.5, 0.9] {
+                let [x, _] = image_position(offset, 0.0, distance);
+                let d_az = offset_to_arcsec(x - 0.5, fov_x) / 30.0f64.to_radians().cos();
+                assert!((d_az - offset).abs() < 0.01, "azimuth offset {}\" rendered as {}\"", offset, d_az);
+
+                let [_, y] = image_position(0.0, offset, distance);
+                let d_alt = offset_to_arcsec(0.5 - y, FIELD_OF_VIEW_Y_DEG);
+                assert!((d_alt - offset).abs() < 0.01, "altitude offset {}\" rendered as {}\"", offset, d_alt);
+            }
+        }
+    }
+}
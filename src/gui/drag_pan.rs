@@ -0,0 +1,59 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Panning of the mount by dragging in the camera view with the middle mouse button (like pushing a hand paddle).
+//!
+//! The slew rates are proportional to the drag vector: dragging right slews axis 1 in the positive direction,
+//! dragging up slews axis 2 in the positive direction. Releasing the button stops the slew.
+
+const COLOR: [f32; 4] = [0.3, 0.8, 1.0, 0.8];
+
+/// Drag distance (as a fraction of the image height) corresponding to the full selected slew rate.
+const FULL_RATE_DRAG: f32 = 0.25;
+
+pub struct DragPan {
+    /// Title of the camera view window the drag started in.
+    window: String,
+    /// Screen position where the drag started.
+    start: [f32; 2]
+}
+
+/// Handles the drag; to be called right after drawing the camera view image. Returns the deflection (-1 to 1 per
+/// axis; zero once the drag ends) to be passed to `MountControl::slew_proportional`, or `None` if there is no drag
+/// in this window.
+pub fn handle_input(
+    drag: &mut Option<DragPan>,
+    window: &str,
+    ui: &imgui::Ui,
+    image_min: [f32; 2],
+    image_max: [f32; 2]
+) -> Option<[f64; 2]> {
+    let mouse_pos = ui.io().mouse_pos;
+
+    if ui.is_item_hovered() && ui.is_mouse_clicked(imgui::MouseButton::Middle) {
+        *drag = Some(DragPan{ window: window.to_string(), start: mouse_pos });
+    }
+
+    let start = match drag {
+        Some(d) if d.window == window => d.start,
+        _ => return None
+    };
+
+    if !ui.is_mouse_down(imgui::MouseButton::Middle) {
+        *drag = None;
+        return Some([0.0; 2]);
+    }
+
+    let draw_list = ui.get_window_draw_list();
+    draw_list.add_circle(start, 4.0, COLOR).build();
+    draw_list.add_line(start, mouse_pos, COLOR).thickness(2.0).build();
+
+    let full_rate_distance = FULL_RATE_DRAG * (image_max[1] - image_min[1]).max(1.0);
+    let deflection = |value: f32| (value / full_rate_distance).clamp(-1.0, 1.0) as f64;
+    Some([deflection(mouse_pos[0] - start[0]), deflection(start[1] - mouse_pos[1])])
+}
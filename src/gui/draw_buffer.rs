@@ -6,7 +6,10 @@
 // (see the LICENSE file for details).
 //
 
-use glium::glutin::surface::WindowSurface;
+use crate::{
+    gui::{async_readback::AsyncReadback, lens_distortion::LensDistortion, seeing::Seeing, sensor_noise::SensorNoise},
+    runner::GlContext
+};
 use glium::Surface;
 use glium::texture::{
     depth_texture2d_multisample::DepthTexture2dMultisample,
@@ -15,8 +18,10 @@ use glium::texture::{
     texture2d::Texture2d,
 };
 use glium::uniform;
-use std::cell::RefCell;
+use pointing_utils::uom;
+use std::cell::{Cell, Ref, RefCell};
 use std::rc::Rc;
+use uom::{si::f64, si::angle};
 
 const INITIAL_DRAW_BUF_SIZE: u32 = 256;
 
@@ -24,22 +29,29 @@ const COLOR_FORMAT: glium::texture::UncompressedFloatFormat = glium::texture::Un
 
 const DEPTH_FORMAT: glium::texture::DepthFormat = glium::texture::DepthFormat::I24;
 
-const NUM_SAMPLES: u32 = 8;
+/// Defocus blur below this (standard deviation in pixels) is not applied.
+const MIN_DEFOCUS_SIGMA_PX: f32 = 0.1;
+
+pub const DEFAULT_NUM_SAMPLES: u32 = 8;
 
-#[derive(Copy, Clone, PartialEq)]
-pub enum Sampling { Single, Multi }
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sampling {
+    Single,
+    /// Multisampling with the specified number of samples per pixel.
+    Multi(u32)
+}
 
-/// Contains (draw buffer, depth buffer).
+/// Contains (draw buffer, depth buffer[, number of samples]).
 enum Buffers {
     SingleSampling(Texture2d, DepthTexture2d),
-    MultiSampling(Texture2dMultisample, DepthTexture2dMultisample)
+    MultiSampling(Texture2dMultisample, DepthTexture2dMultisample, u32)
 }
 
 impl Buffers {
     fn sampling(&self) -> Sampling {
         match self {
             Buffers::SingleSampling(_, _) => Sampling::Single,
-            Buffers::MultiSampling(_, _) => Sampling::Multi
+            Buffers::MultiSampling(_, _, num_samples) => Sampling::Multi(*num_samples)
         }
     }
 }
@@ -50,7 +62,7 @@ pub struct DrawBuffer {
 
     renderer: Rc<RefCell<imgui_glium_renderer::Renderer>>,
 
-    display: glium::Display<WindowSurface>,
+    display: GlContext,
 
     /// Used for rendering.
     draw_bufs: Buffers,
@@ -64,7 +76,28 @@ pub struct DrawBuffer {
     /// GL program to handle texture copying with multi-sampling.
     texture_copy_multi_gl_prog: Rc<glium::Program>,
 
-    unit_quad: Rc<glium::VertexBuffer<crate::data::Vertex2>>
+    unit_quad: Rc<glium::VertexBuffer<crate::data::Vertex2>>,
+
+    /// If set, contents of the storage buffer are read back (asynchronously) after each update.
+    readback: RefCell<Option<AsyncReadback>>,
+
+    /// If set, sensor noise is added when copying to the storage buffer.
+    sensor_noise: Option<SensorNoise>,
+
+    /// Number of storage buffer updates so far; seeds the per-frame noise.
+    frame_index: Cell<u32>,
+
+    /// If set, lens distortion and vignetting are applied when copying to the storage buffer.
+    lens_distortion: Option<LensDistortion>,
+
+    /// If set, seeing blur is applied when copying to the storage buffer.
+    seeing: Option<Seeing>,
+
+    /// Diameter of the defocused image of a point source; approximated by a Gaussian blur combined with seeing.
+    defocus: f64::Angle,
+
+    /// Color transmission (red, green, blue) of the optical path, applied when copying to the storage buffer.
+    filter_transmission: [f32; 3]
 }
 
 impl DrawBuffer {
@@ -83,14 +116,77 @@ impl DrawBuffer {
         self.storage_buf = storage_buf;
     }
 
+    pub fn sensor_noise(&self) -> Option<&SensorNoise> { self.sensor_noise.as_ref() }
+
+    pub fn set_sensor_noise(&mut self, sensor_noise: Option<SensorNoise>) {
+        self.sensor_noise = sensor_noise;
+    }
+
+    pub fn lens_distortion(&self) -> Option<&LensDistortion> { self.lens_distortion.as_ref() }
+
+    pub fn set_lens_distortion(&mut self, lens_distortion: Option<LensDistortion>) {
+        self.lens_distortion = lens_distortion;
+    }
+
+    pub fn seeing(&self) -> Option<&Seeing> { self.seeing.as_ref() }
+
+    pub fn set_seeing(&mut self, seeing: Option<Seeing>) {
+        self.seeing = seeing;
+    }
+
+    pub fn set_defocus(&mut self, defocus: f64::Angle) {
+        self.defocus = defocus;
+    }
+
+    pub fn set_filter_transmission(&mut self, transmission: [f32; 3]) {
+        self.filter_transmission = transmission;
+    }
+
     /// If something was rendered using the result of `frame_buf()`, this method must be called afterwards.
-    pub fn update_storage_buf(&self) {
+    ///
+    /// `focal_length_px`: focal length (in pixels) of the rendered view; used for lens distortion and seeing.
+    pub fn update_storage_buf(&self, focal_length_px: f32) {
         let mut fbo = glium::framebuffer::SimpleFrameBuffer::new(&self.display, &*self.storage_buf).unwrap();
 
+        self.frame_index.set(self.frame_index.get().wrapping_add(1));
+        let noise = self.sensor_noise.clone().unwrap_or_default();
+        let lens = self.lens_distortion.clone().unwrap_or_default();
+        let image_size = [self.width() as f32, self.height() as f32];
+        let to_px = |value: f64::Angle| (value.get::<angle::radian>() * focal_length_px as f64) as f32;
+        let (seeing_sigma, seeing_offset) = match &self.seeing {
+            Some(seeing) => {
+                let (sigma, offset) = seeing.current();
+                (to_px(sigma), offset.map(to_px))
+            },
+            None => (0.0, [0.0; 2])
+        };
+        // standard deviation of a uniform disc is 1/4 of its diameter
+        let defocus_sigma = to_px(self.defocus) / 4.0;
+        let blur_enabled = self.seeing.is_some() || defocus_sigma > MIN_DEFOCUS_SIGMA_PX;
+        let seeing_sigma = seeing_sigma.hypot(defocus_sigma);
+
         match &self.draw_bufs {
             Buffers::SingleSampling(draw_buf, _) => {
                 let uniforms = uniform! {
-                    source_texture: draw_buf.sampled()
+                    source_texture: draw_buf.sampled(),
+                    brightness: 1.0f32,
+                    sensor_noise_enabled: self.sensor_noise.is_some(),
+                    read_noise: noise.read_noise,
+                    full_well: noise.full_well,
+                    hot_pixel_fraction: noise.hot_pixel_fraction,
+                    noise_seed: self.frame_index.get(),
+                    hot_pixel_seed: noise.hot_pixel_seed,
+                    lens_distortion_enabled: self.lens_distortion.is_some(),
+                    lens_k1: lens.k1,
+                    lens_k2: lens.k2,
+                    vignetting: lens.vignetting,
+                    focal_length_px: focal_length_px,
+                    image_size: image_size,
+                    seeing_enabled: blur_enabled,
+                    seeing_sigma_px: seeing_sigma,
+                    seeing_offset_px: seeing_offset,
+                    filter_enabled: true,
+                    filter_transmission: self.filter_transmission
                 };
 
                 fbo.draw(
@@ -102,9 +198,27 @@ impl DrawBuffer {
                 ).unwrap();
             },
 
-            Buffers::MultiSampling(draw_buf, _) => {
+            Buffers::MultiSampling(draw_buf, _, num_samples) => {
                 let uniforms = uniform! {
-                    source_texture: draw_buf.sampled()
+                    source_texture: draw_buf.sampled(),
+                    num_samples: *num_samples as i32,
+                    sensor_noise_enabled: self.sensor_noise.is_some(),
+                    read_noise: noise.read_noise,
+                    full_well: noise.full_well,
+                    hot_pixel_fraction: noise.hot_pixel_fraction,
+                    noise_seed: self.frame_index.get(),
+                    hot_pixel_seed: noise.hot_pixel_seed,
+                    lens_distortion_enabled: self.lens_distortion.is_some(),
+                    lens_k1: lens.k1,
+                    lens_k2: lens.k2,
+                    vignetting: lens.vignetting,
+                    focal_length_px: focal_length_px,
+                    image_size: image_size,
+                    seeing_enabled: blur_enabled,
+                    seeing_sigma_px: seeing_sigma,
+                    seeing_offset_px: seeing_offset,
+                    filter_enabled: true,
+                    filter_transmission: self.filter_transmission
                 };
 
                 fbo.draw(
@@ -116,6 +230,19 @@ impl DrawBuffer {
                 ).unwrap();
             },
         };
+
+        if let Some(readback) = self.readback.borrow_mut().as_mut() {
+            readback.push(&self.storage_buf);
+        }
+    }
+
+    /// Enables asynchronous readback of the storage buffer with the specified latency (in frames).
+    pub fn set_readback(&mut self, latency: Option<usize>) {
+        *self.readback.borrow_mut() = latency.map(AsyncReadback::new);
+    }
+
+    pub fn readback(&self) -> Ref<'_, Option<AsyncReadback>> {
+        self.readback.borrow()
     }
 
     pub fn storage_buf(&self) -> &Rc<Texture2d> {
@@ -128,12 +255,14 @@ impl DrawBuffer {
                 &self.display, draw_buf, depth_buf
             ).unwrap(),
 
-            Buffers::MultiSampling(draw_buf, depth_buf) => glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
+            Buffers::MultiSampling(draw_buf, depth_buf, _) => glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(
                 &self.display, draw_buf, depth_buf
             ).unwrap()
         }
     }
 
+    pub fn sampling(&self) -> Sampling { self.draw_bufs.sampling() }
+
     pub fn width(&self) -> u32 { self.storage_buf.width() }
 
     pub fn height(&self) -> u32 { self.storage_buf.height() }
@@ -143,7 +272,7 @@ impl DrawBuffer {
         texture_copy_single_gl_prog: &Rc<glium::Program>,
         texture_copy_multi_gl_prog: &Rc<glium::Program>,
         unit_quad: &Rc<glium::VertexBuffer<crate::data::Vertex2>>,
-        display: &glium::Display<WindowSurface>,
+        display: &GlContext,
         renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>
     ) -> DrawBuffer {
         let (id, draw_bufs, storage_buf) = DrawBuffer::create(
@@ -164,7 +293,14 @@ impl DrawBuffer {
             storage_buf,
             unit_quad: Rc::clone(unit_quad),
             texture_copy_single_gl_prog: Rc::clone(texture_copy_single_gl_prog),
-            texture_copy_multi_gl_prog: Rc::clone(texture_copy_multi_gl_prog)
+            texture_copy_multi_gl_prog: Rc::clone(texture_copy_multi_gl_prog),
+            readback: RefCell::new(None),
+            sensor_noise: None,
+            frame_index: Cell::new(0),
+            lens_distortion: None,
+            seeing: None,
+            defocus: f64::Angle::new::<angle::degree>(0.0),
+            filter_transmission: [1.0; 3]
         }
     }
 
@@ -173,7 +309,7 @@ impl DrawBuffer {
         texture_copy_single_gl_prog: &Rc<glium::Program>,
         texture_copy_multi_gl_prog: &Rc<glium::Program>,
         unit_quad: &Rc<glium::VertexBuffer<crate::data::Vertex2>>,
-        display: &glium::Display<WindowSurface>,
+        display: &GlContext,
         renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
         width: u32,
         height: u32
@@ -196,7 +332,14 @@ impl DrawBuffer {
             storage_buf,
             unit_quad: Rc::clone(unit_quad),
             texture_copy_single_gl_prog: Rc::clone(texture_copy_single_gl_prog),
-            texture_copy_multi_gl_prog: Rc::clone(texture_copy_multi_gl_prog)
+            texture_copy_multi_gl_prog: Rc::clone(texture_copy_multi_gl_prog),
+            readback: RefCell::new(None),
+            sensor_noise: None,
+            frame_index: Cell::new(0),
+            lens_distortion: None,
+            seeing: None,
+            defocus: f64::Angle::new::<angle::degree>(0.0),
+            filter_transmission: [1.0; 3]
         }
     }
 
@@ -210,7 +353,7 @@ impl DrawBuffer {
         width: u32,
         height: u32,
         format: glium::texture::UncompressedFloatFormat,
-        display: &glium::Display<WindowSurface>,
+        display: &GlContext,
         renderer: &mut imgui_glium_renderer::Renderer
     ) -> (imgui::TextureId, Buffers, Rc<Texture2d>) {
         let draw_bufs = match sampling {
@@ -231,14 +374,14 @@ impl DrawBuffer {
                 ).unwrap()
             ),
 
-            Sampling::Multi => Buffers::MultiSampling(
+            Sampling::Multi(num_samples) => Buffers::MultiSampling(
                 Texture2dMultisample::empty_with_format(
                     display,
                     format,
                     glium::texture::MipmapsOption::NoMipmap,
                     width,
                     height,
-                    NUM_SAMPLES
+                    num_samples
                 ).unwrap(),
                 DepthTexture2dMultisample::empty_with_format(
                     display,
@@ -246,8 +389,9 @@ impl DrawBuffer {
                     glium::texture::MipmapsOption::NoMipmap,
                     width,
                     height,
-                    NUM_SAMPLES
-                ).unwrap()
+                    num_samples
+                ).unwrap(),
+                num_samples
             )
         };
 
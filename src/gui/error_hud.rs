@@ -0,0 +1,104 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Pointing error readout in the camera view and an edge arrow towards the target when it is out of view.
+
+use crate::{gui::operator_assist::Guidance, units::{AngleScale, DisplayUnits}};
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+const TEXT_COLOR: [f32; 4] = [0.4, 1.0, 0.4, 1.0];
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const ARROW_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+
+/// Distance (pixels) of the edge arrow's tip from the image edge.
+const EDGE_MARGIN: f32 = 12.0;
+
+const ARROW_SIZE: f32 = 18.0;
+
+/// Formats an angle (with sign); arcminutes (or milliradians) are used below 1°.
+fn format_angle(value: f64::Angle, display_units: &DisplayUnits) -> String {
+    if value.get::<angle::degree>().abs() < 1.0 {
+        display_units.signed_angle(value, AngleScale::Small, 2)
+    } else {
+        display_units.signed_angle(value, AngleScale::Normal, 3)
+    }
+}
+
+/// Draws the error readout and, if the target is not in the image, an arrow at the image edge pointing towards it.
+///
+/// `target_pos`: target position in the image (relative, as in `CameraView::direction_at`), `None` if the target
+/// is behind the camera; `image_min`, `image_max`: screen coordinates of the camera image.
+pub fn draw(
+    ui: &imgui::Ui,
+    error: &Guidance,
+    target_pos: Option<[f64; 2]>,
+    image_min: [f32; 2],
+    image_max: [f32; 2],
+    display_units: &DisplayUnits
+) {
+    let draw_list = ui.get_window_draw_list();
+    let total = error.total_offset();
+
+    let text = format!(
+        "pointing error: {}\naz. {}  alt. {}",
+        format_angle(total, display_units).trim_start_matches('+'),
+        format_angle(error.offset.0, display_units),
+        format_angle(error.offset.1, display_units)
+    );
+    let text_pos = [image_min[0] + 8.0, image_min[1] + 8.0];
+    let text_size = ui.calc_text_size(&text);
+    draw_list.add_rect(
+        [text_pos[0] - 4.0, text_pos[1] - 4.0],
+        [text_pos[0] + text_size[0] + 4.0, text_pos[1] + text_size[1] + 4.0],
+        BACKGROUND_COLOR
+    ).filled(true).build();
+    draw_list.add_text(text_pos, TEXT_COLOR, &text);
+
+    let in_view = target_pos.map_or(false, |pos| pos.iter().all(|value| (0.0..=1.0).contains(value)));
+    if in_view { return; }
+
+    let size = [image_max[0] - image_min[0], image_max[1] - image_min[1]];
+    let center = [image_min[0] + size[0] / 2.0, image_min[1] + size[1] / 2.0];
+    // the projected position accounts for camera roll; if the target is behind the camera, use the az/alt offset
+    // (screen X points towards increasing azimuth, screen Y towards decreasing altitude)
+    let dir = match target_pos {
+        Some(pos) => [(pos[0] as f32 - 0.5) * size[0], (pos[1] as f32 - 0.5) * size[1]],
+        None => [error.offset.0.get::<angle::radian>() as f32, -error.offset.1.get::<angle::radian>() as f32]
+    };
+    let dir_len = dir[0].hypot(dir[1]);
+    if dir_len == 0.0 { return; }
+    let dir = [dir[0] / dir_len, dir[1] / dir_len];
+
+    let half_extent = [size[0] / 2.0 - EDGE_MARGIN, size[1] / 2.0 - EDGE_MARGIN];
+    let scale = (0..2)
+        .filter(|&i| dir[i] != 0.0)
+        .map(|i| half_extent[i].max(0.0) / dir[i].abs())
+        .fold(f32::MAX, f32::min);
+    let tip = [center[0] + dir[0] * scale, center[1] + dir[1] * scale];
+    let normal = [-dir[1], dir[0]];
+    let base = [tip[0] - dir[0] * ARROW_SIZE, tip[1] - dir[1] * ARROW_SIZE];
+    draw_list.add_triangle(
+        tip,
+        [base[0] + normal[0] * ARROW_SIZE / 2.0, base[1] + normal[1] * ARROW_SIZE / 2.0],
+        [base[0] - normal[0] * ARROW_SIZE / 2.0, base[1] - normal[1] * ARROW_SIZE / 2.0],
+        ARROW_COLOR
+    ).filled(true).build();
+
+    let label = display_units.angle(total, AngleScale::Normal, 1);
+    let label_size = ui.calc_text_size(&label);
+    let label_center = [
+        base[0] - dir[0] * (label_size[0] / 2.0 + 6.0),
+        base[1] - dir[1] * (label_size[1] / 2.0 + 6.0)
+    ];
+    draw_list.add_text(
+        [label_center[0] - label_size[0] / 2.0, label_center[1] - label_size[1] / 2.0],
+        ARROW_COLOR,
+        label
+    );
+}
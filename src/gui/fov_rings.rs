@@ -0,0 +1,50 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Labeled rings showing fields of view of instruments (co-aligned with the camera) in the camera view.
+
+use crate::{config::InstrumentFov, units::{AngleScale, DisplayUnits}};
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+/// Rings smaller than this (radius in pixels) are not drawn.
+const MIN_RADIUS: f32 = 3.0;
+
+/// Draws the shown instruments' FOVs centered on the camera image (`image_min`, `image_max`: its screen
+/// coordinates).
+pub fn draw(
+    ui: &imgui::Ui,
+    instruments: &[InstrumentFov],
+    image_min: [f32; 2],
+    image_max: [f32; 2],
+    field_of_view_y: f64::Angle,
+    display_units: &DisplayUnits
+) {
+    let size = [image_max[0] - image_min[0], image_max[1] - image_min[1]];
+    let center = [image_min[0] + size[0] / 2.0, image_min[1] + size[1] / 2.0];
+    let px_per_deg = size[1] / field_of_view_y.get::<angle::degree>() as f32;
+
+    let draw_list = ui.get_window_draw_list();
+    draw_list.with_clip_rect_intersect(image_min, image_max, || {
+        for instrument in instruments.iter().filter(|instrument| instrument.shown) {
+            let radius = instrument.fov_deg as f32 / 2.0 * px_per_deg;
+            // rings much larger than the image are entirely off-screen
+            if !(MIN_RADIUS..=4.0 * size[0].max(size[1])).contains(&radius) { continue; }
+
+            draw_list.add_circle(center, radius, instrument.color).num_segments(128).thickness(1.5).build();
+            let fov = display_units.angle(f64::Angle::new::<angle::degree>(instrument.fov_deg), AngleScale::Normal, 2);
+            let label = format!("{} ({})", instrument.name, fov);
+            let label_size = ui.calc_text_size(&label);
+            draw_list.add_text(
+                [center[0] - label_size[0] / 2.0, center[1] - radius - label_size[1] - 2.0],
+                instrument.color,
+                label
+            );
+        }
+    });
+}
@@ -0,0 +1,39 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use cgmath::{InnerSpace, Matrix4, Point3, Vector4};
+
+/// View frustum, used for culling objects before issuing draw calls.
+pub struct Frustum {
+    /// Planes (normal, distance) with normals pointing inside the frustum.
+    planes: [Vector4<f64>; 6]
+}
+
+impl Frustum {
+    /// Extracts frustum planes from the `projection * view` matrix (Gribb & Hartmann).
+    pub fn from_matrix(m: &Matrix4<f64>) -> Frustum {
+        let row = |i: usize| Vector4::new(m.x[i], m.y[i], m.z[i], m.w[i]);
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+
+        let normalize = |p: Vector4<f64>| p / p.truncate().magnitude();
+
+        Frustum{ planes: [
+            normalize(r3 + r0),
+            normalize(r3 - r0),
+            normalize(r3 + r1),
+            normalize(r3 - r1),
+            normalize(r3 + r2),
+            normalize(r3 - r2)
+        ]}
+    }
+
+    /// Returns true if a sphere is at least partially inside the frustum.
+    pub fn intersects_sphere(&self, center: Point3<f64>, radius: f64) -> bool {
+        self.planes.iter().all(|p| p.x * center.x + p.y * center.y + p.z * center.z + p.w >= -radius)
+    }
+}
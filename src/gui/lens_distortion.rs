@@ -0,0 +1,19 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Settings of the simulated lens distortion and vignetting (applied by `resources/shaders/lens_distortion.glsl`).
+
+#[derive(Clone, Debug, Default)]
+pub struct LensDistortion {
+    /// Radial distortion coefficients (Brown-Conrady model, as used by OpenCV) for image coordinates normalized
+    /// by the focal length. Negative `k1`: barrel distortion, positive: pincushion.
+    pub k1: f32,
+    pub k2: f32,
+    /// Relative brightness loss at the image corners (0-1); falls off quadratically with distance from the center.
+    pub vignetting: f32
+}
@@ -0,0 +1,54 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Filtering of captured log messages shown in the log console window.
+
+use crate::log_capture::LogEntry;
+
+/// Selectable minimum levels of shown messages.
+pub const LEVELS: [log::Level; 5] = [
+    log::Level::Error,
+    log::Level::Warn,
+    log::Level::Info,
+    log::Level::Debug,
+    log::Level::Trace
+];
+
+pub struct LogConsole {
+    /// Index in `LEVELS`.
+    pub level_idx: usize,
+    /// Only messages containing this text (case-insensitive) are shown.
+    pub search: String,
+    /// If set, the view follows new messages (unless scrolled up).
+    pub auto_scroll: bool
+}
+
+impl Default for LogConsole {
+    fn default() -> LogConsole {
+        LogConsole{ level_idx: 2, search: String::new(), auto_scroll: true }
+    }
+}
+
+impl LogConsole {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        if entry.level > LEVELS[self.level_idx] { return false; }
+        if self.search.is_empty() { return true; }
+
+        let search = self.search.to_lowercase();
+        entry.message.to_lowercase().contains(&search) || entry.target.to_lowercase().contains(&search)
+    }
+}
+
+pub fn level_color(level: log::Level) -> [f32; 4] {
+    match level {
+        log::Level::Error => [1.0, 0.35, 0.35, 1.0],
+        log::Level::Warn => [1.0, 0.8, 0.3, 1.0],
+        log::Level::Info => [0.9, 0.9, 0.9, 1.0],
+        log::Level::Debug | log::Level::Trace => [0.6, 0.6, 0.6, 1.0]
+    }
+}
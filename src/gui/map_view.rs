@@ -0,0 +1,337 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Top-down map of the observer sites, target ground tracks and range rings.
+//!
+//! Uses a simple equirectangular projection centered on the view (adequate at the distances involved). Imagery is
+//! drawn from locally stored map tiles (Web Mercator "XYZ" scheme), if configured.
+
+use crate::{data::Station, runner::GlContext, units::DisplayUnits, workers::Site};
+use glium::texture::{RawImage2d, Texture2d};
+use pointing_utils::{EARTH_RADIUS_M, uom};
+use std::{cell::RefCell, collections::HashMap, f64::consts::PI, rc::Rc};
+use uom::{si::f64, si::length};
+
+/// Range of the map scale (meters per pixel).
+const MIN_SCALE: f64 = 0.5;
+const MAX_SCALE: f64 = 5000.0;
+
+const WHEEL_ZOOM_FACTOR: f64 = 1.25;
+
+const TILE_SIZE: f64 = 256.0;
+const MAX_TILE_ZOOM: i32 = 19;
+/// Imagery is not drawn if more tiles than this would be visible.
+const MAX_VISIBLE_TILES: usize = 100;
+/// Tiles are loaded synchronously; limit the number loaded in one frame.
+const MAX_TILE_LOADS_PER_FRAME: usize = 4;
+
+const BACKGROUND_COLOR: [f32; 4] = [0.1, 0.12, 0.1, 1.0];
+const RING_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 0.6];
+const SITE_COLOR: [f32; 4] = [0.2, 1.0, 0.2, 1.0];
+const TRACK_COLOR: [f32; 4] = [1.0, 0.6, 0.2, 0.8];
+const TARGET_COLOR: [f32; 4] = [1.0, 1.0, 0.3, 1.0];
+
+/// Meters per degree of latitude.
+const M_PER_DEG: f64 = EARTH_RADIUS_M * PI / 180.0;
+
+/// Converts a position in a station's local frame (x: north, y: west; meters) to latitude and longitude (degrees).
+fn local_to_lat_lon(site: &Site, position: &cgmath::Point3<f64>) -> [f64; 2] {
+    [site.lat.0 + position.x / M_PER_DEG, site.lon.0 - position.y / (M_PER_DEG * site.lat.0.to_radians().cos())]
+}
+
+fn tile_count(zoom: i32) -> f64 { 2.0f64.powi(zoom) }
+
+fn tile_x(lon: f64, zoom: i32) -> f64 { (lon + 180.0) / 360.0 * tile_count(zoom) }
+
+fn tile_y(lat: f64, zoom: i32) -> f64 {
+    let lat = lat.clamp(-85.0, 85.0).to_radians();
+    (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * tile_count(zoom)
+}
+
+fn tile_lon(x: f64, zoom: i32) -> f64 { x / tile_count(zoom) * 360.0 - 180.0 }
+
+fn tile_lat(y: f64, zoom: i32) -> f64 { (PI * (1.0 - 2.0 * y / tile_count(zoom))).sinh().atan().to_degrees() }
+
+fn load_tile(path: &str, display: &GlContext) -> Result<Option<Texture2d>, String> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        // tile sets often cover only some areas
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.to_string())
+    };
+    let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    let pixels = &buf[..info.buffer_size()];
+    let rgba: Vec<u8> = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => pixels.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => pixels.iter().flat_map(|p| [*p, *p, *p, 255]).collect(),
+        png::ColorType::Indexed => return Err("unexpected indexed color".into())
+    };
+    Texture2d::new(display, RawImage2d::from_raw_rgba(rgba, (info.width, info.height)))
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Default)]
+struct TileCache {
+    /// Path template the cached tiles have been loaded with.
+    template: String,
+    /// Keyed by (zoom, x, y); `None` if the tile is not available.
+    textures: HashMap<(i32, i32, i32), Option<imgui::TextureId>>
+}
+
+impl TileCache {
+    fn clear(&mut self, renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>) {
+        for id in self.textures.drain().filter_map(|(_, id)| id) {
+            renderer.borrow_mut().textures().remove(id);
+        }
+    }
+
+    /// Returns the tile's texture, loading it if needed (and if `loads_left` > 0).
+    fn get(
+        &mut self,
+        tile: (i32, i32, i32),
+        loads_left: &mut usize,
+        renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
+        display: &GlContext
+    ) -> Option<imgui::TextureId> {
+        if let Some(id) = self.textures.get(&tile) { return *id; }
+        if *loads_left == 0 { return None; }
+        *loads_left -= 1;
+
+        let path = self.template
+            .replace("{z}", &tile.0.to_string())
+            .replace("{x}", &tile.1.to_string())
+            .replace("{y}", &tile.2.to_string());
+        let id = match load_tile(&path, display) {
+            Ok(texture) => texture.map(|texture| renderer.borrow_mut().textures().insert(imgui_glium_renderer::Texture{
+                texture: Rc::new(texture),
+                sampler: glium::uniforms::SamplerBehavior{
+                    magnify_filter: glium::uniforms::MagnifySamplerFilter::Linear,
+                    minify_filter: glium::uniforms::MinifySamplerFilter::Linear,
+                    ..Default::default()
+                }
+            })),
+            Err(e) => { log::error!("failed to load map tile {}: {}", path, e); None }
+        };
+        self.textures.insert(tile, id);
+        id
+    }
+}
+
+/// Maps between latitude/longitude and screen positions.
+struct Projection {
+    /// Latitude, longitude (degrees).
+    center: [f64; 2],
+    /// Meters per pixel.
+    scale: f64,
+    /// Screen position of `center`.
+    origin: [f32; 2]
+}
+
+impl Projection {
+    fn to_screen(&self, lat_lon: [f64; 2]) -> [f32; 2] {
+        let m_per_deg_lon = M_PER_DEG * self.center[0].to_radians().cos();
+        [
+            self.origin[0] + ((lat_lon[1] - self.center[1]) * m_per_deg_lon / self.scale) as f32,
+            self.origin[1] - ((lat_lon[0] - self.center[0]) * M_PER_DEG / self.scale) as f32
+        ]
+    }
+
+    fn to_lat_lon(&self, pos: [f32; 2]) -> [f64; 2] {
+        let m_per_deg_lon = M_PER_DEG * self.center[0].to_radians().cos();
+        [
+            self.center[0] - (pos[1] - self.origin[1]) as f64 * self.scale / M_PER_DEG,
+            self.center[1] + (pos[0] - self.origin[0]) as f64 * self.scale / m_per_deg_lon
+        ]
+    }
+}
+
+pub struct MapView {
+    /// Latitude, longitude (degrees) of the view center; if not set, the view is centered on the first station.
+    center: Option<[f64; 2]>,
+    /// Meters per pixel.
+    scale: f64,
+    pub range_ring_spacing: f64::Length,
+    pub show_tracks: bool,
+    tiles: TileCache
+}
+
+impl Default for MapView {
+    fn default() -> MapView {
+        MapView{
+            center: None,
+            scale: 50.0,
+            range_ring_spacing: f64::Length::new::<length::kilometer>(5.0),
+            show_tracks: true,
+            tiles: TileCache::default()
+        }
+    }
+}
+
+impl MapView {
+    /// Re-centers the view on the first station.
+    pub fn reset_center(&mut self) { self.center = None; }
+
+    /// Returns the map scale (meters per pixel).
+    pub fn scale(&self) -> f64 { self.scale }
+
+    /// Draws the map in the remaining space of the current window and handles mouse input (drag: pan, wheel: zoom).
+    /// `tile_template`: see `config::Settings::map_tiles`.
+    pub fn draw(
+        &mut self,
+        ui: &imgui::Ui,
+        stations: &[Station],
+        display_units: &DisplayUnits,
+        tile_template: &str,
+        renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
+        display: &GlContext
+    ) {
+        let origin = ui.cursor_screen_pos();
+        let avail = ui.content_region_avail();
+        let size = [avail[0].max(50.0), (avail[1] - ui.text_line_height_with_spacing()).max(50.0)];
+        ui.invisible_button("##map", size);
+        let (hovered, active) = (ui.is_item_hovered(), ui.is_item_active());
+        let canvas_max = [origin[0] + size[0], origin[1] + size[1]];
+
+        let center = self.center.unwrap_or_else(|| stations.first().map_or([0.0; 2], |s| [s.site.lat.0, s.site.lon.0]));
+        let mut projection = Projection{
+            center,
+            scale: self.scale,
+            origin: [origin[0] + size[0] / 2.0, origin[1] + size[1] / 2.0]
+        };
+
+        let mouse_pos = ui.io().mouse_pos;
+        if active && ui.is_mouse_dragging(imgui::MouseButton::Left) {
+            let delta = ui.io().mouse_delta;
+            let shifted = [projection.origin[0] - delta[0], projection.origin[1] - delta[1]];
+            projection.center = projection.to_lat_lon(shifted);
+            self.center = Some(projection.center);
+        }
+        let wheel = ui.io().mouse_wheel;
+        if hovered && wheel != 0.0 {
+            // keep the point under the cursor in place
+            let cursor_lat_lon = projection.to_lat_lon(mouse_pos);
+            projection.scale = (self.scale / WHEEL_ZOOM_FACTOR.powf(wheel as f64)).clamp(MIN_SCALE, MAX_SCALE);
+            let moved = projection.to_screen(cursor_lat_lon);
+            let shifted = [
+                projection.origin[0] + moved[0] - mouse_pos[0],
+                projection.origin[1] + moved[1] - mouse_pos[1]
+            ];
+            projection.center = projection.to_lat_lon(shifted);
+            self.scale = projection.scale;
+            self.center = Some(projection.center);
+        }
+
+        if self.tiles.template != tile_template {
+            self.tiles.clear(renderer);
+            self.tiles.template = tile_template.to_string();
+        }
+
+        let draw_list = ui.get_window_draw_list();
+        draw_list.with_clip_rect_intersect(origin, canvas_max, || {
+            draw_list.add_rect(origin, canvas_max, BACKGROUND_COLOR).filled(true).build();
+
+            if !tile_template.is_empty() {
+                self.draw_tiles(&draw_list, &projection, origin, canvas_max, renderer, display);
+            }
+
+            let spacing_px = (self.range_ring_spacing.get::<length::meter>() / projection.scale) as f32;
+            let max_radius = size[0].hypot(size[1]);
+            for station in stations {
+                let site_pos = projection.to_screen([station.site.lat.0, station.site.lon.0]);
+
+                if spacing_px >= 10.0 {
+                    let mut ring = 1;
+                    while ring as f32 * spacing_px < max_radius {
+                        let radius = ring as f32 * spacing_px;
+                        draw_list.add_circle(site_pos, radius, RING_COLOR).num_segments(96).build();
+                        draw_list.add_text(
+                            [site_pos[0] + 2.0, site_pos[1] - radius],
+                            RING_COLOR,
+                            display_units.length(self.range_ring_spacing * ring as f64, 0)
+                        );
+                        ring += 1;
+                    }
+                }
+
+                let history: Vec<cgmath::Point3<f64>> =
+                    station.target_interpolator.borrow().history().cloned().collect();
+                if self.show_tracks && history.len() >= 2 {
+                    let points: Vec<[f32; 2]> = history.iter()
+                        .map(|p| projection.to_screen(local_to_lat_lon(&station.site, p)))
+                        .collect();
+                    draw_list.add_polyline(points, TRACK_COLOR).thickness(1.5).build();
+                }
+                if let Some(target) = history.last() {
+                    let pos = projection.to_screen(local_to_lat_lon(&station.site, target));
+                    draw_list.add_circle(pos, 4.0, TARGET_COLOR).filled(true).build();
+                    draw_list.add_text(
+                        [pos[0] + 6.0, pos[1] - 6.0],
+                        TARGET_COLOR,
+                        format!("alt. {}", display_units.length(f64::Length::new::<length::meter>(target.z), 0))
+                    );
+                }
+
+                const SITE_SIZE: f32 = 6.0;
+                draw_list.add_triangle(
+                    [site_pos[0], site_pos[1] - SITE_SIZE],
+                    [site_pos[0] - SITE_SIZE, site_pos[1] + SITE_SIZE],
+                    [site_pos[0] + SITE_SIZE, site_pos[1] + SITE_SIZE],
+                    SITE_COLOR
+                ).filled(true).build();
+                draw_list.add_text([site_pos[0] + SITE_SIZE + 2.0, site_pos[1]], SITE_COLOR, &station.name);
+            }
+        });
+
+        if hovered {
+            let lat_lon = projection.to_lat_lon(mouse_pos);
+            ui.text(format!("{:.5}°, {:.5}°", lat_lon[0], lat_lon[1]));
+        } else {
+            ui.text_disabled("drag: pan, mouse wheel: zoom");
+        }
+    }
+
+    fn draw_tiles(
+        &mut self,
+        draw_list: &imgui::DrawListMut<'_>,
+        projection: &Projection,
+        canvas_min: [f32; 2],
+        canvas_max: [f32; 2],
+        renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
+        display: &GlContext
+    ) {
+        // zoom level at which tile pixels are closest to screen pixels
+        let m_per_tile_px = 2.0 * PI * EARTH_RADIUS_M * projection.center[0].to_radians().cos() / TILE_SIZE;
+        let zoom = ((m_per_tile_px / projection.scale).log2().round() as i32).clamp(0, MAX_TILE_ZOOM);
+
+        let top_left = projection.to_lat_lon(canvas_min);
+        let bottom_right = projection.to_lat_lon(canvas_max);
+        let max_index = tile_count(zoom) as i32 - 1;
+        let index_range = |first: f64, last: f64| (first.floor() as i32).max(0)..=(last.floor() as i32).min(max_index);
+        let x_range = index_range(tile_x(top_left[1], zoom), tile_x(bottom_right[1], zoom));
+        let y_range = index_range(tile_y(top_left[0], zoom), tile_y(bottom_right[0], zoom));
+        if x_range.clone().count() * y_range.clone().count() > MAX_VISIBLE_TILES { return; }
+
+        let mut loads_left = MAX_TILE_LOADS_PER_FRAME;
+        for y in y_range {
+            for x in x_range.clone() {
+                if let Some(id) = self.tiles.get((zoom, x, y), &mut loads_left, renderer, display) {
+                    let p_min = projection.to_screen([tile_lat(y as f64, zoom), tile_lon(x as f64, zoom)]);
+                    let p_max = projection.to_screen([tile_lat((y + 1) as f64, zoom), tile_lon((x + 1) as f64, zoom)]);
+                    draw_list.add_image(id, p_min, p_max).build();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Measurement of angular offsets in the camera view.
+//!
+//! Clicking in the image shows the offset of the clicked point from the mount's boresight; clicking near the target
+//! shows the (live) residual pointing error. Click-dragging shows the offset between the drag's end points.
+
+use crate::{
+    gui::CameraView,
+    target_geometry::separation,
+    tracking_controller::normalize,
+    units,
+    units::{AngleScale, DisplayUnits},
+    workers::MountState
+};
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+/// A click within this distance (pixels) from the target's image measures the pointing error.
+const TARGET_SNAP_DISTANCE: f32 = 15.0;
+
+/// Mouse movement (pixels) above which a click becomes a drag.
+const MIN_DRAG_DISTANCE: f32 = 3.0;
+
+const COLOR: [f32; 4] = [1.0, 0.5, 0.2, 1.0];
+
+#[derive(Copy, Clone, PartialEq)]
+enum Kind {
+    /// Offset of a point from the boresight.
+    FromBoresight,
+    /// Offset of the target from the boresight.
+    PointingError,
+    /// Offset between two points.
+    Drag
+}
+
+pub struct Measurement {
+    /// Title of the camera view window the measurement was made in.
+    window: String,
+    /// Positions relative to the image (as in `CameraView::direction_at`).
+    start: [f32; 2],
+    end: [f32; 2],
+    /// Whether the mouse button is still held down.
+    in_progress: bool,
+    kind: Kind
+}
+
+/// Offset from one direction to another.
+struct Offset {
+    /// Measured on the sky (i.e., scaled by cos(altitude)), positive towards east.
+    horizontal: f64::Angle,
+    /// Positive upwards.
+    vertical: f64::Angle,
+    total: f64::Angle
+}
+
+impl Offset {
+    fn new(from_az: f64::Angle, from_alt: f64::Angle, to_az: f64::Angle, to_alt: f64::Angle) -> Offset {
+        let az_diff = normalize((to_az - from_az).get::<angle::radian>());
+        let mean_alt = ((from_alt + to_alt) / 2.0).get::<angle::radian>();
+        Offset{
+            horizontal: f64::Angle::new::<angle::radian>(az_diff * mean_alt.cos()),
+            vertical: to_alt - from_alt,
+            total: separation(from_az, from_alt, to_az, to_alt)
+        }
+    }
+
+    fn describe(&self, label: &str, display_units: &DisplayUnits) -> String {
+        format!(
+            "{}: {}\nhorz. {}, vert. {}",
+            label,
+            display_units.angle(self.total, AngleScale::Small, 2),
+            display_units.signed_angle(self.horizontal, AngleScale::Small, 2),
+            display_units.signed_angle(self.vertical, AngleScale::Small, 2)
+        )
+    }
+}
+
+fn to_relative(pos: [f32; 2], image_min: [f32; 2], image_max: [f32; 2]) -> [f32; 2] {
+    [(pos[0] - image_min[0]) / (image_max[0] - image_min[0]), (pos[1] - image_min[1]) / (image_max[1] - image_min[1])]
+}
+
+fn to_screen(pos: [f32; 2], image_min: [f32; 2], image_max: [f32; 2]) -> [f32; 2] {
+    [image_min[0] + pos[0] * (image_max[0] - image_min[0]), image_min[1] + pos[1] * (image_max[1] - image_min[1])]
+}
+
+fn distance(p1: [f32; 2], p2: [f32; 2]) -> f32 { (p1[0] - p2[0]).hypot(p1[1] - p2[1]) }
+
+/// Updates `measurement` from mouse input over the camera view image (which must be the last item); `window`: title
+/// of the camera view window.
+pub fn handle_input(
+    measurement: &mut Option<Measurement>,
+    window: &str,
+    ui: &imgui::Ui,
+    camera_view: &CameraView,
+    image_min: [f32; 2],
+    image_max: [f32; 2]
+) {
+    let mouse_pos = ui.io().mouse_pos;
+
+    if ui.is_item_hovered() && ui.is_mouse_clicked(imgui::MouseButton::Left) {
+        let pos = to_relative(mouse_pos, image_min, image_max);
+        *measurement = Some(Measurement{
+            window: window.to_string(),
+            start: pos,
+            end: pos,
+            in_progress: true,
+            kind: Kind::FromBoresight
+        });
+        return;
+    }
+
+    let measurement = match measurement {
+        Some(m) if m.window == window && m.in_progress => m,
+        _ => return
+    };
+
+    let start = to_screen(measurement.start, image_min, image_max);
+    if measurement.kind == Kind::Drag || distance(start, mouse_pos) > MIN_DRAG_DISTANCE {
+        measurement.kind = Kind::Drag;
+        measurement.end = to_relative(mouse_pos, image_min, image_max).map(|value| value.clamp(0.0, 1.0));
+    }
+
+    if !ui.is_mouse_down(imgui::MouseButton::Left) {
+        measurement.in_progress = false;
+        let near_target = camera_view.target_image_position().map_or(false, |pos| {
+            distance(to_screen([pos[0] as f32, pos[1] as f32], image_min, image_max), start) <= TARGET_SNAP_DISTANCE
+        });
+        if measurement.kind == Kind::FromBoresight && near_target {
+            measurement.kind = Kind::PointingError;
+        }
+    }
+}
+
+impl Measurement {
+    /// Returns title of the camera view window the measurement was made in.
+    pub fn window(&self) -> &str { &self.window }
+
+    /// Draws the measurement over the camera image.
+    pub fn draw(
+        &self,
+        ui: &imgui::Ui,
+        camera_view: &CameraView,
+        image_min: [f32; 2],
+        image_max: [f32; 2],
+        mount_state: &MountState,
+        display_units: &DisplayUnits
+    ) {
+        let boresight = camera_view.direction_image_position(mount_state.boresight_az, mount_state.boresight_alt)
+            .map(|pos| [pos[0] as f32, pos[1] as f32]);
+        let (from, to, label) = match self.kind {
+            Kind::FromBoresight => match boresight {
+                Some(boresight) => (boresight, self.end, "from boresight"),
+                None => return
+            },
+            Kind::PointingError => match (boresight, camera_view.target_image_position()) {
+                (Some(boresight), Some(target)) => (boresight, [target[0] as f32, target[1] as f32], "pointing error"),
+                _ => return
+            },
+            Kind::Drag => (self.start, self.end, "offset")
+        };
+
+        let (dir_from, dir_to) = (camera_view.direction_at(from), camera_view.direction_at(to));
+        let offset = Offset::new(
+            units::deg(dir_from.az), units::deg(dir_from.alt), units::deg(dir_to.az), units::deg(dir_to.alt)
+        );
+
+        let (from, to) = (to_screen(from, image_min, image_max), to_screen(to, image_min, image_max));
+        let draw_list = ui.get_window_draw_list();
+        draw_list.with_clip_rect_intersect(image_min, image_max, || {
+            draw_list.add_line(from, to, COLOR).thickness(1.5).build();
+            draw_list.add_circle(from, 3.0, COLOR).build();
+            draw_list.add_circle(to, 3.0, COLOR).filled(true).build();
+            draw_list.add_text([to[0] + 8.0, to[1] + 8.0], COLOR, offset.describe(label, display_units));
+        });
+    }
+}
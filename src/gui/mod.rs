@@ -7,33 +7,162 @@
 //
 
 mod camera_view;
+mod camera_presets;
+mod async_readback;
 mod draw_buffer;
+mod error_hud;
+mod drag_pan;
+mod frustum;
+mod fov_rings;
+mod lens_distortion;
+mod log_console;
+mod map_view;
+mod measurement;
+mod motion_blur;
+mod nav_lights;
+mod notifications;
+mod operator_assist;
+mod quality_governor;
+mod render_settings;
+mod scenario_editor;
+mod reticle;
+mod seeing;
+mod sensor_noise;
+mod session_player;
+mod sky_chart;
+mod state_snapshot;
+mod status_bar;
+mod theme;
+mod traffic_inspector;
+mod zoom_inset;
 
-use crate::{data, runner, workers::MountState};
-use glium::glutin::surface::WindowSurface;
+use crate::{
+    challenge,
+    challenge::Challenge,
+    cloud_layer::{CloudLayer, Coverage},
+    command_console::CommandConsole,
+    color_mode::ColorMode,
+    config,
+    data,
+    log_capture::LogBuffer,
+    frame_capture::{FrameCapture, TARGET_CLASS},
+    gamepad::Gamepad,
+    mount_comparison::MountComparison,
+    mount_control,
+    mount_control::MountControl,
+    runner,
+    scoring::Scoring,
+    telemetry,
+    telemetry::Telemetry,
+    tracking_controller::{ControllerKind, TrackingController},
+    units,
+    units::{AngleScale, DisplayUnits},
+    workers,
+    workers::{ClientStatus, Derotator, EquatorialSettings, MAX_GUSTS_PER_MINUTE, Mount, MountMode, MountState},
+    zenith_keyhole::KeyholeStatus
+};
 use pointing_utils::uom;
-use std::{cell::RefCell, rc::Rc};
-use uom::si::angle;
+use std::{cell::RefCell, path::PathBuf, rc::Rc, sync::Arc};
+use uom::{si::f64, si::{angle, angular_velocity, length, velocity}};
 
-pub use camera_view::CameraView;
+pub use camera_view::{CameraView, Misalignment, Visibility};
+pub use scenario_editor::ScenarioEditor;
+pub use sky_chart::SkyChart;
 
 /// Zoom factor per one step of mouse wheel.
 const MOUSE_WHEEL_ZOOM_FACTOR: f32 = 1.1;
 
+/// Initial settings of a cloud layer enabled in the camera view.
+const DEFAULT_CLOUD_ALTITUDE: f64 = 2000.0; // m
+const DEFAULT_CLOUD_DRIFT: f64 = 10.0; // m/s, towards east
+
+/// Initial fixed sensor resolution (when enabled in the camera view).
+const DEFAULT_SENSOR_RESOLUTION: [u32; 2] = [1936, 1216];
+
+const MAX_SENSOR_RESOLUTION: u32 = 8192;
+
+/// How far ahead the target's path is predicted.
+const TARGET_PATH_PREDICTION: std::time::Duration = std::time::Duration::from_secs(20);
+
+const TARGET_PATH_STEP: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Interval (seconds) between marks on the predicted target path.
+const TARGET_PATH_MARK_INTERVAL: u64 = 1;
+
+/// Range of font sizes selectable in the Settings window.
+const MIN_FONT_SIZE: f32 = 8.0;
+const MAX_FONT_SIZE: f32 = 48.0;
+
+/// Range (seconds) of wind turbulence correlation time and gust duration selectable in the Wind window.
+const MIN_WIND_TIME: f32 = 0.001;
+const MAX_WIND_TIME: f32 = 3600.0;
+
 #[derive(Default)]
 pub struct GuiState {
     hidpi_factor: f64,
     // pub mouse_drag_origin: [f32; 2],
     // pub message_box: Option<MessageBox>,
     pub font_size: f32,
-    pub provisional_font_size: Option<f32>
+    pub provisional_font_size: Option<f32>,
+    pub quality_governor: quality_governor::QualityGovernor,
+    pub render_settings: render_settings::RenderSettings,
+    pub operator_assist: operator_assist::OperatorAssist,
+    pub reticle: reticle::Reticle,
+    /// If set, camera views are rendered at this resolution (regardless of window size and adaptive quality)
+    /// and scaled for display.
+    pub sensor_resolution: Option<[u32; 2]>,
+    /// If set, camera images of the first station are sent to the external display.
+    pub external_feed: Option<runner::ExternalFeed>,
+    /// Number of read back frames when the last one was sent to the external display.
+    external_feed_frames: u64,
+    /// If set, camera images of the first station are sent to the video stream.
+    pub video_stream: Option<crossbeam::channel::Sender<workers::VideoFrame>>,
+    /// Number of read back frames when the last one was sent to the video stream.
+    video_stream_frames: u64,
+    /// If set, the target's recent and predicted path is shown in camera views.
+    pub target_path: bool,
+    /// If set, the target's bounding box is shown in camera views.
+    pub bounding_box: bool,
+    /// If set, the pointing error (and the direction to an out-of-view target) is shown in camera views.
+    pub error_hud: bool,
+    camera_presets: camera_presets::PresetSelection,
+    pub zoom_inset: zoom_inset::ZoomInset,
+    /// Angular measurement made in a camera view.
+    measurement: Option<measurement::Measurement>,
+    /// Ongoing middle-button drag commanding a slew.
+    drag_pan: Option<drag_pan::DragPan>,
+    /// Settings edited in the Settings window.
+    pub settings: config::Settings,
+    /// Settings file given on the command line (default: the configuration directory's).
+    pub settings_path: Option<PathBuf>,
+    /// Captured log messages.
+    pub log_buffer: LogBuffer,
+    log_console: log_console::LogConsole,
+    pub status_bar: status_bar::StatusBar,
+    /// Captured messages of the mount and target feed links.
+    pub traffic_monitor: workers::TrafficMonitor,
+    traffic_inspector: traffic_inspector::TrafficInspector,
+    /// Set if a recorded session is replayed; controlled in the session playback window.
+    pub session_replay: Option<workers::SessionReplay>,
+    pub scenario_editor: scenario_editor::ScenarioEditor,
+    gamepad: Gamepad,
+    notifications: notifications::Notifications,
+    map_view: map_view::MapView,
+    /// Whether the theme from the settings has been applied to the imgui style.
+    theme_applied: bool
 }
 
 impl GuiState {
-    pub fn new(hidpi_factor: f64, font_size: f32) -> GuiState {
+    pub fn new(hidpi_factor: f64, settings: config::Settings) -> GuiState {
+        let mut quality_governor = quality_governor::QualityGovernor::default();
+        quality_governor.set_enabled(settings.render.adaptive_quality);
         GuiState{
             hidpi_factor,
-            font_size,
+            font_size: settings.font_size,
+            quality_governor,
+            render_settings: render_settings::RenderSettings::from_config(&settings.render),
+            settings,
+            error_hud: true,
             ..Default::default()
         }
     }
@@ -50,46 +179,1870 @@ pub fn handle_gui(
     program_data: &mut data::ProgramData,
     ui: &imgui::Ui,
     renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
-    display: &glium::Display<WindowSurface>
+    display: &runner::GlContext
 ) -> Option<runner::FontSizeRequest> {
-    unsafe { imgui::sys::igDockSpaceOverViewport(
+    if !program_data.gui_state.theme_applied {
+        theme::apply(&program_data.gui_state.settings.theme);
+        program_data.gui_state.theme_applied = true;
+    }
+    program_data.gui_state.status_bar.update();
+    let feed_clients = program_data.gui_state.status_bar.feed_clients();
+    program_data.gui_state.notifications.check_feeds(&feed_clients);
+    let mut reset_layout = false;
+    if let Some(_menu_bar) = ui.begin_main_menu_bar() {
+        if let Some(_menu) = ui.begin_menu("View") {
+            reset_layout = ui.menu_item("Reset layout");
+        }
+        let sim_time = program_data.stations[0].camera_view.borrow().sky_model().map(|sky| sky.time());
+        program_data.gui_state.status_bar.draw(ui, sim_time);
+    }
+
+    let dockspace_id = unsafe { imgui::sys::igDockSpaceOverViewport(
         imgui::sys::igGetMainViewport(),
         imgui::sys::ImGuiDockNodeFlags_PassthruCentralNode as i32,
         std::ptr::null()
-    ); }
+    ) };
+    if reset_layout {
+        // all windows become floating (with their last floating sizes); the new arrangement is saved as usual
+        unsafe {
+            imgui::sys::igDockBuilderRemoveNodeDockedWindows(dockspace_id, true);
+            imgui::sys::igDockBuilderRemoveNodeChildNodes(dockspace_id);
+        }
+        log::info!("window layout reset");
+    }
+
+    program_data.gui_state.quality_governor.update();
 
-    handle_camera_view(
-        &mut program_data.camera_view.borrow_mut(),
-        ui,
-        &mut program_data.gui_state,
-        &program_data.mount.get()
+    if ui.io().key_ctrl && ui.is_key_pressed_no_repeat(imgui::Key::T) {
+        for station in &program_data.stations {
+            snap_to_target(&mut station.tracking_controller.borrow_mut());
+        }
+    }
+    program_data.challenge.update(&program_data.stations[0]);
+    handle_challenge(
+        "Challenge",
+        &mut program_data.challenge,
+        &program_data.stations[0],
+        &program_data.gui_state.settings.units,
+        ui
     );
+    if let Some(comparison) = &program_data.comparison {
+        handle_mount_comparison(
+            "Mount comparison",
+            &mut comparison.borrow_mut(),
+            &program_data.gui_state.settings.units,
+            ui
+        );
+    }
+
+    handle_render_settings("Render settings", &mut program_data.gui_state, ui);
+    let font_size_request = handle_settings("Settings", &mut program_data.gui_state, ui);
+    handle_log_console("Log", &mut program_data.gui_state, ui);
+    handle_traffic_inspector("Traffic inspector", &mut program_data.gui_state, ui);
+    if let Some(replay) = &program_data.gui_state.session_replay {
+        handle_session_playback("Session playback", replay, ui);
+    }
+    handle_scenario_editor(
+        "Scenario editor",
+        &mut program_data.gui_state.scenario_editor,
+        &program_data.gui_state.settings.units,
+        ui
+    );
+    {
+        let gamepad = &mut program_data.gui_state.gamepad;
+        if let Some(station) = program_data.stations.get(gamepad.station_idx) {
+            gamepad.update(&mut station.mount_control.borrow_mut());
+        }
+        handle_gamepad("Gamepad", gamepad, &program_data.stations, ui);
+    }
+    handle_map("Map", &mut program_data.gui_state, &program_data.stations, renderer, display, ui);
+
+    let num_stations = program_data.stations.len();
+    for (station_idx, station) in program_data.stations.iter().enumerate() {
+        // window titles are distinguished only if there is more than one station
+        let title = |title: &str| if num_stations > 1 {
+            format!("{} ({})", title, station.name)
+        } else {
+            title.to_string()
+        };
+
+        let mount_state = station.mount.get();
+        program_data.gui_state.notifications.check_station(
+            if num_stations > 1 { &station.name } else { "" },
+            &station.mount,
+            &mount_state,
+            station.target_interpolator.borrow().data_age()
+        );
+        let assist = &program_data.gui_state.operator_assist;
+        let guidance = if assist.enabled {
+            station.tracking_controller.borrow().last_target()
+                .and_then(|target| operator_assist::Guidance::new(target, &mount_state))
+                .filter(|guidance| guidance.total_offset() > assist.threshold)
+        } else {
+            None
+        };
+        // the readout and the edge arrow both refer to the interpolated target
+        let pointing_error = if program_data.gui_state.error_hud {
+            station.target_interpolator.borrow().current().and_then(|target| {
+                operator_assist::Guidance::new(&target, &mount_state).map(|error| (error, target.position.0))
+            })
+        } else {
+            None
+        };
+        // the mirror mount's boresight is shown in the primary mount's camera view
+        let compared = match &program_data.comparison {
+            Some(comparison) if station_idx == 0 => {
+                let comparison = comparison.borrow();
+                Some((comparison.mounts[1].name.clone(), comparison.mounts[1].mount.get()))
+            },
+            _ => None
+        };
+        let target_path = if program_data.gui_state.target_path {
+            let interpolator = station.target_interpolator.borrow();
+            Some((
+                interpolator.history().cloned().collect(),
+                interpolator.predicted_path(TARGET_PATH_PREDICTION, TARGET_PATH_STEP)
+            ))
+        } else {
+            None
+        };
+
+        let pan = handle_camera_view(
+            &title("Camera view"),
+            &mut station.camera_view.borrow_mut(),
+            ui,
+            &mut program_data.gui_state,
+            &mount_state,
+            station.keyhole_monitor.borrow().status(),
+            CameraOverlays{ guidance, pointing_error, compared, target_path }
+        );
+        if let Some(deflection) = pan { station.mount_control.borrow_mut().slew_proportional(deflection); }
+        station.frame_capture.borrow_mut().update();
+        station.mount_control.borrow_mut().update();
+        station.telemetry.borrow_mut().update();
+        station.sky_chart.borrow_mut().update(&mount_state);
+
+        handle_pointing_model(&title("Pointing model"), &station.mount, ui);
+        handle_mount_mode(&title("Mount mode"), &station.mount, ui);
+        handle_mount_control(
+            &title("Mount control"),
+            &mut station.mount_control.borrow_mut(),
+            &mount_state,
+            &program_data.gui_state.settings.units,
+            ui
+        );
+        handle_wind(&title("Wind"), &station.mount, ui);
+        handle_focuser(&title("Focuser"), &station.mount, ui);
+        handle_filter_wheel(&title("Filter wheel"), &station.mount, ui);
+        let display_units = &program_data.gui_state.settings.units;
+        handle_tracking_controller(
+            &title("Tracking controller"),
+            &mut station.tracking_controller.borrow_mut(),
+            display_units,
+            ui
+        );
+        handle_state_snapshot(&title("State snapshot"), station, display_units, ui);
+        handle_scoring(&title("Scoring"), &mut station.scoring.borrow_mut(), display_units, ui);
+        handle_telemetry(
+            &title("Telemetry"),
+            &mut station.telemetry.borrow_mut(),
+            &program_data.gui_state.settings.units,
+            ui
+        );
+        handle_sky_chart(&title("Sky chart"), station, &mount_state, ui);
+        handle_command_console(&title("Command console"), &mut station.command_console.borrow_mut(), ui);
+        handle_frame_capture(&title("Frame capture"), &mut station.frame_capture.borrow_mut(), ui);
+    }
+
+    if let Some(feed) = &program_data.gui_state.external_feed {
+        update_external_feed(
+            feed,
+            &mut program_data.stations[0].camera_view.borrow_mut(),
+            &mut program_data.gui_state.external_feed_frames
+        );
+    }
+
+    if let Some(sender) = &program_data.gui_state.video_stream {
+        update_video_stream(
+            sender,
+            &mut program_data.stations[0].camera_view.borrow_mut(),
+            &mut program_data.gui_state.video_stream_frames
+        );
+    }
+
+    program_data.gui_state.notifications.draw(ui);
+
+    font_size_request
+}
+
+/// Sends the most recently read back camera image to the external display.
+fn update_external_feed(feed: &runner::ExternalFeed, camera_view: &mut CameraView, frames_sent: &mut u64) {
+    // the external display is fed via readback
+    if camera_view.readback().is_none() { camera_view.set_readback_enabled(true); }
+
+    if let Some(readback) = &*camera_view.readback() {
+        if readback.num_frames() == *frames_sent { return; }
+        if let Some(image) = readback.latest() {
+            *feed.borrow_mut() = Some(runner::ExternalFrame{
+                width: image.width,
+                height: image.height,
+                data: image.data.to_vec()
+            });
+            *frames_sent = readback.num_frames();
+        }
+    }
+}
+
+/// Sends the most recently read back camera image to the video stream worker.
+fn update_video_stream(
+    sender: &crossbeam::channel::Sender<workers::VideoFrame>,
+    camera_view: &mut CameraView,
+    frames_sent: &mut u64
+) {
+    if camera_view.readback().is_none() { camera_view.set_readback_enabled(true); }
+
+    if let Some(readback) = &*camera_view.readback() {
+        if readback.num_frames() == *frames_sent { return; }
+        if let Some(image) = readback.latest() {
+            // if the worker is still busy with the previous frame, this one is dropped
+            let _ = sender.try_send(workers::VideoFrame{
+                width: image.width,
+                height: image.height,
+                data: image.data.to_vec(),
+                color_mode: camera_view.color_mode()
+            });
+            *frames_sent = readback.num_frames();
+        }
+    }
+}
+
+fn handle_state_snapshot(title: &str, station: &data::Station, display_units: &DisplayUnits, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([420.0, 260.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let snapshot = state_snapshot::StateSnapshot::new(station);
+            if ui.button("copy as text") {
+                ui.set_clipboard_text(snapshot.to_text(display_units));
+            }
+            ui.same_line();
+            if ui.button("copy as JSON") {
+                ui.set_clipboard_text(snapshot.to_json());
+            }
+            ui.separator();
+            ui.text_wrapped(snapshot.to_text(display_units));
+        });
+}
+
+fn handle_challenge(
+    title: &str,
+    challenge: &mut Challenge,
+    station: &data::Station,
+    display_units: &DisplayUnits,
+    ui: &imgui::Ui
+) {
+    ui.window(title)
+        .size([360.0, 280.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            match challenge.current_round() {
+                Some((round, remaining)) => {
+                    ui.text(format!(
+                        "round {}/{}, {:.0} s left", round + 1, challenge::MAX_ROUNDS, remaining.as_secs_f64()
+                    ));
+                    if ui.button("abort") { challenge.abort(station); }
+                },
+                None => {
+                    ui.text_wrapped(format!(
+                        "{} rounds of {:.0} s with increasing target speed, maneuvering and wind; \
+                        a round scored below {:.0} ends the challenge.",
+                        challenge::MAX_ROUNDS, challenge::ROUND_DURATION.as_secs_f64(), challenge::MIN_PASSING_SCORE
+                    ));
+                    if ui.button("start") { challenge.start(station); }
+                }
+            }
+
+            for result in challenge.results() {
+                ui.text(format!(
+                    "round {}: {}, score {:.0} ({})",
+                    result.round,
+                    display_units.speed(
+                        f64::Velocity::new::<velocity::meter_per_second>(result.settings.target_speed_m_per_s),
+                        0
+                    ),
+                    result.summary.score,
+                    result.summary.grade
+                ));
+            }
+        });
+}
+
+fn handle_scoring(title: &str, scoring: &mut Scoring, display_units: &DisplayUnits, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([340.0, 260.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            if scoring.is_running() {
+                if ui.button("stop") { scoring.stop(); }
+            } else {
+                if ui.button("start") { scoring.start(); }
+                ui.same_line();
+                if ui.button("export") {
+                    match scoring.export() {
+                        Ok(path) => log::info!("scoring results saved to {}", path.display()),
+                        Err(e) => log::error!("failed to export scoring results: {}", e)
+                    }
+                }
+            }
+
+            if let Some(summary) = scoring.summary() {
+                ui.text(format!("duration: {:.0} s", summary.duration_s));
+                for (radius, fraction) in summary.ring_radii_deg.iter().zip(summary.time_on_target) {
+                    ui.text(format!(
+                        "within {}: {:.1}%",
+                        display_units.angle(units::deg(*radius), AngleScale::Normal, 2),
+                        fraction * 100.0
+                    ));
+                }
+                ui.text(format!(
+                    "maneuvers: {}, mean reaction time: {}",
+                    summary.num_maneuvers,
+                    summary.mean_reaction_time_s.map_or("-".to_string(), |t| format!("{:.1} s", t))
+                ));
+                ui.text(format!("score: {:.0} (grade {})", summary.score, summary.grade));
+            }
+        });
+}
+
+fn handle_telemetry(title: &str, telemetry: &mut Telemetry, display_units: &DisplayUnits, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([420.0, 420.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            ui.checkbox("pause", &mut telemetry.paused);
+            ui.same_line();
+            if ui.button("clear") { telemetry.clear(); }
+            ui.same_line();
+            if ui.button("export") {
+                match telemetry.export() {
+                    Ok(path) => log::info!("telemetry saved to {}", path.display()),
+                    Err(e) => log::error!("failed to export telemetry: {}", e)
+                }
+            }
+            ui.combo_simple_string(
+                "time span",
+                &mut telemetry.time_span_idx,
+                &telemetry::TIME_SPANS_S.map(|span| format!("{} s", span))
+            );
+
+            let samples: Vec<&telemetry::Sample> = telemetry.visible_samples().collect();
+            let plot_width = ui.content_region_avail()[0];
+            let plot = |label: &str, value: &dyn Fn(&telemetry::Sample) -> Option<f64>| {
+                // missing values (pointing error without a target) are plotted as zero
+                let values: Vec<f32> = samples.iter().map(|s| value(s).unwrap_or(0.0) as f32).collect();
+                let last = samples.last().and_then(|s| value(s)).map_or("-".to_string(), |v| format!("{:.3}", v));
+                ui.plot_lines(format!("##{}", label), &values)
+                    .overlay_text(format!("{}: {}", label, last))
+                    .graph_size([plot_width, 60.0])
+                    .build();
+            };
+            let angle_unit = display_units.angle;
+            let position = |deg: f64| angle_unit.value(units::deg(deg), AngleScale::Normal);
+            let speed = |deg_per_s: f64| angle_unit.rate_value(
+                f64::AngularVelocity::new::<angular_velocity::degree_per_second>(deg_per_s)
+            );
+            let error = |arcsec: f64| angle_unit.value(f64::Angle::new::<angle::second>(arcsec), AngleScale::Fine);
+            let position_symbol = angle_unit.symbol(AngleScale::Normal).trim();
+            let speed_symbol = angle_unit.rate_symbol().trim();
+            plot(&format!("axis 1 position ({})", position_symbol), &|s| Some(position(s.axis_pos_deg[0])));
+            plot(&format!("axis 2 position ({})", position_symbol), &|s| Some(position(s.axis_pos_deg[1])));
+            plot(&format!("axis 1 speed ({})", speed_symbol), &|s| Some(speed(s.axis_spd_deg_per_s[0])));
+            plot(&format!("axis 2 speed ({})", speed_symbol), &|s| Some(speed(s.axis_spd_deg_per_s[1])));
+            plot(
+                &format!("pointing error ({})", angle_unit.symbol(AngleScale::Fine).trim()),
+                &|s| s.error_arcsec.map(error)
+            );
+        });
+}
+
+fn handle_map(
+    title: &str,
+    gui_state: &mut GuiState,
+    stations: &[data::Station],
+    renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
+    display: &runner::GlContext,
+    ui: &imgui::Ui
+) {
+    ui.window(title)
+        .size([480.0, 480.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let map_view = &mut gui_state.map_view;
+            let length_unit = gui_state.settings.units.length;
+            if ui.button("center") { map_view.reset_center(); }
+            ui.same_line();
+            ui.checkbox("tracks", &mut map_view.show_tracks);
+            ui.same_line();
+            ui.set_next_item_width(80.0);
+            let mut spacing = length_unit.value(map_view.range_ring_spacing) as f32;
+            if ui.input_float(format!("range rings ({})", length_unit.symbol()), &mut spacing).build() {
+                map_view.range_ring_spacing = length_unit.quantity(spacing as f64)
+                    .max(f64::Length::new::<length::meter>(1.0));
+            }
+            ui.same_line();
+            ui.text_disabled(format!(
+                "{}/px", gui_state.settings.units.length(f64::Length::new::<length::meter>(map_view.scale()), 1)
+            ));
+
+            map_view.draw(ui, stations, &gui_state.settings.units, &gui_state.settings.map_tiles, renderer, display);
+        });
+}
+
+fn handle_sky_chart(title: &str, station: &data::Station, mount_state: &MountState, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([320.0, 340.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let history: Vec<cgmath::Point3<f64>> = station.target_interpolator.borrow().history().cloned().collect();
+            station.sky_chart.borrow().draw(
+                ui,
+                &station.mount,
+                mount_state,
+                station.tracking_controller.borrow().last_target(),
+                &history
+            );
+        });
+}
+
+fn handle_mount_comparison(
+    title: &str,
+    comparison: &mut MountComparison,
+    display_units: &DisplayUnits,
+    ui: &imgui::Ui
+) {
+    /// Plot colors of the primary and mirror mount.
+    const COLORS: [[f32; 4]; 2] = [[1.0, 0.8, 0.2, 1.0], [0.2, 0.9, 1.0, 1.0]];
+
+    ui.window(title)
+        .size([420.0, 260.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let angle_unit = display_units.angle;
+            // errors are recorded in arcseconds
+            let error_value = |error: f64| angle_unit.value(f64::Angle::new::<angle::second>(error), AngleScale::Fine);
+            for (compared, color) in comparison.mounts.iter().zip(COLORS) {
+                let config = compared.mount.config();
+                let rate = |deg_per_s: f64| angle_unit.rate_value(
+                    f64::AngularVelocity::new::<angular_velocity::degree_per_second>(deg_per_s)
+                );
+                ui.text_colored(color, format!(
+                    "{}: accel. {:.1}/{:.1}{}², max rate {:.1}/{:.1}{}; RMS error {}",
+                    compared.name,
+                    rate(config.axis1.acceleration_deg_per_s2),
+                    rate(config.axis2.acceleration_deg_per_s2),
+                    angle_unit.rate_symbol(),
+                    rate(config.axis1.max_rate_deg_per_s),
+                    rate(config.axis2.max_rate_deg_per_s),
+                    angle_unit.rate_symbol(),
+                    compared.rms_error().map_or("-".to_string(), |e| display_units.angle(
+                        f64::Angle::new::<angle::second>(e),
+                        AngleScale::Fine,
+                        1
+                    ))
+                ));
+            }
+            if ui.button("clear") { comparison.clear(); }
+
+            ui.text(format!("error ({})", display_units.angle_label(AngleScale::Fine)));
+            let size = [ui.content_region_avail()[0], ui.content_region_avail()[1].max(50.0)];
+            let origin = ui.cursor_screen_pos();
+            ui.invisible_button("##comparison_plot", size);
+
+            let max_error = comparison.mounts.iter()
+                .flat_map(|compared| compared.errors.iter().map(|error| error_value(*error as f64) as f32))
+                .fold(1.0f32, f32::max);
+            let max_len = comparison.mounts.iter().map(|compared| compared.errors.len()).max().unwrap_or(0);
+            if max_len < 2 { return; }
+
+            let draw_list = ui.get_window_draw_list();
+            draw_list.add_rect(origin, [origin[0] + size[0], origin[1] + size[1]], [0.5, 0.5, 0.5, 1.0]).build();
+            for (compared, color) in comparison.mounts.iter().zip(COLORS) {
+                // both series are aligned to the right (most recent values)
+                let offset = max_len - compared.errors.len();
+                let points: Vec<[f32; 2]> = compared.errors.iter().enumerate().map(|(i, error)| [
+                    origin[0] + (offset + i) as f32 / (max_len - 1) as f32 * size[0],
+                    origin[1] + (1.0 - error_value(*error as f64) as f32 / max_error) * size[1]
+                ]).collect();
+                draw_list.add_polyline(points, color).build();
+            }
+            draw_list.add_text([origin[0] + 2.0, origin[1] + 2.0], [0.8, 0.8, 0.8, 1.0], format!("{:.1}", max_error));
+        });
+}
+
+fn handle_render_settings(title: &str, gui_state: &mut GuiState, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([300.0, 140.0], imgui::Condition::FirstUseEver)
+        .build(|| render_settings_controls(gui_state, ui));
+}
+
+fn render_settings_controls(gui_state: &mut GuiState, ui: &imgui::Ui) {
+    let settings = &mut gui_state.render_settings;
+
+    let mut msaa_idx = render_settings::MSAA_SAMPLE_COUNTS.iter()
+        .position(|n| *n == settings.msaa_samples)
+        .unwrap_or(0);
+    let msaa_labels = render_settings::MSAA_SAMPLE_COUNTS
+        .map(|n| if n == 1 { "off".to_string() } else { format!("{}x", n) });
+    if ui.combo_simple_string("MSAA", &mut msaa_idx, &msaa_labels) {
+        settings.msaa_samples = render_settings::MSAA_SAMPLE_COUNTS[msaa_idx];
+    }
+
+    let mut ss_idx = render_settings::SUPERSAMPLING_FACTORS.iter()
+        .position(|f| *f == settings.supersampling)
+        .unwrap_or(0);
+    let ss_labels = render_settings::SUPERSAMPLING_FACTORS
+        .map(|f| if f == 1.0 { "off".to_string() } else { format!("{}x", f) });
+    if ui.combo_simple_string("supersampling", &mut ss_idx, &ss_labels) {
+        settings.supersampling = render_settings::SUPERSAMPLING_FACTORS[ss_idx];
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text("Render at a higher resolution than displayed (not used with fixed sensor resolution)");
+    }
+
+    let mut adaptive_quality = gui_state.quality_governor.enabled();
+    if ui.checkbox("adaptive quality", &mut adaptive_quality) {
+        gui_state.quality_governor.set_enabled(adaptive_quality);
+    }
+    if gui_state.quality_governor.enabled() {
+        ui.text(format!("quality level: {}", gui_state.quality_governor.level_index()));
+    }
+}
+
+/// Returns a font size request if a new font size has been applied.
+fn handle_settings(title: &str, gui_state: &mut GuiState, ui: &imgui::Ui) -> Option<runner::FontSizeRequest> {
+    let mut font_size_request = None;
+    ui.window(title)
+        .size([360.0, 420.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let font_size = gui_state.provisional_font_size.get_or_insert(gui_state.font_size);
+            ui.input_float("font size", font_size).step(1.0).build();
+            *font_size = font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+            ui.same_line();
+            if ui.button("apply") {
+                gui_state.font_size = *font_size;
+                gui_state.settings.font_size = *font_size;
+                font_size_request = Some(runner::FontSizeRequest(*font_size));
+            }
+
+            if ui.collapsing_header("Rendering", imgui::TreeNodeFlags::empty()) {
+                render_settings_controls(gui_state, ui);
+            }
+
+            if ui.collapsing_header("Instrument FOVs", imgui::TreeNodeFlags::empty()) {
+                instrument_fov_controls(&mut gui_state.settings.instruments, ui);
+            }
+
+            if ui.collapsing_header("Units", imgui::TreeNodeFlags::empty()) {
+                unit_controls(&mut gui_state.settings.units, ui);
+            }
+
+            if ui.collapsing_header("Map", imgui::TreeNodeFlags::empty()) {
+                ui.input_text("tiles", &mut gui_state.settings.map_tiles).hint("/path/{z}/{x}/{y}.png").build();
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Path of map tile images (PNG, XYZ scheme); leave empty for no imagery");
+                }
+            }
+
+            if ui.collapsing_header("Theme", imgui::TreeNodeFlags::empty())
+                && theme::controls(&mut gui_state.settings.theme, ui) {
+                theme::apply(&gui_state.settings.theme);
+            }
+
+            let settings = &mut gui_state.settings;
+            ui.text_disabled("The following take effect after restart.");
+
+            if ui.collapsing_header("Ports", imgui::TreeNodeFlags::empty()) {
+                for (label, port) in [("mount", &mut settings.mount_port), ("target feed", &mut settings.target_port)] {
+                    let mut value = *port as i32;
+                    if ui.input_int(label, &mut value).build() {
+                        *port = value.clamp(1, u16::MAX as i32) as u16;
+                    }
+                }
+            }
+
+            if ui.collapsing_header("Observer location", imgui::TreeNodeFlags::empty()) {
+                let location = &mut settings.location;
+                for (label, value, range) in [
+                    ("latitude (°)", &mut location.lat_deg, -90.0..=90.0),
+                    ("longitude (°)", &mut location.lon_deg, -180.0..=180.0),
+                    ("elevation (m)", &mut location.elevation_m, -500.0..=10000.0)
+                ] {
+                    let mut value_f32 = *value as f32;
+                    if ui.input_float(label, &mut value_f32).build() {
+                        *value = (value_f32 as f64).clamp(*range.start(), *range.end());
+                    }
+                }
+            }
+
+            if ui.collapsing_header("Mount", imgui::TreeNodeFlags::empty()) {
+                ui.text_disabled("(not used if a profile is selected with --mount-profile)");
+                for (name, axis) in [("axis 1", &mut settings.mount.axis1), ("axis 2", &mut settings.mount.axis2)] {
+                    for (label, value) in [
+                        ("acceleration (°/s²)", &mut axis.acceleration_deg_per_s2),
+                        ("max. rate (°/s)", &mut axis.max_rate_deg_per_s),
+                        ("backlash (\")", &mut axis.backlash_arcsec),
+                        ("periodic error (\")", &mut axis.periodic_error_arcsec)
+                    ] {
+                        let mut value_f32 = *value as f32;
+                        if ui.input_float(format!("{} {}", name, label), &mut value_f32).build() {
+                            *value = value_f32.max(0.0) as f64;
+                        }
+                    }
+                }
+                let mut guide_rate = settings.mount.guide_rate_sidereal as f32;
+                if ui.input_float("guide rate (× sidereal)", &mut guide_rate).build() {
+                    settings.mount.guide_rate_sidereal = guide_rate.max(0.0) as f64;
+                }
+            }
+
+            ui.separator();
+            if ui.button("save") { save_settings(gui_state); }
+        });
+
+    font_size_request
+}
+
+/// Saves the settings (including the current render options).
+fn save_settings(gui_state: &mut GuiState) {
+    let settings = &mut gui_state.settings;
+    settings.render = config::RenderConfig{
+        msaa_samples: gui_state.render_settings.msaa_samples,
+        supersampling: gui_state.render_settings.supersampling,
+        adaptive_quality: gui_state.quality_governor.enabled()
+    };
+    match config::save_settings(settings, gui_state.settings_path.as_deref()) {
+        Ok(path) => log::info!("settings saved to {}", path.display()),
+        Err(e) => log::error!("failed to save settings: {}", e)
+    }
+}
+
+fn camera_preset_controls(gui_state: &mut GuiState, camera_view: &mut CameraView, ui: &imgui::Ui) {
+    let names: Vec<String> = gui_state.settings.camera_presets.iter().map(|preset| preset.name.clone()).collect();
+    if !names.is_empty() {
+        let selection = &mut gui_state.camera_presets;
+        selection.selected = selection.selected.min(names.len() - 1);
+        if ui.combo_simple_string("preset", &mut selection.selected, &names) {
+            let preset = gui_state.settings.camera_presets[gui_state.camera_presets.selected].clone();
+            camera_presets::apply(&preset, gui_state, camera_view);
+        }
+        ui.same_line();
+        if ui.button("delete") {
+            gui_state.settings.camera_presets.remove(gui_state.camera_presets.selected);
+            save_settings(gui_state);
+        }
+    }
+
+    ui.set_next_item_width(140.0);
+    ui.input_text("##preset_name", &mut gui_state.camera_presets.name).build();
+    ui.same_line();
+    if ui.button("save preset") {
+        let name = gui_state.camera_presets.name.trim().to_string();
+        if name.is_empty() {
+            log::error!("camera preset name is empty");
+        } else {
+            let preset = camera_presets::capture(&name, gui_state, camera_view);
+            let presets = &mut gui_state.settings.camera_presets;
+            // a preset with the same name is replaced
+            match presets.iter().position(|p| p.name == name) {
+                Some(i) => presets[i] = preset,
+                None => presets.push(preset)
+            }
+            gui_state.camera_presets.selected = presets.iter().position(|p| p.name == name).unwrap_or(0);
+            save_settings(gui_state);
+        }
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text("Save FOV, reticle, overlays and sensor resolution under the entered name");
+    }
+}
+
+fn instrument_fov_controls(instruments: &mut Vec<config::InstrumentFov>, ui: &imgui::Ui) {
+    let mut removed = None;
+    for (i, instrument) in instruments.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(i);
+        ui.checkbox("##shown", &mut instrument.shown);
+        ui.same_line();
+        ui.set_next_item_width(120.0);
+        ui.input_text("##name", &mut instrument.name).build();
+        ui.same_line();
+        ui.set_next_item_width(70.0);
+        let mut fov = instrument.fov_deg as f32;
+        if ui.input_float("°##fov", &mut fov).build() { instrument.fov_deg = fov.max(0.001) as f64; }
+        ui.same_line();
+        ui.color_edit4_config("##color", &mut instrument.color).inputs(false).build();
+        ui.same_line();
+        if ui.small_button("remove") { removed = Some(i); }
+    }
+    if let Some(i) = removed { instruments.remove(i); }
+    if ui.button("add instrument") { instruments.push(config::InstrumentFov::default()); }
+}
+
+fn unit_controls(display_units: &mut DisplayUnits, ui: &imgui::Ui) {
+    fn unit_combo<T: Copy + PartialEq + std::fmt::Display>(label: &str, unit: &mut T, all: &[T], ui: &imgui::Ui) {
+        let labels: Vec<String> = all.iter().map(|u| u.to_string()).collect();
+        let mut idx = all.iter().position(|u| u == unit).unwrap_or(0);
+        if ui.combo_simple_string(label, &mut idx, &labels) { *unit = all[idx]; }
+    }
+
+    unit_combo("angles", &mut display_units.angle, &units::AngleUnit::ALL, ui);
+    unit_combo("distances", &mut display_units.length, &units::LengthUnit::ALL, ui);
+    unit_combo("speeds", &mut display_units.speed, &units::SpeedUnit::ALL, ui);
+}
+
+fn handle_log_console(title: &str, gui_state: &mut GuiState, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([640.0, 300.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let console = &mut gui_state.log_console;
+            ui.set_next_item_width(100.0);
+            let level_labels = log_console::LEVELS.map(|level| level.to_string());
+            ui.combo_simple_string("level", &mut console.level_idx, &level_labels);
+            ui.same_line();
+            ui.set_next_item_width(200.0);
+            ui.input_text("search", &mut console.search).build();
+            ui.same_line();
+            ui.checkbox("auto-scroll", &mut console.auto_scroll);
+            ui.same_line();
+            let copy = ui.button("copy");
+            ui.same_line();
+            if ui.button("clear") { gui_state.log_buffer.clear(); }
+
+            let entries = gui_state.log_buffer.entries();
+            let visible: Vec<&crate::log_capture::LogEntry> = entries.iter().filter(|e| console.matches(e)).collect();
+            if copy {
+                ui.set_clipboard_text(visible.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n"));
+            }
+
+            ui.child_window("##log_entries").horizontal_scrollbar(true).build(|| {
+                let mut clipper = imgui::ListClipper::new(visible.len() as i32).begin(ui);
+                while clipper.step() {
+                    for entry in &visible[clipper.display_start() as usize..clipper.display_end() as usize] {
+                        ui.text_colored(log_console::level_color(entry.level), entry.to_string());
+                    }
+                }
+                if console.auto_scroll && ui.scroll_y() >= ui.scroll_max_y() {
+                    ui.set_scroll_here_y_with_ratio(1.0);
+                }
+            });
+        });
+}
+
+fn handle_traffic_inspector(title: &str, gui_state: &mut GuiState, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([640.0, 300.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let monitor = &gui_state.traffic_monitor;
+            let inspector = &mut gui_state.traffic_inspector;
+            let mut capture = monitor.enabled();
+            if ui.checkbox("capture", &mut capture) { monitor.set_enabled(capture); }
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Record messages exchanged with mount and target feed clients");
+            }
+            ui.same_line();
+            ui.set_next_item_width(200.0);
+            ui.input_text("search", &mut inspector.search).build();
+            ui.same_line();
+            ui.checkbox("errors only", &mut inspector.errors_only);
+            ui.same_line();
+            ui.checkbox("auto-scroll", &mut inspector.auto_scroll);
+            ui.same_line();
+            let copy = ui.button("copy");
+            ui.same_line();
+            if ui.button("clear") { monitor.clear(); }
+
+            let entries = monitor.entries();
+            let visible: Vec<&workers::TrafficEntry> = entries.iter().filter(|e| inspector.matches(e)).collect();
+            if copy {
+                ui.set_clipboard_text(
+                    visible.iter().map(|e| traffic_inspector::format_entry(e)).collect::<Vec<_>>().join("\n")
+                );
+            }
+
+            ui.child_window("##traffic_entries").horizontal_scrollbar(true).build(|| {
+                let mut clipper = imgui::ListClipper::new(visible.len() as i32).begin(ui);
+                while clipper.step() {
+                    for entry in &visible[clipper.display_start() as usize..clipper.display_end() as usize] {
+                        let text = traffic_inspector::format_entry(entry);
+                        if entry.parse_error.is_some() {
+                            ui.text_colored(traffic_inspector::PARSE_ERROR_COLOR, text);
+                        } else {
+                            ui.text(text);
+                        }
+                    }
+                }
+                if inspector.auto_scroll && ui.scroll_y() >= ui.scroll_max_y() {
+                    ui.set_scroll_here_y_with_ratio(1.0);
+                }
+            });
+        });
+}
+
+fn handle_session_playback(title: &str, replay: &workers::SessionReplay, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([640.0, 300.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let session = &replay.session;
+            ui.text(format!(
+                "recorded {} ({} station(s), {:.1} s)",
+                session.header.start, session.header.num_stations, session.duration
+            ));
+
+            let mut playback = replay.playback.lock().unwrap();
+            if ui.button(if playback.playing { "pause" } else { "play" }) {
+                // playing at the end starts over
+                if !playback.playing && playback.position >= session.duration { playback.position = 0.0; }
+                playback.playing = !playback.playing;
+            }
+            ui.same_line();
+            ui.set_next_item_width(150.0);
+            ui.slider_config("speed", session_player::MIN_SPEED, session_player::MAX_SPEED)
+                .flags(imgui::SliderFlags::LOGARITHMIC)
+                .display_format("%.2fx")
+                .build(&mut playback.speed);
+            ui.set_next_item_width(-1.0);
+            ui.slider_config("##position", 0.0, session.duration)
+                .display_format("%.1f s")
+                .build(&mut playback.position);
+            let (position, playing) = (playback.position, playback.playing);
+            drop(playback);
+
+            for station in 0..session.header.num_stations {
+                if let Some(sample) = session.mount_sample(station, position) {
+                    ui.text(format!(
+                        "mount {}: az. {:.3}°, alt. {:.3}°; axis 1: {:.3}° ({:.3}°/s), axis 2: {:.3}° ({:.3}°/s)",
+                        station + 1,
+                        sample.azimuth_deg,
+                        sample.altitude_deg,
+                        sample.axis1_deg,
+                        sample.axis1_speed_deg_per_s,
+                        sample.axis2_deg,
+                        sample.axis2_speed_deg_per_s
+                    ));
+                }
+            }
+
+            let traffic = session_player::recent_traffic(session, position);
+            ui.child_window("##session_traffic").horizontal_scrollbar(true).build(|| {
+                let mut clipper = imgui::ListClipper::new(traffic.len() as i32).begin(ui);
+                while clipper.step() {
+                    for sample in &traffic[clipper.display_start() as usize..clipper.display_end() as usize] {
+                        ui.text(session_player::format_traffic(sample));
+                    }
+                }
+                if playing { ui.set_scroll_here_y_with_ratio(1.0); }
+            });
+        });
+}
+
+fn handle_gamepad(title: &str, gamepad: &mut Gamepad, stations: &[data::Station], ui: &imgui::Ui) {
+    ui.window(title)
+        .size([360.0, 220.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            if !gamepad.available() {
+                ui.text_disabled("gamepad support unavailable");
+                return;
+            }
+
+            let connected = gamepad.connected();
+            if connected.is_empty() {
+                ui.text_disabled("no gamepad connected");
+            }
+            for name in &connected { ui.text(name); }
+
+            ui.checkbox("enabled", &mut gamepad.enabled);
+            if stations.len() > 1 {
+                let names: Vec<&str> = stations.iter().map(|station| station.name.as_str()).collect();
+                let prev_idx = gamepad.station_idx;
+                if ui.combo_simple_string("station", &mut gamepad.station_idx, &names) {
+                    // end the slew of the previously controlled station
+                    stations[prev_idx].mount_control.borrow_mut().slew_proportional([0.0; 2]);
+                }
+            }
 
-    None
+            ui.separator();
+            ui.text_disabled("left stick: slew (up to the selected rate)");
+            ui.text_disabled("bumpers: change slew rate");
+            ui.text_disabled("south (A/cross): stop");
+            ui.text_disabled("east (B/circle): park/unpark");
+        });
+}
+
+fn handle_scenario_editor(
+    title: &str,
+    editor: &mut scenario_editor::ScenarioEditor,
+    display_units: &DisplayUnits,
+    ui: &imgui::Ui
+) {
+    let input_f64 = |label: &str, value: &mut f64| {
+        let mut value_f32 = *value as f32;
+        let changed = ui.input_float(label, &mut value_f32).build();
+        if changed { *value = value_f32 as f64; }
+        changed
+    };
+    let (length_unit, speed_unit) = (display_units.length, display_units.speed);
+
+    ui.window(title)
+        .size([420.0, 520.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let file_names: Vec<String> = editor.files().iter()
+                .map(|path| path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned()))
+                .collect();
+            ui.set_next_item_width(160.0);
+            ui.combo_simple_string("##files", &mut editor.file_idx, &file_names);
+            ui.same_line();
+            if ui.button("load") {
+                if let Err(e) = editor.load_selected() { log::error!("failed to load scenario: {}", e); }
+            }
+            ui.same_line();
+            if ui.button("refresh") { editor.refresh_files(); }
+
+            ui.set_next_item_width(160.0);
+            ui.input_text("##name", &mut editor.name).build();
+            ui.same_line();
+            if ui.button("save") {
+                match editor.save() {
+                    Ok(path) => log::info!("scenario saved to {}", path.display()),
+                    Err(e) => log::error!("failed to save scenario: {}", e)
+                }
+            }
+            ui.same_line();
+            if ui.button("save and launch") {
+                match editor.save().and_then(|path| scenario_editor::relaunch(&path)) {
+                    Ok(()) => (),
+                    Err(e) => log::error!("failed to launch scenario: {}", e)
+                }
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Restart the simulator with this scenario");
+            }
+            ui.separator();
+
+            let scenario = &mut editor.scenario;
+            ui.input_text_multiline("description", &mut scenario.description, [0.0, ui.text_line_height() * 3.0])
+                .build();
+
+            if ui.collapsing_header("Observer", imgui::TreeNodeFlags::DEFAULT_OPEN) {
+                input_f64("latitude (°)", &mut scenario.observer.lat_deg);
+                input_f64("longitude (°)", &mut scenario.observer.lon_deg);
+                let mut elevation = length_unit.value(f64::Length::new::<length::meter>(scenario.observer.elevation_m));
+                if input_f64(&format!("elevation ({})", length_unit.symbol()), &mut elevation) {
+                    scenario.observer.elevation_m = length_unit.quantity(elevation).get::<length::meter>();
+                }
+                scenario.observer.lat_deg = scenario.observer.lat_deg.clamp(-90.0, 90.0);
+                scenario.observer.lon_deg = scenario.observer.lon_deg.clamp(-180.0, 180.0);
+            }
+
+            if ui.collapsing_header("Simulation", imgui::TreeNodeFlags::DEFAULT_OPEN) {
+                let mut sky_model = scenario.start_time.is_some();
+                if ui.checkbox("sky model", &mut sky_model) {
+                    scenario.start_time = if sky_model { Some("now".into()) } else { None };
+                }
+                if let Some(start_time) = &mut scenario.start_time {
+                    ui.input_text("start time", start_time).build();
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text("RFC 3339 time (e.g., 2024-06-21T22:00:00Z) or \"now\"");
+                    }
+                }
+                let mut seed = scenario.seed as i32;
+                if ui.input_int("seed", &mut seed).build() { scenario.seed = seed.max(0) as u64; }
+                let mut generated = scenario.generated_targets as i32;
+                if ui.input_int("generated targets", &mut generated).build() {
+                    scenario.generated_targets = generated.clamp(0, 1000) as usize;
+                }
+            }
+
+            if ui.collapsing_header("Faults", imgui::TreeNodeFlags::empty()) {
+                let faults = &mut scenario.faults;
+                ui.checkbox("ADS-B CPR glitches", &mut faults.adsb_cpr_glitches);
+                let mut target_swap = faults.target_swap_probability.is_some();
+                if ui.checkbox("target swaps", &mut target_swap) {
+                    faults.target_swap_probability = if target_swap { Some(0.01) } else { None };
+                }
+                if let Some(probability) = &mut faults.target_swap_probability {
+                    input_f64("swap probability", probability);
+                    *probability = probability.clamp(0.0, 1.0);
+                }
+            }
+
+            if ui.collapsing_header("Targets", imgui::TreeNodeFlags::DEFAULT_OPEN) {
+                let labels: Vec<String> = editor.scenario.targets.iter()
+                    .map(|target| format!("{} ({:?})", target.id, target.kind))
+                    .collect();
+                ui.set_next_item_width(160.0);
+                ui.combo_simple_string("##targets", &mut editor.target_idx, &labels);
+                ui.same_line();
+                if ui.button("add") { editor.add_target(); }
+                ui.same_line();
+                if ui.button("remove") { editor.remove_target(); }
+                if editor.scenario.targets.len() > 1 {
+                    ui.text_disabled("(motion of the first target is adjustable during the simulation)");
+                }
+
+                if let Some(target) = editor.scenario.targets.get_mut(editor.target_idx) {
+                    let mut id = target.id as i32;
+                    if ui.input_int("ID", &mut id).build() { target.id = id.max(1) as u32; }
+                    let kind_labels = workers::TargetKind::ALL.map(|kind| format!("{:?}", kind));
+                    let mut kind_idx = workers::TargetKind::ALL.iter()
+                        .position(|kind| *kind == target.kind)
+                        .unwrap_or(0);
+                    if ui.combo_simple_string("kind", &mut kind_idx, &kind_labels) {
+                        target.kind = workers::TargetKind::ALL[kind_idx];
+                    }
+                    input_f64("latitude (°)##target", &mut target.lat_deg);
+                    input_f64("longitude (°)##target", &mut target.lon_deg);
+                    let mut elevation = length_unit.value(f64::Length::new::<length::meter>(target.elevation_m));
+                    if input_f64(&format!("elevation ({})##target", length_unit.symbol()), &mut elevation) {
+                        target.elevation_m = length_unit.quantity(elevation).get::<length::meter>();
+                    }
+                    input_f64("track (°)", &mut target.track_deg);
+                    let mut speed = speed_unit.value(
+                        f64::Velocity::new::<velocity::meter_per_second>(target.speed_m_per_s)
+                    );
+                    if input_f64(&format!("speed ({})", speed_unit.symbol()), &mut speed) {
+                        target.speed_m_per_s = speed_unit.quantity(speed).get::<velocity::meter_per_second>();
+                    }
+                    input_f64("track noise (°/√s)", &mut target.track_noise);
+                    target.speed_m_per_s = target.speed_m_per_s.max(0.0);
+                    target.track_noise = target.track_noise.max(0.0);
+                }
+            }
+        });
+}
+
+fn handle_command_console(title: &str, console: &mut CommandConsole, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([480.0, 300.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            ui.set_next_item_width(-160.0);
+            let mut send = ui.input_text("##message", &mut console.input).enter_returns_true(true).build();
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Message of the mount text protocol; sent as if by an external client");
+            }
+            ui.same_line();
+            send |= ui.button("send");
+            ui.same_line();
+            let mut template = None;
+            if let Some(_token) = ui.begin_combo("##templates", "templates") {
+                for msg in console.templates() {
+                    if ui.selectable(msg) { template = Some(msg.clone()); }
+                }
+            }
+            if let Some(template) = template { console.input = template; }
+            if send { console.send(); }
+
+            if ui.button("clear") { console.clear_history(); }
+            ui.separator();
+
+            ui.child_window("##exchanges").horizontal_scrollbar(true).build(|| {
+                for exchange in console.history() {
+                    ui.text(format!("{} > {}", exchange.time.format("%H:%M:%S%.3f"), exchange.message));
+                    match &exchange.result {
+                        Ok(Some(response)) => ui.text(format!("  < {}", response)),
+                        Ok(None) => ui.text_disabled("  (no response)"),
+                        Err(e) => ui.text_colored(traffic_inspector::PARSE_ERROR_COLOR, format!("  error: {}", e))
+                    }
+                }
+                if send { ui.set_scroll_here_y_with_ratio(1.0); }
+            });
+        });
+}
+
+fn handle_frame_capture(title: &str, frame_capture: &mut FrameCapture, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([340.0, 140.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            if ui.button("save frame") {
+                match frame_capture.save_frame() {
+                    Ok(path) => log::info!("camera frame saved to {}", path.display()),
+                    Err(e) => log::error!("failed to save camera frame: {}", e)
+                }
+            }
+
+            ui.checkbox("export bounding boxes", &mut frame_capture.export_boxes);
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Save the target's bounding box with frames (applies to sequences started afterwards)");
+            }
+
+            let mut interval_ms = frame_capture.sequence_interval.as_millis() as i32;
+            if ui.input_int("sequence interval (ms)", &mut interval_ms).step(10).build() {
+                frame_capture.sequence_interval = std::time::Duration::from_millis(interval_ms.max(1) as u64);
+            }
+
+            match frame_capture.sequence_status() {
+                Some((dir, num_frames)) => {
+                    ui.text_wrapped(format!("recording: {} frame(s) in {}", num_frames, dir.display()));
+                    if ui.button("stop recording") { frame_capture.stop_sequence(); }
+                },
+                None => if ui.button("record sequence") {
+                    match frame_capture.start_sequence() {
+                        Ok(dir) => log::info!("recording frame sequence to {}", dir.display()),
+                        Err(e) => log::error!("failed to start recording frame sequence: {}", e)
+                    }
+                }
+            }
+        });
+}
+
+fn handle_pointing_model(title: &str, mount: &Mount, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([320.0, 140.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let mut errors = mount.pointing_errors();
+            let mut changed = false;
+
+            for (label, value) in [
+                ("cone (arcmin)", &mut errors.cone),
+                ("non-perpendicularity (arcmin)", &mut errors.non_perpendicularity),
+                ("tube flexure (arcmin)", &mut errors.tube_flexure)
+            ] {
+                let mut arcmin = value.get::<angle::minute>() as f32;
+                if ui.input_float(label, &mut arcmin).step(1.0).build() {
+                    *value = f64::Angle::new::<angle::minute>(arcmin as f64);
+                    changed = true;
+                }
+            }
+
+            if changed {
+                mount.set_pointing_errors(errors);
+            }
+        });
+}
+
+fn handle_mount_control(
+    title: &str,
+    control: &mut MountControl,
+    mount_state: &MountState,
+    display_units: &DisplayUnits,
+    ui: &imgui::Ui
+) {
+    ui.window(title)
+        .size([320.0, 280.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            for (name, pos, spd) in [
+                ("axis 1", mount_state.axis1_pos, mount_state.axis1_spd),
+                ("axis 2", mount_state.axis2_pos, mount_state.axis2_spd)
+            ] {
+                ui.text(format!(
+                    "{}: {} ({})",
+                    name,
+                    display_units.angle(pos, AngleScale::Normal, 4),
+                    display_units.angular_velocity(spd, 4)
+                ));
+            }
+
+            ui.combo_simple_string(
+                "slew rate",
+                &mut control.rate_idx,
+                &mount_control::SLEW_RATES.map(|rate| display_units.angular_speed(
+                    f64::AngularVelocity::new::<angular_velocity::degree_per_second>(rate),
+                    2
+                ))
+            );
+
+            // directional slews last while a button is held down
+            const BUTTON_SIZE: [f32; 2] = [50.0, 0.0];
+            let mut held = None;
+            let mut direction_button = |label: &str, direction: mount_control::Direction| {
+                ui.button_with_size(label, BUTTON_SIZE);
+                if ui.is_item_active() { held = Some(direction); }
+            };
+            ui.dummy(BUTTON_SIZE);
+            ui.same_line();
+            direction_button("N", mount_control::Direction::North);
+            direction_button("E", mount_control::Direction::East);
+            ui.same_line();
+            let stop = ui.button_with_size("stop", BUTTON_SIZE);
+            ui.same_line();
+            direction_button("W", mount_control::Direction::West);
+            ui.dummy(BUTTON_SIZE);
+            ui.same_line();
+            direction_button("S", mount_control::Direction::South);
+            match held {
+                Some(direction) => control.start_slew(direction),
+                None => control.end_slew()
+            }
+            if stop { control.stop(); }
+
+            ui.separator();
+            // the inputs are stored in degrees (per second)
+            let angle_unit = display_units.angle;
+            let mut rates = control.rates_input.map(|rate| angle_unit.rate_value(
+                f64::AngularVelocity::new::<angular_velocity::degree_per_second>(rate as f64)
+            ) as f32);
+            if ui.input_float2(format!("axis rates ({})", angle_unit.rate_symbol().trim_start()), &mut rates).build() {
+                control.rates_input = rates.map(|rate| angle_unit.rate_quantity(rate as f64)
+                    .get::<angular_velocity::degree_per_second>() as f32);
+            }
+            if ui.button("slew") { control.slew_at_input_rates(); }
+
+            ui.separator();
+            let mut positions = control.goto_input
+                .map(|pos| angle_unit.value(units::deg(pos as f64), AngleScale::Normal) as f32);
+            if ui.input_float2(
+                format!("axis positions ({})", display_units.angle_label(AngleScale::Normal)),
+                &mut positions
+            ).build() {
+                control.goto_input = positions
+                    .map(|pos| angle_unit.quantity(pos as f64, AngleScale::Normal).get::<angle::degree>() as f32);
+            }
+            if ui.button("goto") { control.goto(); }
+            ui.same_line();
+            if ui.button("current") {
+                control.goto_input = [mount_state.axis1_pos, mount_state.axis2_pos]
+                    .map(|pos| pos.get::<angle::degree>() as f32);
+            }
+            if let Some(destination) = control.goto_destination() {
+                ui.text(format!(
+                    "slewing to {}, {}...",
+                    display_units.angle(destination[0], AngleScale::Normal, 3),
+                    display_units.angle(destination[1], AngleScale::Normal, 3)
+                ));
+            } else if let Some(direction) = control.direction() {
+                ui.text(format!("slewing {:?}...", direction).to_lowercase());
+            }
+        });
+}
+
+fn handle_focuser(title: &str, mount: &Mount, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([300.0, 150.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let (position, max_position, moving) = mount.focuser();
+            ui.text(format!("position: {} / {}{}", position, max_position, if moving { " (moving)" } else { "" }));
+            ui.text(format!("defocus: {:.1}\"", mount.get().defocus.get::<angle::second>()));
+
+            let move_to = |target: i64| {
+                if let Err(e) = mount.move_focuser(target.clamp(0, max_position as i64) as u32) {
+                    log::error!("failed to move focuser: {}", e);
+                }
+            };
+            for step in [-100, -10, 10, 100] {
+                if ui.button(format!("{:+}", step)) { move_to(position as i64 + step); }
+                ui.same_line();
+            }
+            if ui.button("halt") { mount.halt_focuser(); }
+        });
+}
+
+fn handle_filter_wheel(title: &str, mount: &Mount, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([300.0, 120.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let position = mount.filter();
+            if position.is_none() { ui.text("moving..."); }
+            for (i, name) in mount.filter_names().iter().enumerate() {
+                if i > 0 { ui.same_line(); }
+                if ui.radio_button_bool(format!("{}##{}", name, i), position == Some(i)) {
+                    if let Err(e) = mount.set_filter(i) { log::error!("failed to change filter: {}", e); }
+                }
+            }
+        });
+}
+
+fn handle_wind(title: &str, mount: &Mount, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([320.0, 200.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let mut settings = mount.wind_settings();
+            let mut changed = ui.checkbox("enabled", &mut settings.enabled);
+
+            let mut rms = settings.turbulence_rms.get::<angle::second>() as f32;
+            if ui.input_float("turbulence RMS (\")", &mut rms).build() {
+                settings.turbulence_rms = f64::Angle::new::<angle::second>(rms.max(0.0) as f64);
+                changed = true;
+            }
+
+            let mut corr_time = settings.turbulence_corr_time.as_secs_f32();
+            if ui.input_float("turbulence corr. time (s)", &mut corr_time).build() {
+                settings.turbulence_corr_time =
+                    std::time::Duration::from_secs_f32(corr_time.max(MIN_WIND_TIME).min(MAX_WIND_TIME));
+                changed = true;
+            }
+
+            let mut gusts_per_minute = settings.gusts_per_minute as f32;
+            if ui.input_float("gusts per minute", &mut gusts_per_minute).build() {
+                settings.gusts_per_minute = gusts_per_minute.max(0.0).min(MAX_GUSTS_PER_MINUTE as f32) as f64;
+                changed = true;
+            }
+
+            let mut amplitude = settings.gust_amplitude.get::<angle::second>() as f32;
+            if ui.input_float("gust amplitude (\")", &mut amplitude).build() {
+                settings.gust_amplitude = f64::Angle::new::<angle::second>(amplitude.max(0.0) as f64);
+                changed = true;
+            }
+
+            let mut duration = settings.gust_duration.as_secs_f32();
+            if ui.input_float("gust duration (s)", &mut duration).build() {
+                settings.gust_duration =
+                    std::time::Duration::from_secs_f32(duration.max(MIN_WIND_TIME).min(MAX_WIND_TIME));
+                changed = true;
+            }
+
+            if changed {
+                mount.set_wind_settings(settings);
+            }
+        });
+}
+
+fn snap_to_target(controller: &mut TrackingController) {
+    if let Err(e) = controller.snap_to_target() {
+        log::error!("failed to snap to target: {}", e);
+    }
+}
+
+fn handle_tracking_controller(
+    title: &str,
+    controller: &mut TrackingController,
+    display_units: &DisplayUnits,
+    ui: &imgui::Ui
+) {
+    ui.window(title)
+        .size([400.0, 420.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let mut enabled = controller.enabled();
+            if ui.checkbox("enabled", &mut enabled) {
+                controller.set_enabled(enabled);
+            }
+
+            let mut settings = controller.settings().clone();
+            let mut changed = false;
+
+            let mut kind_idx = ControllerKind::ALL.iter().position(|k| *k == settings.kind).unwrap();
+            if ui.combo_simple_string("controller", &mut kind_idx, &ControllerKind::ALL.map(|k| k.to_string())) {
+                settings.kind = ControllerKind::ALL[kind_idx];
+                changed = true;
+            }
+
+            let mut kp = settings.kp as f32;
+            if ui.input_float("kp (1/s)", &mut kp).step(0.1).build() {
+                settings.kp = kp.max(0.0) as f64;
+                changed = true;
+            }
+
+            if settings.kind == ControllerKind::PI {
+                let mut ki = settings.ki as f32;
+                if ui.input_float("ki (1/s²)", &mut ki).step(0.01).build() {
+                    settings.ki = ki.max(0.0) as f64;
+                    changed = true;
+                }
+            }
+
+            changed |= ui.checkbox("velocity feed-forward", &mut settings.feed_forward);
+
+            if changed {
+                controller.set_settings(settings);
+            }
+
+            if ui.button("snap to target") {
+                snap_to_target(controller);
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Points the mount at the target and matches its rate (Ctrl+T: all mounts)");
+            }
+
+            if ui.button("autotune") {
+                controller.autotune();
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text(
+                    "Selects gains giving the lowest RMS error in simulated runs against the current target"
+                );
+            }
+            match controller.autotune_result() {
+                Some(Ok(result)) => {
+                    ui.same_line();
+                    ui.text(&format!(
+                        "{}: RMS {}{}",
+                        result.settings,
+                        display_units.angle(result.rms_error, AngleScale::Fine, 1),
+                        if result.trackable { "" } else { " (not trackable)" }
+                    ));
+                },
+                Some(Err(e)) => { ui.same_line(); ui.text(&format!("autotune failed: {}", e)); },
+                None => ()
+            }
+
+            match controller.error() {
+                Some((az_error, alt_error)) => ui.text(&format!(
+                    "error: az. {}, alt. {}",
+                    display_units.signed_angle(az_error, AngleScale::Fine, 1),
+                    display_units.signed_angle(alt_error, AngleScale::Fine, 1)
+                )),
+                None => ui.text("error: -")
+            }
+
+            ui.separator();
+            ui.text(format!("total error ({})", display_units.angle_label(AngleScale::Fine)));
+            let plot_width = ui.content_region_avail()[0];
+            for (i, history) in controller.error_histories().enumerate() {
+                // errors are recorded in arcseconds
+                let values: Vec<f32> = history.values.iter()
+                    .map(|error| f64::Angle::new::<angle::second>(*error as f64))
+                    .map(|error| display_units.angle.value(error, AngleScale::Fine) as f32)
+                    .collect();
+                let last = values.last().copied().unwrap_or(0.0);
+                ui.plot_lines(format!("##error{}", i), &values)
+                    .overlay_text(format!("{}: {:.1}", history.settings, last))
+                    .scale_min(0.0)
+                    .graph_size([plot_width, 50.0])
+                    .build();
+            }
+        });
+}
+
+fn handle_mount_mode(title: &str, mount: &Mount, ui: &imgui::Ui) {
+    ui.window(title)
+        .size([320.0, 240.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let mode = mount.mode();
+
+            if ui.radio_button_bool("alt-az", matches!(mode, MountMode::AltAz)) {
+                mount.set_mode(MountMode::AltAz);
+            }
+            ui.same_line();
+            if ui.radio_button_bool("equatorial", matches!(mode, MountMode::Equatorial(_))) {
+                if !matches!(mode, MountMode::Equatorial(_)) {
+                    mount.set_mode(MountMode::Equatorial(EquatorialSettings::default()));
+                }
+            }
+
+            if let MountMode::Equatorial(mut settings) = mode {
+                let mut changed = false;
+
+                let mut latitude = settings.latitude.get::<angle::degree>() as f32;
+                if ui.input_float("latitude (°)", &mut latitude).build() {
+                    settings.latitude = f64::Angle::new::<angle::degree>(latitude.clamp(-90.0, 90.0) as f64);
+                    changed = true;
+                }
+
+                let mut limit = settings.meridian_limit.get::<angle::degree>() as f32;
+                if ui.input_float("meridian limit (°)", &mut limit).build() {
+                    settings.meridian_limit = f64::Angle::new::<angle::degree>(limit as f64);
+                    changed = true;
+                }
+
+                changed |= ui.checkbox("automatic meridian flip", &mut settings.auto_flip);
+
+                if changed {
+                    mount.set_mode(MountMode::Equatorial(settings));
+                }
+
+                if let Some(pier_side) = mount.get().pier_side {
+                    ui.text(format!("pier side: {}", pier_side));
+                }
+                if ui.button("Meridian flip") {
+                    if let Err(e) = mount.meridian_flip() {
+                        log::error!("{}", e);
+                    }
+                }
+            } else {
+                let derotator = mount.derotator();
+                ui.text("derotator:");
+                ui.same_line();
+                if ui.radio_button_bool("off", derotator == Derotator::Off) {
+                    mount.set_derotator(Derotator::Off);
+                }
+                ui.same_line();
+                if ui.radio_button_bool("auto", derotator == Derotator::Auto) {
+                    mount.set_derotator(Derotator::Auto);
+                }
+                ui.same_line();
+                if ui.radio_button_bool("fixed", matches!(derotator, Derotator::Fixed(_))) {
+                    if !matches!(derotator, Derotator::Fixed(_)) {
+                        mount.set_derotator(Derotator::Fixed(mount.get().camera_roll));
+                    }
+                }
+                if let Derotator::Fixed(rotation) = derotator {
+                    let mut rotation = rotation.get::<angle::degree>() as f32;
+                    if ui.input_float("rotation (°)", &mut rotation).build() {
+                        mount.set_derotator(Derotator::Fixed(f64::Angle::new::<angle::degree>(rotation as f64)));
+                    }
+                }
+            }
+
+            let state = mount.get();
+            ui.text(format!(
+                "parallactic angle: {:.2}°\nimage rotation: {:.2}°",
+                state.parallactic_angle.get::<angle::degree>(),
+                state.camera_roll.get::<angle::degree>()
+            ));
+
+            ui.separator();
+            let status = mount.client_status();
+            let status_text = format!("client: {}", status);
+            if status == ClientStatus::TimedOut {
+                ui.text_colored([1.0, 0.3, 0.3, 1.0], status_text);
+            } else {
+                ui.text(status_text);
+            }
+
+            let mut timeout = mount.heartbeat_timeout().map_or(0.0, |t| t.as_secs_f32());
+            if ui.input_float("heartbeat timeout (s)", &mut timeout).build() {
+                mount.set_heartbeat_timeout(
+                    if timeout > 0.0 { std::time::Duration::try_from_secs_f32(timeout).ok() } else { None }
+                );
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Axes are stopped if the client is silent for longer than this; 0 disables");
+            }
+        });
+}
+
+/// Draws the target's recent and predicted path (positions in the local frame) over the camera image.
+fn draw_target_path(
+    ui: &imgui::Ui,
+    camera_view: &CameraView,
+    image_min: [f32; 2],
+    image_max: [f32; 2],
+    history: &[cgmath::Point3<f64>],
+    prediction: &[cgmath::Point3<f64>]
+) {
+    const HISTORY_COLOR: [f32; 4] = [1.0, 1.0, 0.3, 0.5];
+    const PREDICTION_COLOR: [f32; 4] = [1.0, 0.4, 1.0, 0.9];
+
+    let to_screen = |point: &cgmath::Point3<f64>| camera_view.point_image_position(*point).map(|pos| [
+        image_min[0] + pos[0] as f32 * (image_max[0] - image_min[0]),
+        image_min[1] + pos[1] as f32 * (image_max[1] - image_min[1])
+    ]);
+
+    let draw_list = ui.get_window_draw_list();
+    draw_list.with_clip_rect_intersect(image_min, image_max, || {
+        for (points, color) in [(history, HISTORY_COLOR), (prediction, PREDICTION_COLOR)] {
+            // points behind the camera split the path
+            let screen_points: Vec<Option<[f32; 2]>> = points.iter().map(to_screen).collect();
+            for segment in screen_points.split(|p| p.is_none()) {
+                if segment.len() > 1 {
+                    draw_list.add_polyline(segment.iter().flatten().copied().collect(), color).thickness(1.5).build();
+                }
+            }
+        }
+
+        let steps_per_mark = (TARGET_PATH_MARK_INTERVAL * 1000 / TARGET_PATH_STEP.as_millis() as u64).max(1) as usize;
+        for pos in prediction.iter().step_by(steps_per_mark).skip(1).filter_map(to_screen) {
+            draw_list.add_circle(pos, 2.5, PREDICTION_COLOR).filled(true).build();
+        }
+    });
+}
+
+/// Indications drawn over the camera image.
+struct CameraOverlays {
+    guidance: Option<operator_assist::Guidance>,
+    /// Offset of the target from the boresight, with the target's position (local frame).
+    pointing_error: Option<(operator_assist::Guidance, cgmath::Point3<f64>)>,
+    /// Name and state of another mount whose boresight is to be shown.
+    compared: Option<(String, MountState)>,
+    /// Recent and predicted target positions (local frame).
+    target_path: Option<(Vec<cgmath::Point3<f64>>, Vec<cgmath::Point3<f64>>)>
 }
 
 fn handle_camera_view(
+    title: &str,
     camera_view: &mut CameraView,
     ui: &imgui::Ui,
     gui_state: &mut GuiState,
-    mount_state: &MountState
-) {
-    ui.window(&format!("Camera view"))
+    mount_state: &MountState,
+    keyhole: Option<KeyholeStatus>,
+    overlays: CameraOverlays
+) -> Option<[f64; 2]> {
+    let mut pan = None;
+    ui.window(title)
         .size([640.0, 640.0], imgui::Condition::FirstUseEver)
         .build(|| {
             let hidpi_f = gui_state.hidpi_factor as f32;
 
             let adjusted = adjust_pos_for_exact_hidpi_scaling(ui, 0.0, hidpi_f);
 
-            camera_view.update_size(
-                adjusted.physical_size[0],
-                adjusted.physical_size[1]
-            );
+            let quality = gui_state.quality_governor.level();
+            camera_view.set_sampling(gui_state.render_settings.sampling(quality.multisampling));
+            let render_scale = quality.render_scale * gui_state.render_settings.supersampling;
+            let image_size = match gui_state.sensor_resolution {
+                Some([width, height]) => {
+                    camera_view.update_size(width, height);
+                    // fit the window, preserving aspect ratio
+                    let scale = (adjusted.logical_size[0] / width as f32).min(adjusted.logical_size[1] / height as f32);
+                    [width as f32 * scale, height as f32 * scale]
+                },
+                None => {
+                    camera_view.update_size(
+                        ((adjusted.physical_size[0] as f32 * render_scale) as u32).max(1),
+                        ((adjusted.physical_size[1] as f32 * render_scale) as u32).max(1)
+                    );
+                    adjusted.logical_size
+                }
+            };
 
             camera_view.set_mount_state(mount_state);
 
             let image_start_pos = ui.cursor_pos();
-            imgui::Image::new(camera_view.draw_buf_id(), adjusted.logical_size).build(ui);
+            imgui::Image::new(camera_view.draw_buf_id(), image_size).build(ui);
+            let (image_min, image_max) = (ui.item_rect_min(), ui.item_rect_max());
+            let cursor_dir = if ui.is_item_hovered() {
+                let mouse_pos = ui.io().mouse_pos;
+                Some(camera_view.direction_at([
+                    (mouse_pos[0] - image_min[0]) / (image_max[0] - image_min[0]),
+                    (mouse_pos[1] - image_min[1]) / (image_max[1] - image_min[1])
+                ]))
+            } else {
+                None
+            };
+            measurement::handle_input(&mut gui_state.measurement, title, ui, camera_view, image_min, image_max);
+            pan = drag_pan::handle_input(&mut gui_state.drag_pan, title, ui, image_min, image_max);
+            gui_state.reticle.draw(ui, image_min, image_max, camera_view.field_of_view_y());
+            fov_rings::draw(
+                ui,
+                &gui_state.settings.instruments,
+                image_min,
+                image_max,
+                camera_view.field_of_view_y(),
+                &gui_state.settings.units
+            );
+
+            if ui.is_item_clicked_with_button(imgui::MouseButton::Right) {
+                ui.open_popup("camera_view_menu");
+            }
+            if let Some(_token) = ui.begin_popup("camera_view_menu") {
+                camera_preset_controls(gui_state, camera_view, ui);
+                ui.separator();
+
+                let mut readback_enabled = camera_view.readback().is_some();
+                if ui.checkbox("async readback", &mut readback_enabled) {
+                    camera_view.set_readback_enabled(readback_enabled);
+                }
+
+                let mut fixed_resolution = gui_state.sensor_resolution.is_some();
+                if ui.checkbox("fixed sensor resolution", &mut fixed_resolution) {
+                    gui_state.sensor_resolution = if fixed_resolution { Some(DEFAULT_SENSOR_RESOLUTION) } else { None };
+                }
+                if let Some(resolution) = &mut gui_state.sensor_resolution {
+                    let mut values = resolution.map(|value| value as i32);
+                    if ui.input_int2("resolution (px)", &mut values).build() {
+                        *resolution = values.map(|value| value.clamp(1, MAX_SENSOR_RESOLUTION as i32) as u32);
+                    }
+                }
+
+                let mut mode_idx = ColorMode::ALL.iter().position(|m| *m == camera_view.color_mode()).unwrap();
+                if ui.combo_simple_string("color mode", &mut mode_idx, &ColorMode::ALL.map(|m| m.to_string())) {
+                    camera_view.set_color_mode(ColorMode::ALL[mode_idx]);
+                }
+                if ui.is_item_hovered() { ui.tooltip_text("Color mode of recorded and streamed frames"); }
+
+                let mut noise = camera_view.sensor_noise().cloned();
+                let mut noise_enabled = noise.is_some();
+                let mut noise_changed = ui.checkbox("sensor noise", &mut noise_enabled);
+                if noise_changed { noise = if noise_enabled { Some(Default::default()) } else { None }; }
+                if let Some(noise) = &mut noise {
+                    let mut read_noise_dn = noise.read_noise * 255.0;
+                    if ui.input_float("read noise (DN)", &mut read_noise_dn).build() {
+                        noise.read_noise = read_noise_dn.max(0.0) / 255.0;
+                        noise_changed = true;
+                    }
+                    if ui.input_float("full well (e-)", &mut noise.full_well).build() {
+                        noise.full_well = noise.full_well.max(0.0);
+                        noise_changed = true;
+                    }
+                    let mut hot_pixels_ppm = noise.hot_pixel_fraction * 1.0e6;
+                    if ui.input_float("hot pixels (ppm)", &mut hot_pixels_ppm).build() {
+                        noise.hot_pixel_fraction = (hot_pixels_ppm / 1.0e6).clamp(0.0, 1.0);
+                        noise_changed = true;
+                    }
+                }
+                if noise_changed { camera_view.set_sensor_noise(noise); }
+
+                let mut exposure = camera_view.exposure().cloned();
+                let mut blur_enabled = exposure.is_some();
+                let mut exposure_changed = ui.checkbox("motion blur", &mut blur_enabled);
+                if exposure_changed { exposure = if blur_enabled { Some(Default::default()) } else { None }; }
+                if let Some(exposure) = &mut exposure {
+                    let mut time_ms = exposure.time.as_secs_f32() * 1000.0;
+                    if ui.input_float("exposure (ms)", &mut time_ms).build() {
+                        let max_ms = motion_blur::MAX_EXPOSURE_TIME.as_secs_f32() * 1000.0;
+                        exposure.time = std::time::Duration::from_secs_f32(time_ms.clamp(0.0, max_ms) / 1000.0);
+                        exposure_changed = true;
+                    }
+                    if ui.slider("exposure samples", 2, motion_blur::MAX_NUM_SAMPLES, &mut exposure.num_samples) {
+                        exposure_changed = true;
+                    }
+                }
+                if exposure_changed { camera_view.set_exposure(exposure); }
+
+                let mut lens = camera_view.lens_distortion().cloned();
+                let mut lens_enabled = lens.is_some();
+                let mut lens_changed = ui.checkbox("lens distortion", &mut lens_enabled);
+                if lens_changed { lens = if lens_enabled { Some(Default::default()) } else { None }; }
+                if let Some(lens) = &mut lens {
+                    lens_changed |= ui.input_float("k1", &mut lens.k1).build();
+                    if ui.is_item_hovered() { ui.tooltip_text("Negative: barrel distortion, positive: pincushion"); }
+                    lens_changed |= ui.input_float("k2", &mut lens.k2).build();
+                    lens_changed |= ui.slider("vignetting", 0.0, 1.0, &mut lens.vignetting);
+                }
+                if lens_changed { camera_view.set_lens_distortion(lens); }
+
+                let mut seeing = camera_view.seeing().cloned();
+                let mut seeing_enabled = seeing.is_some();
+                let mut seeing_changed = ui.checkbox("seeing", &mut seeing_enabled);
+                if seeing_changed { seeing = if seeing_enabled { Some(Default::default()) } else { None }; }
+                if let Some(seeing) = &mut seeing {
+                    let mut fwhm = seeing.fwhm.get::<angle::second>() as f32;
+                    if ui.input_float("seeing FWHM (\")", &mut fwhm).build() {
+                        seeing.fwhm = f64::Angle::new::<angle::second>(fwhm.max(0.0) as f64);
+                        seeing_changed = true;
+                    }
+                    let mut boil_rate = seeing.boil_rate as f32;
+                    if ui.input_float("boil rate (Hz)", &mut boil_rate).build() {
+                        seeing.boil_rate = boil_rate.max(0.0) as f64;
+                        seeing_changed = true;
+                    }
+                }
+                if seeing_changed { camera_view.set_seeing(seeing); }
+
+                let misalignment = camera_view.misalignment();
+                let mut values = [misalignment.horizontal, misalignment.vertical, misalignment.roll]
+                    .map(|value| value.get::<angle::minute>() as f32);
+                if ui.input_float3("misalignment (')", &mut values).build() {
+                    let [horizontal, vertical, roll] =
+                        values.map(|value| f64::Angle::new::<angle::minute>(value as f64));
+                    camera_view.set_misalignment(Misalignment{ horizontal, vertical, roll });
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Camera vs. mount boresight: horizontal & vertical offset, counter-clockwise roll");
+                }
+
+                let reticle = &mut gui_state.reticle;
+                let mut style_idx = reticle::ReticleStyle::ALL.iter().position(|s| *s == reticle.style).unwrap();
+                let style_names = reticle::ReticleStyle::ALL.map(|s| s.to_string());
+                if ui.combo_simple_string("reticle", &mut style_idx, &style_names) {
+                    reticle.style = reticle::ReticleStyle::ALL[style_idx];
+                }
+                if matches!(reticle.style, reticle::ReticleStyle::MilDot | reticle::ReticleStyle::Circles) {
+                    let mut spacing = reticle.spacing.get::<angle::minute>() as f32;
+                    if ui.input_float("reticle spacing (')", &mut spacing).build() {
+                        reticle.spacing = f64::Angle::new::<angle::minute>(spacing.max(0.01) as f64);
+                    }
+                    if ui.button("1 mil") { reticle.spacing = f64::Angle::new::<angle::radian>(0.001); }
+                }
+                if reticle.style != reticle::ReticleStyle::None {
+                    ui.color_edit4("reticle color", &mut reticle.color);
+                    ui.slider("reticle thickness", 1.0, 5.0, &mut reticle.thickness);
+                }
+
+                ui.checkbox("target path", &mut gui_state.target_path);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(format!(
+                        "Recent path and path predicted for the next {} s (dots every {} s)",
+                        TARGET_PATH_PREDICTION.as_secs(), TARGET_PATH_MARK_INTERVAL
+                    ));
+                }
+                ui.checkbox("bounding box", &mut gui_state.bounding_box);
+                ui.checkbox("pointing error", &mut gui_state.error_hud);
+
+                let inset = &mut gui_state.zoom_inset;
+                ui.checkbox("zoom inset", &mut inset.enabled);
+                if inset.enabled {
+                    if ui.radio_button_bool("on target", inset.center == zoom_inset::InsetCenter::Target) {
+                        inset.center = zoom_inset::InsetCenter::Target;
+                    }
+                    ui.same_line();
+                    if ui.radio_button_bool("on boresight", inset.center == zoom_inset::InsetCenter::Boresight) {
+                        inset.center = zoom_inset::InsetCenter::Boresight;
+                    }
+                    ui.slider_config("inset zoom", zoom_inset::MIN_ZOOM, zoom_inset::MAX_ZOOM)
+                        .flags(imgui::SliderFlags::LOGARITHMIC)
+                        .display_format("%.1fx")
+                        .build(&mut inset.zoom);
+                }
+
+                if gui_state.measurement.is_some() && ui.button("clear measurement") {
+                    gui_state.measurement = None;
+                    ui.close_current_popup();
+                }
+
+                let assist = &mut gui_state.operator_assist;
+                ui.checkbox("operator assist", &mut assist.enabled);
+                if assist.enabled {
+                    let mut threshold = assist.threshold.get::<angle::minute>() as f32;
+                    if ui.input_float("assist threshold (')", &mut threshold).build() {
+                        assist.threshold = f64::Angle::new::<angle::minute>(threshold.max(0.0) as f64);
+                    }
+                }
+
+                let weather = camera_view.weather().cloned();
+                let mut haze = camera_view.visibility().is_some();
+                if ui.checkbox("haze", &mut haze) {
+                    camera_view.set_visibility(if haze {
+                        Some(match &weather {
+                            Some(weather) => Visibility::Weather(Arc::clone(weather)),
+                            None => Visibility::Fixed(f64::Length::new::<length::kilometer>(50.0))
+                        })
+                    } else {
+                        None
+                    });
+                }
+                if let Some(visibility) = camera_view.visibility() {
+                    let current = visibility.current();
+                    let mut follow_weather = matches!(visibility, Visibility::Weather(_));
+                    if let Some(weather) = &weather {
+                        if ui.checkbox("visibility from weather", &mut follow_weather) {
+                            camera_view.set_visibility(Some(if follow_weather {
+                                Visibility::Weather(Arc::clone(weather))
+                            } else {
+                                Visibility::Fixed(current)
+                            }));
+                        }
+                    }
+                    let mut visibility_km = current.get::<length::kilometer>() as f32;
+                    if follow_weather {
+                        ui.text(format!("visibility: {:.1} km", visibility_km));
+                    } else if ui.input_float("visibility (km)", &mut visibility_km).build() {
+                        camera_view.set_visibility(Some(Visibility::Fixed(
+                            f64::Length::new::<length::kilometer>(visibility_km.max(0.1) as f64)
+                        )));
+                    }
+                }
+
+                let mut clouds = camera_view.cloud_layer().is_some();
+                if ui.checkbox("clouds", &mut clouds) {
+                    camera_view.set_cloud_layer(if clouds {
+                        Some(CloudLayer::new(
+                            match &weather {
+                                Some(weather) => Coverage::Weather(Arc::clone(weather)),
+                                None => Coverage::Fixed(0.5)
+                            },
+                            f64::Length::new::<length::meter>(DEFAULT_CLOUD_ALTITUDE),
+                            cgmath::Vector2{ x: 0.0, y: -DEFAULT_CLOUD_DRIFT }
+                        ))
+                    } else {
+                        None
+                    });
+                }
+                if let Some(layer) = camera_view.cloud_layer_mut() {
+                    if let Some(weather) = &weather {
+                        let mut follow_weather = matches!(layer.coverage, Coverage::Weather(_));
+                        if ui.checkbox("coverage from weather", &mut follow_weather) {
+                            layer.coverage = if follow_weather {
+                                Coverage::Weather(Arc::clone(weather))
+                            } else {
+                                Coverage::Fixed(layer.coverage())
+                            };
+                        }
+                    }
+                    match &mut layer.coverage {
+                        Coverage::Fixed(coverage) => {
+                            let mut value = *coverage as f32;
+                            if ui.slider("coverage", 0.0, 1.0, &mut value) { *coverage = value as f64; }
+                        },
+                        Coverage::Weather(weather) => ui.text(format!("coverage: {:.2}", weather.current().cloud_cover))
+                    }
+                    let length_unit = gui_state.settings.units.length;
+                    let mut altitude = length_unit.value(layer.altitude) as f32;
+                    if ui.input_float(format!("cloud altitude ({})", length_unit.symbol()), &mut altitude).build() {
+                        layer.altitude = length_unit.quantity(altitude as f64)
+                            .max(f64::Length::new::<length::meter>(1.0));
+                    }
+                }
+            }
 
             if ui.is_item_hovered() {
                 let wheel = ui.io().mouse_wheel;
@@ -99,18 +2052,180 @@ fn handle_camera_view(
                 }
             }
 
+            if let Some(guidance) = &overlays.guidance {
+                operator_assist::draw_guidance(
+                    ui,
+                    guidance,
+                    image_min,
+                    image_max,
+                    camera_view.field_of_view_y(),
+                    &gui_state.settings.units
+                );
+            }
+
+            if let Some((error, target_pos)) = &overlays.pointing_error {
+                error_hud::draw(
+                    ui,
+                    error,
+                    camera_view.point_image_position(*target_pos),
+                    image_min,
+                    image_max,
+                    &gui_state.settings.units
+                );
+            }
+
+            if let Some((history, prediction)) = &overlays.target_path {
+                draw_target_path(ui, camera_view, image_min, image_max, history, prediction);
+            }
+
+            if gui_state.bounding_box {
+                if let Some([x_min, y_min, x_max, y_max]) = camera_view.target_bounding_box() {
+                    const COLOR: [f32; 4] = [0.3, 1.0, 0.3, 0.9];
+                    let to_screen = |x: f64, y: f64| [
+                        image_min[0] + x as f32 * (image_max[0] - image_min[0]),
+                        image_min[1] + y as f32 * (image_max[1] - image_min[1])
+                    ];
+                    let (p_min, p_max) = (to_screen(x_min, y_min), to_screen(x_max, y_max));
+                    let draw_list = ui.get_window_draw_list();
+                    draw_list.add_rect(p_min, p_max, COLOR).thickness(1.5).build();
+                    draw_list.add_text([p_min[0], p_min[1] - ui.text_line_height()], COLOR, TARGET_CLASS);
+                }
+            }
+
+            if let Some(measurement) = gui_state.measurement.as_ref().filter(|m| m.window() == title) {
+                measurement.draw(ui, camera_view, image_min, image_max, mount_state, &gui_state.settings.units);
+            }
+
+            if let Some((name, state)) = &overlays.compared {
+                if let Some(pos) = camera_view.direction_image_position(state.boresight_az, state.boresight_alt) {
+                    const COLOR: [f32; 4] = [0.2, 0.9, 1.0, 0.9];
+                    const SIZE: f32 = 8.0;
+                    let x = image_min[0] + pos[0] as f32 * (image_max[0] - image_min[0]);
+                    let y = image_min[1] + pos[1] as f32 * (image_max[1] - image_min[1]);
+                    let draw_list = ui.get_window_draw_list();
+                    draw_list.with_clip_rect_intersect(image_min, image_max, || {
+                        draw_list.add_circle([x, y], SIZE, COLOR).thickness(1.5).build();
+                        draw_list.add_line([x - 2.0 * SIZE, y], [x + 2.0 * SIZE, y], COLOR).build();
+                        draw_list.add_line([x, y - 2.0 * SIZE], [x, y + 2.0 * SIZE], COLOR).build();
+                        draw_list.add_text([x + SIZE, y + SIZE], COLOR, name);
+                    });
+                }
+            }
+
+            gui_state.zoom_inset.draw(ui, camera_view, image_min, image_max, mount_state);
+
             ui.set_cursor_pos(image_start_pos);
             let _disabled = ui.begin_disabled(true);
             let _token1 = ui.push_style_color(imgui::StyleColor::Text, [0.0, 0.0, 0.0, 1.0]);
             let _token2 = ui.push_style_color(imgui::StyleColor::Button, [1.0, 1.0, 1.0, 0.8]);
+            let display_units = gui_state.settings.units;
             let a1deg = mount_state.axis1_pos.get::<angle::degree>();
+            let render_stats = camera_view.render_stats();
+            let readback_status = match &*camera_view.readback() {
+                Some(readback) => format!(
+                    "\nreadback: {} frames ({}), latency {}",
+                    readback.num_frames(),
+                    readback.latest().map_or("-".to_string(), |image| format!("{}x{}", image.width, image.height)),
+                    readback.latency()
+                ),
+                None => String::new()
+            };
+            let quality_status = if gui_state.quality_governor.enabled() {
+                format!(
+                    "\nquality level {}, frame time {:.1} ms",
+                    gui_state.quality_governor.level_index(),
+                    gui_state.quality_governor.avg_frame_time().as_secs_f64() * 1000.0
+                )
+            } else {
+                String::new()
+            };
+            let keyhole_status = match keyhole {
+                Some(keyhole) => format!(
+                    "\nzenith keyhole r = {}{}",
+                    display_units.angle(keyhole.radius, AngleScale::Normal, 1),
+                    if keyhole.target_inside() { " - TARGET IN BLIND SPOT" } else { "" }
+                ),
+                None => String::new()
+            };
+            let sky_status = match camera_view.sky_model() {
+                Some(sky) => format!(
+                    "\n{} UTC, Sun alt. {}",
+                    sky.time().format("%Y-%m-%d %H:%M:%S"),
+                    display_units.angle(units::deg(sky.sun().alt), AngleScale::Normal, 1)
+                ),
+                None => String::new()
+            };
+            let cloud_status = match camera_view.cloud_layer() {
+                Some(layer) => format!(
+                    "\nclouds: {:.0}% at {}, obscured objects: {}",
+                    layer.coverage() * 100.0,
+                    display_units.length(layer.altitude, 0),
+                    render_stats.obscured
+                ),
+                None => String::new()
+            };
+            let blur_status = match (camera_view.exposure(), render_stats.streak) {
+                (Some(exposure), Some(streak)) => format!(
+                    "\nexposure {:.0} ms, streak {:.1}\" ({:.1} px)",
+                    exposure.time.as_secs_f64() * 1000.0,
+                    streak.get::<angle::second>(),
+                    (streak / camera_view.field_of_view_y()).value * adjusted.physical_size[1] as f64
+                ),
+                _ => String::new()
+            };
+            let cursor_status = match cursor_dir {
+                Some(dir) => format!(
+                    "\ncursor: az. {}, alt. {}{}",
+                    display_units.angle(units::deg(dir.az), AngleScale::Normal, 3),
+                    display_units.angle(units::deg(dir.alt), AngleScale::Normal, 3),
+                    camera_view.sky_model().map_or(String::new(), |sky| {
+                        let (ra, dec) = sky.horizontal_to_equatorial(dir);
+                        format!(", RA {}, Dec {}", format_ra(ra), format_dec(dec))
+                    })
+                ),
+                None => String::new()
+            };
             ui.small_button(&format!(
-                "az. {:.1}°, alt. {:.1}°\nFOVy {:.02}°",
-                if a1deg >= 0.0 && a1deg <= 180.0 { a1deg } else { 360.0 + a1deg },
-                mount_state.axis2_pos.get::<angle::degree>(),
-                camera_view.field_of_view_y().0
+                "az. {}, alt. {}\nFOVy {}\nobjects drawn: {}, culled: {}, below horizon: {}{}{}{}{}{}{}{}",
+                display_units.angle(
+                    units::deg(if a1deg >= 0.0 && a1deg <= 180.0 { a1deg } else { 360.0 + a1deg }),
+                    AngleScale::Normal,
+                    1
+                ),
+                display_units.angle(mount_state.axis2_pos, AngleScale::Normal, 1),
+                display_units.angle(camera_view.field_of_view_y(), AngleScale::Normal, 2),
+                render_stats.drawn,
+                render_stats.culled,
+                render_stats.occluded,
+                readback_status,
+                quality_status,
+                keyhole_status,
+                sky_status,
+                cloud_status,
+                blur_status,
+                cursor_status
             ));
         });
+
+    pan
+}
+
+/// Formats right ascension (given in degrees) as hours, minutes and seconds.
+fn format_ra(ra_deg: f64) -> String {
+    let seconds = (ra_deg / 15.0 * 3600.0 * 10.0).round() / 10.0;
+    format!("{:02}h{:02}m{:04.1}s", (seconds / 3600.0) as u32 % 24, (seconds / 60.0) as u32 % 60, seconds % 60.0)
+}
+
+/// Formats declination (given in degrees) as degrees, arcminutes and arcseconds.
+fn format_dec(dec_deg: f64) -> String {
+    let seconds = (dec_deg.abs() * 3600.0).round();
+    format!(
+        "{}{:02}°{:02}'{:02}\"",
+        if dec_deg < 0.0 { '-' } else { '+' },
+        (seconds / 3600.0) as u32,
+        (seconds / 60.0) as u32 % 60,
+        seconds as u32 % 60
+    )
 }
 
 /// Adjusts cursor screen position and returns size to be used for an `imgui::Image` (meant to fill the remaining window
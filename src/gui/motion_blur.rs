@@ -0,0 +1,139 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Exposure model: the target is rendered at several instants of the exposure window (accounting for both its own
+//! motion and the camera's) and the results are averaged, producing a streak at high angular rates.
+
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3, VectorSpace};
+use crate::runner::GlContext;
+use glium::texture::{depth_texture2d::DepthTexture2d, texture2d::Texture2d};
+use pointing_utils::uom;
+use std::{collections::VecDeque, time::{Duration, Instant}};
+use uom::{si::f64, si::angle};
+
+/// Poses older than this are discarded.
+pub const MAX_EXPOSURE_TIME: Duration = Duration::from_secs(1);
+
+pub const MAX_NUM_SAMPLES: usize = 64;
+
+#[derive(Clone)]
+pub struct Exposure {
+    pub time: Duration,
+    /// Number of instants of the exposure window at which the target is rendered.
+    pub num_samples: usize
+}
+
+impl Default for Exposure {
+    fn default() -> Exposure {
+        Exposure{ time: Duration::from_millis(40), num_samples: 16 }
+    }
+}
+
+/// Camera direction and apparent target position & heading.
+#[derive(Clone)]
+pub struct Pose {
+    pub dir: Vector3<f64>,
+    pub target_pos: Point3<f64>,
+    pub target_heading: f64::Angle
+}
+
+impl Pose {
+    /// Returns unit vector pointing at the target in the camera frame (X: right, Y: up, Z: backwards).
+    pub fn target_in_camera_frame(&self, up: Vector3<f64>) -> Vector3<f64> {
+        let view = cgmath::Matrix4::look_to_rh(Point3::origin(), self.dir, up);
+        (view * self.target_pos.to_homogeneous()).truncate().normalize()
+    }
+}
+
+/// Recent poses; updated whenever the camera or the target moves.
+#[derive(Default)]
+pub struct PoseHistory {
+    poses: VecDeque<(Instant, Pose)>
+}
+
+impl PoseHistory {
+    pub fn record(&mut self, pose: Pose) {
+        let now = Instant::now();
+        // keep one pose older than `MAX_EXPOSURE_TIME` for interpolation
+        while self.poses.len() > 1 && now - self.poses[1].0 > MAX_EXPOSURE_TIME {
+            self.poses.pop_front();
+        }
+        self.poses.push_back((now, pose));
+    }
+
+    /// Returns poses at `exposure.num_samples` evenly spaced instants of the exposure window ending now.
+    pub fn samples(&self, exposure: &Exposure) -> Vec<Pose> {
+        let now = Instant::now();
+        let n = exposure.num_samples.max(2);
+        (0..n)
+            .map(|i| now.checked_sub(exposure.time.mul_f64((n - 1 - i) as f64 / (n - 1) as f64)).unwrap_or(now))
+            .filter_map(|t| self.pose_at(t))
+            .collect()
+    }
+
+    fn pose_at(&self, t: Instant) -> Option<Pose> {
+        let next = self.poses.iter().position(|(time, _)| *time >= t);
+        match next {
+            None => self.poses.back().map(|(_, pose)| pose.clone()),
+            Some(0) => self.poses.front().map(|(_, pose)| pose.clone()),
+            Some(i) => {
+                let (t0, p0) = &self.poses[i - 1];
+                let (t1, p1) = &self.poses[i];
+                let f = (t - *t0).as_secs_f64() / (*t1 - *t0).as_secs_f64();
+                Some(Pose{
+                    dir: p0.dir.lerp(p1.dir, f).normalize(),
+                    target_pos: p0.target_pos + (p1.target_pos - p0.target_pos) * f,
+                    target_heading: if f < 0.5 { p0.target_heading } else { p1.target_heading }
+                })
+            }
+        }
+    }
+}
+
+/// Returns angular length of the streak traced by the target in the camera frame.
+pub fn streak_length(poses: &[Pose], up: Vector3<f64>) -> f64::Angle {
+    let radians = match (poses.first(), poses.last()) {
+        (Some(first), Some(last)) => first.target_in_camera_frame(up).angle(last.target_in_camera_frame(up)).0,
+        _ => 0.0
+    };
+    f64::Angle::new::<angle::radian>(radians)
+}
+
+/// Buffers accumulating the target renderings of all exposure samples (color is premultiplied by coverage).
+pub struct AccumulationBuffer {
+    color: Texture2d,
+    depth: DepthTexture2d
+}
+
+impl AccumulationBuffer {
+    pub fn new(display: &GlContext, width: u32, height: u32) -> AccumulationBuffer {
+        AccumulationBuffer{
+            color: Texture2d::empty_with_format(
+                display,
+                // floating-point, so that small per-sample contributions are not lost
+                glium::texture::UncompressedFloatFormat::F16F16F16F16,
+                glium::texture::MipmapsOption::NoMipmap,
+                width,
+                height
+            ).unwrap(),
+            depth: DepthTexture2d::empty_with_format(
+                display,
+                glium::texture::DepthFormat::I24,
+                glium::texture::MipmapsOption::NoMipmap,
+                width,
+                height
+            ).unwrap()
+        }
+    }
+
+    pub fn texture(&self) -> &Texture2d { &self.color }
+
+    pub fn frame_buf(&self, display: &GlContext) -> glium::framebuffer::SimpleFrameBuffer {
+        glium::framebuffer::SimpleFrameBuffer::with_depth_buffer(display, &self.color, &self.depth).unwrap()
+    }
+}
@@ -0,0 +1,71 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Navigation, anti-collision beacon and strobe lights of the target aircraft.
+
+/// Lights are shown if the Sun is below this altitude (degrees).
+pub const MAX_SUN_ALTITUDE: f64 = 0.0;
+
+/// Size of a light's image (pixels).
+pub const LIGHT_SIZE: f32 = 3.0;
+
+const RED: [f32; 4] = [1.0, 0.15, 0.1, 1.0];
+const GREEN: [f32; 4] = [0.1, 1.0, 0.3, 1.0];
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+#[derive(Copy, Clone)]
+enum Pattern {
+    Steady,
+    /// Flashes every `period` (s), each given as start and end (s) within the period.
+    Flashes{ period: f64, flashes: &'static [(f64, f64)] }
+}
+
+struct Light {
+    /// In the target mesh's frame (see `data::create_target_mesh`): X towards the nose, Y towards the left wing.
+    position: [f32; 3],
+    color: [f32; 4],
+    pattern: Pattern
+}
+
+const BEACON: Pattern = Pattern::Flashes{ period: 1.0, flashes: &[(0.0, 0.1)] };
+const STROBE: Pattern = Pattern::Flashes{ period: 1.2, flashes: &[(0.5, 0.55), (0.65, 0.7)] };
+
+/// Wingtips and tail of the target mesh (with a small margin, so that the lights are not hidden by the surface).
+const WINGTIP_X: f32 = -26.85;
+const WINGTIP_Y: f32 = 15.6;
+const TAIL_X: f32 = -17.9;
+const BEACON_Z: f32 = 2.0;
+
+/// Strobes are listed first, so that (being drawn first) they are not hidden by the navigation lights at the same
+/// positions.
+const LIGHTS: [Light; 7] = [
+    Light{ position: [WINGTIP_X, WINGTIP_Y, 0.0], color: WHITE, pattern: STROBE },
+    Light{ position: [WINGTIP_X, -WINGTIP_Y, 0.0], color: WHITE, pattern: STROBE },
+    Light{ position: [WINGTIP_X, WINGTIP_Y, 0.0], color: RED, pattern: Pattern::Steady },
+    Light{ position: [WINGTIP_X, -WINGTIP_Y, 0.0], color: GREEN, pattern: Pattern::Steady },
+    Light{ position: [TAIL_X, 0.0, 0.0], color: WHITE, pattern: Pattern::Steady },
+    Light{ position: [0.0, 0.0, BEACON_Z], color: RED, pattern: BEACON },
+    Light{ position: [0.0, 0.0, -BEACON_Z], color: RED, pattern: BEACON }
+];
+
+impl Pattern {
+    fn is_on(&self, t: f64) -> bool {
+        match self {
+            Pattern::Steady => true,
+            Pattern::Flashes{ period, flashes } => {
+                let phase = t.rem_euclid(*period);
+                flashes.iter().any(|(start, end)| phase >= *start && phase < *end)
+            }
+        }
+    }
+}
+
+/// Returns positions and colors of the lights lit at time `t` (seconds).
+pub fn lit_lights(t: f64) -> Vec<([f32; 3], [f32; 4])> {
+    LIGHTS.iter().filter(|light| light.pattern.is_on(t)).map(|light| (light.position, light.color)).collect()
+}
@@ -0,0 +1,161 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Transient notifications ("toasts") of important events: client connections, axis limits, stale target data.
+
+use crate::workers::{ClientStatus, Mount, MountState};
+use pointing_utils::uom;
+use std::{collections::{BTreeMap, HashMap, VecDeque}, time::{Duration, Instant}};
+use uom::si::angle;
+
+const DISPLAY_TIME: Duration = Duration::from_secs(6);
+
+const MAX_SHOWN: usize = 6;
+
+/// Target data is considered stale if no message has been received for this long.
+const STALE_DATA_AGE: Duration = Duration::from_secs(3);
+
+/// An axis within this distance (degrees) from a position limit is considered to be at the limit.
+const LIMIT_TOLERANCE_DEG: f64 = 0.01;
+
+const INFO_COLOR: [f32; 4] = [0.8, 0.9, 1.0, 1.0];
+const WARNING_COLOR: [f32; 4] = [1.0, 0.7, 0.3, 1.0];
+
+#[derive(Copy, Clone)]
+enum Severity {
+    Info,
+    Warning
+}
+
+struct Toast {
+    text: String,
+    severity: Severity,
+    created: Instant
+}
+
+/// Last known state of a station (to detect changes).
+#[derive(Default)]
+struct StationState {
+    client: Option<ClientStatus>,
+    at_limit: [bool; 2],
+    stale: bool
+}
+
+#[derive(Default)]
+pub struct Notifications {
+    toasts: VecDeque<Toast>,
+    /// Keyed by station name.
+    stations: HashMap<String, StationState>,
+    /// Number of clients of each target feed (keyed by port).
+    feed_clients: BTreeMap<u16, usize>
+}
+
+fn at_limit(pos: uom::si::f64::Angle, min_deg: Option<f64>, max_deg: Option<f64>) -> bool {
+    let pos = pos.get::<angle::degree>();
+    min_deg.map_or(false, |min| pos <= min + LIMIT_TOLERANCE_DEG)
+        || max_deg.map_or(false, |max| pos >= max - LIMIT_TOLERANCE_DEG)
+}
+
+impl Notifications {
+    fn push(&mut self, severity: Severity, text: String) {
+        self.toasts.push_back(Toast{ text, severity, created: Instant::now() });
+        while self.toasts.len() > MAX_SHOWN { self.toasts.pop_front(); }
+    }
+
+    /// Detects events of a station; to be called every frame. `target_data_age`: time since the last target
+    /// message (`None` if none has been received).
+    pub fn check_station(
+        &mut self,
+        name: &str,
+        mount: &Mount,
+        mount_state: &MountState,
+        target_data_age: Option<Duration>
+    ) {
+        let mut events = vec![];
+        let state = self.stations.entry(name.to_string()).or_default();
+        let prefix = if name.is_empty() { String::new() } else { format!("{}: ", name) };
+
+        let client = mount.client_status();
+        if state.client.map_or(false, |prev| prev != client) {
+            let (severity, text) = match client {
+                ClientStatus::Connected => (Severity::Info, "mount client connected"),
+                ClientStatus::NotConnected => (Severity::Warning, "mount client disconnected"),
+                ClientStatus::TimedOut => (Severity::Warning, "mount client timed out; axes stopped")
+            };
+            events.push((severity, format!("{}{}", prefix, text)));
+        }
+        state.client = Some(client);
+
+        let config = mount.config();
+        let limits = [
+            at_limit(mount_state.axis1_pos, config.axis1.min_pos_deg, config.axis1.max_pos_deg),
+            at_limit(mount_state.axis2_pos, config.axis2.min_pos_deg, config.axis2.max_pos_deg)
+        ];
+        for (i, reached) in limits.iter().enumerate() {
+            if *reached && !state.at_limit[i] {
+                events.push((Severity::Warning, format!("{}axis {} limit reached", prefix, i + 1)));
+            }
+        }
+        state.at_limit = limits;
+
+        let stale = target_data_age.map_or(false, |age| age >= STALE_DATA_AGE);
+        if stale != state.stale {
+            events.push(if stale {
+                let text = format!("{}target data stale (no update for {} s)", prefix, STALE_DATA_AGE.as_secs());
+                (Severity::Warning, text)
+            } else {
+                (Severity::Info, format!("{}target data resumed", prefix))
+            });
+        }
+        state.stale = stale;
+
+        for (severity, text) in events { self.push(severity, text); }
+    }
+
+    /// Detects connections and disconnections of target feed clients; `feeds`: (port, number of clients).
+    pub fn check_feeds(&mut self, feeds: &[(u16, usize)]) {
+        for &(port, clients) in feeds {
+            let text = match self.feed_clients.insert(port, clients) {
+                Some(prev) if clients > prev => "client connected",
+                Some(prev) if clients < prev => "client disconnected",
+                _ => continue
+            };
+            self.push(Severity::Info, format!("target feed {}: {}", port, text));
+        }
+    }
+
+    /// Draws the current notifications in the bottom right corner of the main window.
+    pub fn draw(&mut self, ui: &imgui::Ui) {
+        self.toasts.retain(|toast| toast.created.elapsed() < DISPLAY_TIME);
+        if self.toasts.is_empty() { return; }
+
+        let display_size = ui.io().display_size;
+        ui.window("##notifications")
+            .position([display_size[0] - 10.0, display_size[1] - 10.0], imgui::Condition::Always)
+            .position_pivot([1.0, 1.0])
+            .bg_alpha(0.8)
+            .flags(
+                imgui::WindowFlags::NO_DECORATION
+                    | imgui::WindowFlags::ALWAYS_AUTO_RESIZE
+                    | imgui::WindowFlags::NO_SAVED_SETTINGS
+                    | imgui::WindowFlags::NO_FOCUS_ON_APPEARING
+                    | imgui::WindowFlags::NO_NAV
+                    | imgui::WindowFlags::NO_DOCKING
+                    | imgui::WindowFlags::NO_INPUTS
+            )
+            .build(|| {
+                for toast in &self.toasts {
+                    let color = match toast.severity {
+                        Severity::Info => INFO_COLOR,
+                        Severity::Warning => WARNING_COLOR
+                    };
+                    ui.text_colored(color, &toast.text);
+                }
+            });
+    }
+}
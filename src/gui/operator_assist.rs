@@ -0,0 +1,123 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! "Operator assist": guidance arrows in the camera view for manual tracking.
+
+use crate::{
+    target_geometry::TargetDirection,
+    tracking_controller::normalize,
+    units::{AngleScale, DisplayUnits},
+    workers::MountState
+};
+use pointing_utils::{TargetInfoMessage, uom};
+use uom::{si::f64, si::{angle, angular_velocity}};
+
+/// Time in which following the suggested slew rates re-centers the target.
+const RECENTER_TIME: f64 = 2.0; // s
+
+const ARROW_COLOR: [f32; 4] = [1.0, 0.8, 0.0, 1.0];
+
+pub struct OperatorAssist {
+    pub enabled: bool,
+    /// Guidance is shown when the target is farther than this from the boresight.
+    pub threshold: f64::Angle
+}
+
+impl Default for OperatorAssist {
+    fn default() -> OperatorAssist {
+        OperatorAssist{ enabled: false, threshold: f64::Angle::new::<angle::minute>(30.0) }
+    }
+}
+
+/// Offset of the target from the boresight and slew rates needed to re-center it.
+pub struct Guidance {
+    /// Offset in azimuth (measured on the sky, i.e., scaled by cos(altitude)) and altitude.
+    pub offset: (f64::Angle, f64::Angle),
+    pub az_rate: f64::AngularVelocity,
+    pub alt_rate: f64::AngularVelocity
+}
+
+impl Guidance {
+    pub fn new(target: &TargetInfoMessage, state: &MountState) -> Option<Guidance> {
+        let target = TargetDirection::from_message(target)?;
+        let az_error = normalize((target.az - state.boresight_az).get::<angle::radian>());
+        let alt_error = (target.alt - state.boresight_alt).get::<angle::radian>();
+
+        let rate = |error: f64, target_rate: f64::AngularVelocity| {
+            f64::AngularVelocity::new::<angular_velocity::radian_per_second>(
+                error / RECENTER_TIME + target_rate.get::<angular_velocity::radian_per_second>()
+            )
+        };
+
+        Some(Guidance{
+            offset: (
+                f64::Angle::new::<angle::radian>(az_error * target.alt.get::<angle::radian>().cos()),
+                f64::Angle::new::<angle::radian>(alt_error)
+            ),
+            az_rate: rate(az_error, target.az_rate),
+            alt_rate: rate(alt_error, target.alt_rate)
+        })
+    }
+
+    pub fn total_offset(&self) -> f64::Angle {
+        f64::Angle::new::<angle::radian>(
+            self.offset.0.get::<angle::radian>().hypot(self.offset.1.get::<angle::radian>())
+        )
+    }
+}
+
+/// Draws an arrow from the image center towards the target and the suggested slew rates.
+///
+/// `image_min`, `image_max`: screen coordinates of the camera image.
+pub fn draw_guidance(
+    ui: &imgui::Ui,
+    guidance: &Guidance,
+    image_min: [f32; 2],
+    image_max: [f32; 2],
+    field_of_view_y: f64::Angle,
+    display_units: &DisplayUnits
+) {
+    let size = [image_max[0] - image_min[0], image_max[1] - image_min[1]];
+    let center = [image_min[0] + size[0] / 2.0, image_min[1] + size[1] / 2.0];
+    let pixels_per_rad = size[1] as f64 / field_of_view_y.get::<angle::radian>();
+
+    // screen X points towards increasing azimuth, screen Y towards decreasing altitude
+    let offset = [
+        (guidance.offset.0.get::<angle::radian>() * pixels_per_rad) as f32,
+        (-guidance.offset.1.get::<angle::radian>() * pixels_per_rad) as f32
+    ];
+    let offset_len = offset[0].hypot(offset[1]);
+    if offset_len < 1.0 { return; }
+    let dir = [offset[0] / offset_len, offset[1] / offset_len];
+    let length = offset_len.clamp(30.0, 0.35 * size[0].min(size[1]));
+
+    let tip = [center[0] + dir[0] * length, center[1] + dir[1] * length];
+    let head = 15.0;
+    let normal = [-dir[1], dir[0]];
+    let head_base = [tip[0] - dir[0] * head, tip[1] - dir[1] * head];
+
+    let draw_list = ui.get_window_draw_list();
+    draw_list.add_line(center, head_base, ARROW_COLOR).thickness(3.0).build();
+    draw_list.add_triangle(
+        tip,
+        [head_base[0] + normal[0] * head / 2.0, head_base[1] + normal[1] * head / 2.0],
+        [head_base[0] - normal[0] * head / 2.0, head_base[1] - normal[1] * head / 2.0],
+        ARROW_COLOR
+    ).filled(true).build();
+
+    draw_list.add_text(
+        [tip[0] + dir[0] * 10.0, tip[1] + dir[1] * 10.0],
+        ARROW_COLOR,
+        format!(
+            "{} off\nslew az. {}\nslew alt. {}",
+            display_units.angle(guidance.total_offset(), AngleScale::Normal, 2),
+            display_units.angular_velocity(guidance.az_rate, 3),
+            display_units.angular_velocity(guidance.alt_rate, 3)
+        )
+    );
+}
@@ -0,0 +1,100 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use std::time::{Duration, Instant};
+
+/// Frame time above which quality is reduced.
+const MAX_FRAME_TIME: Duration = Duration::from_millis(40);
+
+/// Frame time below which quality is restored.
+const MIN_FRAME_TIME: Duration = Duration::from_millis(20);
+
+/// Minimum time between consecutive quality changes (lets the frame time settle after a change).
+const MIN_CHANGE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Weight of the newest frame time in the moving average.
+const FRAME_TIME_SMOOTHING: f64 = 0.1;
+
+#[derive(Copy, Clone)]
+pub struct QualityLevel {
+    /// Resolution of the camera view's render target relative to its on-screen size.
+    pub render_scale: f32,
+    /// Whether multisampling (as configured in render settings) is allowed.
+    pub multisampling: bool
+}
+
+/// From highest to lowest.
+const LEVELS: [QualityLevel; 4] = [
+    QualityLevel{ render_scale: 1.0, multisampling: true },
+    QualityLevel{ render_scale: 1.0, multisampling: false },
+    QualityLevel{ render_scale: 0.75, multisampling: false },
+    QualityLevel{ render_scale: 0.5, multisampling: false }
+];
+
+/// Monitors frame time and adjusts rendering quality, so that the GUI (and the built-in tracking controller
+/// driven by it) keeps a stable update rate on weak machines.
+pub struct QualityGovernor {
+    enabled: bool,
+    /// Index in `LEVELS`.
+    level: usize,
+    last_frame: Option<Instant>,
+    /// Moving average of frame time (seconds).
+    avg_frame_time: f64,
+    last_change: Instant
+}
+
+impl Default for QualityGovernor {
+    fn default() -> QualityGovernor {
+        QualityGovernor{
+            enabled: false,
+            level: 0,
+            last_frame: None,
+            avg_frame_time: 0.0,
+            last_change: Instant::now()
+        }
+    }
+}
+
+impl QualityGovernor {
+    pub fn enabled(&self) -> bool { self.enabled }
+
+    /// Disabling the governor restores the highest quality.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled { self.level = 0; }
+    }
+
+    pub fn level(&self) -> QualityLevel { LEVELS[self.level] }
+
+    /// Returns index of the current level (0 = highest quality).
+    pub fn level_index(&self) -> usize { self.level }
+
+    pub fn avg_frame_time(&self) -> Duration { Duration::from_secs_f64(self.avg_frame_time) }
+
+    /// Must be called once per frame.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            let frame_time = now.duration_since(last_frame).as_secs_f64();
+            self.avg_frame_time += FRAME_TIME_SMOOTHING * (frame_time - self.avg_frame_time);
+        }
+        self.last_frame = Some(now);
+
+        if !self.enabled || self.last_change.elapsed() < MIN_CHANGE_INTERVAL { return; }
+
+        if self.avg_frame_time > MAX_FRAME_TIME.as_secs_f64() && self.level + 1 < LEVELS.len() {
+            self.level += 1;
+            self.last_change = now;
+            log::info!("frame time {:.1} ms; reducing quality to level {}", self.avg_frame_time * 1000.0, self.level);
+        } else if self.avg_frame_time < MIN_FRAME_TIME.as_secs_f64() && self.level > 0 {
+            self.level -= 1;
+            self.last_change = now;
+            log::info!("frame time {:.1} ms; restoring quality to level {}", self.avg_frame_time * 1000.0, self.level);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! User-selected rendering quality of camera views (the quality governor, if enabled, may lower it further).
+
+use crate::{config, gui::draw_buffer::{DEFAULT_NUM_SAMPLES, Sampling}};
+
+/// Selectable MSAA sample counts (1 = multisampling disabled).
+pub const MSAA_SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/// Selectable supersampling factors (resolution of the render target relative to the on-screen size).
+pub const SUPERSAMPLING_FACTORS: [f32; 3] = [1.0, 1.5, 2.0];
+
+pub struct RenderSettings {
+    pub msaa_samples: u32,
+    /// Does not apply if a fixed sensor resolution is set.
+    pub supersampling: f32
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings{ msaa_samples: DEFAULT_NUM_SAMPLES, supersampling: 1.0 }
+    }
+}
+
+impl RenderSettings {
+    /// Creates render settings from the saved ones, replacing unsupported values with the defaults.
+    pub fn from_config(config: &config::RenderConfig) -> RenderSettings {
+        let default = RenderSettings::default();
+        let msaa_samples = if MSAA_SAMPLE_COUNTS.contains(&config.msaa_samples) {
+            config.msaa_samples
+        } else {
+            log::error!("unsupported number of MSAA samples: {}; using {}", config.msaa_samples, default.msaa_samples);
+            default.msaa_samples
+        };
+        let supersampling = if SUPERSAMPLING_FACTORS.contains(&config.supersampling) {
+            config.supersampling
+        } else {
+            log::error!("unsupported supersampling factor: {}; using {}", config.supersampling, default.supersampling);
+            default.supersampling
+        };
+
+        RenderSettings{ msaa_samples, supersampling }
+    }
+
+    /// Returns the sampling mode to use; `multisampling`: whether multisampling is allowed by the quality governor.
+    pub fn sampling(&self, multisampling: bool) -> Sampling {
+        if multisampling && self.msaa_samples > 1 { Sampling::Multi(self.msaa_samples) } else { Sampling::Single }
+    }
+}
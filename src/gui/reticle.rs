@@ -0,0 +1,110 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Reticle overlays drawn over the camera image.
+
+use pointing_utils::uom;
+use uom::{si::f64, si::angle};
+
+/// Maximum number of marks (dots, circles) drawn from the center outwards.
+const MAX_MARKS: usize = 50;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReticleStyle {
+    None,
+    Crosshair,
+    /// Crosshair with dots at `Reticle::spacing` intervals.
+    MilDot,
+    /// Concentric circles spaced by `Reticle::spacing`.
+    Circles
+}
+
+impl ReticleStyle {
+    pub const ALL: [ReticleStyle; 4] = [
+        ReticleStyle::None,
+        ReticleStyle::Crosshair,
+        ReticleStyle::MilDot,
+        ReticleStyle::Circles
+    ];
+}
+
+impl std::fmt::Display for ReticleStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ReticleStyle::None => "none",
+            ReticleStyle::Crosshair => "crosshair",
+            ReticleStyle::MilDot => "mil-dot",
+            ReticleStyle::Circles => "circles"
+        })
+    }
+}
+
+pub struct Reticle {
+    pub style: ReticleStyle,
+    /// Angular spacing of dots or circles.
+    pub spacing: f64::Angle,
+    pub color: [f32; 4],
+    pub thickness: f32
+}
+
+impl Default for Reticle {
+    fn default() -> Reticle {
+        Reticle{
+            style: ReticleStyle::Crosshair,
+            // 1 mil
+            spacing: f64::Angle::new::<angle::radian>(0.001),
+            color: [1.0, 0.2, 0.2, 0.8],
+            thickness: 1.0
+        }
+    }
+}
+
+impl Reticle {
+    /// Draws the reticle centered on the camera image (`image_min`, `image_max`: its screen coordinates).
+    pub fn draw(&self, ui: &imgui::Ui, image_min: [f32; 2], image_max: [f32; 2], field_of_view_y: f64::Angle) {
+        if self.style == ReticleStyle::None { return; }
+
+        let size = [image_max[0] - image_min[0], image_max[1] - image_min[1]];
+        let center = [image_min[0] + size[0] / 2.0, image_min[1] + size[1] / 2.0];
+        // the image spans `field_of_view_y` vertically; small-angle approximation is used for the marks
+        let spacing_px = (self.spacing / field_of_view_y).value as f32 * size[1];
+        let max_extent = size[0].max(size[1]) / 2.0;
+
+        let draw_list = ui.get_window_draw_list();
+        draw_list.with_clip_rect_intersect(image_min, image_max, || {
+            if self.style != ReticleStyle::Circles {
+                draw_list.add_line([image_min[0], center[1]], [image_max[0], center[1]], self.color)
+                    .thickness(self.thickness).build();
+                draw_list.add_line([center[0], image_min[1]], [center[0], image_max[1]], self.color)
+                    .thickness(self.thickness).build();
+            }
+
+            // skip marks too dense to be distinguishable
+            if spacing_px < 3.0 { return; }
+            let marks = (1..=MAX_MARKS).map(|i| i as f32 * spacing_px).take_while(|r| *r <= max_extent);
+
+            match self.style {
+                ReticleStyle::MilDot => for r in marks {
+                    for (dx, dy) in [(r, 0.0), (-r, 0.0), (0.0, r), (0.0, -r)] {
+                        draw_list.add_circle([center[0] + dx, center[1] + dy], 1.5 * self.thickness + 1.0, self.color)
+                            .filled(true).build();
+                    }
+                },
+
+                ReticleStyle::Circles => {
+                    draw_list.add_circle(center, 2.0 * self.thickness, self.color).filled(true).build();
+                    for r in marks {
+                        draw_list.add_circle(center, r, self.color).num_segments(64).thickness(self.thickness).build();
+                    }
+                },
+
+                _ => ()
+            }
+        });
+    }
+}
@@ -0,0 +1,117 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! State of the scenario editor window.
+
+use crate::{scenario, scenario::Scenario, workers::TargetDefinition};
+use std::path::{Path, PathBuf};
+
+pub struct ScenarioEditor {
+    pub scenario: Scenario,
+    /// File name (without extension) in the scenarios directory.
+    pub name: String,
+    /// Files available for loading.
+    files: Vec<PathBuf>,
+    /// Index in `files`.
+    pub file_idx: usize,
+    /// Index of the target being edited.
+    pub target_idx: usize
+}
+
+impl Default for ScenarioEditor {
+    fn default() -> ScenarioEditor {
+        ScenarioEditor{
+            scenario: Scenario::default(),
+            name: "scenario".into(),
+            files: scenario::list_scenarios(),
+            file_idx: 0,
+            target_idx: 0
+        }
+    }
+}
+
+impl ScenarioEditor {
+    /// Creates an editor of the scenario loaded from `path`.
+    pub fn new(scenario: Scenario, path: &Path) -> ScenarioEditor {
+        let name = path.file_stem().map_or("scenario".into(), |stem| stem.to_string_lossy().into_owned());
+        ScenarioEditor{ scenario, name, ..Default::default() }
+    }
+
+    pub fn files(&self) -> &[PathBuf] { &self.files }
+
+    pub fn refresh_files(&mut self) {
+        self.files = scenario::list_scenarios();
+        self.file_idx = self.file_idx.min(self.files.len().saturating_sub(1));
+    }
+
+    /// Loads the selected file.
+    pub fn load_selected(&mut self) -> Result<(), String> {
+        let path = self.files.get(self.file_idx).ok_or_else(|| "no scenario selected".to_string())?;
+        self.scenario = Scenario::load(path)?;
+        self.name = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+        self.target_idx = 0;
+        Ok(())
+    }
+
+    /// Saves the scenario in the scenarios directory; returns the file's path.
+    pub fn save(&mut self) -> Result<PathBuf, String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.contains(['/', '\\']) { return Err(format!("invalid scenario name: \"{}\"", name)); }
+        let dir = scenario::scenarios_dir().ok_or_else(|| "cannot determine configuration directory".to_string())?;
+        let path = dir.join(format!("{}.toml", name));
+        self.scenario.save(&path)?;
+        self.refresh_files();
+        Ok(path)
+    }
+
+    /// Adds a target with the next free ID (a copy of the one being edited, if any).
+    pub fn add_target(&mut self) {
+        let id = self.scenario.targets.iter().map(|target| target.id + 1).max().unwrap_or(1);
+        let target = self.scenario.targets.get(self.target_idx).cloned()
+            .unwrap_or_else(|| TargetDefinition::default_near(&self.scenario.observer.site()));
+        self.scenario.targets.push(TargetDefinition{ id, ..target });
+        self.target_idx = self.scenario.targets.len() - 1;
+    }
+
+    pub fn remove_target(&mut self) {
+        if self.target_idx < self.scenario.targets.len() {
+            self.scenario.targets.remove(self.target_idx);
+            self.target_idx = self.target_idx.min(self.scenario.targets.len().saturating_sub(1));
+        }
+    }
+}
+
+/// Restarts the simulator with the given scenario (keeping other command-line arguments). Returns only
+/// on failure.
+pub fn relaunch(path: &Path) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut args = vec![];
+    let mut old_args = std::env::args().skip(1);
+    while let Some(arg) = old_args.next() {
+        if arg == "--scenario" {
+            old_args.next();
+        } else if !arg.starts_with("--scenario=") {
+            args.push(arg);
+        }
+    }
+    args.push("--scenario".into());
+    args.push(path.to_string_lossy().into_owned());
+
+    log::info!("restarting with scenario {}", path.display());
+    // listening sockets are closed on exec, so the new process can bind the same ports
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        Err(std::process::Command::new(exe).args(args).exec().to_string())
+    }
+    #[cfg(not(unix))]
+    {
+        std::process::Command::new(exe).args(args).spawn().map_err(|e| e.to_string())?;
+        std::process::exit(0);
+    }
+}
@@ -0,0 +1,72 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Simple atmospheric seeing model (applied by `resources/shaders/seeing.glsl`).
+//!
+//! The long-exposure image of a point source is a Gaussian of the configured FWHM. Half of its variance comes
+//! from image motion (tip-tilt), the other half from a short-exposure blur whose width also fluctuates; both
+//! change at the "boil rate".
+
+use pointing_utils::uom;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::time::Instant;
+use uom::{si::f64, si::angle};
+
+/// Ratio of FWHM to standard deviation of a Gaussian.
+const FWHM_TO_SIGMA: f64 = 2.3548;
+
+const NUM_HARMONICS: usize = 8;
+
+/// Relative fluctuation (standard deviation) of the short-exposure blur width.
+const BLUR_FLUCTUATION: f64 = 0.2;
+
+/// (relative frequency, phase) of harmonics of a unit-variance, zero-mean random process.
+type Harmonics = [(f64, f64); NUM_HARMONICS];
+
+#[derive(Clone)]
+pub struct Seeing {
+    /// Long-exposure FWHM.
+    pub fwhm: f64::Angle,
+    /// Mean frequency (Hz) of the changes.
+    pub boil_rate: f64,
+    /// Harmonics of: image motion (X, Y), blur width.
+    harmonics: [Harmonics; 3],
+    start: Instant
+}
+
+impl Default for Seeing {
+    fn default() -> Seeing {
+        Seeing::new(f64::Angle::new::<angle::second>(2.0), 10.0, 0)
+    }
+}
+
+impl Seeing {
+    pub fn new(fwhm: f64::Angle, boil_rate: f64, seed: u64) -> Seeing {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let harmonics = [(); 3].map(|_| [(); NUM_HARMONICS].map(|_| {
+            (rng.gen_range(0.2..1.8), rng.gen_range(0.0..2.0 * std::f64::consts::PI))
+        }));
+        Seeing{ fwhm, boil_rate, harmonics, start: Instant::now() }
+    }
+
+    /// Returns current standard deviation of the blur kernel and image motion (X, Y).
+    pub fn current(&self) -> (f64::Angle, [f64::Angle; 2]) {
+        let t = self.start.elapsed().as_secs_f64();
+        let value = |harmonics: &Harmonics| {
+            (2.0 / NUM_HARMONICS as f64).sqrt() * harmonics.iter()
+                .map(|(freq, phase)| (2.0 * std::f64::consts::PI * freq * self.boil_rate * t + phase).sin())
+                .sum::<f64>()
+        };
+
+        // image motion and short-exposure blur contribute equal variances
+        let component_sigma = self.fwhm / FWHM_TO_SIGMA / 2.0f64.sqrt();
+        let blur = component_sigma * (1.0 + BLUR_FLUCTUATION * value(&self.harmonics[2])).max(0.0);
+
+        (blur, [component_sigma * value(&self.harmonics[0]), component_sigma * value(&self.harmonics[1])])
+    }
+}
@@ -0,0 +1,33 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Settings of the simulated image sensor noise (applied by `resources/shaders/sensor_noise.glsl`).
+
+#[derive(Clone, Debug)]
+pub struct SensorNoise {
+    /// Standard deviation of the read noise (fraction of full scale).
+    pub read_noise: f32,
+    /// Full well capacity (electrons) corresponding to full scale; the lower, the stronger the shot noise.
+    /// 0 disables shot noise.
+    pub full_well: f32,
+    /// Fraction of hot pixels.
+    pub hot_pixel_fraction: f32,
+    /// Determines the (persistent) hot pixel map.
+    pub hot_pixel_seed: u32
+}
+
+impl Default for SensorNoise {
+    fn default() -> SensorNoise {
+        SensorNoise{
+            read_noise: 0.01,
+            full_well: 1000.0,
+            hot_pixel_fraction: 0.0005,
+            hot_pixel_seed: 0
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Helpers of the session playback window (see `--replay-session`).
+
+use crate::workers::{Session, TrafficSample};
+
+/// Range of playback speeds (relative to real time).
+pub const MIN_SPEED: f64 = 0.05;
+pub const MAX_SPEED: f64 = 20.0;
+
+/// Traffic recorded within this time (s) before the playback position is shown.
+const TRAFFIC_WINDOW: f64 = 10.0;
+
+/// Returns traffic messages recorded shortly before `position`, oldest first.
+pub fn recent_traffic(session: &Session, position: f64) -> &[TrafficSample] {
+    let end = session.traffic.partition_point(|sample| sample.t <= position);
+    let start = session.traffic[..end].partition_point(|sample| sample.t < position - TRAFFIC_WINDOW);
+    &session.traffic[start..end]
+}
+
+/// Returns the message as a single line of text.
+pub fn format_traffic(sample: &TrafficSample) -> String {
+    format!(
+        "{:9.3} {} {} {}",
+        sample.t,
+        sample.link,
+        if sample.to_client { "->" } else { "<-" },
+        sample.text
+    )
+}
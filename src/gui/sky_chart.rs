@@ -0,0 +1,156 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Polar az/alt chart of the mount's boresight and the target.
+//!
+//! The zenith is at the center and the horizon at the edge; north is up and east to the left (as seen when looking
+//! up at the sky).
+
+use cgmath::EuclideanSpace;
+use crate::{target_geometry::TargetDirection, units, workers::{Mount, MountMode, MountState}};
+use pointing_utils::{TargetInfoMessage, uom};
+use std::{collections::VecDeque, time::{Duration, Instant}};
+use uom::{si::f64, si::angle};
+
+const TRAIL_INTERVAL: Duration = Duration::from_millis(200);
+
+const TRAIL_DURATION: Duration = Duration::from_secs(60);
+
+const GRID_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 0.8];
+const LIMIT_COLOR: [f32; 4] = [1.0, 0.2, 0.2, 0.25];
+const BORESIGHT_COLOR: [f32; 4] = [0.2, 1.0, 0.2, 1.0];
+const TARGET_COLOR: [f32; 4] = [1.0, 1.0, 0.3, 1.0];
+
+/// Altitudes (degrees) of the grid circles.
+const GRID_ALTITUDES: [f64; 2] = [30.0, 60.0];
+
+#[derive(Default)]
+pub struct SkyChart {
+    /// Recent boresight directions (azimuth, altitude), oldest first.
+    boresight_trail: VecDeque<(Instant, f64::Angle, f64::Angle)>
+}
+
+/// Returns azimuth and altitude of a point in the local frame.
+fn direction(point: &cgmath::Point3<f64>) -> (f64::Angle, f64::Angle) {
+    // local frame: x points north, y west, z up
+    (
+        units::azimuth(point.to_vec()),
+        f64::Angle::new::<angle::radian>(point.z.atan2(point.x.hypot(point.y)))
+    )
+}
+
+impl SkyChart {
+    /// Records the boresight direction; to be called every frame.
+    pub fn update(&mut self, mount_state: &MountState) {
+        let now = Instant::now();
+        if self.boresight_trail.back().map_or(true, |(t, _, _)| now - *t >= TRAIL_INTERVAL) {
+            self.boresight_trail.push_back((now, mount_state.boresight_az, mount_state.boresight_alt));
+        }
+        while self.boresight_trail.front().map_or(false, |(t, _, _)| now - *t > TRAIL_DURATION) {
+            self.boresight_trail.pop_front();
+        }
+    }
+
+    /// Draws the chart in the remaining space of the current window; `target_history`: recent target positions
+    /// in the local frame.
+    pub fn draw(
+        &self,
+        ui: &imgui::Ui,
+        mount: &Mount,
+        mount_state: &MountState,
+        target: Option<&TargetInfoMessage>,
+        target_history: &[cgmath::Point3<f64>]
+    ) {
+        let avail = ui.content_region_avail();
+        let size = avail[0].min(avail[1]).max(50.0);
+        let origin = ui.cursor_screen_pos();
+        ui.invisible_button("##sky_chart", [size, size]);
+
+        let center = [origin[0] + size / 2.0, origin[1] + size / 2.0];
+        // leave room for the cardinal direction labels
+        let radius = size / 2.0 - ui.text_line_height() - 2.0;
+        let radius_at = |alt: f64| (radius * (1.0 - alt / 90.0) as f32).max(0.0);
+        let to_screen = |az: f64::Angle, alt: f64::Angle| {
+            let r = radius_at(alt.get::<angle::degree>().max(0.0));
+            let az = az.get::<angle::radian>() as f32;
+            [center[0] - r * az.sin(), center[1] - r * az.cos()]
+        };
+
+        let draw_list = ui.get_window_draw_list();
+
+        if matches!(mount.mode(), MountMode::AltAz) {
+            let config = mount.config();
+            if let Some(max_alt) = config.axis2.max_pos_deg.filter(|alt| *alt < 90.0) {
+                draw_list.add_circle(center, radius_at(max_alt), LIMIT_COLOR).filled(true).build();
+            }
+            if let Some(min_alt) = config.axis2.min_pos_deg.filter(|alt| *alt > 0.0) {
+                // an annulus between the limit and the horizon
+                let inner = radius_at(min_alt);
+                draw_list.add_circle(center, (inner + radius) / 2.0, LIMIT_COLOR)
+                    .thickness(radius - inner)
+                    .num_segments(64)
+                    .build();
+            }
+            if let (Some(min_az), Some(max_az)) = (config.axis1.min_pos_deg, config.axis1.max_pos_deg) {
+                if max_az - min_az < 360.0 {
+                    for az in [min_az, max_az] {
+                        let edge = to_screen(
+                            f64::Angle::new::<angle::degree>(az),
+                            f64::Angle::new::<angle::degree>(0.0)
+                        );
+                        draw_list.add_line(center, edge, LIMIT_COLOR).thickness(2.0).build();
+                    }
+                }
+            }
+        }
+
+        draw_list.add_circle(center, radius, GRID_COLOR).num_segments(64).build();
+        for alt in GRID_ALTITUDES {
+            draw_list.add_circle(center, radius_at(alt), GRID_COLOR).num_segments(64).build();
+        }
+        draw_list.add_line([center[0] - radius, center[1]], [center[0] + radius, center[1]], GRID_COLOR).build();
+        draw_list.add_line([center[0], center[1] - radius], [center[0], center[1] + radius], GRID_COLOR).build();
+        let label_offset = radius + ui.text_line_height() / 2.0 + 2.0;
+        for (label, dx, dy) in [("N", 0.0, -1.0), ("S", 0.0, 1.0), ("E", -1.0, 0.0), ("W", 1.0, 0.0)] {
+            let text_size = ui.calc_text_size(label);
+            let pos = [
+                center[0] + dx * label_offset - text_size[0] / 2.0,
+                center[1] + dy * label_offset - text_size[1] / 2.0
+            ];
+            draw_list.add_text(pos, GRID_COLOR, label);
+        }
+
+        let target_trail: Vec<[f32; 2]> = target_history.iter()
+            .map(direction)
+            .filter(|(_, alt)| alt.get::<angle::degree>() >= 0.0)
+            .map(|(az, alt)| to_screen(az, alt))
+            .collect();
+        if target_trail.len() > 1 {
+            draw_list.add_polyline(target_trail, [TARGET_COLOR[0], TARGET_COLOR[1], TARGET_COLOR[2], 0.5]).build();
+        }
+        if let Some(target) = target.and_then(TargetDirection::from_message) {
+            draw_list.add_circle(to_screen(target.az, target.alt), 4.0, TARGET_COLOR).filled(true).build();
+        }
+
+        let boresight_trail: Vec<[f32; 2]> = self.boresight_trail.iter()
+            .map(|(_, az, alt)| to_screen(*az, *alt))
+            .collect();
+        if boresight_trail.len() > 1 {
+            draw_list.add_polyline(
+                boresight_trail,
+                [BORESIGHT_COLOR[0], BORESIGHT_COLOR[1], BORESIGHT_COLOR[2], 0.5]
+            ).build();
+        }
+        let boresight = to_screen(mount_state.boresight_az, mount_state.boresight_alt);
+        draw_list.add_circle(boresight, 5.0, BORESIGHT_COLOR).thickness(2.0).build();
+        draw_list.add_line([boresight[0] - 8.0, boresight[1]], [boresight[0] + 8.0, boresight[1]], BORESIGHT_COLOR)
+            .build();
+        draw_list.add_line([boresight[0], boresight[1] - 8.0], [boresight[0], boresight[1] + 8.0], BORESIGHT_COLOR)
+            .build();
+    }
+}
@@ -0,0 +1,195 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use cgmath::{EuclideanSpace, InnerSpace};
+use crate::{
+    config::MountConfig,
+    data::Station,
+    target_geometry::TargetDirection,
+    units::{self, AngleScale, DisplayUnits},
+    workers::MountMode
+};
+use pointing_utils::uom;
+use serde::Serialize;
+use std::fmt::Write;
+use uom::{si::f64, si::{angle, angular_velocity, length}};
+
+#[derive(Serialize)]
+pub struct MountSnapshot {
+    pub mode: String,
+    pub axis1_pos_deg: f64,
+    pub axis2_pos_deg: f64,
+    pub axis1_spd_deg_per_s: f64,
+    pub axis2_spd_deg_per_s: f64,
+    pub boresight_az_deg: f64,
+    pub boresight_alt_deg: f64,
+    pub pier_side: Option<String>,
+    pub parked: bool,
+    pub client: String
+}
+
+#[derive(Serialize)]
+pub struct TargetSnapshot {
+    pub az_deg: f64,
+    pub alt_deg: f64,
+    pub az_rate_deg_per_s: f64,
+    pub alt_rate_deg_per_s: f64,
+    pub distance_m: f64
+}
+
+#[derive(Serialize)]
+pub struct TrackingSnapshot {
+    pub enabled: bool,
+    pub settings: String,
+    pub error_az_arcsec: Option<f64>,
+    pub error_alt_arcsec: Option<f64>,
+    /// RMS of the current error history.
+    pub rms_error_arcsec: Option<f64>
+}
+
+/// State of a station, for pasting exact conditions into bug reports.
+#[derive(Serialize)]
+pub struct StateSnapshot {
+    pub station: String,
+    pub mount: MountSnapshot,
+    pub target: Option<TargetSnapshot>,
+    pub tracking: TrackingSnapshot,
+    /// Mount configuration values differing from the defaults (`<key> = <value>`).
+    pub config_diff: Vec<String>
+}
+
+impl StateSnapshot {
+    pub fn new(station: &Station) -> StateSnapshot {
+        let mount = &station.mount;
+        let state = mount.get();
+        let controller = station.tracking_controller.borrow();
+
+        let target = controller.last_target().and_then(|msg| {
+            TargetDirection::from_message(msg).map(|dir| TargetSnapshot{
+                az_deg: dir.az.get::<angle::degree>(),
+                alt_deg: dir.alt.get::<angle::degree>(),
+                az_rate_deg_per_s: dir.az_rate.get::<angular_velocity::degree_per_second>(),
+                alt_rate_deg_per_s: dir.alt_rate.get::<angular_velocity::degree_per_second>(),
+                distance_m: msg.position.0.to_vec().magnitude()
+            })
+        });
+
+        let history = &controller.error_histories().last().unwrap().values;
+        let rms_error_arcsec = if history.is_empty() {
+            None
+        } else {
+            Some((history.iter().map(|e| (*e as f64).powi(2)).sum::<f64>() / history.len() as f64).sqrt())
+        };
+
+        StateSnapshot{
+            station: station.name.clone(),
+            mount: MountSnapshot{
+                mode: match mount.mode() {
+                    MountMode::AltAz => "alt-az".to_string(),
+                    MountMode::Equatorial(settings) =>
+                        format!("equatorial (latitude {:.4}°)", settings.latitude.get::<angle::degree>())
+                },
+                axis1_pos_deg: state.axis1_pos.get::<angle::degree>(),
+                axis2_pos_deg: state.axis2_pos.get::<angle::degree>(),
+                axis1_spd_deg_per_s: state.axis1_spd.get::<angular_velocity::degree_per_second>(),
+                axis2_spd_deg_per_s: state.axis2_spd.get::<angular_velocity::degree_per_second>(),
+                boresight_az_deg: state.boresight_az.get::<angle::degree>(),
+                boresight_alt_deg: state.boresight_alt.get::<angle::degree>(),
+                pier_side: state.pier_side.map(|side| side.to_string()),
+                parked: mount.is_parked(),
+                client: mount.client_status().to_string()
+            },
+            target,
+            tracking: TrackingSnapshot{
+                enabled: controller.enabled(),
+                settings: controller.settings().to_string(),
+                error_az_arcsec: controller.error().map(|e| e.0.get::<angle::second>()),
+                error_alt_arcsec: controller.error().map(|e| e.1.get::<angle::second>()),
+                rms_error_arcsec
+            },
+            config_diff: config_diff(mount.config())
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|e| format!("serialization error: {}", e))
+    }
+
+    /// Returns the snapshot as text, in the selected display units (JSON uses the units given in field names).
+    pub fn to_text(&self, display_units: &DisplayUnits) -> String {
+        let angle = |deg: f64| display_units.angle(units::deg(deg), AngleScale::Normal, 5);
+        let rate = |deg_per_s: f64| display_units.angular_velocity(
+            f64::AngularVelocity::new::<angular_velocity::degree_per_second>(deg_per_s),
+            5
+        );
+        let error = |arcsec: Option<f64>| match arcsec {
+            Some(arcsec) => display_units.angle(f64::Angle::new::<angle::second>(arcsec), AngleScale::Fine, 1),
+            None => "-".to_string()
+        };
+
+        let mut s = String::new();
+        let m = &self.mount;
+        let _ = writeln!(s, "station: {}", self.station);
+        let _ = writeln!(s, "mount: {}, client {}{}", m.mode, m.client, if m.parked { ", parked" } else { "" });
+        let _ = writeln!(s, "  axis 1: {} ({})", angle(m.axis1_pos_deg), rate(m.axis1_spd_deg_per_s));
+        let _ = writeln!(s, "  axis 2: {} ({})", angle(m.axis2_pos_deg), rate(m.axis2_spd_deg_per_s));
+        let _ = writeln!(s, "  boresight: az. {}, alt. {}", angle(m.boresight_az_deg), angle(m.boresight_alt_deg));
+        if let Some(pier_side) = &m.pier_side {
+            let _ = writeln!(s, "  pier side: {}", pier_side);
+        }
+        match &self.target {
+            Some(t) => {
+                let _ = writeln!(
+                    s, "target: az. {}, alt. {}, rates {}, {}, distance {}",
+                    angle(t.az_deg), angle(t.alt_deg), rate(t.az_rate_deg_per_s), rate(t.alt_rate_deg_per_s),
+                    display_units.length(f64::Length::new::<length::meter>(t.distance_m), 0)
+                );
+            },
+            None => { let _ = writeln!(s, "target: -"); }
+        }
+        let t = &self.tracking;
+        let _ = writeln!(
+            s, "tracking: {} ({}), error az. {}, alt. {}, RMS {}",
+            if t.enabled { "enabled" } else { "disabled" }, t.settings,
+            error(t.error_az_arcsec), error(t.error_alt_arcsec), error(t.rms_error_arcsec)
+        );
+        if self.config_diff.is_empty() {
+            let _ = writeln!(s, "config: default");
+        } else {
+            let _ = writeln!(s, "config (non-default):");
+            for entry in &self.config_diff {
+                let _ = writeln!(s, "  {}", entry);
+            }
+        }
+
+        s
+    }
+}
+
+/// Returns `<key> = <value>` entries of `config` which differ from the default configuration.
+fn config_diff(config: &MountConfig) -> Vec<String> {
+    fn diff(prefix: &str, value: &serde_json::Value, default: &serde_json::Value, result: &mut Vec<String>) {
+        match (value, default) {
+            (serde_json::Value::Object(fields), serde_json::Value::Object(default_fields)) => {
+                for (name, field) in fields {
+                    let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+                    let default_field = default_fields.get(name).unwrap_or(&serde_json::Value::Null);
+                    diff(&key, field, default_field, result);
+                }
+            },
+            _ => if value != default { result.push(format!("{} = {}", prefix, value)); }
+        }
+    }
+
+    let mut result = vec![];
+    match (serde_json::to_value(config), serde_json::to_value(MountConfig::default())) {
+        (Ok(value), Ok(default)) => diff("", &value, &default, &mut result),
+        _ => log::error!("failed to serialize mount configuration")
+    }
+    result
+}
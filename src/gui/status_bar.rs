@@ -0,0 +1,95 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Connection and simulation state shown in the main menu bar.
+
+use crate::workers::{ClientStatus, StatusUpdate};
+use std::{collections::BTreeMap, time::Instant};
+
+const WARNING_COLOR: [f32; 4] = [1.0, 0.4, 0.3, 1.0];
+
+struct FeedStatus {
+    clients: usize,
+    messages_per_s: f64
+}
+
+struct MountStatus {
+    client: ClientStatus,
+    messages_per_s: f64
+}
+
+pub struct StatusBar {
+    receiver: Option<crossbeam::channel::Receiver<StatusUpdate>>,
+    /// Keyed by port.
+    feeds: BTreeMap<u16, FeedStatus>,
+    /// Keyed by mount instance.
+    mounts: BTreeMap<usize, MountStatus>,
+    start: Instant
+}
+
+impl Default for StatusBar {
+    fn default() -> StatusBar {
+        StatusBar{ receiver: None, feeds: BTreeMap::new(), mounts: BTreeMap::new(), start: Instant::now() }
+    }
+}
+
+impl StatusBar {
+    pub fn set_receiver(&mut self, receiver: crossbeam::channel::Receiver<StatusUpdate>) {
+        self.receiver = Some(receiver);
+    }
+
+    /// Processes status updates received from the workers.
+    pub fn update(&mut self) {
+        let receiver = match &self.receiver {
+            Some(receiver) => receiver,
+            None => return
+        };
+        for update in receiver.try_iter() {
+            match update {
+                StatusUpdate::TargetFeed{ port, clients, messages_per_s } => {
+                    self.feeds.insert(port, FeedStatus{ clients, messages_per_s });
+                },
+                StatusUpdate::Mount{ instance, client, messages_per_s } => {
+                    self.mounts.insert(instance, MountStatus{ client, messages_per_s });
+                }
+            }
+        }
+    }
+
+    /// Returns the number of clients of each target feed: (port, clients).
+    pub fn feed_clients(&self) -> Vec<(u16, usize)> {
+        self.feeds.iter().map(|(port, feed)| (*port, feed.clients)).collect()
+    }
+
+    /// Draws the status in the current menu bar; `sim_time`: time of the sky model (if enabled).
+    pub fn draw(&self, ui: &imgui::Ui, sim_time: Option<chrono::DateTime<chrono::Utc>>) {
+        let clients: usize = self.feeds.values().map(|feed| feed.clients).sum();
+        let rate: f64 = self.feeds.values().map(|feed| feed.messages_per_s).sum();
+        ui.separator();
+        ui.text(format!("target feeds: {} client(s), {:.0} msg/s", clients, rate));
+
+        for (instance, mount) in &self.mounts {
+            ui.separator();
+            let text = format!("mount {}: {} ({:.0} msg/s)", instance + 1, mount.client, mount.messages_per_s);
+            if mount.client == ClientStatus::TimedOut {
+                ui.text_colored(WARNING_COLOR, text);
+            } else {
+                ui.text(text);
+            }
+        }
+
+        ui.separator();
+        match sim_time {
+            Some(time) => ui.text(format!("sim. time: {}", time.format("%Y-%m-%d %H:%M:%S UTC"))),
+            None => {
+                let elapsed = self.start.elapsed().as_secs();
+                ui.text(format!("running: {:02}:{:02}:{:02}", elapsed / 3600, elapsed / 60 % 60, elapsed % 60));
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! GUI color themes and their editor.
+
+use crate::config::{ThemeBase, ThemeConfig};
+use imgui::StyleColor;
+
+const MAX_ROUNDING: f32 = 12.0;
+
+/// Replaces the imgui style's colors and rounding with those of `theme`.
+pub fn apply(theme: &ThemeConfig) {
+    // same as `imgui::Context::style_mut` (the context is not accessible from the GUI code)
+    let style = unsafe { &mut *(imgui::sys::igGetStyle() as *mut imgui::Style) };
+    match theme.base {
+        ThemeBase::Dark => { style.use_dark_colors(); },
+        ThemeBase::Light => { style.use_light_colors(); },
+        ThemeBase::Classic => { style.use_classic_colors(); },
+        ThemeBase::Night => {
+            style.use_dark_colors();
+            // brightness of each color mapped to (dimmed) red
+            for color in style.colors.iter_mut() {
+                let luminance = 0.3 * color[0] + 0.59 * color[1] + 0.11 * color[2];
+                *color = [0.8 * luminance, 0.05 * luminance, 0.03 * luminance, color[3]];
+            }
+        }
+    }
+    style.window_rounding = theme.window_rounding;
+    style.child_rounding = theme.window_rounding;
+    style.popup_rounding = theme.window_rounding;
+    style.frame_rounding = theme.frame_rounding;
+    style.grab_rounding = theme.frame_rounding;
+
+    for color in StyleColor::VARIANTS {
+        if let Some(value) = theme.colors.get(color.name()) { style[color] = *value; }
+    }
+}
+
+/// Shows the theme editor; returns true if `theme` has been changed.
+pub fn controls(theme: &mut ThemeConfig, ui: &imgui::Ui) -> bool {
+    let mut changed = false;
+
+    let labels = ThemeBase::ALL.map(|base| base.to_string());
+    let mut base_idx = ThemeBase::ALL.iter().position(|base| *base == theme.base).unwrap_or(0);
+    if ui.combo_simple_string("theme", &mut base_idx, &labels) {
+        theme.base = ThemeBase::ALL[base_idx];
+        changed = true;
+    }
+    changed |= ui.slider("window rounding", 0.0, MAX_ROUNDING, &mut theme.window_rounding);
+    changed |= ui.slider("frame rounding", 0.0, MAX_ROUNDING, &mut theme.frame_rounding);
+
+    if let Some(_node) = ui.tree_node("Colors") {
+        ui.text_disabled(format!("{} color(s) customized", theme.colors.len()));
+        ui.same_line();
+        if ui.small_button("reset") {
+            theme.colors.clear();
+            changed = true;
+        }
+        for color in StyleColor::VARIANTS {
+            let mut value = ui.style_color(color);
+            if ui.color_edit4_config(color.name(), &mut value).alpha_bar(true).build() {
+                theme.colors.insert(color.name().to_string(), value);
+                changed = true;
+            }
+        }
+    }
+
+    changed
+}
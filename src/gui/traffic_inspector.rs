@@ -0,0 +1,51 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Filtering of captured protocol messages shown in the traffic inspector window.
+
+use crate::workers::TrafficEntry;
+
+pub const PARSE_ERROR_COLOR: [f32; 4] = [1.0, 0.35, 0.35, 1.0];
+
+pub struct TrafficInspector {
+    /// Only messages whose text or link contains this text (case-insensitive) are shown.
+    pub search: String,
+    /// If set, only messages which could not be parsed are shown.
+    pub errors_only: bool,
+    /// If set, the view follows new messages (unless scrolled up).
+    pub auto_scroll: bool
+}
+
+impl Default for TrafficInspector {
+    fn default() -> TrafficInspector {
+        TrafficInspector{ search: String::new(), errors_only: false, auto_scroll: true }
+    }
+}
+
+impl TrafficInspector {
+    pub fn matches(&self, entry: &TrafficEntry) -> bool {
+        if self.errors_only && entry.parse_error.is_none() { return false; }
+        if self.search.is_empty() { return true; }
+
+        let search = self.search.to_lowercase();
+        entry.text.to_lowercase().contains(&search) || entry.link.to_lowercase().contains(&search)
+    }
+}
+
+/// Returns the entry as a single line of text.
+pub fn format_entry(entry: &TrafficEntry) -> String {
+    let mut text = format!(
+        "{} {} {} {}",
+        entry.time.format("%H:%M:%S%.3f"),
+        entry.link,
+        entry.direction,
+        entry.text
+    );
+    if let Some(error) = &entry.parse_error { text += &format!("  [{}]", error); }
+    text
+}
@@ -0,0 +1,95 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Picture-in-picture inset showing a digitally magnified crop of the camera image.
+
+use crate::{gui::CameraView, workers::MountState};
+
+/// Size of the inset relative to the camera image.
+const INSET_SIZE: f32 = 0.3;
+
+/// Distance (pixels) of the inset from the image's edges.
+const INSET_MARGIN: f32 = 8.0;
+
+const BORDER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.8];
+
+pub const MIN_ZOOM: f32 = 1.5;
+pub const MAX_ZOOM: f32 = 32.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InsetCenter {
+    Target,
+    Boresight
+}
+
+pub struct ZoomInset {
+    pub enabled: bool,
+    /// The inset is centered on the boresight if the target is not in view.
+    pub center: InsetCenter,
+    /// Magnification relative to the camera image.
+    pub zoom: f32
+}
+
+impl Default for ZoomInset {
+    fn default() -> ZoomInset {
+        ZoomInset{ enabled: false, center: InsetCenter::Target, zoom: 4.0 }
+    }
+}
+
+impl ZoomInset {
+    /// Draws the inset in the bottom right corner of the camera image (`image_min`, `image_max`: its screen
+    /// coordinates).
+    pub fn draw(
+        &self,
+        ui: &imgui::Ui,
+        camera_view: &CameraView,
+        image_min: [f32; 2],
+        image_max: [f32; 2],
+        mount_state: &MountState
+    ) {
+        if !self.enabled { return; }
+
+        let boresight = || camera_view.direction_image_position(mount_state.boresight_az, mount_state.boresight_alt);
+        let (center, label) = match self.center {
+            InsetCenter::Target => match camera_view.target_image_position()
+                .filter(|pos| pos.iter().all(|value| (0.0..=1.0).contains(value)))
+            {
+                Some(pos) => (Some(pos), "target"),
+                None => (boresight(), "boresight")
+            },
+            InsetCenter::Boresight => (boresight(), "boresight")
+        };
+        let center = match center {
+            Some(center) => [center[0] as f32, center[1] as f32],
+            None => return
+        };
+
+        // crop (in relative image coordinates, which are also the texture coordinates) kept within the image
+        let crop_size = INSET_SIZE / self.zoom;
+        let crop_min = center.map(|value| (value - crop_size / 2.0).clamp(0.0, 1.0 - crop_size));
+        let crop_max = crop_min.map(|value| value + crop_size);
+
+        let size = [(image_max[0] - image_min[0]) * INSET_SIZE, (image_max[1] - image_min[1]) * INSET_SIZE];
+        let inset_max = [image_max[0] - INSET_MARGIN, image_max[1] - INSET_MARGIN];
+        let inset_min = [inset_max[0] - size[0], inset_max[1] - size[1]];
+
+        let draw_list = ui.get_window_draw_list();
+        draw_list.with_clip_rect_intersect(image_min, image_max, || {
+            draw_list.add_image(camera_view.draw_buf_id(), inset_min, inset_max)
+                .uv_min(crop_min)
+                .uv_max(crop_max)
+                .build();
+            draw_list.add_rect(inset_min, inset_max, BORDER_COLOR).build();
+            draw_list.add_text(
+                [inset_min[0] + 4.0, inset_min[1] + 2.0],
+                BORDER_COLOR,
+                format!("{:.1}x ({})", self.zoom, label)
+            );
+        });
+    }
+}
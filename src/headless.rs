@@ -0,0 +1,103 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Headless mode: the simulator serves only as a network endpoint (mount and target feeds), without a GUI.
+
+use crate::{
+    event_hooks::{EventHooks, Hook},
+    scenario_script::ScenarioScript,
+    target_interpolator::TargetInterpolator,
+    workers::{self, ClientStatus, Mount, StatusUpdate, TargetMotion}
+};
+use crossbeam::channel::{Receiver, RecvTimeoutError};
+use pointing_utils::TargetInfoMessage;
+use std::{cell::RefCell, collections::HashMap, path::Path, rc::{Rc, Weak}, sync::{Arc, Mutex}, time::Duration};
+use subscriber_rs::Subscriber;
+
+/// Interval of calling the scenario script.
+const SCRIPT_TICK_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Scenario script controlling the first station, with its target feed and event hooks (as in the GUI).
+pub struct HeadlessScript {
+    script: ScenarioScript,
+    target_receiver: Receiver<TargetInfoMessage>,
+    target_interpolator: Rc<RefCell<TargetInterpolator>>,
+    event_hooks: Rc<RefCell<EventHooks>>
+}
+
+impl HeadlessScript {
+    /// Loads the script; `target_port`: port of the station's target feed.
+    pub fn load(
+        path: &Path,
+        hooks: Vec<Hook>,
+        mount: Arc<Mount>,
+        target_port: u16,
+        target_motion: Arc<Mutex<TargetMotion>>
+    ) -> Result<HeadlessScript, String> {
+        let (sender, target_receiver) = crossbeam::channel::unbounded();
+        std::thread::spawn(move || { workers::target_receiver(sender, target_port) });
+
+        let target_interpolator = Rc::new(RefCell::new(TargetInterpolator::new()));
+        // there is no camera view, so `on_target_enter_fov` hooks never fire
+        let event_hooks = Rc::new(RefCell::new(EventHooks::new(hooks, Arc::clone(&mount), Weak::new())));
+        target_interpolator.borrow_mut().add_subscriber(Rc::downgrade(&event_hooks) as _);
+        let script = ScenarioScript::load(path, mount, Rc::clone(&event_hooks), target_motion)?;
+        target_interpolator.borrow_mut().add_subscriber(script.target_subscriber());
+
+        Ok(HeadlessScript{ script, target_receiver, target_interpolator, event_hooks })
+    }
+
+    /// Delivers target updates and calls the script; once it finishes, returns whether all checks have passed.
+    fn tick(&mut self) -> Option<bool> {
+        while let Ok(msg) = self.target_receiver.try_recv() {
+            if !self.event_hooks.borrow().feed_dropped() { self.target_interpolator.borrow_mut().notify(&msg); }
+        }
+        self.target_interpolator.borrow_mut().interpolate();
+
+        self.script.tick()
+    }
+}
+
+/// Logs changes of the workers' status; returns when all workers have stopped or, if `script` is set, when it
+/// finishes (then returns whether all its checks have passed).
+pub fn run(status_receiver: Receiver<StatusUpdate>, mut script: Option<HeadlessScript>) -> Option<bool> {
+    let mut mount_clients = HashMap::<usize, ClientStatus>::new();
+    let mut feed_clients = HashMap::<u16, usize>::new();
+
+    loop {
+        let update = match status_receiver.recv_timeout(SCRIPT_TICK_INTERVAL) {
+            Ok(update) => Some(update),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break
+        };
+        if let Some(passed) = script.as_mut().and_then(HeadlessScript::tick) { return Some(passed); }
+
+        match update {
+            None => (),
+            Some(StatusUpdate::Mount{ instance, client, .. }) => {
+                if mount_clients.insert(instance, client) == Some(client) { continue; }
+                let text = match client {
+                    ClientStatus::Connected => "client connected",
+                    ClientStatus::NotConnected => "no client connected",
+                    ClientStatus::TimedOut => "client timed out; axes stopped"
+                };
+                log::info!("mount {}: {}", instance + 1, text);
+            },
+
+            Some(StatusUpdate::TargetFeed{ port, clients, .. }) => {
+                if feed_clients.insert(port, clients) != Some(clients) {
+                    log::info!("target feed {}: {} client(s)", port, clients);
+                }
+            }
+        }
+    }
+
+    log::info!("all workers have stopped");
+
+    None
+}
@@ -0,0 +1,107 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use cgmath::Vector3;
+use crate::units;
+use pointing_utils::uom;
+use std::path::Path;
+use uom::si::angle;
+
+const HORIZON_FILE_NAME: &str = "horizon.csv";
+
+/// Number of azimuth samples of the horizon profile (1° spacing).
+const NUM_SAMPLES: usize = 360;
+
+/// Local horizon: altitude of the terrain skyline as a function of azimuth.
+pub struct HorizonProfile {
+    /// Altitude (degrees) at azimuths 0°, 1°, ..., 359°.
+    altitudes: Vec<f64>
+}
+
+impl HorizonProfile {
+    /// Flat ground (horizon at 0° altitude in all directions).
+    pub fn flat() -> HorizonProfile {
+        HorizonProfile{ altitudes: vec![0.0; NUM_SAMPLES] }
+    }
+
+    /// Loads a profile from a CSV file with lines `<azimuth (°)>,<altitude (°)>` (e.g., computed from a DEM);
+    /// points are linearly interpolated, lines starting with `#` are ignored.
+    pub fn load(path: &Path) -> Result<HorizonProfile, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+        let mut points = vec![];
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let parse_error = || format!("invalid entry in line {}: {}", i + 1, line);
+            let (az, alt) = line.split_once(',').ok_or_else(parse_error)?;
+            let az = az.trim().parse::<f64>().map_err(|_| parse_error())?.rem_euclid(360.0);
+            let alt = alt.trim().parse::<f64>().map_err(|_| parse_error())?.clamp(-90.0, 90.0);
+            points.push((az, alt));
+        }
+        if points.is_empty() { return Err("no horizon points".into()); }
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let altitudes = (0..NUM_SAMPLES).map(|i| {
+            let az = i as f64 * 360.0 / NUM_SAMPLES as f64;
+            // neighboring points, wrapping around 360°
+            let next = points.iter().position(|p| p.0 >= az).unwrap_or(0);
+            let prev = if next == 0 { points.len() - 1 } else { next - 1 };
+            let (az0, alt0) = points[prev];
+            let (az1, alt1) = points[next];
+            let span = (az1 - az0).rem_euclid(360.0);
+            if span == 0.0 {
+                alt0
+            } else {
+                alt0 + (alt1 - alt0) * (az - az0).rem_euclid(360.0) / span
+            }
+        }).collect();
+
+        Ok(HorizonProfile{ altitudes })
+    }
+
+    /// Returns horizon altitude (degrees) at the given azimuth (degrees).
+    pub fn altitude(&self, azimuth: f64) -> f64 {
+        let x = azimuth.rem_euclid(360.0) * NUM_SAMPLES as f64 / 360.0;
+        let i = x.floor() as usize % NUM_SAMPLES;
+        let frac = x - x.floor();
+        self.altitudes[i] * (1.0 - frac) + self.altitudes[(i + 1) % NUM_SAMPLES] * frac
+    }
+
+    /// Returns true if the given direction (local frame: x north, y west, z up) is below the horizon.
+    pub fn occludes(&self, direction: Vector3<f64>) -> bool {
+        let azimuth = units::azimuth(direction).get::<angle::degree>();
+        let altitude = direction.z.atan2(direction.x.hypot(direction.y)).to_degrees();
+        altitude < self.altitude(azimuth)
+    }
+
+    /// Returns (azimuth, altitude) pairs in degrees at all profile samples.
+    pub fn samples(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.altitudes.iter().enumerate().map(|(i, alt)| (i as f64 * 360.0 / NUM_SAMPLES as f64, *alt))
+    }
+}
+
+/// Loads the horizon profile from the configuration directory; uses flat ground if there is none.
+pub fn load_horizon() -> HorizonProfile {
+    let path = match crate::config::config_dir() {
+        Some(dir) => dir.join(HORIZON_FILE_NAME),
+        None => return HorizonProfile::flat()
+    };
+    if !path.exists() { return HorizonProfile::flat(); }
+
+    match HorizonProfile::load(&path) {
+        Ok(profile) => {
+            log::info!("loaded horizon profile from {}", path.display());
+            profile
+        },
+        Err(e) => {
+            log::error!("failed to load horizon profile from {}: {}; using flat ground", path.display(), e);
+            HorizonProfile::flat()
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Capture of log messages (in addition to the terminal output) for display in the GUI.
+
+use std::{collections::VecDeque, sync::{Arc, Mutex, MutexGuard}};
+
+/// The oldest entries are discarded above this number.
+const MAX_ENTRIES: usize = 5000;
+
+pub struct LogEntry {
+    pub time: chrono::DateTime<chrono::Local>,
+    pub level: log::Level,
+    pub target: String,
+    pub message: String
+}
+
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} [{}] {}: {}", self.time.format("%H:%M:%S%.3f"), self.level, self.target, self.message)
+    }
+}
+
+/// Recent log entries, oldest first.
+#[derive(Clone, Default)]
+pub struct LogBuffer(Arc<Mutex<VecDeque<LogEntry>>>);
+
+impl LogBuffer {
+    pub fn entries(&self) -> MutexGuard<VecDeque<LogEntry>> { self.0.lock().unwrap() }
+
+    pub fn clear(&self) { self.0.lock().unwrap().clear(); }
+}
+
+/// Logger storing messages in a `LogBuffer`; meant to be combined with another logger via
+/// `log_file::DispatchLogger`.
+pub struct CaptureLogger {
+    level: log::LevelFilter,
+    config: simplelog::Config,
+    buffer: LogBuffer
+}
+
+impl CaptureLogger {
+    pub fn new(level: log::LevelFilter, config: simplelog::Config, buffer: LogBuffer) -> Box<CaptureLogger> {
+        Box::new(CaptureLogger{ level, config, buffer })
+    }
+}
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool { metadata.level() <= self.level }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) { return; }
+
+        let mut entries = self.buffer.entries();
+        if entries.len() == MAX_ENTRIES { entries.pop_front(); }
+        entries.push_back(LogEntry{
+            time: chrono::Local::now(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string()
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+impl simplelog::SharedLogger for CaptureLogger {
+    fn level(&self) -> log::LevelFilter { self.level }
+
+    fn config(&self) -> Option<&simplelog::Config> { Some(&self.config) }
+
+    fn as_log(self: Box<Self>) -> Box<dyn log::Log> { Box::new(*self) }
+}
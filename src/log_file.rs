@@ -0,0 +1,188 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Structured log files: one JSON object per line (`time`, `level`, `target`, `message`, `file`, `line`),
+//! rotated by size.
+//!
+//! The logger is installed at startup (before the settings are loaded), alongside the other loggers via
+//! `DispatchLogger`, and starts writing once configured with `LogFile::configure`.
+
+use crate::config::LoggingConfig;
+use std::{
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex}
+};
+
+const LOG_FILE_NAME: &str = "pointing-sim.jsonl";
+
+fn parse_level(s: &str) -> Result<log::LevelFilter, String> {
+    s.parse::<log::LevelFilter>().map_err(|_| format!("invalid log level \"{}\"", s))
+}
+
+struct Writer {
+    directory: PathBuf,
+    level: log::LevelFilter,
+    /// Sorted by decreasing length of the module path, so that the most specific one matches first.
+    module_levels: Vec<(String, log::LevelFilter)>,
+    max_file_size: u64,
+    max_files: usize,
+    file: File,
+    file_size: u64
+}
+
+impl Writer {
+    fn new(config: &LoggingConfig) -> Result<Writer, String> {
+        if config.max_file_size_mb.is_nan() || config.max_file_size_mb <= 0.0 {
+            return Err(format!("max. file size must be positive: {} MB", config.max_file_size_mb));
+        }
+        let directory = PathBuf::from(&config.directory);
+        std::fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+        let mut module_levels = config.module_levels.iter()
+            .map(|(module, level)| Ok((module.clone(), parse_level(level)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        module_levels.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        let (file, file_size) = open(&directory)?;
+
+        Ok(Writer{
+            directory,
+            level: parse_level(&config.level)?,
+            module_levels,
+            max_file_size: (config.max_file_size_mb * 1.0e6) as u64,
+            max_files: config.max_files,
+            file,
+            file_size
+        })
+    }
+
+    fn level(&self, target: &str) -> log::LevelFilter {
+        self.module_levels.iter()
+            .find(|(module, _)| {
+                target.strip_prefix(module.as_str()).map_or(false, |rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> log::LevelFilter {
+        self.module_levels.iter().map(|(_, level)| *level).fold(self.level, std::cmp::max)
+    }
+
+    /// Renames `<name>.<i>` to `<name>.<i + 1>` (the oldest file is removed) and starts a new current file.
+    fn rotate(&mut self) -> Result<(), String> {
+        let rotated = |i: usize| self.directory.join(format!("{}.{}", LOG_FILE_NAME, i));
+        if self.max_files > 0 {
+            let _ = std::fs::remove_file(rotated(self.max_files));
+            for i in (1..self.max_files).rev() {
+                if rotated(i).exists() { std::fs::rename(rotated(i), rotated(i + 1)).map_err(|e| e.to_string())?; }
+            }
+            std::fs::rename(self.directory.join(LOG_FILE_NAME), rotated(1)).map_err(|e| e.to_string())?;
+        } else {
+            std::fs::remove_file(self.directory.join(LOG_FILE_NAME)).map_err(|e| e.to_string())?;
+        }
+        (self.file, self.file_size) = open(&self.directory)?;
+        Ok(())
+    }
+
+    fn write(&mut self, record: &log::Record) {
+        let entry = serde_json::json!({
+            "time": chrono::Local::now().to_rfc3339(),
+            "level": record.level().as_str(),
+            "target": record.target(),
+            "message": record.args().to_string(),
+            "file": record.file(),
+            "line": record.line()
+        });
+        let line = format!("{}\n", entry);
+        // errors cannot be logged from within the logger
+        if self.file.write_all(line.as_bytes()).is_ok() { self.file_size += line.len() as u64; }
+        if self.file_size > self.max_file_size {
+            if let Err(e) = self.rotate() { eprintln!("failed to rotate log files: {}", e); }
+        }
+    }
+}
+
+/// Opens (for appending) the current log file in `directory`; returns it with its size.
+fn open(directory: &std::path::Path) -> Result<(File, u64), String> {
+    let path = directory.join(LOG_FILE_NAME);
+    let file = File::options().create(true).append(true).open(&path).map_err(|e| e.to_string())?;
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    Ok((file, size))
+}
+
+/// Handle of the log files written by a `FileLogger`.
+#[derive(Clone, Default)]
+pub struct LogFile(Arc<Mutex<Option<Writer>>>);
+
+impl LogFile {
+    /// Starts writing log files as specified by `config` (if its directory is not empty); returns the maximum
+    /// level of messages written.
+    pub fn configure(&self, config: &LoggingConfig) -> Result<log::LevelFilter, String> {
+        if config.directory.is_empty() { return Ok(log::LevelFilter::Off); }
+        let writer = Writer::new(config)?;
+        let max_level = writer.max_level();
+        *self.0.lock().unwrap() = Some(writer);
+        Ok(max_level)
+    }
+}
+
+/// Logger writing messages to a `LogFile`; meant to be combined with other loggers via `DispatchLogger`.
+pub struct FileLogger {
+    log_file: LogFile
+}
+
+impl FileLogger {
+    pub fn new(log_file: LogFile) -> Box<FileLogger> {
+        Box::new(FileLogger{ log_file })
+    }
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let writer = self.log_file.0.lock().unwrap();
+        writer.as_ref().map_or(false, |writer| metadata.level() <= writer.level(metadata.target()))
+    }
+
+    fn log(&self, record: &log::Record) {
+        let mut writer = self.log_file.0.lock().unwrap();
+        if let Some(writer) = writer.as_mut().filter(|writer| record.level() <= writer.level(record.target())) {
+            writer.write(record);
+        }
+    }
+
+    // every message is written directly to the file
+    fn flush(&self) {}
+}
+
+/// Passes each message to those of its loggers which accept it.
+///
+/// Unlike `simplelog::CombinedLogger`, which drops messages above the highest level of its loggers as of
+/// installation, it lets `FileLogger` receive messages at the levels configured later (e.g., `debug` for a single
+/// module while the terminal shows only `info`).
+pub struct DispatchLogger(Vec<Box<dyn log::Log>>);
+
+impl DispatchLogger {
+    /// Installs the logger; the global max. level is to be set separately (see `log::set_max_level`).
+    pub fn init(loggers: Vec<Box<dyn log::Log>>) -> Result<(), log::SetLoggerError> {
+        log::set_boxed_logger(Box::new(DispatchLogger(loggers)))
+    }
+}
+
+impl log::Log for DispatchLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.0.iter().any(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &log::Record) {
+        for logger in self.0.iter().filter(|logger| logger.enabled(record.metadata())) { logger.log(record); }
+    }
+
+    fn flush(&self) {
+        for logger in &self.0 { logger.flush(); }
+    }
+}
@@ -6,14 +6,38 @@
 // (see the LICENSE file for details).
 //
 
+mod autotune;
+mod challenge;
+mod cli;
+mod cloud_layer;
+mod command_console;
+mod config;
 mod data;
+mod event_hooks;
+mod frame_capture;
+mod gamepad;
 mod gui;
+mod headless;
+mod horizon;
+mod log_capture;
+mod log_file;
+mod mount_comparison;
+mod mount_control;
+mod plant_model;
 mod runner;
-mod target_interpolator;
-mod workers;
+mod scenario_script;
+mod scoring;
+mod sky_model;
+mod target_geometry;
+mod telemetry;
+mod tracking_controller;
+mod units;
+mod zenith_keyhole;
 
+use clap::Parser;
 use crossbeam::channel::TryRecvError;
-use std::sync::Arc;
+use pointing_sim_core::{color_mode, refraction, scenario, target_interpolator, workers};
+use std::{cell::Cell, rc::Rc, sync::Arc};
 
 fn main() {
     std::panic::set_hook(Box::new(|_| {
@@ -21,47 +45,493 @@ fn main() {
         log::error!("panicked!\n\n{}", backtrace);
     }));
 
+    let args = cli::Args::parse();
+
     let tz_offset = chrono::Local::now().offset().clone();
-    simplelog::SimpleLogger::init(
-        simplelog::LevelFilter::Debug,
-        simplelog::ConfigBuilder::new()
-            .set_target_level(simplelog::LevelFilter::Error)
-            .set_time_offset(time::UtcOffset::from_whole_seconds(tz_offset.local_minus_utc()).unwrap())
-            .set_time_format_custom(simplelog::format_description!(
-                "[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:6]"
-            ))
-            .build(),
-    ).unwrap();
-
-    const DEFAULT_FONT_SIZE: f32 = 15.0;
-    let runner = runner::create_runner(DEFAULT_FONT_SIZE);
+    let log_config = simplelog::ConfigBuilder::new()
+        .set_target_level(simplelog::LevelFilter::Error)
+        .set_time_offset(time::UtcOffset::from_whole_seconds(tz_offset.local_minus_utc()).unwrap())
+        .set_time_format_custom(simplelog::format_description!(
+            "[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:6]"
+        ))
+        .build();
+    // messages are also shown in the GUI's log console and, if configured in the settings, written to log files
+    let log_buffer = log_capture::LogBuffer::default();
+    let log_file = log_file::LogFile::default();
+    log_file::DispatchLogger::init(vec![
+        simplelog::SimpleLogger::new(args.log_level, log_config.clone()),
+        log_capture::CaptureLogger::new(args.log_level, log_config, log_buffer.clone()),
+        log_file::FileLogger::new(log_file.clone())
+    ]).unwrap();
+    log::set_max_level(args.log_level);
+
+    let settings = config::load_settings(args.config.as_deref());
+    match log_file.configure(&settings.logging) {
+        Ok(level) => log::set_max_level(level.max(args.log_level)),
+        Err(e) => log::error!("failed to set up log files in \"{}\": {}", settings.logging.directory, e)
+    }
+    // command-line ports apply only to this run (they are not saved with the settings)
+    let mount_port = args.mount_port.unwrap_or(settings.mount_port);
+    let target_port = args.target_port.unwrap_or(settings.target_port);
+
+    if let Some(path) = &args.export_dynamics {
+        let mount_config = config::load_mount_config(args.mount_profile.as_deref());
+        let latency = std::time::Duration::from_millis(args.dynamics_latency_ms);
+        let model = plant_model::PlantModel::new(&mount_config, latency);
+        match model.save(path) {
+            Ok(()) => log::info!("mount dynamics model saved to {}", path.display()),
+            Err(e) => log::error!("failed to save mount dynamics model to {}: {}", path.display(), e)
+        }
+        return;
+    }
+    if let Some(path) = &args.replay_trace {
+        let address = args.replay_address.clone().unwrap_or_else(|| format!("127.0.0.1:{}", mount_port));
+        if let Err(e) = workers::replay_trace(path, &address) {
+            log::error!("failed to replay trace {}: {}", path.display(), e);
+        }
+        return;
+    }
+    if let Some(path) = &args.convert_motion_log {
+        let output = path.with_extension("csv");
+        match workers::convert_motion_log(path, &output) {
+            Ok(()) => log::info!("motion log converted to {}", output.display()),
+            Err(e) => log::error!("failed to convert motion log {}: {}", path.display(), e)
+        }
+        return;
+    }
+
+    if args.offscreen { log::info!("off-screen mode: the GUI is processed without a window"); }
+    let scenario_path = args.scenario.clone();
+    let scenario = scenario_path.as_ref().and_then(|path| match scenario::Scenario::load(path) {
+        Ok(scenario) => {
+            log::info!("loaded scenario {}", path.display());
+            Some(scenario)
+        },
+        Err(e) => { log::error!("failed to load scenario {}: {}", path.display(), e); None }
+    });
+    let script_path = args.script.clone().or_else(|| {
+        scenario.as_ref().zip(scenario_path.as_ref()).and_then(|(scenario, path)| scenario.script_path(path))
+    });
+    if args.headless {
+        log::info!("headless mode: serving the mount and target feeds without a GUI");
+        let simulation = start_workers(&args, &settings, scenario.as_ref(), (mount_port, target_port));
+        let script = script_path.as_ref().and_then(|path| {
+            let (mount, target_port) = &simulation.stations[0];
+            let hooks = event_hooks::load_hooks(args.event_hooks.as_deref());
+            let target_motion = Arc::clone(&simulation.target_motion);
+            match headless::HeadlessScript::load(path, hooks, Arc::clone(mount), *target_port, target_motion) {
+                Ok(script) => { log::info!("loaded script {}", path.display()); Some(script) },
+                Err(e) => { log::error!("failed to load script {}: {}", path.display(), e); std::process::exit(1); }
+            }
+        });
+        if headless::run(simulation.status_receiver, script) == Some(false) { std::process::exit(1); }
+        return;
+    }
+
+    let runner = runner::create_runner(settings.font_size, args.external_display, args.offscreen);
     let mut data = None;
-    let mut gui_state = Some(gui::GuiState::new(runner.platform().hidpi_factor(), DEFAULT_FONT_SIZE));
+    let mut gui_state = gui::GuiState::new(runner.hidpi_factor(), settings.clone());
+    gui_state.external_feed = runner.external_feed();
+    gui_state.log_buffer = log_buffer;
+    gui_state.settings_path = args.config.clone();
+    if let (Some(scenario), Some(path)) = (&scenario, &scenario_path) {
+        gui_state.scenario_editor = gui::ScenarioEditor::new(scenario.clone(), path);
+    }
+    if let Some(resolution) = args.sensor_resolution { gui_state.sensor_resolution = Some(resolution); }
+    if let Some(settings) = args.video_stream.clone() {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        let port = args.video_stream_port;
+        std::thread::spawn(move || { workers::video_stream(settings, port, receiver) });
+        gui_state.video_stream = Some(sender);
+    }
+    let mut gui_state = Some(gui_state);
+    let mut script = None;
+    // set once the script finishes: whether all its checks have passed
+    let script_result = Rc::new(Cell::new(None));
+    let script_result2 = Rc::clone(&script_result);
 
-    runner.main_loop(move |_, ui, display, renderer| {
+    runner.main_loop(move |run, ui, display, renderer| {
         if data.is_none() {
-            let mount = Arc::new(workers::Mount::new());
-            let mount2 = Arc::clone(&mount);
-            std::thread::spawn(move || { workers::mount_model(mount2) });
-
-            std::thread::spawn(|| { workers::target_source() });
+            let simulation = start_workers(&args, &settings, scenario.as_ref(), (mount_port, target_port));
+            let station_links = simulation.stations.iter()
+                .zip(simulation.station_sites.iter().zip(&simulation.station_feed_latencies))
+                .zip(&simulation.tracked_targets)
+                .map(|(((mount, target_port), (site, feed_latency)), tracked_target)| {
+                    let (sender_worker, receiver_main) = crossbeam::channel::unbounded();
+                    let target_port = *target_port;
+                    std::thread::spawn(move || { workers::target_receiver(sender_worker, target_port) });
+                    data::StationLink{
+                        target_receiver: receiver_main,
+                        mount: Arc::clone(mount),
+                        site: *site,
+                        feed_latency: *feed_latency,
+                        target_control: Arc::clone(&simulation.target_control),
+                        tracked_target: Arc::clone(tracked_target)
+                    }
+                })
+                .collect();
 
-            let (sender_worker, receiver_main) = crossbeam::channel::unbounded();
-            std::thread::spawn(move || { workers::target_receiver(sender_worker) });
+            let hooks = event_hooks::load_hooks(args.event_hooks.as_deref());
 
-            data = Some(data::ProgramData::new(renderer, display, gui_state.take().unwrap(), receiver_main, mount));
+            let mut gui_state = gui_state.take().unwrap();
+            gui_state.status_bar.set_receiver(simulation.status_receiver);
+            gui_state.traffic_monitor = simulation.traffic_monitor;
+            gui_state.session_replay = simulation.session_replay;
+            let target_motion = Arc::clone(&simulation.target_motion);
+            let program_data = data::ProgramData::new(
+                renderer, display, gui_state, station_links, hooks, simulation.target_motion, simulation.comparison
+            );
+            script = script_path.as_ref().and_then(|path| {
+                let station = &program_data.stations[0];
+                let (mount, event_hooks) = (Arc::clone(&station.mount), Rc::clone(&station.event_hooks));
+                match scenario_script::ScenarioScript::load(path, mount, event_hooks, target_motion) {
+                    Ok(script) => {
+                        log::info!("loaded script {}", path.display());
+                        station.target_interpolator.borrow_mut().add_subscriber(script.target_subscriber());
+                        Some(script)
+                    },
+                    Err(e) => {
+                        log::error!("failed to load script {}: {}", path.display(), e);
+                        std::process::exit(1);
+                    }
+                }
+            });
+            let sky_time = args.sky_time.as_deref()
+                .or_else(|| scenario.as_ref().and_then(|scenario| scenario.start_time.as_deref()));
+            let sky_start = sky_time.and_then(|s| if s == "now" {
+                Some(chrono::Utc::now())
+            } else {
+                match chrono::DateTime::parse_from_rfc3339(s) {
+                    Ok(t) => Some(t.with_timezone(&chrono::Utc)),
+                    Err(e) => { log::error!("invalid sky time \"{}\": {}", s, e); None }
+                }
+            });
+            for (station, site) in program_data.stations.iter().zip(&simulation.station_sites) {
+                let mut camera_view = station.camera_view.borrow_mut();
+                camera_view.set_refraction(args.refraction);
+                camera_view.set_sky_model(sky_start.map(|start| sky_model::SkyModel::new(site.lat, site.lon, start)));
+                if let Some(misalignment) = args.camera_misalignment { camera_view.set_misalignment(misalignment); }
+                if let Some(color_mode) = args.color_mode { camera_view.set_color_mode(color_mode); }
+                camera_view.set_weather(Some(Arc::clone(&simulation.weather)));
+                if let Some(clouds) = &args.clouds {
+                    match cloud_layer::CloudLayer::parse(clouds, &simulation.weather) {
+                        Ok(layer) => camera_view.set_cloud_layer(Some(layer)),
+                        Err(e) => log::error!("invalid cloud layer settings: {}", e)
+                    }
+                }
+            }
+            data = Some(program_data);
         }
 
-        match data.as_ref().unwrap().target_receiver.try_recv() {
-            Ok(msg) => data.as_mut().unwrap().target_subscribers.notify(&msg),
-            Err(e) => match e {
-                TryRecvError::Empty => (),
-                _ => panic!("unexpected error: {}", e)
+        for station in &mut data.as_mut().unwrap().stations {
+            match station.target_receiver.try_recv() {
+                Ok(msg) => if !station.event_hooks.borrow().feed_dropped() {
+                    station.target_subscribers.notify(&msg)
+                },
+                Err(e) => match e {
+                    TryRecvError::Empty => (),
+                    _ => panic!("unexpected error: {}", e)
+                }
             }
+
+            station.target_interpolator.borrow_mut().interpolate();
         }
 
-        data.as_ref().unwrap().target_interpolator.borrow_mut().interpolate();
+        if let Some(passed) = script.as_mut().and_then(scenario_script::ScenarioScript::tick) {
+            script_result2.set(Some(passed));
+            *run = false;
+        }
 
         gui::handle_gui(data.as_mut().unwrap(), ui, renderer, display)
     });
+    if script_result.get() == Some(false) { std::process::exit(1); }
+}
+
+/// Offset of the ports used by the second station relative to those of the first one.
+const SECOND_STATION_PORT_OFFSET: u16 = 10;
+/// Offset of the mount port of the mirror mount (see `--compare-mount-profile`).
+const MIRROR_MOUNT_PORT_OFFSET: u16 = 20;
+
+/// Returns `port` + `offset`; logs an error (naming the endpoint as `purpose`) if the result is out of range.
+fn offset_port(port: u16, offset: u16, purpose: &str) -> Option<u16> {
+    let result = port.checked_add(offset);
+    if result.is_none() {
+        log::error!("cannot serve {}: port {} + {} is out of range", purpose, port, offset);
+    }
+    result
+}
+
+/// Workers serving the mount and target feed endpoints.
+struct Simulation {
+    /// Mount of each station with the port of its target feed.
+    stations: Vec<(Arc<workers::Mount>, u16)>,
+    station_sites: Vec<workers::Site>,
+    /// Age of the target data published by each station's feed.
+    station_feed_latencies: Vec<std::time::Duration>,
+    /// Target followed by each station's tracking controller.
+    tracked_targets: Vec<workers::TrackedTarget>,
+    /// Whether the second mount mirrors the first one (see `--compare-mount-profile`).
+    comparison: bool,
+    status_receiver: crossbeam::channel::Receiver<workers::StatusUpdate>,
+    traffic_monitor: workers::TrafficMonitor,
+    weather: Arc<workers::Weather>,
+    target_motion: Arc<std::sync::Mutex<workers::TargetMotion>>,
+    target_control: Arc<std::sync::Mutex<workers::TargetControl>>,
+    /// Set if a recorded session is replayed (see `--replay-session`).
+    session_replay: Option<workers::SessionReplay>
+}
+
+/// Starts the mount models, target source and weather forecast feed; `ports`: mount and target feed ports of
+/// the first station.
+fn start_workers(
+    args: &cli::Args,
+    settings: &config::Settings,
+    scenario: Option<&scenario::Scenario>,
+    ports: (u16, u16)
+) -> Simulation {
+    let (mount_port, target_port) = ports;
+    let mount_config = match &args.mount_profile {
+        Some(profile) => config::load_mount_config(Some(profile)),
+        None => settings.mount.clone()
+    };
+
+    let mut target_source_options = workers::TargetSourceOptions{
+        adsb_cpr_glitch_probability: if args.adsb_cpr {
+            Some(scenario::ADSB_CPR_GLITCH_PROBABILITY)
+        } else {
+            None
+        },
+        num_generated_targets: args.targets,
+        refraction: args.refraction,
+        target_swap_probability: args.target_swap,
+        seed: rand::random(),
+        ..Default::default()
+    };
+    // a scenario overrides the corresponding options
+    if let Some(scenario) = scenario { scenario.apply(&mut target_source_options); }
+    if let Some(seed) = args.seed { target_source_options.seed = seed; }
+    log::info!("simulation seed: {0} (pass --seed {0} to reproduce the run)", target_source_options.seed);
+    for path in &args.replay_targets {
+        match workers::TargetReplay::load(path) {
+            Ok(replay) => target_source_options.sources.push(Box::new(replay)),
+            Err(e) => log::error!("failed to load target replay {}: {}", path.display(), e)
+        }
+    }
+    for address in &args.adsb_input {
+        target_source_options.sources.push(Box::new(workers::AdsbInput::connect(address)));
+    }
+    let session_replay = args.replay_session.as_ref().and_then(|path| match workers::Session::load(path) {
+        Ok(session) => {
+            log::info!("replaying session {} ({:.1} s)", path.display(), session.duration);
+            Some(workers::SessionReplay::new(session))
+        },
+        Err(e) => { log::error!("failed to load session {}: {}", path.display(), e); None }
+    });
+    if let Some(replay) = &session_replay {
+        // only the recorded targets are published
+        target_source_options.targets.clear();
+        target_source_options.num_generated_targets = 0;
+        target_source_options.sources.push(Box::new(replay.clone()));
+    }
+    // the first feed (ADS-B) of the first station
+    target_source_options.feeds[0].socket_path = args.target_socket.clone();
+    // the default feeds belong to the first station
+    let site = scenario.map_or(&settings.location, |scenario| &scenario.observer).site();
+    // without a scenario, the default target flies near the observer
+    if scenario.is_none() && session_replay.is_none() {
+        target_source_options.targets = vec![workers::TargetDefinition::default_near(&site)];
+    }
+    target_source_options.feeds.retain_mut(|feed| {
+        match offset_port(target_port, feed.port - workers::TARGET_SOURCE_PORT, "target feed") {
+            Some(port) => { feed.port = port; feed.site = site; true },
+            None => false
+        }
+    });
+
+    // (mount port, target feed port)
+    let mut station_ports = vec![(mount_port, target_port)];
+    let mut station_sites = vec![site];
+    let second_station_ports = if args.second_mount {
+        offset_port(mount_port, SECOND_STATION_PORT_OFFSET, "second station's mount")
+            .zip(offset_port(target_port, SECOND_STATION_PORT_OFFSET, "second station's target feed"))
+    } else {
+        None
+    };
+    if let Some(ports) = second_station_ports {
+        // ca. 17 km east of the first station (at the equator)
+        let site = workers::Site{ lon: site.lon + cgmath::Deg(0.15), ..site };
+        target_source_options.add_station_feed(ports.1, site);
+        station_ports.push(ports);
+        station_sites.push(site);
+    }
+
+    // the mirror mount shares the first station's site and target feed
+    let compared_profile = args.compare_mount_profile.as_deref();
+    let comparison = match compared_profile {
+        Some(_) if station_ports.len() > 1 => {
+            log::error!("mount comparison cannot be used with a second station");
+            false
+        },
+        Some(_) => match offset_port(mount_port, MIRROR_MOUNT_PORT_OFFSET, "mirror mount") {
+            Some(port) => {
+                station_ports.push((port, target_port));
+                station_sites.push(station_sites[0]);
+                true
+            },
+            None => false
+        },
+        None => false
+    };
+
+    if args.binary_framing {
+        for feed in &mut target_source_options.feeds {
+            feed.binary_port = offset_port(feed.port, workers::BINARY_PORT_OFFSET, "binary target feed");
+        }
+    }
+
+    // worker status is shown in the GUI's status bar (or logged in headless mode)
+    let (status_sender, status_receiver) = crossbeam::channel::unbounded();
+    target_source_options.status = Some(status_sender.clone());
+    // protocol traffic is shown in the GUI's traffic inspector
+    let traffic_monitor = workers::TrafficMonitor::default();
+    target_source_options.traffic = Some(traffic_monitor.clone());
+
+    let trace_dir = args.trace_dir.clone();
+    target_source_options.trace_dir = trace_dir.clone();
+    let motion_log_dir = args.motion_log.clone();
+    let motion_log_rate = args.motion_log_rate;
+
+    let mut stations = vec![];
+    let mut station_feed_latencies = vec![];
+    for (instance, (mount_port, target_port)) in station_ports.into_iter().enumerate() {
+        let is_mirror = comparison && instance == 1;
+        let config = if is_mirror {
+            config::load_mount_config(compared_profile)
+        } else {
+            mount_config.clone()
+        };
+        let mount = Arc::new(workers::Mount::new(config, instance));
+        mount.set_site_latitude(units::deg(station_sites[instance].lat.0));
+        // the mirror mount takes over the primary one's state in `set_mirror`
+        if !is_mirror { mount.restore_state(); }
+        let mount2 = Arc::clone(&mount);
+        let trace_dir2 = trace_dir.clone();
+        let status2 = status_sender.clone();
+        let traffic2 = traffic_monitor.clone();
+        std::thread::spawn(move || {
+            workers::mount_model(mount2, mount_port, trace_dir2, Some(status2), Some(traffic2))
+        });
+
+        if let Some(dir) = motion_log_dir.clone() {
+            let mount2 = Arc::clone(&mount);
+            std::thread::spawn(move || { workers::motion_log(mount2, &dir, motion_log_rate) });
+        }
+
+        if !is_mirror && args.mount_pty {
+            #[cfg(unix)]
+            {
+                let mount2 = Arc::clone(&mount);
+                let trace_dir2 = trace_dir.clone();
+                std::thread::spawn(move || { workers::mount_model_pty(mount2, trace_dir2) });
+            }
+            #[cfg(not(unix))]
+            log::error!("serial port transport is supported only on Unix-like systems");
+        }
+
+        if let Some(path) = args.mount_socket.as_ref().filter(|_| !is_mirror) {
+            // further stations get a numbered suffix
+            let path = if instance == 0 { path.clone() } else { format!("{}.{}", path, instance + 1) };
+            #[cfg(unix)]
+            {
+                let mount2 = Arc::clone(&mount);
+                let trace_dir2 = trace_dir.clone();
+                std::thread::spawn(move || {
+                    workers::mount_model_local_socket(mount2, std::path::PathBuf::from(path), trace_dir2)
+                });
+            }
+            #[cfg(not(unix))]
+            log::error!("local socket transport is not supported on this platform ({})", path);
+        }
+
+        let binary_port = if !is_mirror && args.binary_framing {
+            offset_port(mount_port, workers::BINARY_PORT_OFFSET, "binary mount protocol")
+        } else {
+            None
+        };
+        if let Some(port) = binary_port {
+            let mount2 = Arc::clone(&mount);
+            let trace_dir2 = trace_dir.clone();
+            std::thread::spawn(move || { workers::mount_model_binary(mount2, port, trace_dir2) });
+        }
+
+        stations.push((mount, target_port));
+        station_feed_latencies.push(target_source_options.feeds.iter()
+            .find(|feed| feed.port == target_port)
+            .map_or(std::time::Duration::ZERO, |feed| feed.latency));
+    }
+    if comparison {
+        stations[0].0.set_mirror(Some(Arc::clone(&stations[1].0)));
+    }
+
+    let mounts = || stations.iter().map(|(mount, _)| Arc::clone(mount)).collect::<Vec<_>>();
+    if let Some(path) = args.record_session.clone() {
+        let (mounts, traffic2) = (mounts(), traffic_monitor.clone());
+        let target_control = Arc::clone(&target_source_options.target_control);
+        std::thread::spawn(move || { workers::session_recorder(&path, mounts, target_control, traffic2) });
+    }
+    if let Some(replay) = session_replay.clone() {
+        let mounts = mounts();
+        std::thread::spawn(move || { workers::session_replay(replay, mounts) });
+    }
+
+    let weather = Arc::new(workers::Weather::new(target_source_options.seed));
+    let target_motion = Arc::clone(&target_source_options.target_motion);
+    if args.control_api {
+        let simulation = workers::ControlledSimulation{
+            mounts: mounts(),
+            target_motion: Arc::clone(&target_motion),
+            target_control: Arc::clone(&target_source_options.target_control)
+        };
+        let control_port = args.control_port;
+        std::thread::spawn(move || { workers::control_api(simulation, control_port) });
+    }
+    let tracked_targets: Vec<workers::TrackedTarget> = stations.iter().map(|_| Default::default()).collect();
+    if args.websocket_telemetry {
+        let stations = stations.iter().zip(&station_sites).zip(&tracked_targets)
+            .map(|(((mount, _), site), tracked_target)| workers::TelemetryStation{
+                mount: Arc::clone(mount),
+                site: *site,
+                tracked_target: Arc::clone(tracked_target)
+            })
+            .collect();
+        let target_control = Arc::clone(&target_source_options.target_control);
+        let (port, rate) = (args.websocket_port, args.websocket_rate);
+        std::thread::spawn(move || { workers::websocket_telemetry(stations, target_control, port, rate) });
+    }
+    #[cfg(feature = "grpc")]
+    if args.grpc {
+        let stations = stations.clone();
+        let target_control = Arc::clone(&target_source_options.target_control);
+        let port = args.grpc_port;
+        std::thread::spawn(move || { workers::grpc_server(stations, target_control, port) });
+    }
+    let target_control = Arc::clone(&target_source_options.target_control);
+    std::thread::spawn(move || { workers::target_source(target_source_options) });
+    let weather2 = Arc::clone(&weather);
+    let weather_port = args.weather_port;
+    std::thread::spawn(move || { workers::weather_forecast_feed(weather2, weather_port) });
+
+    Simulation{
+        stations,
+        station_sites,
+        station_feed_latencies,
+        tracked_targets,
+        comparison,
+        status_receiver,
+        traffic_monitor,
+        weather,
+        target_motion,
+        target_control,
+        session_replay
+    }
 }
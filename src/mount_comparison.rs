@@ -0,0 +1,61 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Comparison of two mounts with different parameters receiving the same commands (the second one mirrors
+//! the first; see `Mount::set_mirror`).
+
+use crate::{scoring::offset_deg, target_geometry::TargetDirection, workers::Mount};
+use pointing_utils::TargetInfoMessage;
+use std::{collections::VecDeque, sync::Arc};
+use subscriber_rs::Subscriber;
+
+const MAX_HISTORY_LEN: usize = 1000;
+
+pub struct ComparedMount {
+    pub name: String,
+    pub mount: Arc<Mount>,
+    /// Recent angular distances (arcseconds) between the boresight and the target.
+    pub errors: VecDeque<f32>
+}
+
+impl ComparedMount {
+    pub fn rms_error(&self) -> Option<f64> {
+        if self.errors.is_empty() { return None; }
+        Some((self.errors.iter().map(|e| (*e as f64).powi(2)).sum::<f64>() / self.errors.len() as f64).sqrt())
+    }
+}
+
+pub struct MountComparison {
+    /// The primary mount (receiving commands) and its mirror.
+    pub mounts: [ComparedMount; 2]
+}
+
+impl MountComparison {
+    pub fn new(primary: (String, Arc<Mount>), mirror: (String, Arc<Mount>)) -> MountComparison {
+        let compared = |(name, mount): (String, Arc<Mount>)| ComparedMount{ name, mount, errors: VecDeque::new() };
+        MountComparison{ mounts: [compared(primary), compared(mirror)] }
+    }
+
+    pub fn clear(&mut self) {
+        for compared in &mut self.mounts { compared.errors.clear(); }
+    }
+}
+
+impl Subscriber<TargetInfoMessage> for MountComparison {
+    fn notify(&mut self, value: &TargetInfoMessage) {
+        let target = match TargetDirection::from_message(value) {
+            Some(target) => target,
+            None => return
+        };
+
+        for compared in &mut self.mounts {
+            if compared.errors.len() == MAX_HISTORY_LEN { compared.errors.pop_front(); }
+            compared.errors.push_back((offset_deg(&compared.mount.get(), &target) * 3600.0) as f32);
+        }
+    }
+}
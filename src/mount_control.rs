@@ -0,0 +1,231 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Manual control of a mount: slewing in a direction, at given axis rates, or to given axis positions (goto).
+//!
+//! Directions follow the hand controller convention: north and east move axis 2 and axis 1 (respectively)
+//! in the positive direction. Manual commands disable the tracking controller, which would override them.
+
+use crate::{tracking_controller::TrackingController, workers::Mount};
+use pointing_utils::uom;
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+use uom::{si::f64, si::{angle, angular_velocity}};
+
+/// Selectable rates (°/s) of directional slews and gotos.
+pub const SLEW_RATES: [f64; 5] = [0.01, 0.1, 0.5, 2.0, 5.0];
+
+const DEFAULT_SLEW_RATE_IDX: usize = 2;
+
+/// Goto is finished once both axes are within this distance (arcseconds) from the destination.
+const GOTO_TOLERANCE_ARCSEC: f64 = 2.0;
+
+/// Gain (1/s) of the final approach to the goto destination.
+const GOTO_GAIN: f64 = 2.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West
+}
+
+impl Direction {
+    /// Returns signs of axis 1 and axis 2 rates.
+    fn axis_signs(&self) -> [f64; 2] {
+        match self {
+            Direction::North => [0.0, 1.0],
+            Direction::South => [0.0, -1.0],
+            Direction::East => [1.0, 0.0],
+            Direction::West => [-1.0, 0.0]
+        }
+    }
+}
+
+fn deg_per_s(value: f64) -> f64::AngularVelocity {
+    f64::AngularVelocity::new::<angular_velocity::degree_per_second>(value)
+}
+
+pub struct MountControl {
+    mount: Arc<Mount>,
+    tracking_controller: Rc<RefCell<TrackingController>>,
+    /// Index in `SLEW_RATES`.
+    pub rate_idx: usize,
+    /// Axis rates (°/s) entered by the user.
+    pub rates_input: [f32; 2],
+    /// Axis positions (degrees) entered by the user.
+    pub goto_input: [f32; 2],
+    /// Direction of the ongoing directional slew.
+    direction: Option<Direction>,
+    /// True during a slew started with `slew_proportional`.
+    proportional: bool,
+    /// Axis positions being slewed to.
+    goto: Option<[f64::Angle; 2]>
+}
+
+impl MountControl {
+    pub fn new(mount: Arc<Mount>, tracking_controller: Rc<RefCell<TrackingController>>) -> MountControl {
+        MountControl{
+            mount,
+            tracking_controller,
+            rate_idx: DEFAULT_SLEW_RATE_IDX,
+            rates_input: [0.0; 2],
+            goto_input: [0.0; 2],
+            direction: None,
+            proportional: false,
+            goto: None
+        }
+    }
+
+    fn slew_rate(&self) -> f64 { SLEW_RATES[self.rate_idx] }
+
+    fn take_over(&self) {
+        let mut controller = self.tracking_controller.borrow_mut();
+        if controller.enabled() {
+            controller.set_enabled(false);
+            log::info!("tracking controller disabled for manual control");
+        }
+    }
+
+    pub fn direction(&self) -> Option<Direction> { self.direction }
+
+    pub fn goto_destination(&self) -> Option<[f64::Angle; 2]> { self.goto }
+
+    /// Starts slewing in `direction` at the selected rate (if not slewing in it already).
+    pub fn start_slew(&mut self, direction: Direction) {
+        if self.direction == Some(direction) { return; }
+
+        self.take_over();
+        self.goto = None;
+        let [sign1, sign2] = direction.axis_signs();
+        match self.mount.slew(deg_per_s(sign1 * self.slew_rate()), deg_per_s(sign2 * self.slew_rate())) {
+            Ok(()) => self.direction = Some(direction),
+            Err(e) => {
+                log::error!("failed to slew: {}", e);
+                self.direction = None;
+            }
+        }
+    }
+
+    /// Stops the directional slew (if any).
+    pub fn end_slew(&mut self) {
+        if self.direction.take().is_some() {
+            self.mount.stop();
+        }
+    }
+
+    /// Selects the next (`step` > 0) or previous slew rate.
+    pub fn change_rate(&mut self, step: isize) {
+        self.rate_idx = (self.rate_idx as isize + step).clamp(0, SLEW_RATES.len() as isize - 1) as usize;
+        log::info!("slew rate: {}°/s", self.slew_rate());
+    }
+
+    /// Slews the axes at the selected rate scaled by `deflection` (-1 to 1 per axis, e.g., of a gamepad stick).
+    /// Zero deflection stops a slew started this way.
+    pub fn slew_proportional(&mut self, deflection: [f64; 2]) {
+        if deflection == [0.0; 2] {
+            if self.proportional {
+                self.proportional = false;
+                self.mount.stop();
+            }
+            return;
+        }
+
+        self.take_over();
+        self.direction = None;
+        self.goto = None;
+        let starting = !self.proportional;
+        self.proportional = true;
+        let [rate1, rate2] = deflection.map(|value| deg_per_s(value * self.slew_rate()));
+        if let Err(e) = self.mount.slew(rate1, rate2) {
+            // the slew is repeated as the deflection changes; report the failure once
+            if starting { log::error!("failed to slew: {}", e); }
+        }
+    }
+
+    /// Parks the mount or unparks it (if already parked).
+    pub fn toggle_park(&mut self) {
+        if self.mount.is_parked() {
+            self.mount.unpark();
+        } else {
+            self.take_over();
+            self.direction = None;
+            self.goto = None;
+            self.mount.park();
+        }
+    }
+
+    /// Slews the axes at `rates_input`.
+    pub fn slew_at_input_rates(&mut self) {
+        self.take_over();
+        self.direction = None;
+        self.goto = None;
+        let [rate1, rate2] = self.rates_input.map(|rate| deg_per_s(rate as f64));
+        if let Err(e) = self.mount.slew(rate1, rate2) {
+            log::error!("failed to slew: {}", e);
+        }
+    }
+
+    /// Starts slewing to `goto_input`.
+    pub fn goto(&mut self) {
+        self.take_over();
+        self.direction = None;
+        let [axis1, axis2] = self.goto_input.map(|pos| f64::Angle::new::<angle::degree>(pos as f64));
+        log::info!(
+            "goto to axis 1: {:.3}°, axis 2: {:.3}°",
+            axis1.get::<angle::degree>(),
+            axis2.get::<angle::degree>()
+        );
+        self.goto = Some([axis1, axis2]);
+    }
+
+    pub fn stop(&mut self) {
+        self.direction = None;
+        self.goto = None;
+        self.mount.stop();
+    }
+
+    /// Advances the ongoing goto; to be called every frame.
+    pub fn update(&mut self) {
+        let destination = match self.goto {
+            Some(destination) => destination,
+            None => return
+        };
+
+        if self.tracking_controller.borrow().enabled() {
+            log::info!("goto cancelled by the tracking controller");
+            self.goto = None;
+            return;
+        }
+
+        let state = self.mount.get();
+        let errors = [
+            (destination[0] - state.axis1_pos).get::<angle::degree>(),
+            (destination[1] - state.axis2_pos).get::<angle::degree>()
+        ];
+        if errors.iter().all(|error| error.abs() * 3600.0 <= GOTO_TOLERANCE_ARCSEC) {
+            self.mount.stop();
+            self.goto = None;
+            log::info!("goto finished");
+            return;
+        }
+
+        // decelerate so as to stop at the destination
+        let config = self.mount.config();
+        let rate = |error: f64, acceleration: f64, max_rate: f64| {
+            let braking_rate = (2.0 * acceleration * error.abs()).sqrt();
+            error.signum() * braking_rate.min(GOTO_GAIN * error.abs()).min(max_rate).min(self.slew_rate())
+        };
+        let rate1 = rate(errors[0], config.axis1.acceleration_deg_per_s2, config.axis1.max_rate_deg_per_s);
+        let rate2 = rate(errors[1], config.axis2.acceleration_deg_per_s2, config.axis2.max_rate_deg_per_s);
+        if let Err(e) = self.mount.slew(deg_per_s(rate1), deg_per_s(rate2)) {
+            log::error!("goto failed: {}", e);
+            self.stop();
+        }
+    }
+}
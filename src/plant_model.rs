@@ -0,0 +1,127 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Linearized discrete-time model of the simulated mount axes, for designing controllers offline.
+
+use crate::{config::{AxisConfig, MountConfig}, tracking_controller::COMMAND_INTERVAL};
+use serde::Serialize;
+use std::{error::Error, path::Path};
+
+/// Amplitude of rate command changes for which the acceleration limit is linearized.
+const LINEARIZATION_RATE_STEP: f64 = 0.1; // deg/s
+
+/// Model of a single axis. Input `u`: commanded rate (deg/s), output `y`: encoder position (deg);
+/// the command is held constant (zero-order hold) during each sample period.
+#[derive(Serialize)]
+pub struct AxisModel {
+    pub sample_time_s: f64,
+    /// Time constant of the first-order lag approximating the acceleration-limited response to a rate step of
+    /// `linearization_rate_step_deg_per_s` (equal velocity deficit area).
+    pub time_constant_s: f64,
+    pub linearization_rate_step_deg_per_s: f64,
+    /// Command latency, in whole samples.
+    pub delay_samples: usize,
+    /// State-space model `x[k+1] = A x[k] + B u[k]`, `y[k] = C x[k] + D u[k]`; state: position, velocity,
+    /// followed by `delay_samples` delayed commands.
+    pub a: Vec<Vec<f64>>,
+    pub b: Vec<f64>,
+    pub c: Vec<f64>,
+    pub d: f64,
+    /// Transfer function coefficients in descending powers of z.
+    pub tf_numerator: Vec<f64>,
+    pub tf_denominator: Vec<f64>,
+    /// Nonlinearities not included in the linear model.
+    pub max_rate_deg_per_s: f64,
+    pub acceleration_deg_per_s2: f64,
+    pub encoder_resolution_deg: f64,
+    pub backlash_arcsec: f64,
+    pub periodic_error_arcsec: f64,
+    /// Structural oscillatory mode (not included in the linear model).
+    pub resonance_frequency_hz: Option<f64>,
+    pub resonance_damping_ratio: f64
+}
+
+#[derive(Serialize)]
+pub struct PlantModel {
+    pub axis1: AxisModel,
+    pub axis2: AxisModel
+}
+
+impl PlantModel {
+    /// Creates the model for the built-in controller's command interval.
+    pub fn new(config: &MountConfig, latency: std::time::Duration) -> PlantModel {
+        let sample_time = COMMAND_INTERVAL.as_secs_f64();
+        let delay_samples = (latency.as_secs_f64() / sample_time).round() as usize;
+        PlantModel{
+            axis1: axis_model(&config.axis1, sample_time, delay_samples),
+            axis2: axis_model(&config.axis2, sample_time, delay_samples)
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Discretizes `G(s) = 1 / (s (τs + 1))` (rate command to position) with zero-order hold and appends
+/// a pure delay.
+fn axis_model(config: &AxisConfig, ts: f64, delay_samples: usize) -> AxisModel {
+    // a rate step of amplitude `v` completes after `v/accel`; its velocity deficit area equals that of
+    // a first-order lag with `τ = v / (2 accel)`
+    let tau = if config.acceleration_deg_per_s2 > 0.0 {
+        LINEARIZATION_RATE_STEP / (2.0 * config.acceleration_deg_per_s2)
+    } else {
+        0.0
+    };
+    let p = if tau > 0.0 { (-ts / tau).exp() } else { 0.0 };
+
+    let n = 2 + delay_samples;
+    let mut a = vec![vec![0.0; n]; n];
+    let mut b = vec![0.0; n];
+    a[0][0] = 1.0;
+    a[0][1] = tau * (1.0 - p);
+    a[1][1] = p;
+    let b_pos = ts - tau * (1.0 - p);
+    let b_vel = 1.0 - p;
+    if delay_samples == 0 {
+        b[0] = b_pos;
+        b[1] = b_vel;
+    } else {
+        // the oldest delayed command drives the axis
+        a[0][n - 1] = b_pos;
+        a[1][n - 1] = b_vel;
+        for (i, row) in a.iter_mut().enumerate().skip(3) { row[i - 1] = 1.0; }
+        b[2] = 1.0;
+    }
+    let mut c = vec![0.0; n];
+    c[0] = 1.0;
+
+    let mut tf_denominator = vec![1.0, -(1.0 + p), p];
+    tf_denominator.resize(3 + delay_samples, 0.0);
+
+    AxisModel{
+        sample_time_s: ts,
+        time_constant_s: tau,
+        linearization_rate_step_deg_per_s: LINEARIZATION_RATE_STEP,
+        delay_samples,
+        a,
+        b,
+        c,
+        d: 0.0,
+        tf_numerator: vec![b_pos, tau * (1.0 - p) - p * ts],
+        tf_denominator,
+        max_rate_deg_per_s: config.max_rate_deg_per_s,
+        acceleration_deg_per_s2: config.acceleration_deg_per_s2,
+        encoder_resolution_deg: config.encoder_resolution_deg(),
+        backlash_arcsec: config.backlash_arcsec,
+        periodic_error_arcsec: config.periodic_error_arcsec,
+        resonance_frequency_hz: config.resonance_frequency_hz,
+        resonance_damping_ratio: config.resonance_damping_ratio
+    }
+}
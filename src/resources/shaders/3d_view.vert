@@ -2,6 +2,8 @@
 
 uniform mat4 model;
 uniform mat4 view;
+// `view * model`, computed on the CPU in double precision
+uniform mat4 view_model;
 uniform mat4 projection;
 
 in vec3 position;
@@ -12,7 +14,6 @@ out vec3 view_position;
 
 void main()
 {
-    mat4 view_model = view * model;
     vec4 view_model_position = view_model * vec4(position, 1.0);
     vec4 projected = projection * view_model_position;
 
@@ -0,0 +1,58 @@
+// Cloud layer; inserted after the `#version` directive of shaders using it (see `data::with_library`).
+// Must be kept in sync with `cloud_layer.rs`.
+
+uniform bool cloud_layer_enabled;
+// altitude of the layer (m)
+uniform float cloud_altitude;
+// fraction of the sky covered (0-1)
+uniform float cloud_coverage;
+// displacement of the cloud pattern due to drift (m)
+uniform vec2 cloud_offset;
+uniform vec3 cloud_color;
+
+const float CLOUD_CELL_SIZE = 2000.0;
+const float CLOUD_EDGE_SOFTNESS = 0.05;
+
+float cloud_hash(int x, int y)
+{
+    uint h = uint(x) * 374761393u + uint(y) * 668265263u;
+    h = (h ^ (h >> 13u)) * 1274126177u;
+    h ^= h >> 16u;
+    return float(h) / 4294967295.0;
+}
+
+float cloud_value_noise(vec2 p)
+{
+    ivec2 i = ivec2(floor(p));
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+
+    float bottom = mix(cloud_hash(i.x, i.y), cloud_hash(i.x + 1, i.y), u.x);
+    float top = mix(cloud_hash(i.x, i.y + 1), cloud_hash(i.x + 1, i.y + 1), u.x);
+    return mix(bottom, top, u.y);
+}
+
+float cloud_density(vec2 p)
+{
+    return 0.5 * cloud_value_noise(p) + 0.3 * cloud_value_noise(p * 2.03) + 0.2 * cloud_value_noise(p * 4.01);
+}
+
+// Returns opacity of the clouds along the line of sight from the observer (at origin) in direction `dir`
+// (local frame), up to `max_distance`; `layer_distance` receives the distance to the layer.
+float cloud_opacity(vec3 dir, float max_distance, out float layer_distance)
+{
+    layer_distance = 0.0;
+    if (!cloud_layer_enabled || cloud_altitude <= 0.0 || dir.z <= 0.0) { return 0.0; }
+
+    vec3 ndir = normalize(dir);
+    layer_distance = cloud_altitude / ndir.z;
+    if (layer_distance >= max_distance) { return 0.0; }
+
+    vec2 crossing = ndir.xy * layer_distance;
+    float threshold = 1.0 + CLOUD_EDGE_SOFTNESS - (1.0 + 2.0 * CLOUD_EDGE_SOFTNESS) * cloud_coverage;
+    return smoothstep(
+        threshold - CLOUD_EDGE_SOFTNESS,
+        threshold + CLOUD_EDGE_SOFTNESS,
+        cloud_density((crossing - cloud_offset) / CLOUD_CELL_SIZE)
+    );
+}
@@ -0,0 +1,23 @@
+#version 330 core
+
+// inverse of `projection * view` (rotation only)
+uniform mat4 inv_view_projection;
+uniform vec3 haze_color;
+// atmospheric extinction coefficient (1/m)
+uniform float extinction_coeff;
+
+in vec2 tex_coord;
+out vec4 color;
+
+void main()
+{
+    // the 3D view is rendered with Y negated (see `3d_view.vert`)
+    vec2 clip = tex_coord * 2.0 - vec2(1.0, 1.0);
+    vec4 far_point = inv_view_projection * vec4(clip.x, -clip.y, 1.0, 1.0);
+
+    float layer_distance;
+    float opacity = cloud_opacity(far_point.xyz / far_point.w, 1.0e30, layer_distance);
+    float transmittance = exp(-extinction_coeff * layer_distance);
+
+    color = vec4(mix(haze_color, cloud_color, transmittance), opacity);
+}
@@ -0,0 +1,42 @@
+// Lens distortion and vignetting; inserted after the `#version` directive of shaders using it (see `data::with_library`).
+
+uniform bool lens_distortion_enabled;
+// radial distortion coefficients (Brown-Conrady model) for image coordinates normalized by the focal length
+uniform float lens_k1;
+uniform float lens_k2;
+// relative brightness loss at the image corners
+uniform float vignetting;
+uniform float focal_length_px;
+// pixels
+uniform vec2 image_size;
+
+// Returns texture coordinates (in the undistorted source image) of the point shown at `tex_coord`.
+vec2 undistorted_tex_coord(vec2 tex_coord)
+{
+    if (!lens_distortion_enabled) { return tex_coord; }
+
+    vec2 distorted = (tex_coord - vec2(0.5)) * image_size / focal_length_px;
+    // the model maps undistorted coordinates to distorted ones; invert it by fixed-point iteration
+    vec2 undistorted = distorted;
+    for (int i = 0; i < 10; ++i)
+    {
+        float r2 = dot(undistorted, undistorted);
+        undistorted = distorted / (1.0 + lens_k1 * r2 + lens_k2 * r2 * r2);
+    }
+
+    return undistorted * focal_length_px / image_size + vec2(0.5);
+}
+
+// `source_coord`: value returned by `undistorted_tex_coord(tex_coord)`.
+vec3 apply_lens_effects(vec3 color, vec2 tex_coord, vec2 source_coord)
+{
+    if (!lens_distortion_enabled) { return color; }
+
+    // strong barrel distortion brings in points from outside the source image
+    if (any(lessThan(source_coord, vec2(0.0))) || any(greaterThan(source_coord, vec2(1.0)))) { return vec3(0.0); }
+
+    vec2 offset = (tex_coord - vec2(0.5)) * image_size;
+    float r2_rel_corner = dot(offset, offset) / dot(0.5 * image_size, 0.5 * image_size);
+
+    return color * (1.0 - vignetting * r2_rel_corner);
+}
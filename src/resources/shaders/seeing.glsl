@@ -0,0 +1,21 @@
+// Atmospheric seeing; inserted after the `#version` directive of shaders using it (see `data::with_library`).
+
+uniform bool seeing_enabled;
+// standard deviation of the current blur kernel (pixels)
+uniform float seeing_sigma_px;
+// current image motion (pixels)
+uniform vec2 seeing_offset_px;
+
+// Kernel taps span ±SEEING_KERNEL_RADIUS standard deviations in X and Y.
+const int SEEING_KERNEL_RADIUS = 2;
+
+// Returns offset (pixels) of the source point of kernel tap (i, j), where i, j = -R...R (R: SEEING_KERNEL_RADIUS).
+vec2 seeing_tap_offset(int i, int j)
+{
+    return vec2(i, j) * seeing_sigma_px - seeing_offset_px;
+}
+
+float seeing_tap_weight(int i, int j)
+{
+    return exp(-0.5 * float(i * i + j * j));
+}
@@ -0,0 +1,59 @@
+// Sensor noise; inserted after the `#version` directive of shaders using it (see `data::with_library`).
+
+uniform bool sensor_noise_enabled;
+// standard deviation of read noise (fraction of full scale)
+uniform float read_noise;
+// full well capacity (electrons) corresponding to full scale; determines shot noise (0: no shot noise)
+uniform float full_well;
+uniform float hot_pixel_fraction;
+// changes every frame
+uniform uint noise_seed;
+// constant, so that hot pixels stay in place
+uniform uint hot_pixel_seed;
+
+const float PI = 3.14159265358979;
+
+uint noise_hash(uint x)
+{
+    x ^= x >> 16u;
+    x *= 0x7feb352du;
+    x ^= x >> 15u;
+    x *= 0x846ca68bu;
+    x ^= x >> 16u;
+    return x;
+}
+
+// Returns a pseudorandom value in [0, 1).
+float noise_uniform(uvec2 pixel, uint seed, uint stream)
+{
+    return float(noise_hash(pixel.x ^ noise_hash(pixel.y ^ noise_hash(seed ^ noise_hash(stream))))) / 4294967296.0;
+}
+
+// Returns a pseudorandom value with standard normal distribution (Box-Muller transform).
+float noise_gaussian(uvec2 pixel, uint seed, uint stream)
+{
+    float u1 = max(noise_uniform(pixel, seed, 2u * stream), 1.0e-7);
+    float u2 = noise_uniform(pixel, seed, 2u * stream + 1u);
+    return sqrt(-2.0 * log(u1)) * cos(2.0 * PI * u2);
+}
+
+vec3 apply_sensor_noise(vec3 color, uvec2 pixel)
+{
+    if (!sensor_noise_enabled) { return color; }
+
+    vec3 result;
+    for (int c = 0; c < 3; ++c)
+    {
+        float variance = read_noise * read_noise;
+        // shot noise: Poisson-distributed electron count (approximated as Gaussian)
+        if (full_well > 0.0) { variance += max(color[c], 0.0) / full_well; }
+        result[c] = color[c] + sqrt(variance) * noise_gaussian(pixel, noise_seed, uint(c));
+    }
+
+    if (noise_uniform(pixel, hot_pixel_seed, 100u) < hot_pixel_fraction)
+    {
+        result += vec3(0.3 + 0.7 * noise_uniform(pixel, hot_pixel_seed, 101u));
+    }
+
+    return clamp(result, 0.0, 1.0);
+}
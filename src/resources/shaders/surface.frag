@@ -2,18 +2,46 @@
 
 uniform mat4 view;
 uniform vec3 draw_color;
+// color of the airlight (haze) added along the line of sight
+uniform vec3 haze_color;
+// atmospheric extinction coefficient (1/m)
+uniform float extinction_coeff;
+// direction towards the light source (the Sun), in the local frame
+uniform vec3 to_light_dir;
+// intensity of direct lighting (0: the light source is below the horizon)
+uniform float light_intensity;
+// intensity of diffuse lighting by the sky
+uniform float ambient_intensity;
 
 in vec3 view_normal;
 in vec3 view_position;
 
 out vec4 color;
 
-const vec3 to_light_dir = normalize(vec3(-0.5, -1.0, -0.2));
+const float SPECULAR_STRENGTH = 1.5;
+const float SHININESS = 64.0;
 
 void main()
 {
     vec3 normal_toward_eye = normalize(faceforward(view_normal, view_position, view_normal));
-    float dotp = max(0.0, dot(normal_toward_eye, normalize(mat3(view) * to_light_dir)));
+    vec3 view_to_light = normalize(mat3(view) * to_light_dir);
+    float dotp = max(0.0, dot(normal_toward_eye, view_to_light));
+    // glint of sunlight reflected towards the observer
+    float specular = dotp > 0.0
+        ? pow(max(0.0, dot(reflect(-view_to_light, normal_toward_eye), normalize(-view_position))), SHININESS)
+        : 0.0;
 
-    color = vec4(2.0 * draw_color * dotp, 1.0);
+    // Beer-Lambert extinction of the light from the surface, replaced by haze
+    float transmittance = exp(-extinction_coeff * length(view_position));
+
+    vec3 lit_color = draw_color * (ambient_intensity + 2.0 * light_intensity * dotp)
+        + vec3(SPECULAR_STRENGTH * light_intensity * specular);
+    vec3 surface_color = mix(haze_color, lit_color, transmittance);
+
+    // `view` is a pure rotation, so its transpose transforms back to the local frame
+    float layer_distance;
+    float cloud = cloud_opacity(transpose(mat3(view)) * view_position, length(view_position), layer_distance);
+    vec3 hazed_cloud_color = mix(haze_color, cloud_color, exp(-extinction_coeff * layer_distance));
+
+    color = vec4(mix(surface_color, hazed_cloud_color, cloud), 1.0);
 }
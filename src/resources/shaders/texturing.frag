@@ -4,12 +4,42 @@ in vec2 tex_coord;
 out vec4 output_color;
 
 uniform sampler2D source_texture;
+// transmission (R, G, B) of the filter in the light path; applied if `filter_enabled` is set
+uniform bool filter_enabled;
+uniform vec3 filter_transmission;
 uniform float brightness;
 
 void main()
 {
-    vec4 color = texture(source_texture, tex_coord);
+    vec2 source_coord = undistorted_tex_coord(tex_coord);
+
+    vec4 color = vec4(0.0);
+    if (seeing_enabled)
+    {
+        vec2 texel_size = 1.0 / vec2(textureSize(source_texture, 0));
+        float total_weight = 0.0;
+        for (int i = -SEEING_KERNEL_RADIUS; i <= SEEING_KERNEL_RADIUS; ++i)
+        {
+            for (int j = -SEEING_KERNEL_RADIUS; j <= SEEING_KERNEL_RADIUS; ++j)
+            {
+                float weight = seeing_tap_weight(i, j);
+                color += weight * texture(source_texture, source_coord + seeing_tap_offset(i, j) * texel_size);
+                total_weight += weight;
+            }
+        }
+        color /= total_weight;
+    }
+    else
+    {
+        color = texture(source_texture, source_coord);
+    }
+
     color.rgb *= brightness;
+    if (filter_enabled)
+    {
+        color.rgb *= filter_transmission;
+    }
+    color.rgb = apply_lens_effects(color.rgb, tex_coord, source_coord);
 
-    output_color = color;
+    output_color = vec4(apply_sensor_noise(color.rgb, uvec2(gl_FragCoord.xy)), color.a);
 }
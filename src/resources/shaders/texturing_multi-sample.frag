@@ -4,19 +4,56 @@ in vec2 tex_coord;
 out vec4 output_color;
 
 uniform sampler2DMS source_texture;
+uniform int num_samples;
+// transmission (R, G, B) of the filter in the light path; applied if `filter_enabled` is set
+uniform bool filter_enabled;
+uniform vec3 filter_transmission;
 
-void main()
+// Returns the average of all samples of `texel` (clamped to the texture area).
+vec4 fetch_averaged(ivec2 texel)
 {
-    vec4 color = vec4(0.0);
-
-    ivec2 texel = ivec2(tex_coord * textureSize(source_texture)); //TODO: provide texture size as a uniform for better speed?
+    ivec2 size = textureSize(source_texture);
+    texel = clamp(texel, ivec2(0), size - ivec2(1));
 
+    vec4 color = vec4(0.0);
     //TODO: provide additional input with sample mask, sum only edge samples?
-    for (int i = 0; i < 8; ++i) //TODO: provide sample count as uniform
+    for (int i = 0; i < num_samples; ++i)
     {
         color += texelFetch(source_texture, texel, i);
     }
-    color /= 8.0;
+    return color / float(num_samples);
+}
+
+void main()
+{
+    vec2 source_coord = undistorted_tex_coord(tex_coord);
+    vec2 source_pixel = source_coord * textureSize(source_texture); //TODO: provide texture size as a uniform for better speed?
+
+    vec4 color = vec4(0.0);
+    if (seeing_enabled)
+    {
+        float total_weight = 0.0;
+        for (int i = -SEEING_KERNEL_RADIUS; i <= SEEING_KERNEL_RADIUS; ++i)
+        {
+            for (int j = -SEEING_KERNEL_RADIUS; j <= SEEING_KERNEL_RADIUS; ++j)
+            {
+                float weight = seeing_tap_weight(i, j);
+                color += weight * fetch_averaged(ivec2(source_pixel + seeing_tap_offset(i, j)));
+                total_weight += weight;
+            }
+        }
+        color /= total_weight;
+    }
+    else
+    {
+        color = fetch_averaged(ivec2(source_pixel));
+    }
+
+    if (filter_enabled)
+    {
+        color.rgb *= filter_transmission;
+    }
+    color.rgb = apply_lens_effects(color.rgb, tex_coord, source_coord);
 
-    output_color = color;
+    output_color = vec4(apply_sensor_noise(color.rgb, uvec2(gl_FragCoord.xy)), color.a);
 }
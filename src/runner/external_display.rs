@@ -0,0 +1,95 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Secondary borderless window showing only the camera image (at native resolution), e.g., for capture cards
+//! and recorders expecting a clean feed.
+
+use glium::{glutin::{config::Config, surface::WindowSurface}, Surface, texture::{RawImage2d, Texture2d}};
+use imgui_winit_support::winit::{dpi, event_loop::EventLoop, window::{Window, WindowBuilder, WindowId}};
+use std::{cell::RefCell, rc::Rc};
+
+/// Camera image to be shown in the external display.
+pub struct ExternalFrame {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA, top row first.
+    pub data: Vec<u8>
+}
+
+/// Holds the most recent frame not yet shown.
+pub type ExternalFeed = Rc<RefCell<Option<ExternalFrame>>>;
+
+pub struct ExternalDisplay {
+    window: Window,
+    display: glium::Display<WindowSurface>,
+    feed: ExternalFeed,
+    /// Most recent frame (rows stored top-to-bottom).
+    texture: Option<Texture2d>
+}
+
+impl ExternalDisplay {
+    pub fn new(event_loop: &EventLoop<()>, cfg: &Config) -> ExternalDisplay {
+        const INITIAL_WIDTH: u32 = 640;
+        const INITIAL_HEIGHT: u32 = 480;
+
+        let window_builder = WindowBuilder::new()
+            .with_title("Pointing Simulator - camera feed".to_owned())
+            .with_decorations(false)
+            .with_inner_size(dpi::PhysicalSize::new(INITIAL_WIDTH, INITIAL_HEIGHT));
+        let window = glutin_winit::finalize_window(event_loop, window_builder, cfg)
+            .expect("Failed to create external display window");
+        let display = super::create_display(cfg, &window, INITIAL_WIDTH, INITIAL_HEIGHT);
+
+        ExternalDisplay{ window, display, feed: Rc::new(RefCell::new(None)), texture: None }
+    }
+
+    pub fn feed(&self) -> ExternalFeed { Rc::clone(&self.feed) }
+
+    pub fn window_id(&self) -> WindowId { self.window.id() }
+
+    pub fn resize(&self, new_size: dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.display.resize((new_size.width, new_size.height));
+        }
+    }
+
+    /// Shows the most recent frame from the feed.
+    pub fn draw(&mut self) {
+        if let Some(frame) = self.feed.borrow_mut().take() {
+            let size = dpi::PhysicalSize::new(frame.width, frame.height);
+            if self.window.inner_size() != size {
+                // the image is shown at native resolution, without scaling
+                let _ = self.window.request_inner_size(size);
+            }
+            match Texture2d::new(&self.display, RawImage2d::from_raw_rgba(frame.data, (frame.width, frame.height))) {
+                Ok(texture) => self.texture = Some(texture),
+                Err(e) => log::error!("failed to create external display texture: {}", e)
+            }
+        }
+
+        let mut target = self.display.draw();
+        target.clear_color(0.0, 0.0, 0.0, 1.0);
+        if let Some(texture) = &self.texture {
+            let (_, target_height) = target.get_dimensions();
+            // flip vertically, as the texture rows are stored top-to-bottom
+            texture.as_surface().blit_whole_color_to(
+                &target,
+                &glium::BlitTarget{
+                    left: 0,
+                    bottom: target_height,
+                    width: texture.width() as i32,
+                    height: -(texture.height() as i32)
+                },
+                glium::uniforms::MagnifySamplerFilter::Nearest
+            );
+        }
+        if let Err(e) = target.finish() {
+            log::error!("failed to swap buffers of external display: {}", e);
+        }
+    }
+}
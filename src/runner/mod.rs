@@ -8,8 +8,9 @@
 
 use glium::{
     Surface,
+    backend::Facade,
     glutin::{
-        config::ConfigTemplateBuilder,
+        config::{Config, ConfigTemplateBuilder},
         context::{ContextAttributesBuilder, NotCurrentGlContext},
         display::{GetGlDisplay, GlDisplay},
         surface::{SurfaceAttributesBuilder, WindowSurface}
@@ -23,20 +24,44 @@ use imgui_winit_support::winit::{
     window::{Window, WindowBuilder}
 };
 use raw_window_handle::HasRawWindowHandle;
-use std::{cell::RefCell, num::NonZeroU32, rc::Rc};
+use std::{cell::RefCell, num::NonZeroU32, rc::Rc, time::{Duration, Instant}};
 
 mod clipboard_support;
+mod external_display;
+mod offscreen;
+
+pub use external_display::{ExternalFeed, ExternalFrame};
+
+/// Interval between frames in off-screen mode.
+const OFFSCREEN_FRAME_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Logical size of the GUI in off-screen mode.
+const OFFSCREEN_GUI_SIZE: [f32; 2] = [1024.0, 768.0];
+
+/// OpenGL context of the GUI (of the main window or, in off-screen mode, a headless one).
+pub type GlContext = Rc<glium::backend::Context>;
 
 #[derive(Copy, Clone)]
 pub struct FontSizeRequest(pub f32);
 
+enum Backend {
+    /// The GUI is shown in the main window.
+    Windowed{
+        event_loop: EventLoop<()>,
+        display: glium::Display<WindowSurface>,
+        window: Window,
+        platform: imgui_winit_support::WinitPlatform,
+        external_display: Option<external_display::ExternalDisplay>
+    },
+    /// There is no window; the GUI is processed (rendering camera views off-screen), but not drawn.
+    Offscreen(glium::backend::glutin::headless::Headless)
+}
+
 pub struct Runner {
-    event_loop: EventLoop<()>,
-    display: glium::Display<WindowSurface>,
     imgui: imgui::Context,
-    pub window: Window,
-    platform: imgui_winit_support::WinitPlatform,
-    renderer: Rc<RefCell<imgui_glium_renderer::Renderer>>
+    renderer: Rc<RefCell<imgui_glium_renderer::Renderer>>,
+    hidpi_factor: f64,
+    backend: Backend
 }
 
 fn create_font(physical_font_size: f32) -> imgui::FontSource<'static> {
@@ -60,40 +85,24 @@ fn create_font(physical_font_size: f32) -> imgui::FontSource<'static> {
     }.into()
 }
 
-pub fn create_runner(logical_font_size: f32) -> Runner {
-    const INITIAL_WIDTH: u32 = 1024;
-    const INITIAL_HEIGHT: u32 = 768;
-
-    let event_loop = EventLoop::new().expect("Failed to create EventLoop");
-
-    let window_builder = WindowBuilder::new()
-        .with_title("Pointing Simulator".to_owned())
-        .with_inner_size(dpi::LogicalSize::new(INITIAL_WIDTH as f64, INITIAL_HEIGHT as f64));
-
-    let (window, cfg) = glutin_winit::DisplayBuilder::new()
-        .with_window_builder(Some(window_builder))
-        .build(&event_loop, ConfigTemplateBuilder::new(), |mut configs| {
-            configs.next().unwrap()
-        })
-        .expect("Failed to create OpenGL window");
-    let window = window.unwrap();
-
+/// Creates an OpenGL context and a window surface for `window`.
+fn create_display(cfg: &Config, window: &Window, width: u32, height: u32) -> glium::Display<WindowSurface> {
     let context_attribs = ContextAttributesBuilder::new().build(Some(window.raw_window_handle()));
     let context = unsafe {
         cfg.display()
-            .create_context(&cfg, &context_attribs)
+            .create_context(cfg, &context_attribs)
             .expect("Failed to create OpenGL context")
     };
 
     let surface_attribs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
         window.raw_window_handle(),
-        NonZeroU32::new(INITIAL_WIDTH).unwrap(),
-        NonZeroU32::new(INITIAL_HEIGHT).unwrap(),
+        NonZeroU32::new(width).unwrap(),
+        NonZeroU32::new(height).unwrap(),
     );
 
     let surface = unsafe {
         cfg.display()
-            .create_window_surface(&cfg, &surface_attribs)
+            .create_window_surface(cfg, &surface_attribs)
             .expect("Failed to create OpenGL surface")
     };
 
@@ -101,12 +110,15 @@ pub fn create_runner(logical_font_size: f32) -> Runner {
         .make_current(&surface)
         .expect("Failed to make OpenGL context current");
 
-    let display = glium::Display::from_context_surface(context, surface)
-        .expect("Failed to create glium Display");
-
+    glium::Display::from_context_surface(context, surface)
+        .expect("Failed to create glium Display")
+}
 
+/// Creates the ImGui context with fonts and settings of the GUI.
+fn create_imgui(logical_font_size: f32, hidpi_factor: f32) -> imgui::Context {
     let mut imgui = imgui::Context::create();
-    imgui.set_ini_filename(None);
+    // window sizes and the docking arrangement are saved periodically and on exit
+    imgui.set_ini_filename(crate::config::layout_file());
 
     if let Some(backend) = clipboard_support::init() {
         imgui.set_clipboard_backend(backend);
@@ -114,56 +126,113 @@ pub fn create_runner(logical_font_size: f32) -> Runner {
         eprintln!("Failed to initialize clipboard.");
     }
 
-    let mut platform = imgui_winit_support::WinitPlatform::init(&mut imgui);
-    platform.attach_window(imgui.io_mut(), &window, imgui_winit_support::HiDpiMode::Default);
-
-    let hidpi_factor = platform.hidpi_factor() as f32;
-    let font_size = logical_font_size * hidpi_factor;
-
-    imgui.fonts().add_font(&[create_font(font_size)]);
+    imgui.fonts().add_font(&[create_font(logical_font_size * hidpi_factor)]);
 
     imgui.io_mut().font_global_scale = 1.0 / hidpi_factor;
     imgui.io_mut().config_flags |= imgui::ConfigFlags::DOCKING_ENABLE;
     imgui.io_mut().config_windows_move_from_title_bar_only = true;
 
+    imgui
+}
+
+/// If `external_display` is true, a secondary borderless window showing only the camera image is also created
+/// (see `Runner::external_feed`).
+///
+/// If `offscreen` is true, no window is created; the GUI uses a headless EGL context (see `offscreen`), so that no
+/// display server is needed, and frames are produced at a fixed rate for the recording and video output subsystems.
+pub fn create_runner(logical_font_size: f32, external_display: bool, offscreen: bool) -> Runner {
+    if offscreen { return create_offscreen_runner(logical_font_size); }
+
+    const INITIAL_WIDTH: u32 = 1024;
+    const INITIAL_HEIGHT: u32 = 768;
+
+    let event_loop = EventLoop::new().expect("Failed to create EventLoop");
+
+    let window_builder = WindowBuilder::new()
+        .with_title("Pointing Simulator".to_owned())
+        .with_inner_size(dpi::LogicalSize::new(INITIAL_WIDTH as f64, INITIAL_HEIGHT as f64));
+
+    let (window, cfg) = glutin_winit::DisplayBuilder::new()
+        .with_preference(glutin_winit::ApiPreference::FallbackEgl)
+        .with_window_builder(Some(window_builder))
+        .build(&event_loop, ConfigTemplateBuilder::new(), |mut configs| {
+            configs.next().unwrap()
+        })
+        .expect("Failed to create OpenGL window");
+    let window = window.unwrap();
+
+    let external_display = if external_display {
+        Some(external_display::ExternalDisplay::new(&event_loop, &cfg))
+    } else {
+        None
+    };
+
+    // created last, so that its context is current
+    let display = create_display(&cfg, &window, INITIAL_WIDTH, INITIAL_HEIGHT);
+
+    let mut imgui = imgui::Context::create();
+    let mut platform = imgui_winit_support::WinitPlatform::init(&mut imgui);
+    platform.attach_window(imgui.io_mut(), &window, imgui_winit_support::HiDpiMode::Default);
+    let hidpi_factor = platform.hidpi_factor();
+    // the context is created anew, as the platform needs one to determine the DPI factor
+    let mut imgui = create_imgui(logical_font_size, hidpi_factor as f32);
+    platform.attach_window(imgui.io_mut(), &window, imgui_winit_support::HiDpiMode::Default);
+
     let renderer = imgui_glium_renderer::Renderer::init(&mut imgui, &display).expect("failed to initialize renderer");
 
     Runner{
-        event_loop,
-        display,
         imgui,
-        window,
-        platform,
-        renderer: Rc::new(RefCell::new(renderer))
+        renderer: Rc::new(RefCell::new(renderer)),
+        hidpi_factor,
+        backend: Backend::Windowed{ event_loop, display, window, platform, external_display }
     }
 }
 
-impl Runner {
-    pub fn platform(&self) -> &imgui_winit_support::WinitPlatform {
-        &self.platform
+fn create_offscreen_runner(logical_font_size: f32) -> Runner {
+    let context = offscreen::create_context().expect("Failed to create off-screen OpenGL context");
+
+    let mut imgui = create_imgui(logical_font_size, 1.0);
+    imgui.io_mut().display_size = OFFSCREEN_GUI_SIZE;
+
+    let renderer = imgui_glium_renderer::Renderer::init(&mut imgui, &context).expect("failed to initialize renderer");
+
+    Runner{
+        imgui,
+        renderer: Rc::new(RefCell::new(renderer)),
+        hidpi_factor: 1.0,
+        backend: Backend::Offscreen(context)
     }
+}
 
-    pub fn display(&self) -> &glium::Display<WindowSurface> {
-        &self.display
+impl Runner {
+    pub fn hidpi_factor(&self) -> f64 { self.hidpi_factor }
+
+    /// Returns the feed of the external display (if enabled); frames placed there are shown in it.
+    pub fn external_feed(&self) -> Option<ExternalFeed> {
+        match &self.backend {
+            Backend::Windowed{ external_display, .. } => external_display.as_ref().map(|external| external.feed()),
+            Backend::Offscreen(_) => None
+        }
     }
 
     pub fn main_loop<F>(self, mut run_ui: F)
         where F: FnMut(
             &mut bool,
             &mut imgui::Ui,
-            &glium::Display<WindowSurface>,
+            &GlContext,
             &Rc<RefCell<imgui_glium_renderer::Renderer>>
         ) -> Option<FontSizeRequest> + 'static
     {
-        let Runner {
-            event_loop,
-            display,
-            mut imgui,
-            window,
-            mut platform,
-            renderer,
-            ..
-        } = self;
+        let Runner{ mut imgui, renderer, backend, .. } = self;
+        let (event_loop, display, window, mut platform, mut external_display) = match backend {
+            Backend::Windowed{ event_loop, display, window, platform, external_display } =>
+                (event_loop, display, window, platform, external_display),
+
+            Backend::Offscreen(context) => {
+                run_offscreen(imgui, &context, &renderer, run_ui);
+                return;
+            }
+        };
 
         let mut last_frame = std::time::Instant::now();
 
@@ -182,15 +251,15 @@ impl Runner {
             },
 
             Event::WindowEvent {
-                event: WindowEvent::RedrawRequested,
-                ..
-            } => {
+                window_id,
+                event: WindowEvent::RedrawRequested
+            } if window_id == window.id() => {
                 let font_size_request;
                 {
                     let mut ui = imgui.frame();
 
                     let mut run = true;
-                    font_size_request = run_ui(&mut run, &mut ui, &display, &renderer);
+                    font_size_request = run_ui(&mut run, &mut ui, display.get_context(), &renderer);
                     if !run {
                         window_target.exit();
                     }
@@ -204,6 +273,9 @@ impl Runner {
                         .expect("rendering failed");
                     target.finish().expect("failed to swap buffers");
                 }
+                if let Some(external) = &mut external_display {
+                    external.draw();
+                }
                 if let Some(fsr) = font_size_request {
                     imgui.fonts().clear();
                     imgui.fonts().add_font(&[create_font(platform.hidpi_factor() as f32 * fsr.0)]);
@@ -211,6 +283,15 @@ impl Runner {
                 }
             },
 
+            Event::WindowEvent {
+                window_id,
+                event
+            } if external_display.as_ref().map_or(false, |external| external.window_id() == window_id) => {
+                if let WindowEvent::Resized(new_size) = event {
+                    external_display.as_ref().unwrap().resize(new_size);
+                }
+            },
+
             Event::WindowEvent {
                 event: WindowEvent::CloseRequested,
                 ..
@@ -235,6 +316,37 @@ impl Runner {
     }
 }
 
+/// Processes the GUI at a fixed rate without drawing it, until `run_ui` requests exit.
+fn run_offscreen<F>(
+    mut imgui: imgui::Context,
+    context: &glium::backend::glutin::headless::Headless,
+    renderer: &Rc<RefCell<imgui_glium_renderer::Renderer>>,
+    mut run_ui: F
+)
+    where F: FnMut(
+        &mut bool,
+        &mut imgui::Ui,
+        &GlContext,
+        &Rc<RefCell<imgui_glium_renderer::Renderer>>
+    ) -> Option<FontSizeRequest>
+{
+    let mut last_frame = Instant::now();
+    loop {
+        let now = Instant::now();
+        imgui.io_mut().update_delta_time(now - last_frame);
+        last_frame = now;
+
+        let ui = imgui.frame();
+        let mut run = true;
+        // font size changes are irrelevant, as the GUI is not drawn
+        let _ = run_ui(&mut run, ui, context.get_context(), renderer);
+        imgui.render();
+        if !run { break; }
+
+        std::thread::sleep(OFFSCREEN_FRAME_INTERVAL.saturating_sub(now.elapsed()));
+    }
+}
+
 fn convert_touch_to_mouse<'a, T>(event: Event<T>) -> Event<T> {
     match event {
         Event::WindowEvent {
@@ -0,0 +1,53 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Headless OpenGL context of the off-screen mode: a surfaceless EGL context created directly on a rendering
+//! device (e.g., with Mesa's EGL_MESA_platform_surfaceless or a GPU driver's EGL_EXT_platform_device), so that
+//! neither a window nor a display server is needed. All rendering goes to framebuffer objects.
+
+use glium::{
+    backend::glutin::headless::Headless,
+    glutin::{
+        api::egl::{device::Device, display::Display},
+        config::{ConfigSurfaceTypes, ConfigTemplateBuilder},
+        context::{ContextAttributesBuilder, PossiblyCurrentContext},
+        display::GlDisplay
+    }
+};
+
+/// Creates a surfaceless context on the first EGL device which supports one.
+pub fn create_context() -> Result<Headless, String> {
+    let devices = Device::query_devices().map_err(|e| format!("cannot query EGL devices: {}", e))?;
+    let mut errors = vec![];
+    for (i, device) in devices.enumerate() {
+        match create_device_context(&device) {
+            Ok(context) => return Ok(context),
+            Err(e) => errors.push(format!("device {}: {}", i, e))
+        }
+    }
+
+    Err(if errors.is_empty() { "no EGL device found".into() } else { errors.join("; ") })
+}
+
+fn create_device_context(device: &Device) -> Result<Headless, String> {
+    let display = unsafe { Display::with_device(device, None) }.map_err(|e| e.to_string())?;
+
+    // no surfaces are created
+    let template = ConfigTemplateBuilder::new().with_surface_type(ConfigSurfaceTypes::empty()).build();
+    let config = unsafe { display.find_configs(template) }
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| "no suitable config".to_string())?;
+
+    let context = unsafe { display.create_context(&config, &ContextAttributesBuilder::new().build(None)) }
+        .map_err(|e| e.to_string())?
+        .make_current_surfaceless()
+        .map_err(|e| e.to_string())?;
+
+    Headless::new(PossiblyCurrentContext::Egl(context)).map_err(|e| e.to_string())
+}
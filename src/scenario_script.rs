@@ -0,0 +1,243 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Scenario scripts (Rhai): target maneuvers, fault injections and checks of conditions during a run.
+//!
+//! The script's top-level code runs once at start; then its function `tick(t)` is called every frame with the
+//! time since start (s). `this` is an object map preserved between calls (missing properties read as `()`).
+//! Numeric arguments are floats (e.g. `30.0`). Available functions:
+//!
+//! - `error_deg()`: pointing error of the first station (degrees), `()` if there is no target data
+//! - `set_target_speed(m_per_s)`, `set_target_track_noise(deg_per_sqrt_s)`: motion of the default target
+//! - `drop_feed(duration_s)`, `stop_mount()`, `park()`, `set_wind(enabled)`, `marker(text)`: as the event hooks'
+//! actions
+//! - `expect(name, condition)`: the condition must be true whenever checked
+//! - `expect_for(name, condition, duration_s)`: the condition must be true continuously for the given time
+//! - `finish()`: ends the script and the program, reporting the results of the checks; the exit status is 1 if any
+//! of them failed (or the script has failed with an error)
+//!
+//! ```rhai
+//! fn tick(t) {
+//!     if t > 10.0 && this.turned == () { set_target_speed(300.0); this.turned = true; }
+//!     let error = error_deg();
+//!     expect_for("tracking after speed-up", error != () && error < 0.1, 30.0);
+//!     if t > 120.0 { finish(); }
+//! }
+//! ```
+
+use crate::{
+    event_hooks::{Action, EventHooks},
+    target_geometry::{separation, TargetDirection},
+    workers::{Mount, TargetMotion}
+};
+use pointing_utils::{TargetInfoMessage, uom};
+use std::{cell::RefCell, collections::BTreeMap, path::Path, rc::{Rc, Weak}, sync::{Arc, Mutex}, time::Instant};
+use subscriber_rs::Subscriber;
+use uom::si::angle;
+
+/// Max. number of operations of the top-level code and of each call of `tick`; a script exceeding it (e.g., stuck in
+/// an endless loop) fails with an error instead of freezing the program.
+const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+
+/// Condition checked by the script.
+struct Check {
+    /// For `expect_for`: required duration (s) of the condition being true.
+    duration: Option<f64>,
+    /// Time since when the condition has been true.
+    held_since: Option<f64>,
+    /// `None` if not decided yet.
+    passed: Option<bool>
+}
+
+/// State shared with the functions available to the script.
+struct ScriptState {
+    mount: Arc<Mount>,
+    target_motion: Arc<Mutex<TargetMotion>>,
+    /// Pointing error (degrees).
+    error: Option<f64>,
+    /// Time since start of the script (s).
+    t: f64,
+    /// Actions requested by the script during the current call.
+    actions: Vec<Action>,
+    checks: BTreeMap<String, Check>,
+    finished: bool
+}
+
+impl ScriptState {
+    fn check(&mut self, name: &str, condition: bool, duration: Option<f64>) {
+        let t = self.t;
+        let check = self.checks.entry(name.to_string()).or_insert(Check{ duration, held_since: None, passed: None });
+        if check.passed.is_some() { return; }
+        match (condition, check.duration) {
+            (false, None) => {
+                log::error!("script check failed at t = {:.1} s: {}", t, name);
+                check.passed = Some(false);
+            },
+            (true, Some(duration)) => {
+                let since = *check.held_since.get_or_insert(t);
+                if t - since >= duration {
+                    log::info!("script check passed at t = {:.1} s: {}", t, name);
+                    check.passed = Some(true);
+                }
+            },
+            (false, Some(_)) => check.held_since = None,
+            (true, None) => ()
+        }
+    }
+}
+
+impl Subscriber<TargetInfoMessage> for ScriptState {
+    fn notify(&mut self, value: &TargetInfoMessage) {
+        let state = self.mount.get();
+        self.error = TargetDirection::from_message(value).map(|target| {
+            separation(state.boresight_az, state.boresight_alt, target.az, target.alt).get::<angle::degree>()
+        });
+    }
+}
+
+pub struct ScenarioScript {
+    engine: rhai::Engine,
+    ast: rhai::AST,
+    scope: rhai::Scope<'static>,
+    /// Bound to `this` in `tick`.
+    this: rhai::Dynamic,
+    state: Rc<RefCell<ScriptState>>,
+    event_hooks: Rc<RefCell<EventHooks>>,
+    start: Instant
+}
+
+fn register_functions(engine: &mut rhai::Engine, state: &Rc<RefCell<ScriptState>>) {
+    let s = Rc::clone(state);
+    engine.register_fn("error_deg", move || -> rhai::Dynamic {
+        s.borrow().error.map_or(rhai::Dynamic::UNIT, rhai::Dynamic::from_float)
+    });
+    let s = Rc::clone(state);
+    engine.register_fn("set_target_speed", move |speed: f64| {
+        s.borrow().target_motion.lock().unwrap().speed = speed;
+    });
+    let s = Rc::clone(state);
+    engine.register_fn("set_target_track_noise", move |track_noise: f64| {
+        s.borrow().target_motion.lock().unwrap().track_noise = track_noise;
+    });
+    let s = Rc::clone(state);
+    engine.register_fn("drop_feed", move |duration_s: f64| {
+        s.borrow_mut().actions.push(Action::DropFeed{ duration_s });
+    });
+    let s = Rc::clone(state);
+    engine.register_fn("stop_mount", move || s.borrow_mut().actions.push(Action::StopMount));
+    let s = Rc::clone(state);
+    engine.register_fn("park", move || s.borrow_mut().actions.push(Action::Park));
+    let s = Rc::clone(state);
+    engine.register_fn("set_wind", move |enabled: bool| {
+        let action = Action::SetWind{ enabled, turbulence_rms_arcsec: None, gust_amplitude_arcsec: None };
+        s.borrow_mut().actions.push(action);
+    });
+    let s = Rc::clone(state);
+    engine.register_fn("marker", move |text: &str| {
+        s.borrow_mut().actions.push(Action::LogMarker{ text: text.to_string() });
+    });
+    let s = Rc::clone(state);
+    engine.register_fn("expect", move |name: &str, condition: bool| s.borrow_mut().check(name, condition, None));
+    let s = Rc::clone(state);
+    engine.register_fn("expect_for", move |name: &str, condition: bool, duration_s: f64| {
+        s.borrow_mut().check(name, condition, Some(duration_s));
+    });
+    let s = Rc::clone(state);
+    engine.register_fn("finish", move || s.borrow_mut().finished = true);
+}
+
+impl ScenarioScript {
+    /// Loads the script and runs its top-level code; the script controls `mount` (the first station's) and performs
+    /// actions via its `event_hooks`. Target updates are to be delivered to `target_subscriber`.
+    pub fn load(
+        path: &Path,
+        mount: Arc<Mount>,
+        event_hooks: Rc<RefCell<EventHooks>>,
+        target_motion: Arc<Mutex<TargetMotion>>
+    ) -> Result<ScenarioScript, String> {
+        let state = Rc::new(RefCell::new(ScriptState{
+            mount,
+            target_motion,
+            error: None,
+            t: 0.0,
+            actions: vec![],
+            checks: BTreeMap::new(),
+            finished: false
+        }));
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+        engine.on_print(|text| log::info!("script: {}", text));
+        register_functions(&mut engine, &state);
+
+        let ast = engine.compile_file(path.to_path_buf()).map_err(|e| e.to_string())?;
+        let mut scope = rhai::Scope::new();
+        engine.run_ast_with_scope(&mut scope, &ast).map_err(|e| e.to_string())?;
+
+        let mut script = ScenarioScript{
+            engine,
+            ast,
+            scope,
+            this: rhai::Dynamic::from_map(rhai::Map::new()),
+            state,
+            event_hooks,
+            start: Instant::now()
+        };
+        script.execute_actions();
+
+        Ok(script)
+    }
+
+    pub fn finished(&self) -> bool { self.state.borrow().finished }
+
+    /// Receives target updates, from which the pointing error is determined.
+    pub fn target_subscriber(&self) -> Weak<RefCell<dyn Subscriber<TargetInfoMessage>>> {
+        Rc::downgrade(&self.state) as _
+    }
+
+    /// Calls the script's `tick` function; to be called every frame. Once the script finishes, returns whether all
+    /// checks have passed.
+    pub fn tick(&mut self) -> Option<bool> {
+        if self.finished() { return None; }
+
+        let t = self.start.elapsed().as_secs_f64();
+        self.state.borrow_mut().t = t;
+        let options = rhai::CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.this);
+        let result = self.engine
+            .call_fn_with_options::<rhai::Dynamic>(options, &mut self.scope, &self.ast, "tick", (t,));
+        let failed = result.is_err();
+        if let Err(e) = result {
+            log::error!("script error: {}", e);
+            self.state.borrow_mut().finished = true;
+        }
+        self.execute_actions();
+
+        if self.finished() { Some(self.report() && !failed) } else { None }
+    }
+
+    fn execute_actions(&mut self) {
+        let actions = std::mem::take(&mut self.state.borrow_mut().actions);
+        for action in &actions { self.event_hooks.borrow_mut().execute(action); }
+    }
+
+    /// Logs results of the checks; returns true if all have passed.
+    fn report(&self) -> bool {
+        let mut state = self.state.borrow_mut();
+        let mut num_failed = 0;
+        for (name, check) in &mut state.checks {
+            // checks not decided by now: `expect` ones have passed, `expect_for` ones have failed
+            let passed = *check.passed.get_or_insert(check.duration.is_none());
+            if !passed {
+                log::error!("script check failed: {}", name);
+                num_failed += 1;
+            }
+        }
+        log::info!("script finished: {} check(s) passed, {} failed", state.checks.len() - num_failed, num_failed);
+
+        num_failed == 0
+    }
+}
@@ -0,0 +1,232 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Scoring of manual tracking sessions (e.g., an operator slewing the mount with a joystick client).
+
+use cgmath::{InnerSpace, Vector3};
+use crate::{target_geometry::TargetDirection, workers::{Mount, MountState}};
+use pointing_utils::{TargetInfoMessage, uom};
+use serde::Serialize;
+use std::{path::PathBuf, sync::Arc, time::{Duration, Instant}};
+use subscriber_rs::Subscriber;
+use uom::si::{angle, angular_velocity};
+
+/// Radii (degrees) of the rings around the target; time spent within each is accumulated separately.
+const RINGS_DEG: [f64; 3] = [0.1, 0.25, 0.5];
+
+/// Weights of the time fractions spent within each ring in the score.
+const RING_WEIGHTS: [f64; 3] = [0.5, 0.3, 0.2];
+
+/// Change of the target's angular velocity (relative to its recent average) regarded as a maneuver.
+const MANEUVER_THRESHOLD: f64 = 0.1; // deg/s
+
+/// Time constant of the target angular velocity averaging.
+const VELOCITY_AVERAGING_TIME: f64 = 3.0; // s
+
+/// After a maneuver, the target is considered re-acquired when within this ring (index into `RINGS_DEG`).
+const REACQUISITION_RING: usize = 1;
+
+/// Reaction times longer than this reduce the score.
+const REACTION_TIME_ALLOWANCE: f64 = 2.0; // s
+
+/// Score penalty per second of mean reaction time over `REACTION_TIME_ALLOWANCE`.
+const REACTION_TIME_PENALTY: f64 = 5.0;
+
+/// Results of a tracking session.
+#[derive(Clone, Serialize)]
+pub struct ScoringSummary {
+    pub station: String,
+    pub start_time: String,
+    pub duration_s: f64,
+    /// Fractions (0-1) of session time with the target within each of `RINGS_DEG`.
+    pub time_on_target: [f64; 3],
+    pub ring_radii_deg: [f64; 3],
+    pub num_maneuvers: usize,
+    /// Times from detected maneuvers to re-acquisition (maneuvers not followed by re-acquisition are omitted).
+    pub reaction_times_s: Vec<f64>,
+    pub mean_reaction_time_s: Option<f64>,
+    /// 0-100.
+    pub score: f64,
+    pub grade: char
+}
+
+struct Session {
+    start: Instant,
+    start_time: chrono::DateTime<chrono::Local>,
+    time_in_ring: [Duration; 3],
+    last_update: Option<Instant>,
+    /// Average angular velocity (deg/s) of the target on the sky (azimuthal, altitudinal).
+    avg_velocity: Option<(f64, f64)>,
+    num_maneuvers: usize,
+    /// Time of the last maneuver not yet followed by re-acquisition.
+    pending_maneuver: Option<Instant>,
+    reaction_times: Vec<Duration>
+}
+
+impl Session {
+    fn new() -> Session {
+        Session{
+            start: Instant::now(),
+            start_time: chrono::Local::now(),
+            time_in_ring: [Duration::ZERO; 3],
+            last_update: None,
+            avg_velocity: None,
+            num_maneuvers: 0,
+            pending_maneuver: None,
+            reaction_times: vec![]
+        }
+    }
+
+    fn update(&mut self, offset_deg: f64, velocity: (f64, f64)) {
+        let now = Instant::now();
+        if let Some(last_update) = self.last_update {
+            let dt = now - last_update;
+            for (ring, time) in RINGS_DEG.iter().zip(self.time_in_ring.iter_mut()) {
+                if offset_deg <= *ring { *time += dt; }
+            }
+
+            let avg = self.avg_velocity.get_or_insert(velocity);
+            if (velocity.0 - avg.0).hypot(velocity.1 - avg.1) > MANEUVER_THRESHOLD {
+                if self.pending_maneuver.is_none() {
+                    self.num_maneuvers += 1;
+                    self.pending_maneuver = Some(now);
+                }
+                // restart averaging after a maneuver, so that it is not detected repeatedly
+                *avg = velocity;
+            } else {
+                let f = (dt.as_secs_f64() / VELOCITY_AVERAGING_TIME).min(1.0);
+                *avg = (avg.0 + f * (velocity.0 - avg.0), avg.1 + f * (velocity.1 - avg.1));
+            }
+        }
+        self.last_update = Some(now);
+
+        // re-acquisition is checked starting with the next update after a maneuver
+        if let Some(maneuver) = self.pending_maneuver {
+            if maneuver < now && offset_deg <= RINGS_DEG[REACQUISITION_RING] {
+                self.reaction_times.push(now - maneuver);
+                self.pending_maneuver = None;
+            }
+        }
+    }
+
+    fn summary(&self, station: &str) -> ScoringSummary {
+        let duration = self.start.elapsed().as_secs_f64();
+        let time_on_target = self.time_in_ring.map(|t| if duration > 0.0 { t.as_secs_f64() / duration } else { 0.0 });
+        let reaction_times_s: Vec<f64> = self.reaction_times.iter().map(|t| t.as_secs_f64()).collect();
+        let mean_reaction_time_s = if reaction_times_s.is_empty() {
+            None
+        } else {
+            Some(reaction_times_s.iter().sum::<f64>() / reaction_times_s.len() as f64)
+        };
+
+        let penalty = mean_reaction_time_s
+            .map_or(0.0, |t| (t - REACTION_TIME_ALLOWANCE).max(0.0) * REACTION_TIME_PENALTY);
+        let score = (100.0 * time_on_target.iter().zip(RING_WEIGHTS).map(|(f, w)| f * w).sum::<f64>() - penalty)
+            .clamp(0.0, 100.0);
+
+        ScoringSummary{
+            station: station.to_string(),
+            start_time: self.start_time.to_rfc3339(),
+            duration_s: duration,
+            time_on_target,
+            ring_radii_deg: RINGS_DEG,
+            num_maneuvers: self.num_maneuvers,
+            reaction_times_s,
+            mean_reaction_time_s,
+            score,
+            grade: grade(score)
+        }
+    }
+}
+
+fn grade(score: f64) -> char {
+    match score {
+        s if s >= 85.0 => 'A',
+        s if s >= 70.0 => 'B',
+        s if s >= 50.0 => 'C',
+        s if s >= 30.0 => 'D',
+        _ => 'F'
+    }
+}
+
+/// Returns the unit vector pointing at the given azimuth and altitude (radians) in the local frame.
+fn direction(az: f64, alt: f64) -> Vector3<f64> {
+    // local frame: x points north, y west, z up
+    Vector3{ x: alt.cos() * az.cos(), y: -alt.cos() * az.sin(), z: alt.sin() }
+}
+
+/// Returns angular distance (degrees) between the boresight and the target.
+pub fn offset_deg(state: &MountState, target: &TargetDirection) -> f64 {
+    let boresight = direction(state.boresight_az.get::<angle::radian>(), state.boresight_alt.get::<angle::radian>());
+    let target = direction(target.az.get::<angle::radian>(), target.alt.get::<angle::radian>());
+    boresight.cross(target).magnitude().atan2(boresight.dot(target)).to_degrees()
+}
+
+pub struct Scoring {
+    station: String,
+    mount: Arc<Mount>,
+    session: Option<Session>,
+    /// Summary of the last finished session.
+    last_summary: Option<ScoringSummary>
+}
+
+impl Scoring {
+    pub fn new(station: String, mount: Arc<Mount>) -> Scoring {
+        Scoring{ station, mount, session: None, last_summary: None }
+    }
+
+    pub fn start(&mut self) {
+        self.session = Some(Session::new());
+        self.last_summary = None;
+    }
+
+    pub fn stop(&mut self) {
+        self.last_summary = self.session.take().map(|s| s.summary(&self.station));
+    }
+
+    pub fn is_running(&self) -> bool { self.session.is_some() }
+
+    /// Returns summary of the current session, or of the last one if none is running.
+    pub fn summary(&self) -> Option<ScoringSummary> {
+        match &self.session {
+            Some(session) => Some(session.summary(&self.station)),
+            None => self.last_summary.clone()
+        }
+    }
+
+    /// Saves the summary of the last finished session as JSON in the configuration directory; returns its path.
+    pub fn export(&self) -> Result<PathBuf, String> {
+        let summary = self.last_summary.as_ref().ok_or_else(|| "no finished session".to_string())?;
+        let dir = crate::config::config_dir().ok_or_else(|| "cannot determine configuration directory".to_string())?
+            .join("scores");
+        let start_time = chrono::DateTime::parse_from_rfc3339(&summary.start_time).map_err(|e| e.to_string())?;
+        let path = dir.join(format!("{}-{}.json", self.station.replace(' ', "_"), start_time.format("%Y%m%d-%H%M%S")));
+        let contents = serde_json::to_string_pretty(summary).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, contents)).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+impl Subscriber<TargetInfoMessage> for Scoring {
+    fn notify(&mut self, value: &TargetInfoMessage) {
+        let session = match &mut self.session {
+            Some(session) => session,
+            None => return
+        };
+        let target = match TargetDirection::from_message(value) {
+            Some(target) => target,
+            None => return
+        };
+
+        let velocity = (
+            target.az_rate.get::<angular_velocity::degree_per_second>() * target.alt.get::<angle::radian>().cos(),
+            target.alt_rate.get::<angular_velocity::degree_per_second>()
+        );
+        session.update(offset_deg(&self.mount.get(), &target), velocity);
+    }
+}
@@ -0,0 +1,121 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Low-precision (ca. 0.01° for the Sun, ca. 1° for the Moon) positions of the Sun and Moon, and sky brightness.
+
+use cgmath::Deg;
+
+/// Angular radius of the Sun and Moon (approximately equal).
+pub const SUN_MOON_ANGULAR_RADIUS: Deg<f64> = Deg(0.26);
+
+/// Sky background color vs. solar altitude (degrees): day, civil, nautical and astronomical twilight, night.
+const SKY_COLORS: [(f64, [f32; 3]); 5] = [
+    (-18.0, [0.01, 0.01, 0.03]),
+    (-12.0, [0.03, 0.04, 0.10]),
+    (-6.0, [0.12, 0.14, 0.30]),
+    (0.0, [0.35, 0.40, 0.65]),
+    (10.0, [0.40, 0.60, 0.90])
+];
+
+/// Horizontal coordinates (degrees); azimuth measured from north towards east.
+#[derive(Copy, Clone, Debug)]
+pub struct AzAlt {
+    pub az: f64,
+    pub alt: f64
+}
+
+/// Sky as seen from a site, at simulated time running at the real rate from a chosen start.
+pub struct SkyModel {
+    latitude: Deg<f64>,
+    /// Positive towards east.
+    longitude: Deg<f64>,
+    /// Simulated time minus real time.
+    time_offset: chrono::Duration
+}
+
+impl SkyModel {
+    pub fn new(latitude: Deg<f64>, longitude: Deg<f64>, start: chrono::DateTime<chrono::Utc>) -> SkyModel {
+        SkyModel{ latitude, longitude, time_offset: start - chrono::Utc::now() }
+    }
+
+    pub fn time(&self) -> chrono::DateTime<chrono::Utc> { chrono::Utc::now() + self.time_offset }
+
+    /// Days since J2000.0.
+    fn days_since_j2000(&self) -> f64 {
+        let j2000 = chrono::DateTime::parse_from_rfc3339("2000-01-01T12:00:00Z").unwrap();
+        (self.time() - j2000.with_timezone(&chrono::Utc)).num_milliseconds() as f64 / 86_400_000.0
+    }
+
+    pub fn sun(&self) -> AzAlt {
+        let n = self.days_since_j2000();
+        let mean_longitude = 280.460 + 0.9856474 * n;
+        let g = (357.528 + 0.9856003 * n).to_radians();
+        let ecl_longitude = mean_longitude + 1.915 * g.sin() + 0.020 * (2.0 * g).sin();
+        self.ecliptic_to_horizontal(n, ecl_longitude, 0.0)
+    }
+
+    pub fn moon(&self) -> AzAlt {
+        let n = self.days_since_j2000();
+        let mean_longitude = 218.316 + 13.176396 * n;
+        let mean_anomaly = (134.963 + 13.064993 * n).to_radians();
+        let arg_of_latitude = (93.272 + 13.229350 * n).to_radians();
+        self.ecliptic_to_horizontal(
+            n,
+            mean_longitude + 6.289 * mean_anomaly.sin(),
+            5.128 * arg_of_latitude.sin()
+        )
+    }
+
+    /// Converts geocentric ecliptic coordinates (degrees) to horizontal ones (ignoring parallax).
+    fn ecliptic_to_horizontal(&self, n: f64, longitude: f64, latitude: f64) -> AzAlt {
+        let obliquity = (23.439 - 0.0000004 * n).to_radians();
+        let (lon, lat) = (longitude.to_radians(), latitude.to_radians());
+
+        let ra = (lon.sin() * obliquity.cos() - lat.tan() * obliquity.sin()).atan2(lon.cos());
+        let dec = (lat.sin() * obliquity.cos() + lat.cos() * obliquity.sin() * lon.sin()).asin();
+
+        let hour_angle = self.local_sidereal_time(n).to_radians() - ra;
+        let phi = self.latitude.0.to_radians();
+
+        let alt = (phi.sin() * dec.sin() + phi.cos() * dec.cos() * hour_angle.cos()).asin();
+        let az = (-dec.cos() * hour_angle.sin()).atan2(dec.sin() * phi.cos() - dec.cos() * hour_angle.cos() * phi.sin());
+
+        AzAlt{ az: az.to_degrees().rem_euclid(360.0), alt: alt.to_degrees() }
+    }
+
+    /// Returns local mean sidereal time (degrees); `n`: days since J2000.0.
+    fn local_sidereal_time(&self, n: f64) -> f64 {
+        let gmst = 280.46061837 + 360.98564736629 * n;
+        gmst + self.longitude.0
+    }
+
+    /// Converts horizontal coordinates to equatorial ones (right ascension, declination; degrees) of date.
+    pub fn horizontal_to_equatorial(&self, direction: AzAlt) -> (f64, f64) {
+        let (az, alt) = (direction.az.to_radians(), direction.alt.to_radians());
+        let phi = self.latitude.0.to_radians();
+
+        let dec = (phi.sin() * alt.sin() + phi.cos() * alt.cos() * az.cos()).asin();
+        let hour_angle = (-az.sin() * alt.cos()).atan2(phi.cos() * alt.sin() - phi.sin() * alt.cos() * az.cos());
+        let ra = self.local_sidereal_time(self.days_since_j2000()) - hour_angle.to_degrees();
+
+        (ra.rem_euclid(360.0), dec.to_degrees())
+    }
+
+    pub fn background_color(&self) -> [f32; 3] {
+        let sun_alt = self.sun().alt;
+        if sun_alt <= SKY_COLORS[0].0 { return SKY_COLORS[0].1; }
+        for w in SKY_COLORS.windows(2) {
+            let ((alt0, c0), (alt1, c1)) = (w[0], w[1]);
+            if sun_alt <= alt1 {
+                let t = ((sun_alt - alt0) / (alt1 - alt0)) as f32;
+                return [0, 1, 2].map(|i| c0[i] + (c1[i] - c0[i]) * t);
+            }
+        }
+        SKY_COLORS[SKY_COLORS.len() - 1].1
+    }
+}
@@ -0,0 +1,60 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use cgmath::EuclideanSpace;
+use crate::units;
+use pointing_utils::{TargetInfoMessage, uom};
+use uom::{si::f64, si::{angle, angular_velocity}};
+
+/// Direction and angular rates of a target as seen by the observer.
+pub struct TargetDirection {
+    /// Measured from north towards east.
+    pub az: f64::Angle,
+    pub alt: f64::Angle,
+    pub az_rate: f64::AngularVelocity,
+    pub alt_rate: f64::AngularVelocity
+}
+
+impl TargetDirection {
+    /// Returns `None` if the target is (almost) at zenith, where azimuth is undefined.
+    pub fn from_message(value: &TargetInfoMessage) -> Option<TargetDirection> {
+        let p = value.position.0;
+        let v = value.velocity.0;
+        let r_sq = p.x * p.x + p.y * p.y;
+        if r_sq < 1.0 { return None; }
+        let r = r_sq.sqrt();
+
+        // local frame: x points north, y west, z up
+        Some(TargetDirection{
+            az: units::azimuth(p.to_vec()),
+            alt: f64::Angle::new::<angle::radian>(p.z.atan2(r)),
+            az_rate: f64::AngularVelocity::new::<angular_velocity::radian_per_second>(
+                (p.y * v.x - p.x * v.y) / r_sq
+            ),
+            alt_rate: f64::AngularVelocity::new::<angular_velocity::radian_per_second>(
+                (r * v.z - p.z * (p.x * v.x + p.y * v.y) / r) / (r_sq + p.z * p.z)
+            )
+        })
+    }
+
+    /// Returns the angular speed of the target across the sky.
+    pub fn angular_speed(&self) -> f64::AngularVelocity {
+        let az_rate = self.az_rate.get::<angular_velocity::radian_per_second>() * self.alt.get::<angle::radian>().cos();
+        let alt_rate = self.alt_rate.get::<angular_velocity::radian_per_second>();
+        f64::AngularVelocity::new::<angular_velocity::radian_per_second>(az_rate.hypot(alt_rate))
+    }
+}
+
+/// Returns the angle between two directions given as (azimuth, altitude).
+pub fn separation(az1: f64::Angle, alt1: f64::Angle, az2: f64::Angle, alt2: f64::Angle) -> f64::Angle {
+    let (az1, alt1, az2, alt2) = (
+        az1.get::<angle::radian>(), alt1.get::<angle::radian>(), az2.get::<angle::radian>(), alt2.get::<angle::radian>()
+    );
+    let cos_sep = alt1.sin() * alt2.sin() + alt1.cos() * alt2.cos() * (az1 - az2).cos();
+    f64::Angle::new::<angle::radian>(cos_sep.clamp(-1.0, 1.0).acos())
+}
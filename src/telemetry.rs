@@ -0,0 +1,147 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! Recording of mount telemetry (axis positions, speeds and pointing error) for plotting and export.
+
+use crate::{target_geometry::{TargetDirection, separation}, workers::Mount};
+use pointing_utils::{TargetInfoMessage, uom};
+use std::{collections::VecDeque, fmt::Write, path::PathBuf, sync::Arc, time::{Duration, Instant}};
+use subscriber_rs::Subscriber;
+use uom::si::{angle, angular_velocity};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Samples older than this are discarded.
+const MAX_HISTORY: Duration = Duration::from_secs(600);
+
+/// Pointing error is not recorded if there was no target information for this long.
+const TARGET_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Selectable lengths (seconds) of the plotted time span.
+pub const TIME_SPANS_S: [u64; 5] = [10, 30, 60, 300, 600];
+
+const DEFAULT_TIME_SPAN_IDX: usize = 1;
+
+const CSV_HEADER: &str = "time_s,axis1_pos_deg,axis2_pos_deg,axis1_spd_deg_per_s,axis2_spd_deg_per_s,error_arcsec";
+
+#[derive(Clone)]
+pub struct Sample {
+    /// Time since the start of recording.
+    pub time_s: f64,
+    pub axis_pos_deg: [f64; 2],
+    pub axis_spd_deg_per_s: [f64; 2],
+    /// Angular distance between the boresight and the target.
+    pub error_arcsec: Option<f64>
+}
+
+pub struct Telemetry {
+    station: String,
+    mount: Arc<Mount>,
+    start: Instant,
+    samples: VecDeque<Sample>,
+    /// Time and direction of the most recent target information.
+    last_target: Option<(Instant, TargetDirection)>,
+    /// If set, no new samples are recorded.
+    pub paused: bool,
+    /// Index in `TIME_SPANS_S`.
+    pub time_span_idx: usize
+}
+
+impl Telemetry {
+    pub fn new(station: String, mount: Arc<Mount>) -> Telemetry {
+        Telemetry{
+            station,
+            mount,
+            start: Instant::now(),
+            samples: VecDeque::new(),
+            last_target: None,
+            paused: false,
+            time_span_idx: DEFAULT_TIME_SPAN_IDX
+        }
+    }
+
+    /// Records a new sample if due; to be called every frame.
+    pub fn update(&mut self) {
+        if self.paused { return; }
+
+        let now = Instant::now();
+        let time_s = (now - self.start).as_secs_f64();
+        if self.samples.back().map_or(false, |s| time_s - s.time_s < SAMPLE_INTERVAL.as_secs_f64()) {
+            return;
+        }
+
+        let state = self.mount.get();
+        let error_arcsec = self.last_target.as_ref()
+            .filter(|(received, _)| now - *received < TARGET_TIMEOUT)
+            .map(|(_, target)| {
+                separation(state.boresight_az, state.boresight_alt, target.az, target.alt).get::<angle::second>()
+            });
+        self.samples.push_back(Sample{
+            time_s,
+            axis_pos_deg: [state.axis1_pos.get::<angle::degree>(), state.axis2_pos.get::<angle::degree>()],
+            axis_spd_deg_per_s: [
+                state.axis1_spd.get::<angular_velocity::degree_per_second>(),
+                state.axis2_spd.get::<angular_velocity::degree_per_second>()
+            ],
+            error_arcsec
+        });
+
+        while self.samples.front().map_or(false, |s| time_s - s.time_s > MAX_HISTORY.as_secs_f64()) {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Returns samples within the selected time span (ending at the most recent sample).
+    pub fn visible_samples(&self) -> impl Iterator<Item = &Sample> {
+        let span = TIME_SPANS_S[self.time_span_idx] as f64;
+        let end = self.samples.back().map_or(0.0, |s| s.time_s);
+        self.samples.iter().filter(move |s| end - s.time_s <= span)
+    }
+
+    /// Saves all recorded samples as CSV in the configuration directory; returns the file's path.
+    pub fn export(&self) -> Result<PathBuf, String> {
+        if self.samples.is_empty() { return Err("no samples recorded".into()); }
+
+        let dir = crate::config::config_dir().ok_or_else(|| "cannot determine configuration directory".to_string())?
+            .join("telemetry");
+        let path = dir.join(format!(
+            "{}-{}.csv",
+            self.station.replace(' ', "_"),
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+
+        let mut contents = format!("{}\n", CSV_HEADER);
+        for s in &self.samples {
+            writeln!(
+                contents,
+                "{:.3},{:.6},{:.6},{:.6},{:.6},{}",
+                s.time_s,
+                s.axis_pos_deg[0],
+                s.axis_pos_deg[1],
+                s.axis_spd_deg_per_s[0],
+                s.axis_spd_deg_per_s[1],
+                s.error_arcsec.map_or(String::new(), |e| format!("{:.2}", e))
+            ).unwrap();
+        }
+        std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, contents)).map_err(|e| e.to_string())?;
+
+        Ok(path)
+    }
+}
+
+impl Subscriber<TargetInfoMessage> for Telemetry {
+    fn notify(&mut self, value: &TargetInfoMessage) {
+        if let Some(target) = TargetDirection::from_message(value) {
+            self.last_target = Some((Instant::now(), target));
+        }
+    }
+}
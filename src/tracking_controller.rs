@@ -0,0 +1,308 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use crate::{
+    autotune::{autotune, AutotuneResult},
+    target_geometry::TargetDirection,
+    workers::{Mount, MountMode, TrackedTarget}
+};
+use pointing_utils::{TargetInfoMessage, uom};
+use std::{collections::VecDeque, sync::Arc};
+use subscriber_rs::Subscriber;
+use uom::{si::f64, si::{angle, angular_velocity}};
+
+/// Minimum interval between consecutive slew commands.
+pub const COMMAND_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Number of error values kept for plotting (at `COMMAND_INTERVAL`, ca. 30 s).
+const ERROR_HISTORY_LEN: usize = 600;
+
+/// Max. number of runs with previous settings kept for comparison.
+const MAX_ARCHIVED_RUNS: usize = 3;
+
+/// Limit of the integral term's contribution (anti-windup).
+const MAX_INTEGRAL_RATE: f64 = 0.1; // rad/s
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ControllerKind {
+    P,
+    PI
+}
+
+impl ControllerKind {
+    pub const ALL: [ControllerKind; 2] = [ControllerKind::P, ControllerKind::PI];
+}
+
+impl std::fmt::Display for ControllerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ControllerKind::P => "P",
+            ControllerKind::PI => "PI"
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControllerSettings {
+    pub kind: ControllerKind,
+    /// Proportional gain (1/s).
+    pub kp: f64,
+    /// Integral gain (1/s²); used only by the PI controller.
+    pub ki: f64,
+    /// If enabled, the target's angular rate (from the interpolator) is added to the commanded rate.
+    pub feed_forward: bool
+}
+
+impl Default for ControllerSettings {
+    fn default() -> ControllerSettings {
+        ControllerSettings{ kind: ControllerKind::P, kp: 1.0, ki: 0.1, feed_forward: true }
+    }
+}
+
+impl std::fmt::Display for ControllerSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} kp={}", self.kind, self.kp)?;
+        if self.kind == ControllerKind::PI { write!(f, " ki={}", self.ki)?; }
+        if self.feed_forward { write!(f, " +FF")?; }
+        Ok(())
+    }
+}
+
+/// Total pointing error (arcseconds) recorded while running with the given settings.
+pub struct ErrorHistory {
+    pub settings: ControllerSettings,
+    pub values: VecDeque<f32>
+}
+
+impl ErrorHistory {
+    fn new(settings: ControllerSettings) -> ErrorHistory {
+        ErrorHistory{ settings, values: VecDeque::new() }
+    }
+
+    fn push(&mut self, value: f32) {
+        if self.values.len() == ERROR_HISTORY_LEN { self.values.pop_front(); }
+        self.values.push_back(value);
+    }
+}
+
+/// Built-in reference tracking controllers (alt-az mode only).
+///
+/// Slews the mount at a rate computed from the difference between the target's direction and the reported axis
+/// positions (plus, optionally, the target's angular rate as feed-forward). Meant as a baseline for external
+/// controllers.
+pub struct TrackingController {
+    mount: Arc<Mount>,
+    enabled: bool,
+    settings: ControllerSettings,
+    last_command: Option<std::time::Instant>,
+    /// Integrated error (azimuth, altitude), in radian-seconds.
+    integral: (f64, f64),
+    /// Last position error (azimuth, altitude).
+    error: Option<(f64::Angle, f64::Angle)>,
+    history: ErrorHistory,
+    /// Histories recorded with previous settings, for comparison.
+    archived: VecDeque<ErrorHistory>,
+    /// Most recent target information (used by autotuning).
+    last_target: Option<TargetInfoMessage>,
+    /// Age of the received target data (used by autotuning).
+    feed_latency: std::time::Duration,
+    /// Shared copy of `last_target` (e.g., for telemetry).
+    tracked_target: TrackedTarget,
+    autotune_result: Option<Result<AutotuneResult, String>>
+}
+
+impl TrackingController {
+    pub fn new(
+        mount: Arc<Mount>,
+        feed_latency: std::time::Duration,
+        tracked_target: TrackedTarget
+    ) -> TrackingController {
+        TrackingController{
+            mount,
+            enabled: false,
+            settings: ControllerSettings::default(),
+            last_command: None,
+            integral: (0.0, 0.0),
+            error: None,
+            history: ErrorHistory::new(ControllerSettings::default()),
+            archived: VecDeque::new(),
+            last_target: None,
+            feed_latency,
+            tracked_target,
+            autotune_result: None
+        }
+    }
+
+    pub fn enabled(&self) -> bool { self.enabled }
+
+    /// Disabling the controller stops the mount.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if self.enabled && !enabled {
+            self.mount.stop();
+        }
+        self.enabled = enabled;
+        self.last_command = None;
+        self.integral = (0.0, 0.0);
+        self.error = None;
+    }
+
+    pub fn settings(&self) -> &ControllerSettings { &self.settings }
+
+    /// Changing settings starts a new error history; the previous one is kept for comparison.
+    pub fn set_settings(&mut self, settings: ControllerSettings) {
+        if settings == self.settings { return; }
+
+        let prev_history = std::mem::replace(&mut self.history, ErrorHistory::new(settings.clone()));
+        if !prev_history.values.is_empty() {
+            if self.archived.len() == MAX_ARCHIVED_RUNS { self.archived.pop_front(); }
+            self.archived.push_back(prev_history);
+        }
+        self.settings = settings;
+        self.integral = (0.0, 0.0);
+    }
+
+    pub fn error(&self) -> Option<(f64::Angle, f64::Angle)> { self.error }
+
+    /// Returns error histories: archived ones first, current one last.
+    pub fn error_histories(&self) -> impl Iterator<Item = &ErrorHistory> {
+        self.archived.iter().chain(std::iter::once(&self.history))
+    }
+
+    /// Tunes gains in simulated runs against the current target, mount configuration and wind disturbance; on
+    /// success, applies the best settings found.
+    pub fn autotune(&mut self) {
+        let result = match &self.last_target {
+            None => Err("no target".to_string()),
+            Some(target) => autotune(target, &self.mount, self.feed_latency)
+                .ok_or_else(|| "target unsuitable for tuning".to_string())
+        };
+        if let Ok(result) = &result {
+            self.set_settings(result.settings.clone());
+        }
+        self.autotune_result = Some(result);
+    }
+
+    pub fn last_target(&self) -> Option<&TargetInfoMessage> { self.last_target.as_ref() }
+
+    /// Instantly points the mount at the current target and sets the axis rates to match the target's motion
+    /// (e.g., to set up the geometry before handing control to an external client).
+    pub fn snap_to_target(&mut self) -> Result<(), String> {
+        if !matches!(self.mount.mode(), MountMode::AltAz) {
+            return Err("snapping to target requires alt-az mode".into());
+        }
+        let target = self.last_target.as_ref()
+            .and_then(TargetDirection::from_message)
+            .ok_or_else(|| "no target".to_string())?;
+
+        // axis 1 is moved by at most half a turn, so that the mount does not unwind
+        let axis1_pos = self.mount.get().axis1_pos;
+        let az = axis1_pos + f64::Angle::new::<angle::radian>(normalize((target.az - axis1_pos).get::<angle::radian>()));
+        self.mount.snap_to((az, target.az_rate), (target.alt, target.alt_rate))?;
+        self.integral = (0.0, 0.0);
+        log::info!(
+            "mount snapped to target at az. {:.2}°, alt. {:.2}°",
+            target.az.get::<angle::degree>(),
+            target.alt.get::<angle::degree>()
+        );
+
+        Ok(())
+    }
+
+    pub fn autotune_result(&self) -> Option<&Result<AutotuneResult, String>> { self.autotune_result.as_ref() }
+}
+
+impl Subscriber<TargetInfoMessage> for TrackingController {
+    fn notify(&mut self, value: &TargetInfoMessage) {
+        self.last_target = Some(value.clone());
+        *self.tracked_target.lock().unwrap() = Some(value.clone());
+
+        if !self.enabled || self.last_command.map_or(false, |t| t.elapsed() < COMMAND_INTERVAL) {
+            return;
+        }
+
+        if !matches!(self.mount.mode(), MountMode::AltAz) {
+            log::warn!("tracking controller supports alt-az mode only; disabling");
+            self.set_enabled(false);
+            return;
+        }
+
+        let target = match TargetDirection::from_message(value) {
+            Some(target) => target,
+            None => return
+        };
+
+        let state = self.mount.get();
+        let az_error = normalize((target.az - state.axis1_pos).get::<angle::radian>());
+        let alt_error = (target.alt - state.axis2_pos).get::<angle::radian>();
+        self.error = Some((
+            f64::Angle::new::<angle::radian>(az_error),
+            f64::Angle::new::<angle::radian>(alt_error)
+        ));
+        let total_error = (az_error * target.alt.get::<angle::radian>().cos()).hypot(alt_error);
+        self.history.push((total_error.to_degrees() * 3600.0) as f32);
+
+        let dt = self.last_command.map_or(0.0, |t| t.elapsed().as_secs_f64());
+
+        let (az_rate, alt_rate) = control_rates(
+            &self.settings,
+            (az_error, alt_error),
+            &mut self.integral,
+            dt,
+            (
+                target.az_rate.get::<angular_velocity::radian_per_second>(),
+                target.alt_rate.get::<angular_velocity::radian_per_second>()
+            )
+        );
+
+        let result = self.mount.slew(
+            f64::AngularVelocity::new::<angular_velocity::radian_per_second>(az_rate),
+            f64::AngularVelocity::new::<angular_velocity::radian_per_second>(alt_rate)
+        );
+        if let Err(e) = result {
+            log::error!("tracking controller: slew failed ({}); disabling", e);
+            self.set_enabled(false);
+            return;
+        }
+
+        self.last_command = Some(std::time::Instant::now());
+    }
+}
+
+/// Computes commanded axis rates (rad/s) from position error (radians), updating the integral term.
+///
+/// `feed_forward` is the target's angular rate (rad/s), used only if enabled in `settings`.
+pub fn control_rates(
+    settings: &ControllerSettings,
+    error: (f64, f64),
+    integral: &mut (f64, f64),
+    dt: f64,
+    feed_forward: (f64, f64)
+) -> (f64, f64) {
+    let mut az_rate = settings.kp * error.0;
+    let mut alt_rate = settings.kp * error.1;
+
+    if settings.kind == ControllerKind::PI && settings.ki > 0.0 {
+        let max_integral = MAX_INTEGRAL_RATE / settings.ki;
+        integral.0 = (integral.0 + error.0 * dt).clamp(-max_integral, max_integral);
+        integral.1 = (integral.1 + error.1 * dt).clamp(-max_integral, max_integral);
+        az_rate += settings.ki * integral.0;
+        alt_rate += settings.ki * integral.1;
+    }
+
+    if settings.feed_forward {
+        az_rate += feed_forward.0;
+        alt_rate += feed_forward.1;
+    }
+
+    (az_rate, alt_rate)
+}
+
+/// Normalizes angle (in radians) to [-π, π).
+pub fn normalize(angle: f64) -> f64 {
+    (angle + std::f64::consts::PI).rem_euclid(2.0 * std::f64::consts::PI) - std::f64::consts::PI
+}
@@ -0,0 +1,260 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+//! User-selectable units in which quantities are displayed.
+//!
+//! Re-exports the simulation core's angle conversions (`pointing_sim_core::units`).
+
+pub use pointing_sim_core::units::*;
+use pointing_utils::uom;
+use serde::{Deserialize, Serialize};
+use uom::{si::f64, si::{angle, angular_velocity, length, velocity}};
+
+/// Magnitude range of a displayed angle.
+#[derive(Copy, Clone, PartialEq)]
+pub enum AngleScale {
+    /// Positions, fields of view: degrees or radians.
+    Normal,
+    /// Offsets: arcminutes or milliradians.
+    Small,
+    /// Pointing errors: arcseconds or microradians.
+    Fine
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AngleUnit {
+    #[default]
+    Degrees,
+    Radians
+}
+
+impl AngleUnit {
+    pub const ALL: [AngleUnit; 2] = [AngleUnit::Degrees, AngleUnit::Radians];
+
+    pub fn value(self, value: f64::Angle, scale: AngleScale) -> f64 {
+        match (self, scale) {
+            (AngleUnit::Degrees, AngleScale::Normal) => value.get::<angle::degree>(),
+            (AngleUnit::Degrees, AngleScale::Small) => value.get::<angle::minute>(),
+            (AngleUnit::Degrees, AngleScale::Fine) => value.get::<angle::second>(),
+            (AngleUnit::Radians, AngleScale::Normal) => value.get::<angle::radian>(),
+            (AngleUnit::Radians, AngleScale::Small) => value.get::<angle::radian>() * 1.0e3,
+            (AngleUnit::Radians, AngleScale::Fine) => value.get::<angle::radian>() * 1.0e6
+        }
+    }
+
+    pub fn quantity(self, value: f64, scale: AngleScale) -> f64::Angle {
+        match (self, scale) {
+            (AngleUnit::Degrees, AngleScale::Normal) => f64::Angle::new::<angle::degree>(value),
+            (AngleUnit::Degrees, AngleScale::Small) => f64::Angle::new::<angle::minute>(value),
+            (AngleUnit::Degrees, AngleScale::Fine) => f64::Angle::new::<angle::second>(value),
+            (AngleUnit::Radians, AngleScale::Normal) => f64::Angle::new::<angle::radian>(value),
+            (AngleUnit::Radians, AngleScale::Small) => f64::Angle::new::<angle::radian>(value * 1.0e-3),
+            (AngleUnit::Radians, AngleScale::Fine) => f64::Angle::new::<angle::radian>(value * 1.0e-6)
+        }
+    }
+
+    pub fn symbol(self, scale: AngleScale) -> &'static str {
+        match (self, scale) {
+            (AngleUnit::Degrees, AngleScale::Normal) => "°",
+            (AngleUnit::Degrees, AngleScale::Small) => "'",
+            (AngleUnit::Degrees, AngleScale::Fine) => "\"",
+            (AngleUnit::Radians, AngleScale::Normal) => " rad",
+            (AngleUnit::Radians, AngleScale::Small) => " mrad",
+            (AngleUnit::Radians, AngleScale::Fine) => " µrad"
+        }
+    }
+
+    pub fn rate_value(self, value: f64::AngularVelocity) -> f64 {
+        match self {
+            AngleUnit::Degrees => value.get::<angular_velocity::degree_per_second>(),
+            AngleUnit::Radians => value.get::<angular_velocity::radian_per_second>()
+        }
+    }
+
+    pub fn rate_quantity(self, value: f64) -> f64::AngularVelocity {
+        match self {
+            AngleUnit::Degrees => f64::AngularVelocity::new::<angular_velocity::degree_per_second>(value),
+            AngleUnit::Radians => f64::AngularVelocity::new::<angular_velocity::radian_per_second>(value)
+        }
+    }
+
+    pub fn rate_symbol(self) -> &'static str {
+        match self {
+            AngleUnit::Degrees => "°/s",
+            AngleUnit::Radians => " rad/s"
+        }
+    }
+
+    /// Number of decimal places to add (to a precision chosen for degrees, arcminutes or arcseconds) for values
+    /// expressed in this unit to be shown with a similar resolution.
+    fn extra_precision(self, scale: AngleScale) -> usize {
+        match (self, scale) {
+            (AngleUnit::Degrees, _) | (AngleUnit::Radians, AngleScale::Fine) => 0,
+            (AngleUnit::Radians, AngleScale::Small) => 1,
+            (AngleUnit::Radians, AngleScale::Normal) => 2
+        }
+    }
+}
+
+impl std::fmt::Display for AngleUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            AngleUnit::Degrees => "degrees",
+            AngleUnit::Radians => "radians"
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    #[default]
+    Meters,
+    Feet
+}
+
+impl LengthUnit {
+    pub const ALL: [LengthUnit; 2] = [LengthUnit::Meters, LengthUnit::Feet];
+
+    pub fn value(self, value: f64::Length) -> f64 {
+        match self {
+            LengthUnit::Meters => value.get::<length::meter>(),
+            LengthUnit::Feet => value.get::<length::foot>()
+        }
+    }
+
+    pub fn quantity(self, value: f64) -> f64::Length {
+        match self {
+            LengthUnit::Meters => f64::Length::new::<length::meter>(value),
+            LengthUnit::Feet => f64::Length::new::<length::foot>(value)
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            LengthUnit::Meters => "m",
+            LengthUnit::Feet => "ft"
+        }
+    }
+}
+
+impl std::fmt::Display for LengthUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            LengthUnit::Meters => "meters",
+            LengthUnit::Feet => "feet"
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedUnit {
+    #[default]
+    MetersPerSecond,
+    Knots
+}
+
+impl SpeedUnit {
+    pub const ALL: [SpeedUnit; 2] = [SpeedUnit::MetersPerSecond, SpeedUnit::Knots];
+
+    pub fn value(self, value: f64::Velocity) -> f64 {
+        match self {
+            SpeedUnit::MetersPerSecond => value.get::<velocity::meter_per_second>(),
+            SpeedUnit::Knots => value.get::<velocity::knot>()
+        }
+    }
+
+    pub fn quantity(self, value: f64) -> f64::Velocity {
+        match self {
+            SpeedUnit::MetersPerSecond => f64::Velocity::new::<velocity::meter_per_second>(value),
+            SpeedUnit::Knots => f64::Velocity::new::<velocity::knot>(value)
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            SpeedUnit::MetersPerSecond => "m/s",
+            SpeedUnit::Knots => "kn"
+        }
+    }
+}
+
+impl std::fmt::Display for SpeedUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            SpeedUnit::MetersPerSecond => "meters per second",
+            SpeedUnit::Knots => "knots"
+        })
+    }
+}
+
+/// Units in which quantities are displayed (stored with the settings).
+#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DisplayUnits {
+    pub angle: AngleUnit,
+    pub length: LengthUnit,
+    pub speed: SpeedUnit
+}
+
+impl DisplayUnits {
+    /// Formats an angle; `precision`: number of decimal places if shown in degrees (or arcminutes, arcseconds).
+    pub fn angle(&self, value: f64::Angle, scale: AngleScale, precision: usize) -> String {
+        format!(
+            "{:.*}{}",
+            precision + self.angle.extra_precision(scale),
+            self.angle.value(value, scale),
+            self.angle.symbol(scale)
+        )
+    }
+
+    /// Like `angle`, but always shows the sign.
+    pub fn signed_angle(&self, value: f64::Angle, scale: AngleScale, precision: usize) -> String {
+        format!(
+            "{:+.*}{}",
+            precision + self.angle.extra_precision(scale),
+            self.angle.value(value, scale),
+            self.angle.symbol(scale)
+        )
+    }
+
+    /// Formats an angular velocity (with sign); `precision`: number of decimal places if shown in degrees per second.
+    pub fn angular_velocity(&self, value: f64::AngularVelocity, precision: usize) -> String {
+        format!(
+            "{:+.*}{}",
+            precision + self.angle.extra_precision(AngleScale::Normal),
+            self.angle.rate_value(value),
+            self.angle.rate_symbol()
+        )
+    }
+
+    /// Like `angular_velocity`, but without the sign.
+    pub fn angular_speed(&self, value: f64::AngularVelocity, precision: usize) -> String {
+        format!(
+            "{:.*}{}",
+            precision + self.angle.extra_precision(AngleScale::Normal),
+            self.angle.rate_value(value),
+            self.angle.rate_symbol()
+        )
+    }
+
+    /// Returns the unit of angles of the given scale, for labels (e.g., "error (mrad)").
+    pub fn angle_label(&self, scale: AngleScale) -> &'static str {
+        self.angle.symbol(scale).trim_start()
+    }
+
+    pub fn length(&self, value: f64::Length, precision: usize) -> String {
+        format!("{:.*} {}", precision, self.length.value(value), self.length.symbol())
+    }
+
+    pub fn speed(&self, value: f64::Velocity, precision: usize) -> String {
+        format!("{:.*} {}", precision, self.speed.value(value), self.speed.symbol())
+    }
+}
@@ -1,7 +0,0 @@
-mod mount_model;
-mod target_receiver;
-mod target_source;
-
-pub use mount_model::{Mount, MountState, mount_model};
-pub use target_receiver::target_receiver;
-pub use target_source::target_source;
@@ -0,0 +1,67 @@
+//
+// Pointing Simulator
+// Copyright (c) 2024 Filip Szczerek <ga.software@yahoo.com>
+//
+// This project is licensed under the terms of the MIT license
+// (see the LICENSE file for details).
+//
+
+use crate::{target_geometry::TargetDirection, workers::{Mount, MountMode}};
+use pointing_utils::{TargetInfoMessage, uom};
+use std::sync::Arc;
+use subscriber_rs::Subscriber;
+use uom::{si::f64, si::{angle, angular_velocity}};
+
+/// Zenith "keyhole" of an alt-az mount for the current target.
+#[derive(Copy, Clone)]
+pub struct KeyholeStatus {
+    /// Zenith distance below which the azimuth axis cannot keep up with the target.
+    pub radius: f64::Angle,
+    pub target_zenith_distance: f64::Angle
+}
+
+impl KeyholeStatus {
+    /// Returns true if the target is in the blind spot around the zenith.
+    pub fn target_inside(&self) -> bool { self.target_zenith_distance < self.radius }
+}
+
+/// Returns the keyhole radius for a target moving across the sky at `target_rate`.
+///
+/// Azimuth rate needed to follow a target passing at zenith distance ζ peaks at ω / sin ζ (ω: angular speed
+/// of the target), so the azimuth axis limited to `max_az_rate` loses the target within ζ = asin(ω / max_az_rate).
+pub fn keyhole_radius(target_rate: f64::AngularVelocity, max_az_rate: f64::AngularVelocity) -> f64::Angle {
+    let ratio = (target_rate / max_az_rate).value;
+    f64::Angle::new::<angle::radian>(ratio.clamp(0.0, 1.0).asin())
+}
+
+/// Tracks the zenith keyhole status for the current target (alt-az mode only).
+pub struct KeyholeMonitor {
+    mount: Arc<Mount>,
+    status: Option<KeyholeStatus>
+}
+
+impl KeyholeMonitor {
+    pub fn new(mount: Arc<Mount>) -> KeyholeMonitor {
+        KeyholeMonitor{ mount, status: None }
+    }
+
+    pub fn status(&self) -> Option<KeyholeStatus> { self.status }
+}
+
+impl Subscriber<TargetInfoMessage> for KeyholeMonitor {
+    fn notify(&mut self, value: &TargetInfoMessage) {
+        if !matches!(self.mount.mode(), MountMode::AltAz) {
+            self.status = None;
+            return;
+        }
+
+        let max_az_rate = f64::AngularVelocity::new::<angular_velocity::degree_per_second>(
+            self.mount.config().axis1.max_rate_deg_per_s
+        );
+
+        self.status = TargetDirection::from_message(value).map(|target| KeyholeStatus{
+            radius: keyhole_radius(target.angular_speed(), max_az_rate),
+            target_zenith_distance: f64::Angle::new::<angle::degree>(90.0) - target.alt
+        });
+    }
+}